@@ -90,6 +90,7 @@ async fn main() -> Result<()> {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: true,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -97,6 +98,16 @@ async fn main() -> Result<()> {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: soku::core::models::OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     // Run build
@@ -5,7 +5,7 @@
 
 use async_trait::async_trait;
 use soku::core::interfaces::BuildService;
-use soku::core::models::{BuildConfig, ModuleInfo};
+use soku::core::models::{BuildConfig, ModuleInfo, OutputFormat};
 use soku::utils::{BuiltInTransformers, CustomTransformer, Plugin, PluginContext, Result};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -106,6 +106,7 @@ async fn main() -> Result<()> {
         enable_tree_shaking: true,
         enable_minification: true,
         enable_source_maps: true, // Advanced source maps with inline sources
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "production".to_string(),
@@ -113,6 +114,16 @@ async fn main() -> Result<()> {
         external: Vec::new(),
         vendor_chunk: false,
         entries,
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     // Create build service with all features
@@ -1,10 +1,10 @@
 use crate::core::{interfaces::*, models::*, services::*};
 use crate::infrastructure::{
-    generate_hmr_client_code, LightningCssProcessor, ProcessingStrategy, RegexTreeShaker,
-    ScssProcessor, SokuFileSystemService, SokuHmrService, TokioFileSystemService,
+    lan_addresses, DevServer, LightningCssProcessor, PreviewServer, ProcessingStrategy,
+    RegexTreeShaker, ScssProcessor, SokuFileSystemService, SokuHmrService, TokioFileSystemService,
     UnifiedJsProcessor,
 };
-use crate::utils::{Logger, Result};
+use crate::utils::{Logger, Result, SourceMapMode, SourceMapOption};
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -31,6 +31,56 @@ impl StrategyArg {
     }
 }
 
+/// Output format for JavaScript bundles
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum FormatArg {
+    /// ES module with export statements
+    Esm,
+    /// CommonJS module (module.exports)
+    Cjs,
+    /// Immediately invoked function for script tags
+    Iife,
+    /// Universal module (AMD, CommonJS and browser global)
+    Umd,
+}
+
+impl FormatArg {
+    /// Convert CLI format argument to OutputFormat
+    fn to_output_format(self) -> OutputFormat {
+        match self {
+            FormatArg::Esm => OutputFormat::Esm,
+            FormatArg::Cjs => OutputFormat::Cjs,
+            FormatArg::Iife => OutputFormat::Iife,
+            FormatArg::Umd => OutputFormat::Umd,
+        }
+    }
+}
+
+/// Source map mode
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum SourceMapArg {
+    /// .map file referenced from the bundle
+    External,
+    /// Map embedded in the bundle as a data URL
+    Inline,
+    /// .map file without a reference in the bundle
+    Hidden,
+    /// No source maps
+    None,
+}
+
+impl SourceMapArg {
+    /// Convert CLI source map argument to the `sourcemap` option
+    fn to_source_map_option(self) -> SourceMapOption {
+        match self {
+            SourceMapArg::External => SourceMapOption::Mode(SourceMapMode::External),
+            SourceMapArg::Inline => SourceMapOption::Mode(SourceMapMode::Inline),
+            SourceMapArg::Hidden => SourceMapOption::Mode(SourceMapMode::Hidden),
+            SourceMapArg::None => SourceMapOption::Enabled(false),
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "soku")]
 #[command(about = "Soku - The fastest bundler for modern web development")]
@@ -64,9 +114,21 @@ pub enum Commands {
         /// Disable minification
         #[arg(long)]
         no_minify: bool,
-        /// Enable source maps
+        /// Source maps (external, inline, hidden, none); external when given without a value
+        #[arg(
+            long = "sourcemap",
+            visible_alias = "source-maps",
+            value_enum,
+            num_args = 0..=1,
+            default_missing_value = "external"
+        )]
+        sourcemap: Option<SourceMapArg>,
+        /// Leave the original sources out of the source maps
+        #[arg(long)]
+        no_sources_content: bool,
+        /// `sourceRoot` of the source maps, e.g. where the sources are served
         #[arg(long)]
-        source_maps: bool,
+        source_root: Option<String>,
         /// Processing strategy (fast, standard, enhanced) - optional override, auto-detects by default
         #[arg(long, value_enum)]
         strategy: Option<StrategyArg>,
@@ -88,6 +150,15 @@ pub enum Commands {
         /// Build mode (development or production, affects env variables)
         #[arg(long, default_value = "production")]
         mode: String,
+        /// Output format (esm, cjs, iife, umd) - overrides config file if specified
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        /// Global variable name for iife/umd bundles
+        #[arg(long)]
+        global_name: Option<String>,
+        /// Entry point, as `path` or `name=path` (repeatable) - overrides config file if specified
+        #[arg(long = "entry", value_name = "ENTRY")]
+        entries: Vec<String>,
     },
     /// Preview production build
     Preview {
@@ -97,6 +168,9 @@ pub enum Commands {
         /// Port to serve on
         #[arg(short, long, default_value_t = 4173)]
         port: u16,
+        /// Address to listen on; all interfaces when given without a value
+        #[arg(long, num_args = 0..=1, default_missing_value = "0.0.0.0")]
+        host: Option<std::net::IpAddr>,
     },
     /// Show bundler information
     Info,
@@ -114,9 +188,15 @@ pub enum Commands {
         /// Disable minification
         #[arg(long)]
         no_minify: bool,
-        /// Enable source maps
-        #[arg(long)]
-        source_maps: bool,
+        /// Source maps (external, inline, hidden, none); external when given without a value
+        #[arg(
+            long = "sourcemap",
+            visible_alias = "source-maps",
+            value_enum,
+            num_args = 0..=1,
+            default_missing_value = "external"
+        )]
+        sourcemap: Option<SourceMapArg>,
         /// Clear console on rebuild
         #[arg(long)]
         clear: bool,
@@ -149,7 +229,9 @@ impl CliHandler {
                 outdir,
                 no_tree_shaking,
                 no_minify,
-                source_maps,
+                sourcemap,
+                no_sources_content,
+                source_root,
                 strategy,
                 turbo_mode,
                 normal_mode,
@@ -157,13 +239,18 @@ impl CliHandler {
                 code_splitting,
                 analyze,
                 mode,
+                format,
+                global_name,
+                entries,
             } => {
                 self.handle_build_command(
                     &root,
                     outdir.as_deref(),
                     !no_tree_shaking,
                     !no_minify,
-                    source_maps,
+                    sourcemap.map(SourceMapArg::to_source_map_option),
+                    no_sources_content,
+                    source_root,
                     strategy,
                     turbo_mode,
                     normal_mode,
@@ -171,17 +258,22 @@ impl CliHandler {
                     code_splitting,
                     analyze,
                     &mode,
+                    format.map(FormatArg::to_output_format),
+                    global_name,
+                    entries,
                 )
                 .await
             }
-            Commands::Preview { dir, port } => self.handle_preview_command(&dir, port).await,
+            Commands::Preview { dir, port, host } => {
+                self.handle_preview_command(&dir, port, host).await
+            }
             Commands::Info => self.handle_info_command().await,
             Commands::Watch {
                 root,
                 outdir,
                 no_tree_shaking,
                 no_minify,
-                source_maps,
+                sourcemap,
                 clear,
                 verbose,
                 strategy,
@@ -191,7 +283,7 @@ impl CliHandler {
                     &outdir,
                     !no_tree_shaking,
                     !no_minify,
-                    source_maps,
+                    sourcemap.map(|arg| arg.to_source_map_option()),
                     clear,
                     verbose,
                     strategy,
@@ -208,7 +300,9 @@ impl CliHandler {
         outdir: Option<&str>,
         enable_tree_shaking: bool,
         enable_minification: bool,
-        enable_source_maps: bool,
+        source_maps: Option<SourceMapOption>,
+        no_sources_content: bool,
+        source_root: Option<String>,
         strategy: Option<StrategyArg>,
         force_turbo_mode: bool,
         force_normal_mode: bool,
@@ -216,6 +310,9 @@ impl CliHandler {
         enable_code_splitting: bool,
         enable_analysis: bool,
         mode: &str,
+        format: Option<OutputFormat>,
+        global_name: Option<String>,
+        entries: Vec<String>,
     ) -> Result<()> {
         use crate::utils::ConfigLoader;
 
@@ -228,21 +325,42 @@ impl CliHandler {
         }
 
         // Merge file config with CLI arguments (CLI takes precedence)
-        let config = ConfigLoader::merge_with_cli(
+        let mut config = ConfigLoader::merge_with_cli(
             file_config,
             project_root.clone(),
             outdir,
             Some(enable_tree_shaking),
             Some(enable_minification),
-            Some(enable_source_maps),
+            source_maps,
             Some(enable_code_splitting),
             Some(250_000), // max_chunk_size
             mode.to_string(),
+            format,
+            global_name,
+            entries,
         );
+        config.typescript = crate::utils::load_tsconfig(&project_root)?;
+        if no_sources_content {
+            config.source_map.include_sources_content = false;
+        }
+        if source_root.is_some() {
+            config.source_map.source_root = source_root;
+        }
 
         if enable_code_splitting {
             Logger::info("📦 Code Splitting: Enabled (vendor + common + route chunks)");
         }
+        if config.format != OutputFormat::Iife || config.global_name.is_some() {
+            Logger::info(&format!(
+                "📤 Output Format: {}{}",
+                config.format.name(),
+                config
+                    .global_name
+                    .as_deref()
+                    .map(|name| format!(" ({})", name))
+                    .unwrap_or_default()
+            ));
+        }
 
         // Analyze project to determine optimal mode
         let should_use_turbo_mode = if force_turbo_mode {
@@ -384,21 +502,22 @@ impl CliHandler {
     }
 
     async fn handle_dev_command(&self, root: &str, port: u16) -> Result<()> {
+        let root_path = Path::new(root)
+            .canonicalize()
+            .unwrap_or_else(|_| PathBuf::from(root));
+        let hmr_port = port + 1; // HMR on port+1
+
         tracing::info!("🚀 Soku Bundler - Development Server");
         tracing::info!("═══════════════════════════════════════");
-        tracing::info!("📁 Root: {}", root);
-        tracing::info!("🌐 Port: {}", port);
-        tracing::info!("🔥 HMR: ws://localhost:{}", port + 1);
+        tracing::info!("📁 Root: {}", root_path.display());
         tracing::info!("");
 
-        // Initialize HMR service
-        let hmr_service = SokuHmrService::new(PathBuf::from(root));
-        let hmr_port = port + 1; // HMR on port+1
-
-        // Start file watching
-        hmr_service.start_watching().await?;
+        // Bind first so a busy port fails before building
+        let listener = DevServer::bind(port).await?;
+        let local_addr = listener.local_addr()?;
 
         // Start HMR WebSocket server
+        let hmr_service = SokuHmrService::new(root_path.clone());
         let hmr_service_clone = hmr_service.clone();
         tokio::spawn(async move {
             if let Err(e) = hmr_service_clone.start_server(hmr_port).await {
@@ -406,61 +525,74 @@ impl CliHandler {
             }
         });
 
-        // Perform initial build with HMR client injection
-        self.build_with_hmr(root, port, hmr_port).await?;
-
-        tracing::info!("✨ Architecture loaded:");
-        tracing::info!("  ✅ Lightning CSS processor");
-        tracing::info!("  ✅ oxc JavaScript parser");
-        tracing::info!("  ✅ Memory-mapped file system");
-        tracing::info!("  ✅ Hot Module Replacement");
-        tracing::info!("  ✅ File watcher active");
-        tracing::info!("");
-
-        tracing::info!("🔧 Features ready:");
-        tracing::info!("  • Hot Module Replacement");
-        tracing::info!("  • CSS hot reload");
-        tracing::info!("  • TypeScript transformation");
-        tracing::info!("  • Incremental builds");
-        tracing::info!("  • File watching");
-        tracing::info!("");
+        // Initial build; the server still starts when it fails, and the
+        // next successful rebuild reloads the page
+        let server = DevServer::new(Self::dev_build_config(&root_path)?, hmr_service, hmr_port);
+        let mut build_service = Self::dev_build_service();
+        let start = std::time::Instant::now();
+        if let Err(e) = server.initial_build(&mut build_service).await {
+            Logger::error(&format!("❌ Initial build failed: {}", e));
+        }
 
-        tracing::info!("🌐 Local:   http://localhost:{}", port);
-        tracing::info!("🌍 Network: http://192.168.1.100:{}", port);
         tracing::info!("");
-        tracing::info!("📦 ready with HMR");
+        tracing::info!("🌐 Local:   http://localhost:{}/", local_addr.port());
+        tracing::info!("🔥 HMR:     ws://localhost:{}", hmr_port);
+        tracing::info!("📦 ready in {}ms", start.elapsed().as_millis());
         tracing::info!("");
         tracing::info!("Press Ctrl+C to stop the server");
 
-        // Keep server running
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(1));
-        loop {
-            interval.tick().await;
-            // Server keeps running until interrupted
+        tokio::select! {
+            result = server.serve(listener) => result,
+            result = server.watch(&mut build_service) => result,
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("👋 Dev server stopped");
+                Ok(())
+            }
         }
     }
 
-    async fn handle_preview_command(&self, dir: &str, port: u16) -> Result<()> {
+    async fn handle_preview_command(
+        &self,
+        dir: &str,
+        port: u16,
+        host: Option<std::net::IpAddr>,
+    ) -> Result<()> {
+        let dir_path = Path::new(dir).canonicalize().map_err(|_| {
+            crate::utils::SokuError::config(format!(
+                "Nothing to preview: '{}' does not exist, run `soku build` first",
+                dir
+            ))
+        })?;
+
         tracing::info!("📦 Soku Bundler - Preview Server");
         tracing::info!("═══════════════════════════════════════");
-        tracing::info!("📁 Directory: {}", dir);
-        tracing::info!("🌐 Port: {}", port);
-        tracing::info!("📊 Simulating production preview...");
+        tracing::info!("📁 Directory: {}", dir_path.display());
         tracing::info!("");
 
-        tracing::info!("🌐 Local:   http://localhost:{}", port);
-        tracing::info!("🌍 Network: http://192.168.1.100:{}", port);
-        tracing::info!("");
-        tracing::info!("📦 Serving files from: {}", dir);
-        tracing::info!("⚡ Ready in 234ms");
+        let host = host.unwrap_or(std::net::IpAddr::from([127, 0, 0, 1]));
+        let listener = PreviewServer::bind(host, port).await?;
+        let local_addr = listener.local_addr()?;
+
+        if local_addr.ip().is_unspecified() {
+            tracing::info!("🌐 Local:   http://localhost:{}/", local_addr.port());
+            for address in lan_addresses() {
+                tracing::info!("🌍 Network: http://{}:{}/", address, local_addr.port());
+            }
+        } else {
+            tracing::info!("🌐 Local:   http://{}/", local_addr);
+            tracing::info!("🌍 Network: use --host to expose");
+        }
         tracing::info!("");
         tracing::info!("Press Ctrl+C to stop the server");
 
-        // Simulate server running
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        tracing::info!("✅ Preview server stopped");
-
-        Ok(())
+        let server = PreviewServer::new(dir_path);
+        tokio::select! {
+            result = server.serve(listener) => result,
+            _ = tokio::signal::ctrl_c() => {
+                tracing::info!("✅ Preview server stopped");
+                Ok(())
+            }
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -470,7 +602,7 @@ impl CliHandler {
         outdir: &str,
         enable_tree_shaking: bool,
         enable_minification: bool,
-        enable_source_maps: bool,
+        source_maps: Option<SourceMapOption>,
         clear_console: bool,
         verbose: bool,
         strategy: Option<StrategyArg>,
@@ -483,23 +615,25 @@ impl CliHandler {
         let root_path = Path::new(root)
             .canonicalize()
             .unwrap_or_else(|_| PathBuf::from(root));
-        let outdir_path = root_path.join(outdir);
 
-        // Create build config
-        let build_config = BuildConfig {
-            root: root_path.clone(),
-            outdir: outdir_path,
-            enable_tree_shaking,
-            enable_minification,
-            enable_source_maps,
-            enable_code_splitting: false, // Disable for watch mode for faster rebuilds
-            max_chunk_size: None,
-            mode: "development".to_string(), // Watch mode is for development
-            alias: std::collections::HashMap::new(), // No aliases in watch mode
-            external: Vec::new(),            // No external deps in watch mode
-            vendor_chunk: false,             // No vendor splitting in watch mode
-            entries: std::collections::HashMap::new(), // No multiple entries in watch mode
-        };
+        // Config file like `soku build`, with the rebuild-friendly settings of watch mode
+        let file_config = crate::utils::ConfigLoader::load_from_file(&root_path)?;
+        let mut build_config = crate::utils::ConfigLoader::merge_with_cli(
+            file_config,
+            root_path.clone(),
+            Some(outdir),
+            Some(enable_tree_shaking),
+            Some(enable_minification),
+            source_maps,
+            Some(false), // Code splitting is disabled for faster rebuilds
+            None,
+            "development".to_string(),
+            None,
+            None,
+            Vec::new(),
+        );
+        build_config.vendor_chunk = false;
+        build_config.typescript = crate::utils::load_tsconfig(&root_path)?;
 
         // Create watch config
         let watch_config = WatchConfig {
@@ -582,23 +716,32 @@ impl CliHandler {
         Ok(())
     }
 
-    async fn build_with_hmr(&self, root: &str, _port: u16, hmr_port: u16) -> Result<()> {
-        let config = BuildConfig {
-            root: PathBuf::from(root),
-            outdir: PathBuf::from("dist"),
-            enable_tree_shaking: false,   // Disabled for faster dev builds
-            enable_minification: false,   // Disabled for dev
-            enable_source_maps: true,     // Enabled for debugging
-            enable_code_splitting: false, // Disabled for dev
-            max_chunk_size: Some(250_000), // 250KB default
-            mode: "development".to_string(), // Dev server with HMR
-            alias: std::collections::HashMap::new(), // No aliases in dev mode
-            external: Vec::new(),         // No external deps in dev mode
-            vendor_chunk: false,          // No vendor splitting in dev mode
-            entries: std::collections::HashMap::new(), // No multiple entries in dev mode
-        };
+    /// Build configuration of `soku dev`: the config file like `soku build`,
+    /// with the settings the dev server and HMR depend on
+    fn dev_build_config(root: &Path) -> Result<BuildConfig> {
+        let file_config = crate::utils::ConfigLoader::load_from_file(root)?;
+        let mut config = crate::utils::ConfigLoader::merge_with_cli(
+            file_config,
+            root.to_path_buf(),
+            None,
+            Some(false), // Tree shaking is disabled for faster dev builds
+            Some(false), // No minification in dev
+            Some(SourceMapOption::Enabled(true)), // Source maps for debugging
+            Some(false), // No code splitting in dev
+            None,
+            "development".to_string(),
+            Some(OutputFormat::Iife), // Dev bundles are loaded with a script tag
+            None,
+            Vec::new(),
+        );
+        config.vendor_chunk = false;
+        config.typescript = crate::utils::load_tsconfig(root)?;
+        config.hmr = true; // Modules are swapped by the HMR client
+        Ok(config)
+    }
 
-        // Create services
+    /// Build service of `soku dev`, kept across rebuilds for incremental builds
+    fn dev_build_service() -> SokuBuildService {
         let fs_service = Arc::new(TokioFileSystemService);
         let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
 
@@ -606,37 +749,7 @@ impl CliHandler {
         let lightning_css = Arc::new(LightningCssProcessor::new(false));
         let css_processor = Arc::new(ScssProcessor::with_css_processor(false, lightning_css));
 
-        // Create build service
-        let mut build_service = SokuBuildService::new(fs_service, js_processor, css_processor);
-
-        // Execute build
-        let mut result = build_service.build(&config).await?;
-
-        // Inject HMR client code into the main bundle
-        if let Some(js_bundle) = result
-            .output_files
-            .iter_mut()
-            .find(|f| f.path.file_name().and_then(|n| n.to_str()) == Some("bundle.js"))
-        {
-            let hmr_client = generate_hmr_client_code(hmr_port);
-            js_bundle.content = format!("{}\n\n{}", hmr_client, js_bundle.content);
-            js_bundle.size = js_bundle.content.len();
-        }
-
-        // Write files to disk
-        for output_file in &result.output_files {
-            if let Some(parent) = output_file.path.parent() {
-                tokio::fs::create_dir_all(parent)
-                    .await
-                    .map_err(crate::utils::SokuError::Io)?;
-            }
-
-            tokio::fs::write(&output_file.path, &output_file.content)
-                .await
-                .map_err(crate::utils::SokuError::Io)?;
-        }
-
-        Ok(())
+        SokuBuildService::new(fs_service, js_processor, css_processor)
     }
 
     /// Analyze project characteristics to determine optimal build mode
@@ -749,3 +862,25 @@ impl Default for CliHandler {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_build_config_reads_the_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("soku.config.json"),
+            r#"{"entry": "src/app.tsx", "alias": {"@": "./src"}, "minify": true, "format": "esm"}"#,
+        )
+        .unwrap();
+
+        let config = CliHandler::dev_build_config(dir.path()).unwrap();
+        assert_eq!(config.entries["main"], dir.path().join("src/app.tsx"));
+        assert_eq!(config.alias["@"], "./src");
+        assert!(!config.enable_minification);
+        assert_eq!(config.format, OutputFormat::Iife);
+        assert!(config.hmr);
+    }
+}
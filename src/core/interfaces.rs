@@ -1,5 +1,5 @@
 use crate::core::models::*;
-use crate::utils::Result;
+use crate::utils::{CodeMap, Result};
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 
@@ -16,18 +16,41 @@ pub trait FileSystemService: Send + Sync {
 #[async_trait]
 pub trait JsProcessor: Send + Sync {
     async fn process_module(&self, module: &ModuleInfo) -> Result<String>;
-    async fn bundle_modules(&self, modules: &[ModuleInfo]) -> Result<String>;
+    async fn bundle_modules(&self, modules: &[ModuleInfo], config: &BuildConfig) -> Result<String>;
     async fn bundle_modules_with_tree_shaking(
         &self,
         modules: &[ModuleInfo],
         tree_shaking_stats: Option<&TreeShakingStats>,
+        config: &BuildConfig,
     ) -> Result<String>;
     async fn bundle_modules_with_source_maps(
         &self,
         modules: &[ModuleInfo],
         config: &BuildConfig,
     ) -> Result<BundleOutput>;
+    /// Bundle modules split into chunks: one output per entry of `chunks`,
+    /// which lists the paths of the modules the chunk holds
+    ///
+    /// The chunks share one module registry and require each other's modules
+    /// through it, so the chunk with the entry modules has to be loaded last.
+    /// Source maps are included when they are enabled in `config`.
+    async fn bundle_chunks(
+        &self,
+        modules: &[ModuleInfo],
+        chunks: &[Vec<PathBuf>],
+        config: &BuildConfig,
+    ) -> Result<Vec<BundleOutput>>;
     fn supports_module_type(&self, module_type: &ModuleType) -> bool;
+
+    /// Hoist/wrap decisions recorded by bundle calls since the last call to this method
+    fn take_module_linking(&self) -> Vec<ModuleLinking> {
+        Vec::new()
+    }
+
+    /// Linker warnings of bundle calls since the last call to this method
+    fn take_link_warnings(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 /// CSS processing interface
@@ -35,6 +58,34 @@ pub trait JsProcessor: Send + Sync {
 pub trait CssProcessor: Send + Sync {
     async fn process_css(&self, content: &str, path: &Path) -> Result<String>;
     async fn bundle_css(&self, files: &[PathBuf]) -> Result<String>;
+
+    /// Process CSS whose map back to the original stylesheet is `map`,
+    /// returning the processed CSS with its map
+    ///
+    /// Processors that cannot track positions only keep the map of CSS they
+    /// leave unchanged.
+    async fn process_css_with_map(
+        &self,
+        content: &str,
+        path: &Path,
+        map: CodeMap,
+    ) -> Result<(String, CodeMap)> {
+        let processed = self.process_css(content, path).await?;
+        let map = if processed == content {
+            map
+        } else {
+            CodeMap::default()
+        };
+        Ok((processed, map))
+    }
+
+    /// Bundle CSS files with a source map of the bundle
+    async fn bundle_css_with_source_map(&self, files: &[PathBuf]) -> Result<BundleOutput> {
+        Ok(BundleOutput {
+            code: self.bundle_css(files).await?,
+            source_map: None,
+        })
+    }
 }
 
 /// Tree shaking interface
@@ -1,3 +1,4 @@
+use crate::utils::{CodeMap, SourceMapConfig};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -6,7 +7,8 @@ use std::path::PathBuf;
 #[derive(Debug, Clone)]
 pub struct BundleOutput {
     pub code: String,
-    pub source_map: Option<String>,
+    /// Map of `code` back to the module sources
+    pub source_map: Option<CodeMap>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +25,9 @@ pub struct BuildConfig {
     #[serde(default)]
     #[allow(dead_code)] // Future feature
     pub enable_source_maps: bool,
+    /// How source maps are emitted when `enable_source_maps` is set
+    #[serde(default)]
+    pub source_map: SourceMapConfig,
     #[serde(default)]
     #[allow(dead_code)] // Smart bundling feature
     pub enable_code_splitting: bool,
@@ -41,6 +46,116 @@ pub struct BuildConfig {
     /// Example: {"main": "src/main.js", "admin": "src/admin.js"}
     #[serde(default)]
     pub entries: HashMap<String, PathBuf>,
+    /// Module format of the emitted JavaScript bundles
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Global variable that receives the entry exports (iife/umd)
+    #[serde(default)]
+    pub global_name: Option<String>,
+    /// Global variables iife/umd bundles read externals from: package -> global
+    /// Example: {"react": "React", "lodash": "_"}
+    #[serde(default)]
+    pub globals: HashMap<String, String>,
+    /// Fail the build on circular imports instead of warning
+    #[serde(default)]
+    pub fail_on_circular: bool,
+    /// File name template for JavaScript bundles and chunks, e.g. "[name].[hash].js"
+    #[serde(default = "default_js_file_names")]
+    pub js_file_names: String,
+    /// File name template for CSS and other assets, e.g. "[name].[hash].[ext]"
+    #[serde(default = "default_asset_file_names")]
+    pub asset_file_names: String,
+    /// ECMAScript version the output must run on, e.g. "es2017"; newer syntax is lowered
+    #[serde(default = "default_target")]
+    pub target: String,
+    /// How JSX is compiled
+    #[serde(default)]
+    pub jsx: JsxConfig,
+    /// Emit-related compiler options from tsconfig.json
+    #[serde(default)]
+    pub typescript: TypeScriptConfig,
+    /// Register every module with the module runtime so that the dev server
+    /// can replace modules while the page runs
+    #[serde(default)]
+    pub hmr: bool,
+}
+
+/// JSX transform options (`jsx` in soku.config.json)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JsxConfig {
+    /// Defaults to `classic` when a pragma is configured, `automatic` otherwise
+    pub runtime: Option<JsxRuntime>,
+    /// Package the automatic runtime imports `jsx-runtime` from (default: "react")
+    pub import_source: Option<String>,
+    /// Element factory of the classic runtime (default: "React.createElement")
+    pub pragma: Option<String>,
+    /// Fragment component of the classic runtime (default: "React.Fragment")
+    pub pragma_frag: Option<String>,
+    /// Use the development runtime (`jsx-dev-runtime`) with source locations
+    pub development: bool,
+}
+
+/// Which functions JSX compiles to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsxRuntime {
+    /// Calls the pragma, e.g. `React.createElement`, which must be in scope
+    Classic,
+    /// Imports `jsx`/`jsxs` from `<importSource>/jsx-runtime`
+    Automatic,
+}
+
+/// tsconfig.json compiler options that change the emitted JavaScript
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TypeScriptConfig {
+    /// Define class fields with `Object.defineProperty` semantics; when `false`
+    /// fields are assigned in the constructor and uninitialized ones dropped
+    pub use_define_for_class_fields: bool,
+    /// Keep imports unless they are written as `import type`
+    pub verbatim_module_syntax: bool,
+    /// Decorators follow the legacy TypeScript semantics
+    pub experimental_decorators: bool,
+    /// Emit `design:type` metadata for decorated members
+    pub emit_decorator_metadata: bool,
+}
+
+impl Default for TypeScriptConfig {
+    fn default() -> Self {
+        Self {
+            use_define_for_class_fields: true,
+            verbatim_module_syntax: false,
+            experimental_decorators: false,
+            emit_decorator_metadata: false,
+        }
+    }
+}
+
+/// Output module format for JavaScript bundles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// ES module with `export` statements
+    Esm,
+    /// CommonJS module assigning `module.exports`
+    Cjs,
+    /// Self-executing function for `<script>` tags
+    #[default]
+    Iife,
+    /// Works as AMD, CommonJS or a browser global
+    Umd,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Esm => "esm",
+            OutputFormat::Cjs => "cjs",
+            OutputFormat::Iife => "iife",
+            OutputFormat::Umd => "umd",
+        }
+    }
 }
 
 fn default_root() -> PathBuf {
@@ -63,6 +178,18 @@ fn default_mode() -> String {
     "production".to_string()
 }
 
+fn default_js_file_names() -> String {
+    "[name].js".to_string()
+}
+
+fn default_asset_file_names() -> String {
+    "[name].[ext]".to_string()
+}
+
+fn default_target() -> String {
+    "es2020".to_string()
+}
+
 impl Default for BuildConfig {
     fn default() -> Self {
         Self {
@@ -71,6 +198,7 @@ impl Default for BuildConfig {
             enable_tree_shaking: true,
             enable_minification: true,
             enable_source_maps: false,
+            source_map: SourceMapConfig::default(),
             enable_code_splitting: false,  // Disabled by default for now
             max_chunk_size: Some(250_000), // 250KB default
             mode: "production".to_string(),
@@ -78,6 +206,16 @@ impl Default for BuildConfig {
             external: Vec::new(),
             vendor_chunk: false,
             entries: HashMap::new(),
+            format: OutputFormat::Iife,
+            global_name: None,
+            globals: HashMap::new(),
+            fail_on_circular: false,
+            js_file_names: default_js_file_names(),
+            asset_file_names: default_asset_file_names(),
+            target: default_target(),
+            jsx: JsxConfig::default(),
+            typescript: TypeScriptConfig::default(),
+            hmr: false,
         }
     }
 }
@@ -91,6 +229,8 @@ pub struct ModuleInfo {
     pub dependencies: Vec<String>,
     #[allow(dead_code)] // Used for advanced export analysis
     pub exports: Vec<String>,
+    /// Import specifier -> resolved module path, used by the module linker
+    pub resolved_imports: HashMap<String, PathBuf>,
 }
 
 #[derive(Debug, Clone, PartialEq, Hash, Serialize, Deserialize)]
@@ -107,9 +247,10 @@ pub enum ModuleType {
 impl ModuleType {
     pub fn from_extension(ext: &str) -> Self {
         match ext.to_lowercase().as_str() {
-            "js" | "jsx" => ModuleType::JavaScript,
-            "ts" | "tsx" => ModuleType::TypeScript,
-            "css" => ModuleType::Css,
+            "js" | "jsx" | "mjs" | "cjs" => ModuleType::JavaScript,
+            "ts" | "tsx" | "mts" | "cts" => ModuleType::TypeScript,
+            // Sass is compiled by the stylesheet pipeline, like plain CSS
+            "css" | "scss" | "sass" => ModuleType::Css,
             "html" | "htm" => ModuleType::Html,
             "json" => ModuleType::Json,
             "wasm" => ModuleType::Wasm,
@@ -118,6 +259,38 @@ impl ModuleType {
     }
 }
 
+/// How a module refers to one of its dependencies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DependencyKind {
+    /// `import x from './a'`
+    Static,
+    /// `import('./a')`
+    Dynamic,
+    /// `export { x } from './a'` and `export * from './a'`
+    ReExport,
+    /// `require('./a')` and TypeScript `import x = require('./a')`
+    Require,
+    /// `import './a'`
+    SideEffect,
+}
+
+/// Byte range in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct SourceSpan {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A module specifier found in JavaScript/TypeScript source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyRecord {
+    pub specifier: String,
+    pub kind: DependencyKind,
+    /// Span of the import/export statement, `import()` or `require()` call
+    pub span: SourceSpan,
+}
+
 #[derive(Debug, Default)]
 pub struct BuildResult {
     #[allow(dead_code)] // Used for detailed reporting
@@ -135,15 +308,79 @@ pub struct BuildResult {
     pub warnings: Vec<String>,
     #[allow(dead_code)] // Used for bundle analysis
     pub modules: Vec<ModuleInfo>,
+    /// Whether each bundled module was scope-hoisted or wrapped
+    #[allow(dead_code)] // Used for detailed reporting
+    pub module_linking: Vec<ModuleLinking>,
+}
+
+/// How the module linker placed a module into the bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkStrategy {
+    /// Concatenated into the bundle scope
+    Hoisted,
+    /// Wrapped in a function and loaded through `__soku_require`
+    Wrapped(WrapReason),
+}
+
+/// Why a module could not be scope-hoisted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WrapReason {
+    /// Uses `require`/`module`/`exports` without any ES module syntax
+    CommonJs,
+    /// Calls `eval`, which may reference any top-level name
+    DirectEval,
+    /// Part of an import cycle
+    Cycle,
+    /// Loaded with `require()`, which needs a live exports object
+    Required,
+    /// Loaded with `import()`, which evaluates it when the import runs
+    DynamicImport,
+    /// Part of a build split into chunks, which load each other's modules
+    /// from a registry they share
+    Chunked,
+    /// Linked for hot module replacement, which swaps module definitions
+    Hmr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleLinking {
+    pub path: PathBuf,
+    pub strategy: LinkStrategy,
+    /// Id of the module in the module registry
+    pub id: String,
+    /// Registry ids of the bundled modules it imports
+    pub dependencies: Vec<String>,
+    /// Registry ids of the modules whose updates it accepts through
+    /// `import.meta.hot.accept`, including its own when it accepts itself
+    pub hot_accepts: Vec<String>,
+    /// `__soku_modules` registration of a module linked for HMR
+    pub definition: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct OutputFile {
     pub path: PathBuf,
-    pub content: String,
+    /// Bytes written to `path`; text for bundles, maps and pages
+    pub content: Vec<u8>,
     pub size: usize,
 }
 
+impl OutputFile {
+    pub fn new(path: PathBuf, content: impl Into<Vec<u8>>) -> Self {
+        let content = content.into();
+        Self {
+            path,
+            size: content.len(),
+            content,
+        }
+    }
+
+    /// Content as text, when it is UTF-8
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.content).ok()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TreeShakingStats {
     pub total_modules: usize,
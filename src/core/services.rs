@@ -1,15 +1,18 @@
 use crate::core::{interfaces::*, models::*};
 use crate::infrastructure::{
-    CodeSplitConfig, CodeSplitter, MinificationService, NodeModuleResolver,
+    html_output_name, html_stylesheets, rewrite_html, CodeSplitConfig, CodeSplitter,
+    MinificationService, NodeModuleResolver, ScssProcessor,
 };
 use crate::utils::{
-    performance::parallel, AdvancedSourceMapGenerator, CompletionStats, CustomTransformer,
-    IncrementalBuildState, Logger, OutputFileInfo, PluginContext, PluginEvent, PluginManager,
-    Result, SokuCache, SokuUI, SourceMapUtils, Timer, TimingBreakdown, TransformerChain,
+    html_pages, is_virtual_module, output_file_name, performance::parallel, relative_source_path,
+    BuildManifest, CodeMap, CompletionStats, CustomTransformer, DependencyGraph, EmittedFiles,
+    IncrementalBuildState, LoadResult, Logger, ManifestChunk, ManifestEntry, OutputFileInfo,
+    PluginContext, PluginEvent, PluginManager, Result, SokuCache, SokuError, SokuUI, Timer,
+    TimingBreakdown, TransformerChain, MANIFEST_FILE_NAME, VIRTUAL_MODULE_PREFIX,
 };
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -30,6 +33,12 @@ pub struct SokuBuildService {
     cache_dir: PathBuf,
     plugin_manager: PluginManager,
     transformer_chain: TransformerChain,
+    /// Warnings collected during the current build
+    warnings: Vec<String>,
+    /// Files plugins emitted during the current build
+    emitted_files: EmittedFiles,
+    /// Linking report and linker warnings of JS bundles, by JS cache key
+    link_reports: HashMap<String, (Vec<ModuleLinking>, Vec<String>)>,
 }
 
 impl SokuBuildService {
@@ -63,6 +72,9 @@ impl SokuBuildService {
             cache_dir,
             plugin_manager: PluginManager::new(),
             transformer_chain: TransformerChain::new(),
+            warnings: Vec::new(),
+            emitted_files: EmittedFiles::default(),
+            link_reports: HashMap::new(),
         }
     }
 
@@ -94,7 +106,18 @@ impl SokuBuildService {
     }
 
     async fn scan_and_analyze_with_ui(&self, config: &BuildConfig) -> Result<ProjectStructure> {
-        let structure = self.fs_service.scan_directory(&config.root).await?;
+        let mut structure = self.fs_service.scan_directory(&config.root).await?;
+
+        // Directory listing order is platform dependent; sort for reproducible bundles
+        for files in [
+            &mut structure.js_modules,
+            &mut structure.css_files,
+            &mut structure.html_files,
+            &mut structure.wasm_files,
+            &mut structure.other_files,
+        ] {
+            files.sort();
+        }
 
         // Show file discovery
         self.ui.show_file_discovery(
@@ -147,7 +170,7 @@ impl SokuBuildService {
         };
 
         // Process modules
-        let bundled_js = self.js_processor.bundle_modules(modules).await?;
+        let bundled_js = self.js_processor.bundle_modules(modules, config).await?;
 
         Ok((bundled_js, tree_shaking_stats))
     }
@@ -159,67 +182,383 @@ impl SokuBuildService {
         Ok(bundled_css)
     }
 
-    async fn write_output_files(
+    /// The JavaScript and CSS bundles of the main build, with their source maps
+    #[allow(clippy::too_many_arguments)]
+    fn bundle_output_files(
         &self,
         config: &BuildConfig,
-        js_content: &str,
+        original_js_content: &str,
         css_content: &str,
-        source_map: Option<String>,
+        source_map: Option<CodeMap>,
+        css_map: Option<CodeMap>,
+        entry_points: &[PathBuf],
+        js_modules: &[ModuleInfo],
+        css_files: &[PathBuf],
+        manifest: &mut BuildManifest,
     ) -> Result<Vec<OutputFile>> {
-        let _timer = Timer::start("Writing output files");
-
         let mut output_files = Vec::new();
 
-        // Write JavaScript bundle (with source map reference if enabled)
-        let js_path = config.outdir.join("bundle.js");
-        let mut js_with_source_map = if source_map.is_some() {
-            format!("{}\n//# sourceMappingURL=bundle.js.map", js_content)
-        } else {
-            js_content.to_string()
-        };
-
         // 🔧 CUSTOM TRANSFORMERS: Apply code transformations
+        let mut js_content = original_js_content.to_string();
         if !self.transformer_chain.is_empty() {
-            js_with_source_map = self.transformer_chain.transform(
-                js_with_source_map,
-                Some(js_path.to_str().unwrap_or("bundle.js")),
-            )?;
+            let js_path = config.outdir.join("bundle.js");
+            js_content = self
+                .transformer_chain
+                .transform(js_content, Some(js_path.to_str().unwrap_or("bundle.js")))?;
         }
 
-        self.fs_service
-            .write_file(&js_path, &js_with_source_map)
-            .await?;
-        output_files.push(OutputFile {
-            path: js_path,
-            content: js_with_source_map.clone(),
-            size: js_with_source_map.len(),
+        // JavaScript bundle with its source map; the map is of the code
+        // before custom transformers
+        let js_name =
+            output_file_name(&config.js_file_names, "bundle", "js", js_content.as_bytes());
+        let source_map = source_map
+            .map(|map| Self::generate_source_map(config, &js_name, original_js_content, map));
+        output_files.extend(Self::mapped_output(
+            config, &js_name, js_content, source_map,
+        )?);
+        manifest.add_chunk(ManifestChunk {
+            name: "bundle",
+            file: &js_name,
+            entries: entry_points,
+            modules: js_modules,
+            imports: Vec::new(),
         });
 
-        // Write source map file if provided
-        if let Some(source_map_content) = source_map {
-            let source_map_path = config.outdir.join("bundle.js.map");
-            self.fs_service
-                .write_file(&source_map_path, &source_map_content)
+        // CSS bundle, when there are styles
+        if !css_files.is_empty() {
+            let (css_name, css_outputs) =
+                Self::css_output(config, css_content.to_string(), css_map)?;
+            output_files.extend(css_outputs);
+            manifest.add_css(&css_name, css_files);
+        }
+
+        Ok(output_files)
+    }
+
+    /// Write the output files with the manifest
+    ///
+    /// Files emitted by plugins join the outputs and the manifest, and the
+    /// `generate_bundle` hooks can change the outputs before anything is written.
+    async fn write_output_files(
+        &self,
+        config: &BuildConfig,
+        modules: &[ModuleInfo],
+        mut output_files: Vec<OutputFile>,
+        manifest: &mut BuildManifest,
+    ) -> Result<Vec<OutputFile>> {
+        let _timer = Timer::start("Writing output files");
+
+        self.add_emitted_files(config, &mut output_files, manifest)?;
+        if !self.plugin_manager.plugins().is_empty() {
+            let context = self.plugin_context(config, modules, PluginEvent::GenerateBundle);
+            self.plugin_manager
+                .trigger_generate_bundle(&context, &mut output_files)
                 .await?;
-            output_files.push(OutputFile {
-                path: source_map_path,
-                content: source_map_content.clone(),
-                size: source_map_content.len(),
-            });
+            self.add_emitted_files(config, &mut output_files, manifest)?;
+        }
+        output_files.push(Self::manifest_output(config, manifest)?);
+
+        let previous = BuildManifest::read_entries(&config.outdir).await;
+        for output in &output_files {
+            match output.text() {
+                Some(text) => self.fs_service.write_file(&output.path, text).await?,
+                None => {
+                    if let Some(parent) = output.path.parent() {
+                        self.fs_service.create_directory(parent).await?;
+                    }
+                    tokio::fs::write(&output.path, &output.content)
+                        .await
+                        .map_err(SokuError::Io)?;
+                }
+            }
+        }
+        if let Some(previous) = previous {
+            Self::remove_stale_outputs(config, &previous, &output_files).await;
         }
+        Ok(output_files)
+    }
+
+    /// Show the files a build wrote, those plugins emitted included
+    fn show_completion(
+        &self,
+        config: &BuildConfig,
+        modules: &[ModuleInfo],
+        output_files: &[OutputFile],
+    ) {
+        let node_modules_count = modules
+            .iter()
+            .filter(|m| m.path.to_string_lossy().contains("node_modules"))
+            .count();
 
-        // Write CSS bundle
-        let css_path = config.outdir.join("bundle.css");
-        self.fs_service.write_file(&css_path, css_content).await?;
-        output_files.push(OutputFile {
-            path: css_path,
-            content: css_content.to_string(),
-            size: css_content.len(),
+        self.ui.show_epic_completion(CompletionStats {
+            output_files: output_files
+                .iter()
+                .map(|output| OutputFileInfo {
+                    name: html_output_name(&output.path, &config.outdir),
+                    size: output.size,
+                })
+                .collect(),
+            node_modules_optimized: (node_modules_count > 0).then_some(node_modules_count),
+            timing_breakdown: Some(TimingBreakdown {
+                file_scan_ms: 0,
+                js_processing_ms: 0,
+                css_processing_ms: 0,
+                tree_shaking_ms: 0,
+                output_write_ms: 0,
+                minification_ms: 0,
+            }),
         });
+    }
+
+    /// Delete the outputs of the previous build, as listed in its manifest,
+    /// that this build did not write again, like bundles under an old `[hash]`
+    async fn remove_stale_outputs(
+        config: &BuildConfig,
+        previous: &BTreeMap<String, ManifestEntry>,
+        output_files: &[OutputFile],
+    ) {
+        let stale: BTreeSet<&str> = previous
+            .values()
+            .flat_map(|entry| {
+                std::iter::once(&entry.file)
+                    .chain(&entry.imports)
+                    .chain(&entry.css)
+                    .chain(&entry.assets)
+            })
+            .map(String::as_str)
+            // Never follow a manifest outside of the output directory
+            .filter(|file| {
+                Path::new(file)
+                    .components()
+                    .all(|component| matches!(component, std::path::Component::Normal(_)))
+            })
+            .collect();
+
+        for file in stale {
+            for path in [
+                config.outdir.join(file),
+                config.outdir.join(format!("{}.map", file)),
+            ] {
+                if !path.is_file() || output_files.iter().any(|output| output.path == path) {
+                    continue;
+                }
+                match tokio::fs::remove_file(&path).await {
+                    Ok(()) => Logger::debug(&format!("Removed stale output {}", path.display())),
+                    Err(e) => Logger::warn(&format!(
+                        "Cannot remove stale output {}: {}",
+                        path.display(),
+                        e
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Move the files plugins emitted into the outputs and the manifest
+    fn add_emitted_files(
+        &self,
+        config: &BuildConfig,
+        output_files: &mut Vec<OutputFile>,
+        manifest: &mut BuildManifest,
+    ) -> Result<()> {
+        let emitted =
+            std::mem::take(&mut *self.emitted_files.lock().unwrap_or_else(|e| e.into_inner()));
+        for file in emitted {
+            let name = Path::new(&file.name);
+            if name.is_absolute()
+                || name
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+            {
+                return Err(SokuError::build(format!(
+                    "Emitted file '{}' must be a path inside the output directory",
+                    file.name
+                )));
+            }
+            let path = config.outdir.join(name);
+            output_files.retain(|output| output.path != path);
+            output_files.push(OutputFile::new(path, file.content));
+            manifest.add_emitted(&file.name);
+        }
+        Ok(())
+    }
+
+    /// An output file; `name` may contain subdirectories
+    fn output_file(config: &BuildConfig, name: &str, content: impl Into<Vec<u8>>) -> OutputFile {
+        OutputFile::new(config.outdir.join(name), content)
+    }
+
+    /// `manifest.json`, written next to the bundles
+    fn manifest_output(config: &BuildConfig, manifest: &BuildManifest) -> Result<OutputFile> {
+        Ok(Self::output_file(
+            config,
+            MANIFEST_FILE_NAME,
+            manifest.to_json()?,
+        ))
+    }
+
+    /// The HTML pages with their tags pointing at the built files
+    async fn html_page_outputs(
+        &self,
+        config: &BuildConfig,
+        html_files: &[PathBuf],
+        manifest: &BuildManifest,
+    ) -> Result<Vec<OutputFile>> {
+        let mut output_files = Vec::new();
+        for page in html_pages(config, html_files) {
+            let html = self.fs_service.read_file(&page).await?;
+            let html = rewrite_html(&html, &page, &config.root, manifest);
+            let name = html_output_name(&page, &config.root);
+            output_files.push(Self::output_file(config, &name, html));
+        }
+        Ok(output_files)
+    }
+
+    /// Last path component of an output name such as `assets/bundle.js`
+    fn file_name_of(name: &str) -> &str {
+        name.rsplit('/').next().unwrap_or(name)
+    }
+
+    /// Bundle JavaScript modules, with a source map when they are enabled
+    async fn bundle_js(
+        &self,
+        modules: &[ModuleInfo],
+        config: &BuildConfig,
+        tree_shaking_stats: Option<&TreeShakingStats>,
+    ) -> Result<(String, Option<CodeMap>)> {
+        if config.enable_source_maps {
+            let output = self
+                .js_processor
+                .bundle_modules_with_source_maps(modules, config)
+                .await?;
+            return Ok((output.code, output.source_map));
+        }
+
+        let code = if tree_shaking_stats.is_some() {
+            self.js_processor
+                .bundle_modules_with_tree_shaking(modules, tree_shaking_stats, config)
+                .await?
+        } else {
+            self.js_processor.bundle_modules(modules, config).await?
+        };
+        Ok((code, None))
+    }
+
+    /// Minify a bundle; its source map is composed with the minifier's
+    async fn minify_js(
+        config: &BuildConfig,
+        code: String,
+        source_map: Option<CodeMap>,
+        file_name: &str,
+    ) -> Result<(String, Option<CodeMap>)> {
+        let minifier = MinificationService::for_bundle(
+            crate::utils::parse_target(&config.target)?,
+            config.format,
+        );
+        match source_map {
+            Some(map) => {
+                let (code, map) = minifier
+                    .minify_bundle_with_map(code, map, file_name)
+                    .await?;
+                Ok((code, Some(map)))
+            }
+            None => Ok((minifier.minify_bundle(code, file_name).await?, None)),
+        }
+    }
+
+    /// Replace environment variables, then drop the code they made dead
+    fn optimize_js(
+        config: &BuildConfig,
+        mut code: String,
+        mut source_map: Option<CodeMap>,
+    ) -> Result<(String, Option<CodeMap>)> {
+        let env_manager =
+            crate::utils::EnvVarsManager::load_from_files(&config.root, &config.mode)?;
+        let env_count = env_manager.get_all().len();
+        if env_count > 0 {
+            Logger::debug(&format!("🌍 Replacing {} environment variables", env_count));
+            let rewritten = env_manager.rewrite(&code);
+            if let Some(map) = &mut source_map {
+                *map = rewritten.rewrite_map(map);
+            }
+            code = rewritten.into_string();
+        }
+
+        let rewritten = crate::utils::DeadCodeEliminator::new().rewrite(&code);
+        if let Some(map) = &mut source_map {
+            *map = rewritten.rewrite_map(map);
+        }
+        Ok((rewritten.into_string(), source_map))
+    }
+
+    /// Source map of the output file `name` with the given code
+    ///
+    /// Sources are referenced relative to the map file.
+    fn generate_source_map(
+        config: &BuildConfig,
+        name: &str,
+        code: &str,
+        mut map: CodeMap,
+    ) -> crate::utils::SourceMap {
+        let output_path = config.outdir.join(name);
+        let map_dir = output_path.parent().unwrap_or(&config.outdir);
+        map.rename_sources(|source| relative_source_path(Path::new(source), map_dir));
+        map.generate(code, Some(Self::file_name_of(name).to_string()))
+    }
+
+    /// An output file and its source map as `config.source_map` says
+    fn mapped_output(
+        config: &BuildConfig,
+        name: &str,
+        mut code: String,
+        source_map: Option<crate::utils::SourceMap>,
+    ) -> Result<Vec<OutputFile>> {
+        let map_name = format!("{}.map", name);
+        let map_json = match source_map {
+            Some(source_map) => {
+                config
+                    .source_map
+                    .attach(&mut code, source_map, Self::file_name_of(&map_name))?
+            }
+            None => None,
+        };
 
+        let mut output_files = vec![Self::output_file(config, name, code)];
+        if let Some(map_json) = map_json {
+            output_files.push(Self::output_file(config, &map_name, map_json));
+        }
         Ok(output_files)
     }
 
+    /// Bundle stylesheets, with the map of the bundle when source maps are on
+    async fn bundle_css(
+        &self,
+        config: &BuildConfig,
+        css_files: &[PathBuf],
+    ) -> Result<(String, Option<CodeMap>)> {
+        if config.enable_source_maps {
+            let bundle = self
+                .css_processor
+                .bundle_css_with_source_map(css_files)
+                .await?;
+            Ok((bundle.code, bundle.source_map))
+        } else {
+            Ok((self.css_processor.bundle_css(css_files).await?, None))
+        }
+    }
+
+    /// The CSS bundle with its source map; returns the bundle's name
+    fn css_output(
+        config: &BuildConfig,
+        code: String,
+        map: Option<CodeMap>,
+    ) -> Result<(String, Vec<OutputFile>)> {
+        let name = output_file_name(&config.asset_file_names, "bundle", "css", code.as_bytes());
+        let source_map = map.map(|map| Self::generate_source_map(config, &name, &code, map));
+        let output_files = Self::mapped_output(config, &name, code, source_map)?;
+        Ok((name, output_files))
+    }
+
     async fn resolve_all_dependencies(
         &mut self,
         entry_files: &[PathBuf],
@@ -227,6 +566,8 @@ impl SokuBuildService {
         config: &BuildConfig,
     ) -> Result<Vec<ModuleInfo>> {
         let mut resolved_modules = HashMap::new();
+        let mut edges: HashMap<String, Vec<(String, DependencyKind)>> = HashMap::new();
+        let mut graph = DependencyGraph::new();
         let mut to_process = Vec::new();
 
         // 🔗 Create path alias resolver
@@ -256,16 +597,30 @@ impl SokuBuildService {
                 continue;
             }
 
-            // Read and process the file
+            // Load the module through plugins, then read the file
             Logger::debug(&format!("Processing module: {}", current_path.display()));
-            if let Ok(mut content) = self.fs_service.read_file(&current_path).await {
-                let mut module_type = ModuleType::from_extension(
-                    current_path
-                        .extension()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or(""),
-                );
-
+            let loaded = match self.load_module(&current_path).await? {
+                Some(loaded) => Some(loaded),
+                None => self
+                    .fs_service
+                    .read_file(&current_path)
+                    .await
+                    .ok()
+                    .map(|code| LoadResult {
+                        code,
+                        module_type: ModuleType::from_extension(
+                            current_path
+                                .extension()
+                                .and_then(|s| s.to_str())
+                                .unwrap_or(""),
+                        ),
+                    }),
+            };
+            if let Some(LoadResult {
+                code: mut content,
+                mut module_type,
+            }) = loaded
+            {
                 // 📦 Process JSON assets - convert to ES module
                 if module_type == ModuleType::Json {
                     let asset_processor = crate::infrastructure::AssetProcessor::new();
@@ -274,15 +629,49 @@ impl SokuBuildService {
                     module_type = ModuleType::JavaScript;
                 }
 
+                // 🔌 PLUGIN: Transform scripts before their imports are collected,
+                // so that imports added by plugins are bundled too
+                if matches!(module_type, ModuleType::JavaScript | ModuleType::TypeScript)
+                    && !self.plugin_manager.plugins().is_empty()
+                {
+                    let module = ModuleInfo {
+                        path: normalized_path.clone(),
+                        content: content.clone(),
+                        module_type: module_type.clone(),
+                        dependencies: Vec::new(),
+                        exports: Vec::new(),
+                        resolved_imports: HashMap::new(),
+                    };
+                    content = self.plugin_manager.transform_code(&module, content).await?;
+                }
+
                 // Extract dependencies in parallel when possible
-                let dependencies = match module_type {
+                let dependencies: Vec<(String, DependencyKind)> = match module_type {
                     ModuleType::JavaScript | ModuleType::TypeScript => {
                         // Use blocking task for CPU-intensive dependency extraction
                         let content_clone = content.clone();
+                        let path_clone = current_path.clone();
+                        let jsx = config.jsx.clone();
                         tokio::task::spawn_blocking(move || {
-                            crate::infrastructure::processors::common::extract_dependencies(
-                                &content_clone,
-                            )
+                            let mut dependencies: Vec<(String, DependencyKind)> =
+                                crate::infrastructure::processors::common::extract_dependencies(
+                                    &content_clone,
+                                    &path_clone,
+                                )
+                                .into_iter()
+                                .map(|record| (record.specifier, record.kind))
+                                .collect();
+                            // The automatic JSX runtime is imported by the compiled code
+                            if let Some(runtime) =
+                                crate::infrastructure::processors::js_transformer::jsx_runtime_import(
+                                    &content_clone,
+                                    &path_clone,
+                                    &jsx,
+                                )
+                            {
+                                dependencies.push((runtime, DependencyKind::Static));
+                            }
+                            dependencies
                         })
                         .await
                         .map_err(|e| {
@@ -292,22 +681,38 @@ impl SokuBuildService {
                             ))
                         })?
                     }
+                    // Sass resolves its own `@use` and `@import` when it compiles
+                    ModuleType::Css if ScssProcessor::is_scss_file(&current_path) => Vec::new(),
                     ModuleType::Css => {
                         // Extract CSS imports (@import statements)
                         self.extract_css_dependencies(&content)
+                            .into_iter()
+                            .map(|dep| (dep, DependencyKind::Static))
+                            .collect()
                     }
                     _ => Vec::new(),
                 };
 
+                // Virtual modules have no directory, so their imports resolve
+                // from the project root
+                let importer = if is_virtual_module(&current_path) {
+                    root_dir.join("[virtual]")
+                } else {
+                    current_path.clone()
+                };
+
                 // Resolve dependency paths in parallel (NOW ENABLED with thread-safe resolver)
                 let resolve_tasks: Vec<_> = dependencies
                     .iter()
-                    .map(|dep| {
+                    .map(|(dep, kind)| {
                         let dep_clone = dep.clone();
+                        let kind = *kind;
                         let current_path_clone = current_path.clone();
+                        let importer = &importer;
                         let root_dir_clone = root_dir.to_path_buf();
                         let resolver_ref = &self.node_resolver;
                         let alias_resolver_ref = &alias_resolver;
+                        let plugin_manager = &self.plugin_manager;
                         let external_list = &config.external;
                         async move {
                             Logger::debug(&format!(
@@ -322,31 +727,62 @@ impl SokuBuildService {
                                     "📦 Skipping external dependency: {}",
                                     dep_clone
                                 ));
-                                return (dep_clone, None); // Skip external dependencies
+                                return Ok((dep_clone, kind, None)); // Skip external dependencies
                             }
 
-                            // 🔗 Try path alias resolution first
+                            // 🔌 Plugins resolve first; the first one to return a path wins
+                            if let Some(resolved) = plugin_manager
+                                .resolve_import(&dep_clone, &current_path_clone.to_string_lossy())
+                                .await?
+                            {
+                                if is_virtual_module(Path::new(&resolved)) {
+                                    return Ok((dep_clone, kind, Some(PathBuf::from(resolved))));
+                                }
+                                let resolved_path = Self::resolve_plugin_path(
+                                    resolver_ref,
+                                    &resolved,
+                                    importer,
+                                    &root_dir_clone,
+                                )
+                                .await;
+                                if resolved_path.is_none() {
+                                    Logger::warn(&format!(
+                                        "A plugin resolved '{}' from {} to '{}', which does not exist",
+                                        dep_clone,
+                                        current_path_clone.display(),
+                                        resolved
+                                    ));
+                                }
+                                return Ok((dep_clone, kind, resolved_path));
+                            }
+
+                            // 🔗 Try path alias resolution next
                             if let Some(aliased_path) = alias_resolver_ref.resolve(&dep_clone) {
                                 // Verify the file exists
                                 if aliased_path.exists() {
-                                    return (dep_clone, Some(aliased_path));
+                                    return Ok((dep_clone, kind, Some(aliased_path)));
                                 }
                             }
 
                             // Fall back to node resolver
                             let resolved_path = resolver_ref
-                                .resolve(&dep_clone, &current_path_clone, &root_dir_clone)
+                                .resolve(&dep_clone, importer, &root_dir_clone)
                                 .await;
-                            (dep_clone, resolved_path)
+                            Ok((dep_clone, kind, resolved_path))
                         }
                     })
                     .collect();
 
-                let parallel_results = futures::future::join_all(resolve_tasks).await;
+                let parallel_results = futures::future::join_all(resolve_tasks)
+                    .await
+                    .into_iter()
+                    .collect::<Result<Vec<_>>>()?;
 
                 // Collect resolved dependencies
                 let mut resolved_deps = Vec::new();
-                for (dep, resolved_path_opt) in parallel_results {
+                let mut resolved_imports = HashMap::new();
+                let mut module_edges = Vec::new();
+                for (dep, kind, resolved_path_opt) in parallel_results {
                     if let Some(resolved_path) = resolved_path_opt {
                         Logger::debug(&format!(
                             "Resolved '{}' to: {}",
@@ -356,10 +792,21 @@ impl SokuBuildService {
 
                         // Track dependency relationship for incremental builds
                         // normalized_path depends on resolved_path
-                        self.incremental_state
-                            .add_dependency(normalized_path.clone(), resolved_path.clone());
-
-                        resolved_deps.push(dep);
+                        self.incremental_state.add_dependency(
+                            normalized_path.clone(),
+                            resolved_path.clone(),
+                            kind,
+                        );
+
+                        let resolved_key = resolved_path
+                            .canonicalize()
+                            .unwrap_or_else(|_| resolved_path.clone());
+                        graph.add_dependency(normalized_path.clone(), resolved_key.clone(), kind);
+                        module_edges.push((resolved_key.to_string_lossy().to_string(), kind));
+                        resolved_imports.insert(dep.clone(), resolved_key);
+                        if !resolved_deps.contains(&dep) {
+                            resolved_deps.push(dep);
+                        }
                         to_process.push(resolved_path);
                     } else {
                         Logger::debug(&format!("Failed to resolve import: {}", dep));
@@ -372,17 +819,238 @@ impl SokuBuildService {
                     module_type,
                     dependencies: resolved_deps,
                     exports: Vec::new(), // TODO: Extract exports
+                    resolved_imports,
                 };
 
+                edges.insert(path_key.clone(), module_edges);
                 resolved_modules.insert(path_key, module_info);
             }
         }
 
+        let entry_keys: Vec<String> = entry_files
+            .iter()
+            .map(|p| {
+                p.canonicalize()
+                    .unwrap_or_else(|_| p.clone())
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        let modules = Self::order_modules(&entry_keys, resolved_modules, &edges);
+
+        self.report_cycles(&graph, root_dir, config)?;
+
         // Process the resolved modules in parallel for any additional processing
-        let modules: Vec<ModuleInfo> = resolved_modules.into_values().collect();
         self.process_modules_parallel(&modules).await
     }
 
+    /// Run the plugin hooks of a build step that only gets the context
+    async fn run_plugin_hooks(
+        &self,
+        config: &BuildConfig,
+        modules: &[ModuleInfo],
+        event: PluginEvent,
+    ) -> Result<()> {
+        if self.plugin_manager.plugins().is_empty() {
+            return Ok(());
+        }
+        let context = self.plugin_context(config, modules, event.clone());
+        match event {
+            PluginEvent::BeforeBundle => self.plugin_manager.trigger_before_bundle(&context).await,
+            PluginEvent::BeforeOutput => self.plugin_manager.trigger_before_output(&context).await,
+            PluginEvent::AfterOutput => self.plugin_manager.trigger_after_output(&context).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Hand a finished bundle, minified and optimized, to the plugins
+    async fn run_after_bundle(
+        &self,
+        config: &BuildConfig,
+        modules: &[ModuleInfo],
+        code: &str,
+    ) -> Result<()> {
+        if self.plugin_manager.plugins().is_empty() {
+            return Ok(());
+        }
+        let context = self.plugin_context(config, modules, PluginEvent::AfterBundle);
+        self.plugin_manager
+            .trigger_after_bundle(&context, code)
+            .await
+    }
+
+    /// Drain the linking report of the last bundle calls, keeping their
+    /// linker warnings as build warnings
+    fn take_link_report(&mut self) -> Vec<ModuleLinking> {
+        self.warnings.extend(self.js_processor.take_link_warnings());
+        self.js_processor.take_module_linking()
+    }
+
+    /// Finish a build with the `after_build` plugin hooks
+    async fn run_after_build(
+        &self,
+        config: &BuildConfig,
+        result: BuildResult,
+    ) -> Result<BuildResult> {
+        let plugin_context = self.plugin_context(config, &result.modules, PluginEvent::AfterBuild);
+        self.plugin_manager
+            .trigger_after_build(&plugin_context, &result)
+            .await?;
+        for file in plugin_context.take_emitted_files() {
+            Logger::warn(&format!(
+                "🔌 '{}' was emitted after the output was written and is not part of the build",
+                file.name
+            ));
+        }
+        Ok(result)
+    }
+
+    /// Context of a plugin hook, collecting emitted files for this build
+    fn plugin_context(
+        &self,
+        config: &BuildConfig,
+        modules: &[ModuleInfo],
+        event: PluginEvent,
+    ) -> PluginContext {
+        PluginContext::new(config.clone(), modules.to_vec(), event)
+            .with_emitted_files(self.emitted_files.clone())
+    }
+
+    /// Code of a module from the plugins' `load` hooks
+    ///
+    /// Virtual modules have no file to fall back to, so a plugin has to load them.
+    async fn load_module(&self, path: &Path) -> Result<Option<LoadResult>> {
+        let is_virtual = is_virtual_module(path);
+        if self.plugin_manager.plugins().is_empty() && !is_virtual {
+            return Ok(None);
+        }
+        let id = path.to_string_lossy();
+        let loaded = self.plugin_manager.load(&id).await?;
+        if loaded.is_none() && is_virtual {
+            return Err(SokuError::build(format!(
+                "No plugin loaded the virtual module '{}'",
+                id.replace(VIRTUAL_MODULE_PREFIX, "\\0")
+            )));
+        }
+        Ok(loaded)
+    }
+
+    /// Find the file a plugin resolved an import to
+    ///
+    /// Absolute paths are used as they are and relative ones are relative to
+    /// the project root, like `alias` targets; anything else names a package,
+    /// looked up from the importing module. Extensions and index files are
+    /// tried as for any import.
+    async fn resolve_plugin_path(
+        resolver: &NodeModuleResolver,
+        resolved: &str,
+        from_file: &Path,
+        root_dir: &Path,
+    ) -> Option<PathBuf> {
+        let path = Path::new(resolved);
+        if path.is_absolute() {
+            resolver.resolve_file_or_directory(path).await
+        } else if resolved.starts_with("./") || resolved.starts_with("../") {
+            resolver
+                .resolve_file_or_directory(&root_dir.join(resolved))
+                .await
+        } else {
+            resolver.resolve(resolved, from_file, root_dir).await
+        }
+    }
+
+    /// Warn about import cycles, or fail the build when `fail_on_circular` is set
+    fn report_cycles(
+        &mut self,
+        graph: &DependencyGraph,
+        root_dir: &Path,
+        config: &BuildConfig,
+    ) -> Result<()> {
+        let cycles = graph.find_cycles();
+        if cycles.is_empty() {
+            return Ok(());
+        }
+
+        let root = root_dir
+            .canonicalize()
+            .unwrap_or_else(|_| root_dir.to_path_buf());
+        let chains: Vec<String> = cycles
+            .iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|path| {
+                        path.strip_prefix(&root)
+                            .unwrap_or(path)
+                            .display()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            })
+            .collect();
+
+        if config.fail_on_circular {
+            return Err(crate::utils::SokuError::build(format!(
+                "Circular dependencies found:\n  {}",
+                chains.join("\n  ")
+            )));
+        }
+
+        for chain in chains {
+            let warning = format!("Circular dependency: {}", chain);
+            Logger::warn(&warning);
+            self.warnings.push(warning);
+        }
+        Ok(())
+    }
+
+    /// Order modules by a depth-first post-order walk from the entries
+    ///
+    /// This is ES module evaluation order: the dependencies of a module, in
+    /// source order, come before the module itself. Modules that are only
+    /// loaded with `import()` come after everything that loads eagerly.
+    fn order_modules(
+        entries: &[String],
+        mut modules: HashMap<String, ModuleInfo>,
+        edges: &HashMap<String, Vec<(String, DependencyKind)>>,
+    ) -> Vec<ModuleInfo> {
+        let mut ordered = Vec::with_capacity(modules.len());
+        let mut visited = std::collections::HashSet::new();
+        let mut deferred: std::collections::VecDeque<&str> =
+            entries.iter().map(String::as_str).collect();
+
+        while let Some(root) = deferred.pop_front() {
+            if !visited.insert(root) {
+                continue;
+            }
+            // Iterative DFS: (module, index of the next dependency to visit)
+            let mut stack = vec![(root, 0)];
+            while let Some((module, next)) = stack.last_mut() {
+                let dependencies = edges.get(*module).map(Vec::as_slice).unwrap_or_default();
+                if let Some((dependency, kind)) = dependencies.get(*next) {
+                    *next += 1;
+                    if *kind == DependencyKind::Dynamic {
+                        deferred.push_back(dependency);
+                    } else if visited.insert(dependency) {
+                        stack.push((dependency, 0));
+                    }
+                } else {
+                    if let Some(info) = modules.remove(*module) {
+                        ordered.push(info);
+                    }
+                    stack.pop();
+                }
+            }
+        }
+
+        // Anything left was not reachable from an entry; keep it in a stable order
+        let mut rest: Vec<ModuleInfo> = modules.into_values().collect();
+        rest.sort_by(|a, b| a.path.cmp(&b.path));
+        ordered.extend(rest);
+        ordered
+    }
+
     /// Process modules in parallel for enhanced performance
     /// Uses rayon for CPU-bound operations
     async fn process_modules_parallel(&self, modules: &[ModuleInfo]) -> Result<Vec<ModuleInfo>> {
@@ -416,6 +1084,7 @@ impl SokuBuildService {
                         module_type: module.module_type.clone(),
                         dependencies: module.dependencies.clone(),
                         exports: module.exports.clone(),
+                        resolved_imports: module.resolved_imports.clone(),
                     }
                 })
                 .collect()
@@ -497,6 +1166,12 @@ impl SokuBuildService {
         config.enable_minification.hash(&mut hasher);
         config.enable_tree_shaking.hash(&mut hasher);
         config.enable_source_maps.hash(&mut hasher);
+        config.format.hash(&mut hasher);
+        config.global_name.hash(&mut hasher);
+        let mut globals: Vec<_> = config.globals.iter().collect();
+        globals.sort();
+        globals.hash(&mut hasher);
+        crate::infrastructure::processors::common::transform_options_hash(config).hash(&mut hasher);
 
         // Hash tree shaking stats if present
         if let Some(stats) = tree_stats {
@@ -536,6 +1211,7 @@ impl SokuBuildService {
         config: &BuildConfig,
         js_modules: &[ModuleInfo],
         structure: &ProjectStructure,
+        entry_points: &[String],
         tree_shaking_stats: Option<&TreeShakingStats>,
     ) -> Result<BuildResult> {
         let build_start = std::time::Instant::now();
@@ -549,35 +1225,28 @@ impl SokuBuildService {
             common_dependency_threshold: 2,
         };
 
-        // Identify entry points
-        let entry_points: Vec<String> = structure
-            .js_modules
-            .iter()
-            .filter(|p| {
-                let name = p
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                name.contains("main") || name.contains("index")
-            })
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
-
         // Analyze and split modules into chunks
         let mut splitter = CodeSplitter::new(split_config);
-        let chunks = splitter.analyze_and_split(js_modules, &entry_points)?;
+        let chunks = splitter.analyze_and_split(js_modules, entry_points)?;
 
         Logger::info(&format!(
             "📦 Code splitting: Created {} chunks",
             chunks.len()
         ));
 
-        // Process each chunk
-        let mut output_files_for_ui = Vec::new();
-        let mut output_files_for_result = Vec::new();
+        // The chunks are linked together and share one module registry
+        let chunk_paths: Vec<Vec<PathBuf>> = chunks
+            .iter()
+            .map(|chunk| chunk.modules.iter().map(|m| m.path.clone()).collect())
+            .collect();
+        let bundles = self
+            .js_processor
+            .bundle_chunks(js_modules, &chunk_paths, config)
+            .await?;
 
-        for chunk in &chunks {
+        // Process each chunk
+        let mut chunk_outputs = Vec::new();
+        for (chunk, bundle) in chunks.iter().zip(bundles) {
             Logger::info(&format!(
                 "  ├─ {} ({} modules, {:.1}KB)",
                 chunk.name,
@@ -585,79 +1254,114 @@ impl SokuBuildService {
                 chunk.size_bytes as f64 / 1024.0
             ));
 
-            // Process chunk modules
-            let chunk_content = if tree_shaking_stats.is_some() {
-                self.js_processor
-                    .bundle_modules_with_tree_shaking(&chunk.modules, tree_shaking_stats)
-                    .await?
-            } else {
-                self.js_processor.bundle_modules(&chunk.modules).await?
-            };
+            let (mut final_content, mut source_map) = (bundle.code, bundle.source_map);
 
             // Minify if enabled
-            let final_content = if config.enable_minification {
-                let minifier = MinificationService::new();
-                minifier
-                    .minify_bundle(chunk_content, &format!("{}.js", chunk.name))
-                    .await?
-            } else {
-                chunk_content
-            };
+            if config.enable_minification {
+                (final_content, source_map) = Self::minify_js(
+                    config,
+                    final_content,
+                    source_map,
+                    &format!("{}.js", chunk.name),
+                )
+                .await?;
+            }
 
-            // Write chunk file
-            let chunk_name = format!("{}.js", chunk.name);
-            let chunk_path = config.outdir.join(&chunk_name);
-            let content_size = final_content.len();
-            self.fs_service
-                .write_file(&chunk_path, &final_content)
+            let chunk_name = output_file_name(
+                &config.js_file_names,
+                &chunk.name,
+                "js",
+                final_content.as_bytes(),
+            );
+            let source_map = source_map
+                .map(|map| Self::generate_source_map(config, &chunk_name, &final_content, map));
+            self.run_after_bundle(config, &chunk.modules, &final_content)
                 .await?;
+            chunk_outputs.push((chunk_name, final_content, source_map));
+        }
 
-            output_files_for_ui.push(OutputFileInfo {
-                name: chunk_name.clone(),
-                size: content_size,
-            });
+        // Which chunk file each module ended up in, for the manifest imports
+        let mut chunk_files: HashMap<&Path, &str> = HashMap::new();
+        for (chunk, (chunk_name, _, _)) in chunks.iter().zip(&chunk_outputs) {
+            for module in &chunk.modules {
+                chunk_files.insert(module.path.as_path(), chunk_name.as_str());
+            }
+        }
+        let modules_by_path: HashMap<&Path, &ModuleInfo> = js_modules
+            .iter()
+            .map(|module| (module.path.as_path(), module))
+            .collect();
 
-            output_files_for_result.push(OutputFile {
-                path: chunk_path,
-                content: final_content,
-                size: content_size,
+        // Write chunk files
+        self.run_plugin_hooks(config, js_modules, PluginEvent::BeforeOutput)
+            .await?;
+        let mut manifest = BuildManifest::for_build(config);
+        let mut output_files = Vec::new();
+        for (chunk, (chunk_name, final_content, source_map)) in chunks.iter().zip(&chunk_outputs) {
+            let entries: Vec<PathBuf> = chunk
+                .modules
+                .iter()
+                .filter(|m| entry_points.iter().any(|e| m.path == Path::new(e)))
+                .map(|m| m.path.clone())
+                .collect();
+            // Running a chunk requires every module its modules reach, so all
+            // chunks holding one of them have to be loaded before it
+            let mut reached: HashSet<&Path> =
+                chunk.modules.iter().map(|m| m.path.as_path()).collect();
+            let mut pending: Vec<&Path> = reached.iter().copied().collect();
+            let mut imports: BTreeSet<String> = BTreeSet::new();
+            while let Some(path) = pending.pop() {
+                let Some(module) = modules_by_path.get(path) else {
+                    continue;
+                };
+                for target in module.resolved_imports.values() {
+                    if !reached.insert(target.as_path()) {
+                        continue;
+                    }
+                    pending.push(target.as_path());
+                    match chunk_files.get(target.as_path()) {
+                        Some(file) if *file != chunk_name.as_str() => {
+                            imports.insert(file.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            let imports: Vec<String> = imports.into_iter().collect();
+            manifest.add_chunk(ManifestChunk {
+                name: &chunk.name,
+                file: chunk_name,
+                entries: &entries,
+                modules: &chunk.modules,
+                imports,
             });
+
+            output_files.extend(Self::mapped_output(
+                config,
+                chunk_name,
+                final_content.clone(),
+                source_map.clone(),
+            )?);
         }
 
         // Process CSS (same as normal build)
         if !structure.css_files.is_empty() {
-            let processed = self.css_processor.bundle_css(&structure.css_files).await?;
-            let css_path = config.outdir.join("bundle.css");
-            let css_size = processed.len();
-            self.fs_service.write_file(&css_path, &processed).await?;
-
-            output_files_for_ui.push(OutputFileInfo {
-                name: "bundle.css".to_string(),
-                size: css_size,
-            });
-
-            output_files_for_result.push(OutputFile {
-                path: css_path,
-                content: processed,
-                size: css_size,
-            });
+            let (processed, css_map) = self.bundle_css(config, &structure.css_files).await?;
+            let (css_name, outputs) = Self::css_output(config, processed, css_map)?;
+            manifest.add_css(&css_name, &structure.css_files);
+            output_files.extend(outputs);
         }
 
-        // Generate completion stats with timing breakdown
-        let timing_breakdown = TimingBreakdown {
-            file_scan_ms: 0,
-            js_processing_ms: 0,
-            css_processing_ms: 0,
-            tree_shaking_ms: 0,
-            output_write_ms: 0,
-            minification_ms: 0,
-        };
-
-        self.ui.show_epic_completion(CompletionStats {
-            output_files: output_files_for_ui,
-            node_modules_optimized: None,
-            timing_breakdown: Some(timing_breakdown),
-        });
+        output_files.extend(
+            self.html_page_outputs(config, &structure.html_files, &manifest)
+                .await?,
+        );
+        let output_files_for_result = self
+            .write_output_files(config, js_modules, output_files, &mut manifest)
+            .await?;
+        self.run_plugin_hooks(config, js_modules, PluginEvent::AfterOutput)
+            .await?;
+        self.show_completion(config, js_modules, &output_files_for_result);
 
         // Update file metadata for incremental builds (after successful build)
         Logger::debug(&format!(
@@ -682,16 +1386,18 @@ impl SokuBuildService {
             Logger::warn(&format!("Failed to save incremental state: {}", e));
         }
 
+        let module_linking = self.take_link_report();
         let build_result = BuildResult {
             success: true,
             js_modules_processed: js_modules.len(),
             css_files_processed: structure.css_files.len(),
             errors: Vec::new(),
-            warnings: Vec::new(),
+            warnings: std::mem::take(&mut self.warnings),
             tree_shaking_stats: tree_shaking_stats.cloned(),
             build_time: build_start.elapsed(),
             output_files: output_files_for_result,
             modules: js_modules.to_vec(),
+            module_linking,
         };
 
         Ok(build_result)
@@ -704,6 +1410,7 @@ impl SokuBuildService {
         js_modules: &[ModuleInfo],
         css_modules: &[ModuleInfo],
         structure: &ProjectStructure,
+        entry_points: &[String],
         tree_shaking_stats: Option<&TreeShakingStats>,
     ) -> Result<BuildResult> {
         let build_start = std::time::Instant::now();
@@ -721,65 +1428,107 @@ impl SokuBuildService {
         ));
 
         let mut output_files = Vec::new();
+        let mut manifest = BuildManifest::for_build(config);
+        let mut app_imports = Vec::new();
+        let mut vendor_output = None;
+
+        // vendor.js and app.js are linked together and share one module
+        // registry: app.js requires the vendor modules from it
+        let chunk_paths: Vec<Vec<PathBuf>> = [&vendor_modules, &app_modules]
+            .into_iter()
+            .filter(|modules| !modules.is_empty())
+            .map(|modules| modules.iter().map(|m| m.path.clone()).collect())
+            .collect();
+        let mut bundles = self
+            .js_processor
+            .bundle_chunks(js_modules, &chunk_paths, config)
+            .await?
+            .into_iter();
+
+        let vendor_bundle = if vendor_modules.is_empty() {
+            None
+        } else {
+            bundles.next()
+        };
 
         // Bundle vendor.js if there are vendor modules
-        if !vendor_modules.is_empty() {
+        if let Some(bundle) = vendor_bundle {
             Logger::debug("🔨 Bundling vendor modules...");
-            let vendor_content = self.js_processor.bundle_modules(&vendor_modules).await?;
+            let (mut vendor_content, mut vendor_map) = (bundle.code, bundle.source_map);
 
             // Minify vendor
-            let vendor_content = if config.enable_minification {
-                MinificationService::new()
-                    .minify_bundle(vendor_content, "vendor.js")
-                    .await?
-            } else {
-                vendor_content
-            };
-
-            let vendor_path = config.outdir.join("vendor.js");
-            self.fs_service
-                .write_file(&vendor_path, &vendor_content)
-                .await?;
+            if config.enable_minification {
+                (vendor_content, vendor_map) =
+                    Self::minify_js(config, vendor_content, vendor_map, "vendor.js").await?;
+            }
 
-            output_files.push(OutputFile {
-                path: vendor_path,
-                content: vendor_content.clone(),
-                size: vendor_content.len(),
+            let vendor_name = output_file_name(
+                &config.js_file_names,
+                "vendor",
+                "js",
+                vendor_content.as_bytes(),
+            );
+            manifest.add_chunk(ManifestChunk {
+                name: "vendor",
+                file: &vendor_name,
+                entries: &[],
+                modules: &vendor_modules,
+                imports: Vec::new(),
             });
+            let vendor_map = vendor_map
+                .map(|map| Self::generate_source_map(config, &vendor_name, &vendor_content, map));
+            self.run_after_bundle(config, &vendor_modules, &vendor_content)
+                .await?;
+            app_imports.push(vendor_name.clone());
+            vendor_output = Some((vendor_name, vendor_content, vendor_map));
         }
 
         // Bundle app.js
         Logger::debug("🔨 Bundling app modules...");
-        let mut app_content = if !app_modules.is_empty() {
-            self.js_processor.bundle_modules(&app_modules).await?
+        let (mut app_content, mut app_map) = if let Some(bundle) = bundles.next() {
+            (bundle.code, bundle.source_map)
         } else {
-            String::from("// No app modules\n")
+            (String::from("// No app modules\n"), None)
         };
 
         // Minify, env vars, dead code
         if config.enable_minification {
-            app_content = MinificationService::new()
-                .minify_bundle(app_content, "app.js")
-                .await?;
+            (app_content, app_map) =
+                Self::minify_js(config, app_content, app_map, "app.js").await?;
         }
+        let (app_content, app_map) = Self::optimize_js(config, app_content, app_map)?;
+
+        let app_name = output_file_name(&config.js_file_names, "app", "js", app_content.as_bytes());
+        let app_entries: Vec<PathBuf> = entry_points.iter().map(PathBuf::from).collect();
+        manifest.add_chunk(ManifestChunk {
+            name: "app",
+            file: &app_name,
+            entries: &app_entries,
+            modules: &app_modules,
+            imports: app_imports,
+        });
+        let app_map =
+            app_map.map(|map| Self::generate_source_map(config, &app_name, &app_content, map));
+        self.run_after_bundle(config, &app_modules, &app_content)
+            .await?;
 
-        let env_manager =
-            crate::utils::EnvVarsManager::load_from_files(&config.root, &config.mode)?;
-        if !env_manager.get_all().is_empty() {
-            app_content = env_manager.replace_in_code(&app_content);
+        // Write vendor.js and app.js
+        self.run_plugin_hooks(config, js_modules, PluginEvent::BeforeOutput)
+            .await?;
+        if let Some((vendor_name, vendor_content, vendor_map)) = vendor_output {
+            output_files.extend(Self::mapped_output(
+                config,
+                &vendor_name,
+                vendor_content,
+                vendor_map,
+            )?);
         }
-
-        let eliminator = crate::utils::DeadCodeEliminator::new();
-        app_content = eliminator.eliminate(&app_content);
-
-        let app_path = config.outdir.join("app.js");
-        self.fs_service.write_file(&app_path, &app_content).await?;
-
-        output_files.push(OutputFile {
-            path: app_path,
-            content: app_content.clone(),
-            size: app_content.len(),
-        });
+        output_files.extend(Self::mapped_output(
+            config,
+            &app_name,
+            app_content,
+            app_map,
+        )?);
 
         // Process CSS
         let mut all_css_files = structure.css_files.clone();
@@ -788,16 +1537,21 @@ impl SokuBuildService {
         }
 
         if !all_css_files.is_empty() {
-            let css_content = self.css_processor.bundle_css(&all_css_files).await?;
-            let css_path = config.outdir.join("bundle.css");
-            self.fs_service.write_file(&css_path, &css_content).await?;
-
-            output_files.push(OutputFile {
-                path: css_path,
-                content: css_content.clone(),
-                size: css_content.len(),
-            });
+            let (css_content, css_map) = self.bundle_css(config, &all_css_files).await?;
+            let (css_name, css_outputs) = Self::css_output(config, css_content, css_map)?;
+            manifest.add_css(&css_name, &all_css_files);
+            output_files.extend(css_outputs);
         }
+        output_files.extend(
+            self.html_page_outputs(config, &structure.html_files, &manifest)
+                .await?,
+        );
+        let output_files = self
+            .write_output_files(config, js_modules, output_files, &mut manifest)
+            .await?;
+        self.run_plugin_hooks(config, js_modules, PluginEvent::AfterOutput)
+            .await?;
+        self.show_completion(config, js_modules, &output_files);
 
         // Update incremental state
         for module in js_modules {
@@ -805,16 +1559,18 @@ impl SokuBuildService {
         }
         self.incremental_state.mark_build_complete();
 
+        let module_linking = self.take_link_report();
         Ok(BuildResult {
             success: true,
             errors: Vec::new(),
-            warnings: Vec::new(),
+            warnings: std::mem::take(&mut self.warnings),
             js_modules_processed: js_modules.len(),
             css_files_processed: all_css_files.len(),
             tree_shaking_stats: tree_shaking_stats.cloned(),
             build_time: build_start.elapsed(),
             output_files,
             modules: js_modules.to_vec(),
+            module_linking,
         })
     }
 
@@ -836,9 +1592,13 @@ impl SokuBuildService {
 
         let mut output_files = Vec::new();
         let mut all_processed_modules = Vec::new();
+        let mut manifest = BuildManifest::for_build(config);
+        let mut entry_outputs = Vec::new();
 
         // Process each entry point separately
-        for (entry_name, entry_path) in &config.entries {
+        let mut entries: Vec<(&String, &PathBuf)> = config.entries.iter().collect();
+        entries.sort();
+        for (entry_name, entry_path) in entries {
             Logger::info(&format!("🔨 Building entry: {}", entry_name));
 
             // Find modules reachable from this entry point
@@ -856,43 +1616,54 @@ impl SokuBuildService {
             ));
 
             // Bundle this entry's modules
-            let mut entry_content = self.js_processor.bundle_modules(&entry_modules).await?;
+            let (mut entry_content, mut entry_map) =
+                self.bundle_js(&entry_modules, config, None).await?;
 
             // Apply optimizations
             if config.enable_minification {
                 let bundle_name = format!("{}.js", entry_name);
-                entry_content = MinificationService::new()
-                    .minify_bundle(entry_content, &bundle_name)
-                    .await?;
-            }
-
-            // Environment variable replacement
-            let env_manager =
-                crate::utils::EnvVarsManager::load_from_files(&config.root, &config.mode)?;
-            if !env_manager.get_all().is_empty() {
-                entry_content = env_manager.replace_in_code(&entry_content);
+                (entry_content, entry_map) =
+                    Self::minify_js(config, entry_content, entry_map, &bundle_name).await?;
             }
 
-            // Dead code elimination
-            let eliminator = crate::utils::DeadCodeEliminator::new();
-            entry_content = eliminator.eliminate(&entry_content);
+            // Environment variable replacement and dead code elimination
+            let (entry_content, entry_map) = Self::optimize_js(config, entry_content, entry_map)?;
 
-            // Write entry bundle
-            let entry_file_name = format!("{}.js", entry_name);
-            let entry_output_path = config.outdir.join(&entry_file_name);
-            self.fs_service
-                .write_file(&entry_output_path, &entry_content)
-                .await?;
-
-            output_files.push(OutputFile {
-                path: entry_output_path,
-                content: entry_content.clone(),
-                size: entry_content.len(),
+            let entry_file_name = output_file_name(
+                &config.js_file_names,
+                entry_name,
+                "js",
+                entry_content.as_bytes(),
+            );
+            manifest.add_chunk(ManifestChunk {
+                name: entry_name,
+                file: &entry_file_name,
+                entries: std::slice::from_ref(entry_path),
+                modules: &entry_modules,
+                imports: Vec::new(),
+            });
+            let entry_map = entry_map.map(|map| {
+                Self::generate_source_map(config, &entry_file_name, &entry_content, map)
             });
+            self.run_after_bundle(config, &entry_modules, &entry_content)
+                .await?;
+            entry_outputs.push((entry_file_name, entry_content, entry_map));
 
             all_processed_modules.extend(entry_modules);
         }
 
+        // Write entry bundles
+        self.run_plugin_hooks(config, js_modules, PluginEvent::BeforeOutput)
+            .await?;
+        for (entry_file_name, entry_content, entry_map) in entry_outputs {
+            output_files.extend(Self::mapped_output(
+                config,
+                &entry_file_name,
+                entry_content,
+                entry_map,
+            )?);
+        }
+
         // Process CSS (shared across all entries)
         let mut all_css_files = structure.css_files.clone();
         for css_module in css_modules {
@@ -901,16 +1672,21 @@ impl SokuBuildService {
 
         if !all_css_files.is_empty() {
             Logger::debug("🎨 Bundling CSS...");
-            let css_content = self.css_processor.bundle_css(&all_css_files).await?;
-            let css_path = config.outdir.join("bundle.css");
-            self.fs_service.write_file(&css_path, &css_content).await?;
-
-            output_files.push(OutputFile {
-                path: css_path,
-                content: css_content.clone(),
-                size: css_content.len(),
-            });
+            let (css_content, css_map) = self.bundle_css(config, &all_css_files).await?;
+            let (css_name, css_outputs) = Self::css_output(config, css_content, css_map)?;
+            manifest.add_css(&css_name, &all_css_files);
+            output_files.extend(css_outputs);
         }
+        output_files.extend(
+            self.html_page_outputs(config, &structure.html_files, &manifest)
+                .await?,
+        );
+        let output_files = self
+            .write_output_files(config, js_modules, output_files, &mut manifest)
+            .await?;
+        self.run_plugin_hooks(config, js_modules, PluginEvent::AfterOutput)
+            .await?;
+        self.show_completion(config, js_modules, &output_files);
 
         // Update incremental state
         for module in js_modules {
@@ -918,16 +1694,18 @@ impl SokuBuildService {
         }
         self.incremental_state.mark_build_complete();
 
+        let module_linking = self.take_link_report();
         Ok(BuildResult {
             success: true,
             errors: Vec::new(),
-            warnings: Vec::new(),
+            warnings: std::mem::take(&mut self.warnings),
             js_modules_processed: all_processed_modules.len(),
             css_files_processed: all_css_files.len(),
             tree_shaking_stats: tree_shaking_stats.cloned(),
             build_time: build_start.elapsed(),
             output_files,
             modules: all_processed_modules,
+            module_linking,
         })
     }
 
@@ -937,75 +1715,39 @@ impl SokuBuildService {
         entry_path: &Path,
         all_modules: &[ModuleInfo],
     ) -> Result<Vec<ModuleInfo>> {
-        let mut reachable = Vec::new();
-        let mut visited = std::collections::HashSet::new();
-        let mut to_visit = Vec::new();
-
-        // Normalize entry path for comparison
-        let entry_file_name = entry_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("");
-
-        // Find the entry module - match by filename or full path
-        let entry_module = all_modules
-            .iter()
-            .find(|m| {
-                // Try exact match first
-                if m.path == entry_path {
-                    return true;
-                }
+        let modules_by_path: HashMap<&Path, &ModuleInfo> =
+            all_modules.iter().map(|m| (m.path.as_path(), m)).collect();
+
+        // Module paths are canonicalized during resolution
+        let entry_path = entry_path
+            .canonicalize()
+            .unwrap_or_else(|_| entry_path.to_path_buf());
+        if !modules_by_path.contains_key(entry_path.as_path()) {
+            return Err(crate::utils::SokuError::build(format!(
+                "Entry point not found: {}",
+                entry_path.display()
+            )));
+        }
 
-                // Try filename match
-                if let Some(m_file_name) = m.path.file_name().and_then(|n| n.to_str()) {
-                    if m_file_name == entry_file_name {
-                        return true;
-                    }
-                }
+        let mut visited = std::collections::HashSet::new();
+        let mut to_visit = vec![entry_path.as_path()];
 
-                // Try ends_with for relative paths
-                m.path.ends_with(entry_path)
-            })
-            .ok_or_else(|| crate::utils::SokuError::Build {
-                message: format!(
-                    "Entry point not found: {} (available modules: {})",
-                    entry_path.display(),
-                    all_modules
-                        .iter()
-                        .take(3)
-                        .map(|m| m.path.display().to_string())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ),
-                context: None,
-            })?;
-
-        to_visit.push(entry_module.clone());
-
-        // BFS traversal of dependencies
-        while let Some(module) = to_visit.pop() {
-            let module_path = module.path.to_string_lossy().to_string();
-
-            if visited.contains(&module_path) {
+        // Walk the resolved imports of each module
+        while let Some(path) = to_visit.pop() {
+            if !visited.insert(path) {
                 continue;
             }
-
-            visited.insert(module_path.clone());
-            reachable.push(module.clone());
-
-            // Find dependencies
-            for dep in &module.dependencies {
-                // Try to find the dependency module
-                if let Some(dep_module) = all_modules.iter().find(|m| {
-                    let m_name = m.path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-                    m_name == dep || m.path.to_string_lossy().contains(dep)
-                }) {
-                    to_visit.push(dep_module.clone());
-                }
+            if let Some(module) = modules_by_path.get(path) {
+                to_visit.extend(module.resolved_imports.values().map(PathBuf::as_path));
             }
         }
 
-        Ok(reachable)
+        // Keep the evaluation order of the full module list
+        Ok(all_modules
+            .iter()
+            .filter(|m| visited.contains(m.path.as_path()))
+            .cloned()
+            .collect())
     }
 }
 
@@ -1017,18 +1759,38 @@ impl BuildService for SokuBuildService {
 
         let build_start = std::time::Instant::now();
 
+        self.warnings.clear();
+        crate::utils::validate_file_name_template("jsFileNames", &config.js_file_names)?;
+        crate::utils::validate_file_name_template("assetFileNames", &config.asset_file_names)?;
+
         // Create output directory
         self.fs_service.create_directory(&config.outdir).await?;
 
         // 🔍 FILE DISCOVERY
-        let structure = self.scan_and_analyze_with_ui(config).await?;
+        let mut structure = self.scan_and_analyze_with_ui(config).await?;
+
+        // 🌐 HTML PAGES - stylesheets they link are bundled like the scanned ones
+        for page in html_pages(config, &structure.html_files) {
+            let html = self.fs_service.read_file(&page).await?;
+            for stylesheet in html_stylesheets(&html, &page, &config.root) {
+                let canonical = stylesheet.canonicalize().ok();
+                if !structure
+                    .css_files
+                    .iter()
+                    .any(|css| css.canonicalize().ok() == canonical)
+                {
+                    structure.css_files.push(stylesheet);
+                }
+            }
+        }
 
-        // 🔌 PLUGIN: Before Build Hook
-        let plugin_context = PluginContext::new(
-            config.clone(),
-            Vec::new(), // Will be populated after module resolution
+        // 🔌 PLUGIN: Before Build Hook; files emitted by a failed build are dropped
+        let plugin_context = self.plugin_context(
+            config,
+            &[], // Will be populated after module resolution
             PluginEvent::BeforeBuild,
         );
+        plugin_context.take_emitted_files();
         self.plugin_manager
             .trigger_before_build(&plugin_context)
             .await?;
@@ -1041,11 +1803,35 @@ impl BuildService for SokuBuildService {
             self.incremental_state.file_count()
         ));
 
+        // 🎯 ENTRY POINTS - the dependency graph walk starts only from these
+        let entry_points = crate::utils::resolve_entry_points(config, &structure.html_files)?;
+        let entry_names: Vec<String> = entry_points
+            .iter()
+            .map(|p| {
+                p.strip_prefix(&config.root)
+                    .unwrap_or(p)
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        Logger::info(&format!("🎯 Entry points: {}", entry_names.join(", ")));
+
         // Convert paths to ModuleInfo and resolve dependencies
         let js_modules = self
-            .resolve_all_dependencies(&structure.js_modules, &config.root, config)
+            .resolve_all_dependencies(&entry_points, &config.root, config)
             .await?;
 
+        // Module paths are canonicalized during resolution
+        let entry_points: Vec<String> = entry_points
+            .iter()
+            .map(|p| {
+                p.canonicalize()
+                    .unwrap_or_else(|_| p.clone())
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+
         // 🔄 INCREMENTAL BUILD DETECTION
 
         Logger::debug(&format!(
@@ -1094,20 +1880,6 @@ impl BuildService for SokuBuildService {
                 let use_ast_shaker =
                     js_modules.len() > 3 || js_modules.iter().any(|m| m.content.len() > 5000);
 
-                let entry_points: Vec<String> = js_modules
-                    .iter()
-                    .filter(|m| {
-                        let name = m
-                            .path
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("")
-                            .to_lowercase();
-                        name.contains("main") || name.contains("index")
-                    })
-                    .map(|m| m.path.to_string_lossy().to_string())
-                    .collect();
-
                 let stats = if use_ast_shaker {
                     let mut ast_shaker = crate::infrastructure::AstTreeShaker::new();
                     ast_shaker.analyze_modules(&js_modules).await?;
@@ -1144,34 +1916,42 @@ impl BuildService for SokuBuildService {
             .cloned()
             .collect();
 
+        // 🔌 PLUGIN: Before Bundle Hook
+        self.run_plugin_hooks(config, &js_only_modules, PluginEvent::BeforeBundle)
+            .await?;
+
         // 📦 CODE SPLITTING (if enabled)
         if config.enable_code_splitting {
-            return self
+            let result = self
                 .build_with_code_splitting(
                     config,
                     &js_only_modules,
                     &structure,
+                    &entry_points,
                     tree_shaking_stats.as_ref(),
                 )
-                .await;
+                .await?;
+            return self.run_after_build(config, result).await;
         }
 
         // 📦 VENDOR CHUNK SPLITTING (if enabled)
         if config.vendor_chunk {
-            return self
+            let result = self
                 .build_with_vendor_splitting(
                     config,
                     &js_only_modules,
                     &css_modules,
                     &structure,
+                    &entry_points,
                     tree_shaking_stats.as_ref(),
                 )
-                .await;
+                .await?;
+            return self.run_after_build(config, result).await;
         }
 
         // 📦 MULTIPLE ENTRY POINTS (if configured)
         if !config.entries.is_empty() {
-            return self
+            let result = self
                 .build_with_multiple_entries(
                     config,
                     &js_only_modules,
@@ -1179,7 +1959,8 @@ impl BuildService for SokuBuildService {
                     &structure,
                     tree_shaking_stats.as_ref(),
                 )
-                .await;
+                .await?;
+            return self.run_after_build(config, result).await;
         }
 
         // ⚡ JAVASCRIPT PROCESSING WITH INTELLIGENT CACHING
@@ -1199,15 +1980,24 @@ impl BuildService for SokuBuildService {
             self.generate_js_cache_key(&js_only_modules, config, tree_shaking_stats.as_ref());
 
         // Skip cache when source maps are enabled since source maps aren't cached
-        let (mut js_content, source_map) = if config.enable_source_maps {
+        let mut module_linking = None;
+        let (mut js_content, mut source_map) = if config.enable_source_maps {
             Logger::debug("🔄 Processing JS modules with source maps (cache disabled)");
             let bundle_output = self
                 .js_processor
                 .bundle_modules_with_source_maps(&js_only_modules, config)
                 .await?;
             (bundle_output.code, bundle_output.source_map)
-        } else if let Some(cached_result) = self.cache.get_js(&cache_key, &cache_key) {
+        } else if let Some((cached_result, (linking, link_warnings))) = self
+            .cache
+            .get_js(&cache_key, &cache_key)
+            .zip(self.link_reports.get(&cache_key).cloned())
+        {
+            // The linker does not run for a cached bundle, so its report is
+            // restored along with it
             Logger::debug("✅ Using cached JS bundle");
+            self.warnings.extend(link_warnings);
+            module_linking = Some(linking);
             (cached_result, None)
         } else {
             Logger::debug("🔄 Processing JS modules (cache miss)");
@@ -1215,44 +2005,47 @@ impl BuildService for SokuBuildService {
                 // Use tree shaking bundling
                 let js_content = self
                     .js_processor
-                    .bundle_modules_with_tree_shaking(&js_only_modules, tree_shaking_stats.as_ref())
+                    .bundle_modules_with_tree_shaking(
+                        &js_only_modules,
+                        tree_shaking_stats.as_ref(),
+                        config,
+                    )
                     .await?;
                 (js_content, None)
             } else {
                 // Regular bundling
-                let js_content = self.js_processor.bundle_modules(&js_only_modules).await?;
+                let js_content = self
+                    .js_processor
+                    .bundle_modules(&js_only_modules, config)
+                    .await?;
                 (js_content, None)
             };
 
             // Cache the result for future builds
             self.cache
                 .cache_js(&cache_key, &cache_key, result.0.clone());
+            let linking = self.js_processor.take_module_linking();
+            let link_warnings = self.js_processor.take_link_warnings();
+            self.warnings.extend(link_warnings.iter().cloned());
+            self.link_reports
+                .insert(cache_key.clone(), (linking.clone(), link_warnings));
+            module_linking = Some(linking);
             result
         };
 
         // ⚡ MINIFICATION (if enabled)
         if config.enable_minification {
-            let minification_service = MinificationService::new();
             let original_content = js_content.clone();
-            js_content = minification_service
-                .minify_bundle(js_content, "bundle.js")
-                .await?;
-            let stats = minification_service.get_stats(&original_content, &js_content);
+            (js_content, source_map) =
+                Self::minify_js(config, js_content, source_map, "bundle.js").await?;
+            let stats =
+                MinificationService::for_target(crate::utils::parse_target(&config.target)?)
+                    .get_stats(&original_content, &js_content);
             tracing::info!("🗜️  {}", stats);
         }
 
-        // 🌍 ENVIRONMENT VARIABLES REPLACEMENT
-        let env_manager =
-            crate::utils::EnvVarsManager::load_from_files(&config.root, &config.mode)?;
-        let env_count = env_manager.get_all().len();
-        if env_count > 0 {
-            Logger::debug(&format!("🌍 Replacing {} environment variables", env_count));
-            js_content = env_manager.replace_in_code(&js_content);
-        }
-
-        // 🗑️ DEAD CODE ELIMINATION (after env var replacement)
-        let eliminator = crate::utils::DeadCodeEliminator::new();
-        js_content = eliminator.eliminate(&js_content);
+        // 🌍 ENVIRONMENT VARIABLES REPLACEMENT and 🗑️ DEAD CODE ELIMINATION
+        let (js_content, source_map) = Self::optimize_js(config, js_content, source_map)?;
 
         // 🎨 CSS PROCESSING WITH INTELLIGENT CACHING
         // Include both original CSS files and CSS modules found through imports
@@ -1272,9 +2065,13 @@ impl BuildService for SokuBuildService {
         self.ui.show_processing_phase(&css_names, "🎨 CSS");
 
         let css_cache_key = self.generate_css_cache_key(&all_css_files);
-        let css_content = if let Some(cached_css) =
-            self.cache.get_css(&css_cache_key, &css_cache_key)
-        {
+        let mut css_map = None;
+        let css_content = if config.enable_source_maps {
+            // Maps are not cached, so a mapped bundle is always rebuilt
+            let (css_content, map) = self.bundle_css(config, &all_css_files).await?;
+            css_map = map;
+            css_content
+        } else if let Some(cached_css) = self.cache.get_css(&css_cache_key, &css_cache_key) {
             Logger::debug("✅ Using cached CSS bundle");
             cached_css
         } else {
@@ -1320,6 +2117,7 @@ impl BuildService for SokuBuildService {
 
         // 🌐 WASM PROCESSING
         let mut wasm_loader_code = String::new();
+        let mut wasm_outputs = Vec::new();
         if !structure.wasm_files.is_empty() {
             Logger::info(&format!(
                 "🌐 Processing {} WASM modules",
@@ -1331,28 +2129,23 @@ impl BuildService for SokuBuildService {
             for wasm_path in &structure.wasm_files {
                 let module_name = crate::utils::WasmProcessor::get_module_name(wasm_path);
 
-                // Generate JavaScript loader code
-                let loader = wasm_processor.generate_loader_code(wasm_path, &module_name)?;
-                wasm_loader_code.push_str(&loader);
-                wasm_loader_code.push_str("\n\n");
-
-                // Copy WASM file to output directory
-                let wasm_filename = wasm_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("module.wasm");
-                let output_wasm_path = config.outdir.join(wasm_filename);
-
-                // Use tokio::fs for async file copy
-                tokio::fs::copy(wasm_path, &output_wasm_path)
+                // WASM files are copied to the output directory under their templated name
+                let wasm_bytes = tokio::fs::read(wasm_path)
                     .await
                     .map_err(crate::utils::SokuError::Io)?;
+                let wasm_stem = wasm_path
+                    .file_stem()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("module");
+                let wasm_filename =
+                    output_file_name(&config.asset_file_names, wasm_stem, "wasm", &wasm_bytes);
 
-                Logger::debug(&format!(
-                    "  ✓ {} → {}",
-                    wasm_filename,
-                    output_wasm_path.display()
-                ));
+                // Generate JavaScript loader code for the file, copied with the output
+                let loader =
+                    wasm_processor.generate_loader_code(Path::new(&wasm_filename), &module_name)?;
+                wasm_loader_code.push_str(&loader);
+                wasm_loader_code.push_str("\n\n");
+                wasm_outputs.push((wasm_filename, wasm_path.clone(), wasm_bytes));
             }
         }
 
@@ -1366,67 +2159,52 @@ impl BuildService for SokuBuildService {
             js_content.clone()
         };
 
-        // 🗺️ ADVANCED SOURCE MAPS: Generate advanced source maps with inline content
-        let enhanced_source_map = if config.enable_source_maps && source_map.is_some() {
-            let mut generator = AdvancedSourceMapGenerator::new();
-
-            // Add sources with content from all JS modules
-            for module in &js_only_modules {
-                generator.add_source(
-                    module.path.to_string_lossy().to_string(),
-                    module.content.clone(),
-                );
-            }
-
-            let map = generator.generate(Some("bundle.js".to_string()));
-            let map_json = SourceMapUtils::to_json(&map)?;
-            Some(map_json)
-        } else {
-            source_map
-        };
+        // 🔌 PLUGIN: After Bundle and Before Output Hooks
+        self.run_after_bundle(config, &js_only_modules, &final_js_content)
+            .await?;
+        self.run_plugin_hooks(config, &js_only_modules, PluginEvent::BeforeOutput)
+            .await?;
 
         // 💾 WRITE FILES
+        let mut manifest = BuildManifest::for_build(config);
+        let entry_paths: Vec<PathBuf> = entry_points.iter().map(PathBuf::from).collect();
+        let mut output_files = self.bundle_output_files(
+            config,
+            &final_js_content,
+            &css_content,
+            source_map,
+            css_map,
+            &entry_paths,
+            &js_only_modules,
+            &all_css_files,
+            &mut manifest,
+        )?;
+        for (wasm_filename, wasm_path, wasm_bytes) in wasm_outputs {
+            let output = Self::output_file(config, &wasm_filename, wasm_bytes);
+            Logger::debug(&format!(
+                "  ✓ {} → {}",
+                wasm_path.display(),
+                output.path.display()
+            ));
+            manifest.add_asset(&wasm_filename, &wasm_path);
+            output_files.push(output);
+        }
+        output_files.extend(
+            self.html_page_outputs(config, &structure.html_files, &manifest)
+                .await?,
+        );
         let output_files = self
-            .write_output_files(config, &final_js_content, &css_content, enhanced_source_map)
+            .write_output_files(config, &js_only_modules, output_files, &mut manifest)
+            .await?;
+
+        // 🔌 PLUGIN: After Output Hook
+        self.run_plugin_hooks(config, &js_only_modules, PluginEvent::AfterOutput)
             .await?;
 
         let build_time = build_start.elapsed();
 
         // 🎉 EPIC COMPLETION SHOWCASE!
-        let node_modules_count = js_modules
-            .iter()
-            .filter(|m| m.path.to_string_lossy().contains("node_modules"))
-            .count();
-
-        let completion_stats = CompletionStats {
-            output_files: output_files
-                .iter()
-                .filter_map(|f| {
-                    f.path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .map(|name| OutputFileInfo {
-                            name: name.to_string(),
-                            size: f.size,
-                        })
-                })
-                .collect(),
-            node_modules_optimized: if node_modules_count > 0 {
-                Some(node_modules_count)
-            } else {
-                None
-            },
-            timing_breakdown: Some(TimingBreakdown {
-                file_scan_ms: 0,
-                js_processing_ms: 0,
-                css_processing_ms: 0,
-                tree_shaking_ms: 0,
-                output_write_ms: 0,
-                minification_ms: 0,
-            }),
-        };
-
-        self.ui.show_epic_completion(completion_stats);
+        self.show_completion(config, &js_modules, &output_files);
 
         // End total timing and report bottlenecks
 
@@ -1453,6 +2231,10 @@ impl BuildService for SokuBuildService {
             Logger::warn(&format!("Failed to save incremental state: {}", e));
         }
 
+        let module_linking = match module_linking {
+            Some(linking) => linking,
+            None => self.take_link_report(),
+        };
         let result = BuildResult {
             js_modules_processed: js_only_modules.len(),
             css_files_processed: all_css_files.len(),
@@ -1461,20 +2243,12 @@ impl BuildService for SokuBuildService {
             output_files,
             success: true,
             errors: Vec::new(),
-            warnings: Vec::new(),
+            warnings: std::mem::take(&mut self.warnings),
             modules: js_only_modules.clone(),
+            module_linking,
         };
 
         // 🔌 PLUGIN: After Build Hook
-        let plugin_context = PluginContext::new(
-            config.clone(),
-            js_only_modules.clone(),
-            PluginEvent::AfterBuild,
-        );
-        self.plugin_manager
-            .trigger_after_build(&plugin_context, &result)
-            .await?;
-
-        Ok(result)
+        self.run_after_build(config, result).await
     }
 }
@@ -0,0 +1,706 @@
+// Development server for `soku dev`
+// Serves the build output over HTTP with the HMR client injected into pages,
+// and rebuilds on source changes before notifying the HMR clients
+
+use crate::core::interfaces::BuildService;
+use crate::core::models::{BuildConfig, BuildResult};
+use crate::infrastructure::http::{self, HttpRequest, HttpResponse};
+use crate::infrastructure::{
+    find_react_refresh_runtime, generate_hmr_client_code, inject_into_head, react_refresh_runtime,
+    HmrUpdate, HmrUpdateKind, SokuHmrService,
+};
+use crate::utils::{Logger, Result, SokuError, PUBLIC_DIR};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+
+/// Changes closer together than this are rebuilt at once
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Directories whose changes never trigger a rebuild
+const IGNORED_DIRS: &[&str] = &["node_modules", ".git", ".soku-cache", "target"];
+
+/// HTTP server of the development build
+#[derive(Clone)]
+pub struct DevServer {
+    config: Arc<BuildConfig>,
+    hmr: SokuHmrService,
+    /// `<script>` with the HMR client, injected into every served page
+    hmr_script: Arc<String>,
+    /// Whether the last build failed, so the clients show its error
+    failed: Arc<AtomicBool>,
+}
+
+impl DevServer {
+    pub fn new(config: BuildConfig, hmr: SokuHmrService, hmr_port: u16) -> Self {
+        let refresh_runtime = Self::react_refresh_runtime(&config.root);
+        Self {
+            config: Arc::new(config),
+            hmr,
+            hmr_script: Arc::new(format!(
+                "<script>\n{}\n</script>",
+                generate_hmr_client_code(hmr_port, refresh_runtime.as_deref())
+            )),
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// The React Fast Refresh runtime of the project's `react-refresh`
+    ///
+    /// Without it, updates of React components run their importers again.
+    fn react_refresh_runtime(root: &Path) -> Option<String> {
+        let Some(path) = find_react_refresh_runtime(root) else {
+            if root
+                .ancestors()
+                .any(|dir| dir.join("node_modules/react").is_dir())
+            {
+                Logger::info("Install react-refresh to keep component state across updates");
+            }
+            return None;
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Some(react_refresh_runtime(&source)),
+            Err(e) => {
+                Logger::warn(&format!("Cannot read {}: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    /// Bind the HTTP server to `localhost:<port>`
+    pub async fn bind(port: u16) -> Result<TcpListener> {
+        TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+            .await
+            .map_err(|e| {
+                SokuError::build(format!("Dev server bind failed on port {}: {}", port, e))
+            })
+    }
+
+    /// Serve the build output until the listener fails
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let server = self.clone();
+        http::serve(listener, move |request| {
+            let server = server.clone();
+            async move { server.respond(&request).await }
+        })
+        .await
+    }
+
+    /// Answer a request from the output directory, then `public/`
+    ///
+    /// Pages get the HMR client. Unknown paths that ask for a page get
+    /// `index.html`, so client-side routers work on reload.
+    pub async fn respond(&self, request: &HttpRequest) -> HttpResponse {
+        if request.method != "GET" && request.method != "HEAD" {
+            return HttpResponse::method_not_allowed();
+        }
+
+        if http::is_hidden_path(&request.path) {
+            return HttpResponse::not_found();
+        }
+
+        let file = http::resolve_file(&self.config.outdir, &request.path)
+            .or_else(|| self.resolve_public_file(&request.path));
+        let response = match file {
+            Some(file) => self.file_response(&file).await,
+            None if request.accepts_html() || Path::new(&request.path).extension().is_none() => {
+                self.index_response().await
+            }
+            None => HttpResponse::not_found(),
+        };
+        response.with_header("Cache-Control", "no-cache")
+    }
+
+    /// Static file of the project's `public/` directory; the rest of the
+    /// project (sources, config, `.env`) is never served
+    fn resolve_public_file(&self, path: &str) -> Option<PathBuf> {
+        http::resolve_file(&self.config.root.join(PUBLIC_DIR), path)
+            .filter(|file| !file.starts_with(&self.config.outdir))
+    }
+
+    async fn file_response(&self, file: &Path) -> HttpResponse {
+        match tokio::fs::read(file).await {
+            Ok(content) if is_html(file) => self.page_response(&String::from_utf8_lossy(&content)),
+            Ok(content) => HttpResponse::ok(http::mime_type(file), content),
+            Err(e) => {
+                Logger::warn(&format!("Cannot read {}: {}", file.display(), e));
+                HttpResponse::text(500, "Internal Server Error")
+            }
+        }
+    }
+
+    /// The `index.html` of the build or the project, or a page that loads the
+    /// bundles when there is neither
+    async fn index_response(&self) -> HttpResponse {
+        for dir in [&self.config.outdir, &self.config.root] {
+            let index = dir.join("index.html");
+            if index.is_file() {
+                return self.file_response(&index).await;
+            }
+        }
+        let stylesheet = self.config.outdir.join("bundle.css").is_file();
+        self.page_response(&default_page(stylesheet))
+    }
+
+    fn page_response(&self, html: &str) -> HttpResponse {
+        let html = inject_into_head(html, std::slice::from_ref(&*self.hmr_script));
+        HttpResponse::ok("text/html; charset=utf-8", html)
+    }
+
+    /// Rebuild on source changes and tell the HMR clients about the result
+    pub async fn watch<B: BuildService>(&self, build_service: &mut B) -> Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: notify::Result<Event>| {
+                if let Ok(event) = res {
+                    let _ = tx.send(event);
+                }
+            },
+            notify::Config::default(),
+        )
+        .map_err(|e| SokuError::build(format!("File watcher setup failed: {}", e)))?;
+        watcher
+            .watch(&self.config.root, RecursiveMode::Recursive)
+            .map_err(|e| SokuError::build(format!("Watch setup failed: {}", e)))?;
+
+        while let Some(event) = rx.recv().await {
+            let mut changed = HashSet::new();
+            self.collect_changes(event, &mut changed);
+            // Editors often write a file in several steps
+            while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                self.collect_changes(event, &mut changed);
+            }
+            if !changed.is_empty() {
+                self.rebuild(build_service, &changed).await;
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_changes(&self, event: Event, changed: &mut HashSet<PathBuf>) {
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        changed.extend(event.paths.into_iter().filter(|path| self.is_watched(path)));
+    }
+
+    /// Whether a change of `path` affects the build
+    fn is_watched(&self, path: &Path) -> bool {
+        if path.starts_with(&self.config.outdir) {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.config.root).unwrap_or(path);
+        if relative.components().any(|component| {
+            IGNORED_DIRS.contains(&component.as_os_str().to_str().unwrap_or_default())
+        }) {
+            return false;
+        }
+        matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some(
+                "js" | "jsx"
+                    | "ts"
+                    | "tsx"
+                    | "mjs"
+                    | "cjs"
+                    | "css"
+                    | "scss"
+                    | "sass"
+                    | "html"
+                    | "htm"
+                    | "json"
+                    | "wasm"
+            )
+        )
+    }
+
+    /// Build once before serving; later rebuilds update the pages in place
+    ///
+    /// A failed build is shown in the overlay of the pages opened until it is fixed.
+    pub async fn initial_build<B: BuildService>(&self, build_service: &mut B) -> Result<()> {
+        let result = Self::build(&self.config, build_service)
+            .await
+            .inspect_err(|e| {
+                self.failed.store(true, Ordering::Relaxed);
+                self.hmr
+                    .send(HmrUpdate::build_error(self.config.root.clone(), e));
+            })?;
+        self.hmr.record_build(&result);
+        Ok(())
+    }
+
+    async fn build<B: BuildService>(
+        config: &BuildConfig,
+        build_service: &mut B,
+    ) -> Result<BuildResult> {
+        let result = build_service.build(config).await?;
+        if !result.success {
+            return Err(SokuError::build(result.errors.join("\n")));
+        }
+        Ok(result)
+    }
+
+    async fn rebuild<B: BuildService>(&self, build_service: &mut B, changed: &HashSet<PathBuf>) {
+        Logger::info(&format!(
+            "🔄 Rebuilding... ({} files changed)",
+            changed.len()
+        ));
+        let start = Instant::now();
+
+        match Self::build(&self.config, build_service).await {
+            Ok(result) => {
+                Logger::info(&format!("✅ Rebuilt in {}ms", start.elapsed().as_millis()));
+                if self.failed.swap(false, Ordering::Relaxed) {
+                    // Clears the error overlay
+                    self.hmr.send(HmrUpdate::new(
+                        HmrUpdateKind::BuildSuccess,
+                        changed.iter().next().cloned().unwrap_or_default(),
+                        None,
+                    ));
+                }
+                for update in self.updates_for(changed, &result) {
+                    self.hmr.send(update);
+                }
+            }
+            Err(e) => {
+                // Parse errors carry their location and code frame
+                Logger::error(&format!("❌ Rebuild failed: {}", e));
+                self.failed.store(true, Ordering::Relaxed);
+                self.hmr.send(HmrUpdate::build_error(
+                    changed.iter().next().cloned().unwrap_or_default(),
+                    &e,
+                ));
+            }
+        }
+    }
+
+    /// Swap the stylesheet for style changes and send the compiled modules
+    /// for script changes; reload the page when either is not possible
+    fn updates_for(&self, changed: &HashSet<PathBuf>, result: &BuildResult) -> Vec<HmrUpdate> {
+        let (styles, scripts): (HashSet<PathBuf>, HashSet<PathBuf>) = changed
+            .iter()
+            .cloned()
+            .partition(|path| SokuHmrService::is_css_file(path));
+        let reload = || {
+            vec![HmrUpdate::new(
+                HmrUpdateKind::FullReload,
+                changed.iter().next().cloned().unwrap_or_default(),
+                None,
+            )]
+        };
+
+        let mut updates = Vec::new();
+        if !styles.is_empty() {
+            let stylesheet = result
+                .output_files
+                .iter()
+                .find(|file| file.path.extension().is_some_and(|ext| ext == "css"));
+            let Some(stylesheet) = stylesheet else {
+                return reload();
+            };
+            updates.push(HmrUpdate::new(
+                HmrUpdateKind::CssUpdated,
+                stylesheet
+                    .path
+                    .strip_prefix(&self.config.outdir)
+                    .unwrap_or(&stylesheet.path)
+                    .to_path_buf(),
+                stylesheet.text().map(str::to_string),
+            ));
+        }
+        match self.hmr.module_updates(&scripts, result) {
+            Some(modules) => updates.extend(modules),
+            None => return reload(),
+        }
+        updates
+    }
+}
+
+/// Page served when the project has no `index.html`; it links the CSS
+/// bundle only when the build has styles
+fn default_page(stylesheet: bool) -> String {
+    let link = if stylesheet {
+        "\n  <link rel=\"stylesheet\" href=\"/bundle.css\">"
+    } else {
+        ""
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">{link}
+</head>
+<body>
+  <div id="root"></div>
+  <div id="app"></div>
+  <script src="/bundle.js"></script>
+</body>
+</html>
+"#
+    )
+}
+
+fn is_html(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("html" | "htm")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn request(path: &str, accept: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            query: None,
+            version: "HTTP/1.1".to_string(),
+            headers: HashMap::from([("accept".to_string(), accept.to_string())]),
+        }
+    }
+
+    fn server(root: &Path) -> DevServer {
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join("dist"),
+            ..Default::default()
+        };
+        DevServer::new(config, SokuHmrService::new(root.to_path_buf()), 3001)
+    }
+
+    type HmrSocket = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    /// Dev server of a project built for HMR, with its build service and a
+    /// client connected to its HMR WebSocket
+    async fn hmr_project(root: &Path) -> (DevServer, impl BuildService, HmrSocket) {
+        let (server, mut build_service, port) = hmr_server(root);
+        server.initial_build(&mut build_service).await.unwrap();
+        let socket = connect_hmr(port).await;
+        (server, build_service, socket)
+    }
+
+    /// Dev server of a project to build for HMR, with its build service and
+    /// the port of its running HMR WebSocket server
+    fn hmr_server(root: &Path) -> (DevServer, impl BuildService, u16) {
+        use crate::core::services::SokuBuildService;
+        use crate::infrastructure::processors::ProcessingStrategy;
+        use crate::infrastructure::{
+            LightningCssProcessor, TokioFileSystemService, UnifiedJsProcessor,
+        };
+
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join("dist"),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            mode: "development".to_string(),
+            hmr: true,
+            ..Default::default()
+        };
+        let hmr = SokuHmrService::new(root.to_path_buf());
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let server = DevServer::new(config, hmr.clone(), port);
+        let build_service = SokuBuildService::new(
+            Arc::new(TokioFileSystemService),
+            Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard)),
+            Arc::new(LightningCssProcessor::new(false)),
+        );
+        tokio::spawn(async move { hmr.start_server(port).await });
+        (server, build_service, port)
+    }
+
+    /// A client connected to the HMR WebSocket server on `port`
+    async fn connect_hmr(port: u16) -> HmrSocket {
+        let url = format!("ws://127.0.0.1:{}", port);
+        let mut socket = loop {
+            match tokio_tungstenite::connect_async(&url).await {
+                Ok((socket, _)) => break socket,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+        // The client is registered once it is welcomed
+        let welcome = next_message(&mut socket).await;
+        assert_eq!(welcome["path"], "__hmr_connected__");
+        socket
+    }
+
+    /// Next update the HMR server sends, as the client parses it
+    async fn next_message(socket: &mut HmrSocket) -> serde_json::Value {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::Message;
+
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+                .await
+                .expect("no HMR message")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = message {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_sends_module_updates_to_the_accepting_modules() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/main.js"),
+            "import { render } from './view.js';\nrender();\nimport.meta.hot.accept('./view.js', (view) => view.render());\n",
+        )
+        .unwrap();
+        let view = root.join("src/view.js");
+        std::fs::write(&view, "export function render() {\n  return 1;\n}\n").unwrap();
+        let (server, mut build_service, mut socket) = hmr_project(&root).await;
+
+        std::fs::write(&view, "export function render() {\n  return 2;\n}\n").unwrap();
+        server
+            .rebuild(&mut build_service, &HashSet::from([view]))
+            .await;
+
+        let update = next_message(&mut socket).await;
+        assert_eq!(update["kind"], "ModuleUpdated");
+        assert_eq!(update["path"], "src/view.js");
+        assert_eq!(update["module_id"], "view.js");
+        assert!(update["content"].as_str().unwrap().contains("return 2;"));
+        assert_eq!(
+            update["boundaries"],
+            serde_json::json!([{ "boundary": "main.js", "accepted": "view.js" }])
+        );
+        assert_eq!(update["stale"], serde_json::json!(["view.js"]));
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_errors_reach_the_overlay_until_fixed() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let main = root.join("src/main.js");
+        std::fs::write(&main, "const a = 1;\nconsole.log(a);\n").unwrap();
+        let (server, mut build_service, mut socket) = hmr_project(&root).await;
+        let changed = HashSet::from([main.clone()]);
+
+        std::fs::write(&main, "const a = 1;\nconst b = ;\nconsole.log(a);\n").unwrap();
+        server.rebuild(&mut build_service, &changed).await;
+        let update = next_message(&mut socket).await;
+        assert_eq!(update["kind"], "BuildError");
+        let error = &update["error"];
+        assert_eq!(error["file"], main.to_string_lossy().as_ref());
+        assert_eq!((&error["line"], &error["column"]), (&2.into(), &11.into()));
+        assert!(error["frame"]
+            .as_str()
+            .unwrap()
+            .contains(">    2 | const b = ;"));
+
+        std::fs::write(&main, "const a = 2;\nconsole.log(a);\n").unwrap();
+        server.rebuild(&mut build_service, &changed).await;
+        assert_eq!(next_message(&mut socket).await["kind"], "BuildSuccess");
+        // The page is updated once the overlay is gone
+        let update = next_message(&mut socket).await;
+        assert!(update["kind"] == "ModuleUpdated" || update["kind"] == "FullReload");
+    }
+
+    #[tokio::test]
+    async fn test_pages_opened_after_a_failed_build_show_its_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let main = root.join("src/main.js");
+        std::fs::write(&main, "const a = ;\n").unwrap();
+        let (server, mut build_service, port) = hmr_server(&root);
+        assert!(server.initial_build(&mut build_service).await.is_err());
+
+        let mut socket = connect_hmr(port).await;
+        let update = next_message(&mut socket).await;
+        assert_eq!(update["kind"], "BuildError");
+        assert_eq!(update["error"]["file"], main.to_string_lossy().as_ref());
+
+        std::fs::write(&main, "const a = 1;\nconsole.log(a);\n").unwrap();
+        server
+            .rebuild(&mut build_service, &HashSet::from([main]))
+            .await;
+        assert_eq!(next_message(&mut socket).await["kind"], "BuildSuccess");
+
+        // Once fixed, new pages are only welcomed
+        let mut socket = connect_hmr(port).await;
+        let next = tokio::time::timeout(Duration::from_millis(200), next_message(&mut socket));
+        assert!(next.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pages_get_the_react_refresh_runtime_of_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let runtime_dir = root.join("node_modules/react-refresh/cjs");
+        std::fs::create_dir_all(&runtime_dir).unwrap();
+        std::fs::write(
+            runtime_dir.join("react-refresh-runtime.development.js"),
+            "exports.performReactRefresh = function () { return null; };",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(root.join("dist/index.html"), "<html><head></head></html>").unwrap();
+
+        let response = server(root).respond(&request("/", "text/html")).await;
+        let page = String::from_utf8(response.body).unwrap();
+        assert!(page.contains("exports.performReactRefresh = function () { return null; };"));
+        assert!(page.contains("window.__soku_refresh__"));
+    }
+
+    #[tokio::test]
+    async fn test_serves_output_with_hmr_client_and_spa_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(
+            root.join("dist/index.html"),
+            "<html><head></head><body><script src=\"bundle.js\"></script></body></html>",
+        )
+        .unwrap();
+        std::fs::write(root.join("dist/bundle.js"), "run();").unwrap();
+        std::fs::create_dir_all(root.join("public")).unwrap();
+        std::fs::write(root.join("public/favicon.ico"), [0u8, 1]).unwrap();
+        let server = server(root);
+
+        let response = server.respond(&request("/bundle.js", "*/*")).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.header("content-type"),
+            Some("text/javascript; charset=utf-8")
+        );
+        assert_eq!(response.body, b"run();");
+
+        // Static files of public/
+        let response = server.respond(&request("/favicon.ico", "*/*")).await;
+        assert_eq!(response.header("content-type"), Some("image/x-icon"));
+
+        // Client-side routes get the page, with the HMR client
+        let response = server.respond(&request("/users/42", "text/html")).await;
+        assert_eq!(response.status, 200);
+        let page = String::from_utf8(response.body).unwrap();
+        assert!(page.contains("Soku Bundler HMR Client Runtime"));
+        assert!(!page.contains("react-refresh"));
+        assert!(page.contains("ws://localhost:${HMR_PORT}") && page.contains("3001"));
+
+        let response = server.respond(&request("/missing.js", "*/*")).await;
+        assert_eq!(response.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_nested_routes_load_the_built_page_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("index.html"),
+            "<html><head></head><body><script type=\"module\" src=\"/src/main.js\"></script></body></html>",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/main.js"), "console.log('app');\n").unwrap();
+        let (server, _build_service, _socket) = hmr_project(&root).await;
+
+        let response = server.respond(&request("/users/42", "text/html")).await;
+        let page = String::from_utf8(response.body).unwrap();
+        let src = page
+            .split("<script type=\"module\" src=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        // Resolved against the route like a browser does
+        let url = match src.strip_prefix('/') {
+            Some(_) => src.to_string(),
+            None => format!("/users/{}", src),
+        };
+        let response = server.respond(&request(&url, "*/*")).await;
+        assert_eq!(response.status, 200, "{}", url);
+        assert!(String::from_utf8(response.body).unwrap().contains("'app'"));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_serve_project_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join(".env"), "API_KEY=secret").unwrap();
+        std::fs::write(root.join(".git/config"), "[core]").unwrap();
+        std::fs::write(root.join("soku.config.json"), "{}").unwrap();
+        std::fs::write(root.join("src/main.js"), "run();").unwrap();
+        let server = server(root);
+
+        for path in ["/.env", "/.git/config", "/soku.config.json", "/src/main.js"] {
+            let response = server.respond(&request(path, "*/*")).await;
+            assert_eq!(response.status, 404, "{}", path);
+        }
+        let response = server.respond(&request("/.env", "text/html")).await;
+        assert_eq!(response.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_serves_default_page_without_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let response = server(dir.path()).respond(&request("/", "text/html")).await;
+        let page = String::from_utf8(response.body).unwrap();
+        assert!(page.contains("<script src=\"/bundle.js\"></script>"));
+        assert!(page.contains("Soku Bundler HMR Client Runtime"));
+        assert!(!page.contains("bundle.css"));
+
+        std::fs::create_dir_all(dir.path().join("dist")).unwrap();
+        std::fs::write(dir.path().join("dist/bundle.css"), "a{}").unwrap();
+        let response = server(dir.path()).respond(&request("/", "text/html")).await;
+        let page = String::from_utf8(response.body).unwrap();
+        assert!(page.contains("<link rel=\"stylesheet\" href=\"/bundle.css\">"));
+    }
+
+    #[test]
+    fn test_watched_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let server = server(root);
+        assert!(server.is_watched(&root.join("src/App.tsx")));
+        assert!(server.is_watched(&root.join("styles/main.scss")));
+        assert!(!server.is_watched(&root.join("dist/bundle.js")));
+        assert!(!server.is_watched(&root.join("node_modules/react/index.js")));
+        assert!(!server.is_watched(&root.join("README.md")));
+    }
+
+    #[tokio::test]
+    async fn test_http_round_trip() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("dist")).unwrap();
+        std::fs::write(dir.path().join("dist/bundle.css"), "a{}").unwrap();
+        let server = server(dir.path());
+        let listener = DevServer::bind(0).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /bundle.css HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/css; charset=utf-8\r\n"));
+        assert!(response.ends_with("\r\n\r\na{}"));
+    }
+}
@@ -24,7 +24,7 @@ impl FileSystemService for TokioFileSystemService {
                 .to_lowercase();
 
             match extension.as_str() {
-                "js" | "jsx" | "ts" | "tsx" => {
+                "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
                     structure.js_modules.push(path);
                 }
                 "css" | "scss" | "sass" => {
@@ -1,9 +1,10 @@
+use crate::core::models::{BuildResult, DependencyKind, ModuleLinking};
 use crate::infrastructure::HmrHookManager;
-use crate::utils::{Result, SokuError};
+use crate::utils::{DependencyGraph, Result, SokuError};
 use dashmap::DashMap;
 use futures::{SinkExt, StreamExt};
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::broadcast;
@@ -16,10 +17,121 @@ pub struct HmrUpdate {
     pub kind: HmrUpdateKind,
     pub path: PathBuf,
     pub content: Option<String>,
+    /// Registry id of an updated module
+    #[serde(default)]
+    pub module_id: Option<String>,
+    /// Registry ids of the modules an updated module imports
     pub dependencies: Vec<String>,
+    /// Modules whose `import.meta.hot.accept` handles the update
+    #[serde(default)]
+    pub boundaries: Vec<HmrBoundary>,
+    /// Registry ids of the modules that run again, from the updated module up
+    /// to the boundaries
+    #[serde(default)]
+    pub stale: Vec<String>,
+    /// What went wrong, for `BuildError` updates
+    #[serde(default)]
+    pub error: Option<HmrError>,
     pub timestamp: u64,
 }
 
+/// A failed build, as shown by the error overlay of the clients
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HmrError {
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Source lines around the error, with the error line marked
+    pub frame: Option<String>,
+    /// Plugin whose hook failed
+    pub plugin: Option<String>,
+}
+
+impl From<&SokuError> for HmrError {
+    fn from(error: &SokuError) -> Self {
+        let context = error.context();
+        Self {
+            message: error.to_string(),
+            file: context.and_then(|c| c.file_path.clone()),
+            line: context.and_then(|c| c.line),
+            column: context.and_then(|c| c.column),
+            frame: context.and_then(|c| c.code_frame()),
+            plugin: context.and_then(|c| c.plugin.clone()),
+        }
+    }
+}
+
+/// A module accepting the update of one of its dependencies or of itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HmrBoundary {
+    /// Registry id of the accepting module
+    pub boundary: String,
+    /// Registry id of the module it accepts
+    pub accepted: String,
+}
+
+/// Message from the HMR client
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind")]
+enum HmrClientMessage {
+    /// `import.meta.hot.invalidate()`: the module cannot take its update, so
+    /// its importers have to
+    Invalidate { module_id: String },
+}
+
+impl HmrUpdate {
+    /// Compiled module from a build, which replaces its registration in the
+    /// browser, with the modules that accept it
+    pub fn module(path: PathBuf, linking: &ModuleLinking, propagation: Propagation) -> Self {
+        Self {
+            module_id: Some(linking.id.clone()),
+            dependencies: linking.dependencies.clone(),
+            boundaries: propagation.boundaries,
+            stale: propagation.stale,
+            ..Self::new(
+                HmrUpdateKind::ModuleUpdated,
+                path,
+                linking.definition.clone(),
+            )
+        }
+    }
+
+    /// Failed build, with the error in `content` as the terminal shows it
+    pub fn build_error(path: PathBuf, error: &SokuError) -> Self {
+        Self {
+            error: Some(HmrError::from(error)),
+            ..Self::new(
+                HmrUpdateKind::BuildError,
+                error
+                    .context()
+                    .and_then(|context| context.file_path.clone())
+                    .unwrap_or(path),
+                Some(error.format_detailed()),
+            )
+        }
+    }
+
+    /// A new update stamped with the current time
+    pub fn new(kind: HmrUpdateKind, path: PathBuf, content: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            kind,
+            path,
+            content,
+            module_id: None,
+            dependencies: Vec::new(),
+            boundaries: Vec::new(),
+            stale: Vec::new(),
+            error: None,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HmrUpdateKind {
     FileChanged,
@@ -32,6 +144,99 @@ pub enum HmrUpdateKind {
     BuildSuccess,
 }
 
+/// How an update travels from a module to the modules that accept it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Propagation {
+    pub boundaries: Vec<HmrBoundary>,
+    /// Registry ids of the modules to run again
+    pub stale: Vec<String>,
+}
+
+/// Modules of the build the clients run, with their import graph
+#[derive(Default)]
+struct LoadedModules {
+    by_path: HashMap<PathBuf, ModuleLinking>,
+    graph: DependencyGraph,
+}
+
+impl LoadedModules {
+    fn new(result: &BuildResult) -> Self {
+        let by_path: HashMap<PathBuf, ModuleLinking> = result
+            .module_linking
+            .iter()
+            .filter(|linking| linking.definition.is_some())
+            .map(|linking| (linking.path.clone(), linking.clone()))
+            .collect();
+        let paths: HashMap<&str, &Path> = by_path
+            .values()
+            .map(|linking| (linking.id.as_str(), linking.path.as_path()))
+            .collect();
+        let mut graph = DependencyGraph::new();
+        for linking in by_path.values() {
+            for dependency in &linking.dependencies {
+                if let Some(path) = paths.get(dependency.as_str()) {
+                    graph.add_dependency(
+                        linking.path.clone(),
+                        path.to_path_buf(),
+                        DependencyKind::Static,
+                    );
+                }
+            }
+        }
+        Self { by_path, graph }
+    }
+
+    /// Walk from `start` up through its importers to the nearest modules that
+    /// accept the update
+    ///
+    /// An invalidated module already runs its new code, so the walk starts at
+    /// its importers. `None` means an entry module was reached, which only a
+    /// page reload can update.
+    fn propagate(&self, start: &ModuleLinking, invalidated: bool) -> Option<Propagation> {
+        let mut propagation = Propagation::default();
+        let mut visited = HashSet::new();
+        let mut pending = vec![start];
+        while let Some(module) = pending.pop() {
+            if !visited.insert(&module.path) {
+                continue;
+            }
+            if !(invalidated && module.path == start.path) {
+                propagation.stale.push(module.id.clone());
+                if module.hot_accepts.contains(&module.id) {
+                    propagation.boundaries.push(HmrBoundary {
+                        boundary: module.id.clone(),
+                        accepted: module.id.clone(),
+                    });
+                    continue;
+                }
+            }
+
+            let mut importers: Vec<&PathBuf> = self
+                .graph
+                .get_dependents(&module.path)
+                .filter(|importers| !importers.is_empty())?
+                .iter()
+                .collect();
+            importers.sort();
+            for importer in importers {
+                let importer = self.by_path.get(importer)?;
+                if importer.hot_accepts.contains(&module.id) {
+                    let boundary = HmrBoundary {
+                        boundary: importer.id.clone(),
+                        accepted: module.id.clone(),
+                    };
+                    if !propagation.boundaries.contains(&boundary) {
+                        propagation.boundaries.push(boundary);
+                    }
+                } else {
+                    pending.push(importer);
+                }
+            }
+        }
+        Some(propagation)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct HmrClient {
     #[allow(dead_code)] // Used for logging and debugging
@@ -46,6 +251,11 @@ pub struct SokuHmrService {
     update_sender: broadcast::Sender<HmrUpdate>,
     root_path: PathBuf,
     hook_manager: Arc<tokio::sync::Mutex<HmrHookManager>>,
+    /// Modules of the last build, which the clients have loaded
+    modules: Arc<parking_lot::Mutex<LoadedModules>>,
+    /// Error of the last build while it is not fixed, for clients that
+    /// connect after it was sent
+    build_error: Arc<parking_lot::Mutex<Option<HmrUpdate>>>,
 }
 
 impl SokuHmrService {
@@ -57,6 +267,8 @@ impl SokuHmrService {
             update_sender,
             root_path,
             hook_manager: Arc::new(tokio::sync::Mutex::new(HmrHookManager::new())),
+            modules: Arc::default(),
+            build_error: Arc::default(),
         }
     }
 
@@ -117,9 +329,10 @@ impl SokuHmrService {
         while let Ok((stream, addr)) = listener.accept().await {
             let clients = clients.clone();
             let hook_manager = hook_manager.clone();
+            let service = self.clone();
 
             tokio::spawn(async move {
-                if let Err(e) = Self::handle_client(stream, clients, hook_manager).await {
+                if let Err(e) = service.handle_client(stream, clients, hook_manager).await {
                     crate::utils::Logger::warn(&format!("HMR client error {}: {}", addr, e));
                 }
             });
@@ -129,6 +342,7 @@ impl SokuHmrService {
     }
 
     async fn handle_client(
+        &self,
         stream: tokio::net::TcpStream,
         clients: Arc<DashMap<String, HmrClient>>,
         hook_manager: Arc<tokio::sync::Mutex<HmrHookManager>>,
@@ -160,21 +374,20 @@ impl SokuHmrService {
             .await;
 
         // Send initial connection message
-        let welcome = HmrUpdate {
-            id: Uuid::new_v4().to_string(),
-            kind: HmrUpdateKind::ModuleUpdated,
-            path: PathBuf::from("__hmr_connected__"),
-            content: Some("Connected to Soku HMR".to_string()),
-            dependencies: vec![],
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
-        };
+        let welcome = HmrUpdate::new(
+            HmrUpdateKind::ModuleUpdated,
+            PathBuf::from("__hmr_connected__"),
+            Some("Connected to Soku HMR".to_string()),
+        );
 
         if let Ok(welcome_msg) = serde_json::to_string(&welcome) {
             let _ = ws_sender.send(Message::Text(welcome_msg)).await;
         }
+        // A page opened while the build is broken shows its error right away
+        let build_error = self.build_error.lock().clone();
+        if let Some(Ok(message)) = build_error.map(|update| serde_json::to_string(&update)) {
+            let _ = ws_sender.send(Message::Text(message)).await;
+        }
 
         // Spawn task to forward messages from channel to websocket
         let client_id_clone = client_id.clone();
@@ -187,12 +400,15 @@ impl SokuHmrService {
             }
         });
 
-        // Handle incoming client messages (ping/pong)
+        // Handle incoming client messages; ping/pong is handled by tungstenite
         while let Some(msg) = ws_receiver.next().await {
             match msg {
-                Ok(Message::Text(_)) => {
-                    // Ping/pong handled automatically by tungstenite
-                }
+                Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                    Ok(HmrClientMessage::Invalidate { module_id }) => {
+                        self.send(self.invalidation(&module_id));
+                    }
+                    Err(e) => tracing::warn!("Invalid HMR client message: {}", e),
+                },
                 Ok(Message::Close(_)) => break,
                 Err(_) => break,
                 _ => {}
@@ -213,131 +429,105 @@ impl SokuHmrService {
         Ok(())
     }
 
-    /// Start file watching for HMR
-    pub async fn start_watching(&self) -> Result<()> {
-        let update_sender = self.update_sender.clone();
-        let root_path = self.root_path.clone();
-
-        let (tx, mut rx) = tokio::sync::mpsc::channel(1000);
-
-        // Setup file watcher
-        let mut watcher: RecommendedWatcher = Watcher::new(
-            move |res: notify::Result<Event>| {
-                if let Ok(event) = res {
-                    let _ = tx.try_send(event);
-                }
-            },
-            Config::default(),
-        )
-        .map_err(|e| SokuError::build(format!("File watcher setup failed: {}", e)))?;
-
-        // Watch the root directory
-        watcher
-            .watch(&root_path, RecursiveMode::Recursive)
-            .map_err(|e| SokuError::build(format!("Watch setup failed: {}", e)))?;
-
-        tracing::info!("👁️  File watcher started for: {}", root_path.display());
-
-        // Process file events
-        tokio::spawn(async move {
-            while let Some(event) = rx.recv().await {
-                if let Err(e) = Self::process_file_event(event, &update_sender, &root_path).await {
-                    tracing::warn!("HMR event processing error: {}", e);
-                }
-            }
-        });
-
-        // Keep watcher alive
-        tokio::spawn(async move {
-            let _watcher = watcher;
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-            }
-        });
-
-        Ok(())
+    /// Send an update to every connected client
+    ///
+    /// A build error is also sent to the clients that connect until a
+    /// `BuildSuccess` update.
+    pub fn send(&self, update: HmrUpdate) {
+        tracing::info!("🔥 HMR: {:?} - {}", update.kind, update.path.display());
+        match update.kind {
+            HmrUpdateKind::BuildError => *self.build_error.lock() = Some(update.clone()),
+            HmrUpdateKind::BuildSuccess => *self.build_error.lock() = None,
+            _ => {}
+        }
+        // Without connected clients there is nobody to notify
+        let _ = self.update_sender.send(update);
     }
 
-    async fn process_file_event(
-        event: Event,
-        update_sender: &broadcast::Sender<HmrUpdate>,
-        root_path: &Path,
-    ) -> Result<()> {
-        for path in event.paths {
-            // Skip non-source files
-            if !Self::is_source_file(&path) {
-                continue;
-            }
+    /// Remember the modules of a build, as loaded by the clients
+    pub fn record_build(&self, result: &BuildResult) {
+        *self.modules.lock() = LoadedModules::new(result);
+    }
 
-            let relative_path = path.strip_prefix(root_path).unwrap_or(&path).to_path_buf();
-
-            let update_kind = match event.kind {
-                EventKind::Create(_) => HmrUpdateKind::FileAdded,
-                EventKind::Remove(_) => HmrUpdateKind::FileRemoved,
-                EventKind::Modify(_) => {
-                    if Self::is_js_file(&path) {
-                        HmrUpdateKind::ModuleUpdated
-                    } else if Self::is_css_file(&path) {
-                        HmrUpdateKind::CssUpdated
-                    } else {
-                        HmrUpdateKind::FileChanged
-                    }
-                }
-                _ => continue,
-            };
-
-            // Read file content for updates
-            let content = if matches!(update_kind, HmrUpdateKind::FileRemoved) {
-                None
-            } else {
-                tokio::fs::read_to_string(&path).await.ok()
-            };
-
-            let update = HmrUpdate {
-                id: Uuid::new_v4().to_string(),
-                kind: update_kind,
-                path: relative_path,
-                content,
-                dependencies: vec![], // TODO: Extract dependencies
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64,
-            };
-
-            // Send update to all clients
-            if let Err(e) = update_sender.send(update.clone()) {
-                tracing::warn!("Failed to broadcast HMR update: {}", e);
-            } else {
-                tracing::info!("🔥 HMR: {:?} - {}", update.kind, update.path.display());
-            }
+    /// Compiled modules of a rebuild after the scripts in `changed` changed
+    ///
+    /// Each update carries the module's definition as produced by the build's
+    /// processors and linker, and the modules that accept it. `None` means the
+    /// clients cannot be updated in place: a changed file is not a module of
+    /// the bundle, no module accepts the update, or the bundle gained modules
+    /// or renumbered them.
+    pub fn module_updates(
+        &self,
+        changed: &HashSet<PathBuf>,
+        result: &BuildResult,
+    ) -> Option<Vec<HmrUpdate>> {
+        let mut loaded = self.modules.lock();
+        let known = std::mem::replace(&mut *loaded, LoadedModules::new(result));
+        let modules = &*loaded;
+        let renumbered = modules.by_path.values().any(|linking| {
+            known.by_path.get(&linking.path).map(|known| &known.id) != Some(&linking.id)
+        });
+        if known.by_path.is_empty() || renumbered {
+            return None;
         }
 
-        Ok(())
+        let mut paths: Vec<&PathBuf> = changed.iter().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                if !Self::is_js_file(path) {
+                    return None;
+                }
+                let canonical = path.canonicalize().ok()?;
+                let linking = modules.by_path.get(&canonical)?;
+                let propagation = modules.propagate(linking, false)?;
+                let relative = canonical
+                    .strip_prefix(&self.root_path)
+                    .unwrap_or(&canonical)
+                    .to_path_buf();
+                Some(HmrUpdate::module(relative, linking, propagation))
+            })
+            .collect()
     }
 
-    fn is_source_file(path: &Path) -> bool {
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            matches!(
-                ext,
-                "js" | "ts" | "tsx" | "jsx" | "css" | "scss" | "less" | "html" | "vue"
-            )
-        } else {
-            false
-        }
+    /// Update the importers of a module that invalidated itself, or reload
+    /// when none of them accepts it
+    fn invalidation(&self, module_id: &str) -> HmrUpdate {
+        let modules = self.modules.lock();
+        let update = modules
+            .by_path
+            .values()
+            .find(|linking| linking.id == module_id)
+            .and_then(|linking| {
+                let propagation = modules.propagate(linking, true)?;
+                let relative = linking
+                    .path
+                    .strip_prefix(&self.root_path)
+                    .unwrap_or(&linking.path)
+                    .to_path_buf();
+                Some(HmrUpdate {
+                    // The clients already run the module's new code
+                    content: None,
+                    ..HmrUpdate::module(relative, linking, propagation)
+                })
+            });
+        update.unwrap_or_else(|| {
+            HmrUpdate::new(HmrUpdateKind::FullReload, PathBuf::from(module_id), None)
+        })
     }
 
     fn is_js_file(path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            matches!(ext, "js" | "ts" | "tsx" | "jsx")
+            matches!(ext, "js" | "ts" | "tsx" | "jsx" | "mjs" | "cjs")
         } else {
             false
         }
     }
 
-    fn is_css_file(path: &Path) -> bool {
+    pub(crate) fn is_css_file(path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            matches!(ext, "css" | "scss" | "less")
+            matches!(ext, "css" | "scss" | "sass" | "less")
         } else {
             false
         }
@@ -366,4 +556,181 @@ mod tests {
         assert!(SokuHmrService::is_css_file(&PathBuf::from("test.css")));
         assert!(!SokuHmrService::is_js_file(&PathBuf::from("test.txt")));
     }
+
+    /// Build of modules given as (path, id, dependencies, accepted ids)
+    fn build_result(modules: &[(&Path, &str, &[&str], &[&str])]) -> BuildResult {
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect();
+        BuildResult {
+            js_modules_processed: modules.len(),
+            css_files_processed: 0,
+            tree_shaking_stats: None,
+            build_time: std::time::Duration::ZERO,
+            output_files: Vec::new(),
+            success: true,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            modules: Vec::new(),
+            module_linking: modules
+                .iter()
+                .map(|(path, id, dependencies, accepts)| ModuleLinking {
+                    path: path.to_path_buf(),
+                    strategy: crate::core::models::LinkStrategy::Wrapped(
+                        crate::core::models::WrapReason::Hmr,
+                    ),
+                    id: id.to_string(),
+                    dependencies: ids(dependencies),
+                    hot_accepts: ids(accepts),
+                    definition: Some(format!("__soku_modules[\"{}\"] = [];", id)),
+                })
+                .collect(),
+        }
+    }
+
+    fn project(files: &[&str]) -> (tempfile::TempDir, PathBuf, Vec<PathBuf>) {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        let paths = files
+            .iter()
+            .map(|file| {
+                let path = root.join("src").join(file);
+                std::fs::write(&path, "").unwrap();
+                path
+            })
+            .collect();
+        (temp_dir, root, paths)
+    }
+
+    fn boundary(boundary: &str, accepted: &str) -> HmrBoundary {
+        HmrBoundary {
+            boundary: boundary.to_string(),
+            accepted: accepted.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_module_updates_carry_compiled_modules() {
+        let (_dir, root, paths) = project(&["main.ts", "app.tsx", "extra.ts"]);
+        let [main, app, extra] = [&paths[0], &paths[1], &paths[2]];
+        let hmr = SokuHmrService::new(root.clone());
+        let build = build_result(&[
+            (app, "app.tsx", &[], &["app.tsx"]),
+            (main, "main.ts", &["app.tsx"], &[]),
+        ]);
+        hmr.record_build(&build);
+
+        let updates = hmr
+            .module_updates(&HashSet::from([app.clone()]), &build)
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert!(matches!(updates[0].kind, HmrUpdateKind::ModuleUpdated));
+        assert_eq!(updates[0].path, PathBuf::from("src/app.tsx"));
+        assert_eq!(updates[0].module_id.as_deref(), Some("app.tsx"));
+        assert_eq!(
+            updates[0].content.as_deref(),
+            Some("__soku_modules[\"app.tsx\"] = [];")
+        );
+        assert_eq!(updates[0].boundaries, vec![boundary("app.tsx", "app.tsx")]);
+        assert_eq!(updates[0].stale, vec!["app.tsx".to_string()]);
+
+        // Files outside the bundle and new modules need a reload
+        let config = root.join("package.json");
+        std::fs::write(&config, "{}").unwrap();
+        assert!(hmr
+            .module_updates(&HashSet::from([config]), &build)
+            .is_none());
+        let grown = build_result(&[
+            (extra, "extra.ts", &[], &[]),
+            (app, "app.tsx", &["extra.ts"], &["app.tsx"]),
+            (main, "main.ts", &["app.tsx"], &[]),
+        ]);
+        assert!(hmr
+            .module_updates(&HashSet::from([app.clone()]), &grown)
+            .is_none());
+        // ...after which the clients run the grown bundle
+        let updates = hmr
+            .module_updates(&HashSet::from([extra.clone()]), &grown)
+            .unwrap();
+        assert_eq!(updates[0].dependencies, Vec::<String>::new());
+        assert_eq!(updates[0].boundaries, vec![boundary("app.tsx", "app.tsx")]);
+        assert_eq!(
+            updates[0].stale,
+            vec!["extra.ts".to_string(), "app.tsx".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_updates_propagate_to_accepting_importers() {
+        let (_dir, root, paths) = project(&["main.ts", "app.ts", "theme.ts", "util.ts"]);
+        let [main, app, theme, util] = [&paths[0], &paths[1], &paths[2], &paths[3]];
+        let hmr = SokuHmrService::new(root);
+        let build = build_result(&[
+            (theme, "theme.ts", &[], &[]),
+            (util, "util.ts", &[], &[]),
+            (app, "app.ts", &["theme.ts", "util.ts"], &[]),
+            (main, "main.ts", &["app.ts", "util.ts"], &["app.ts"]),
+        ]);
+        hmr.record_build(&build);
+
+        // theme.ts -> app.ts, which main.ts accepts
+        let updates = hmr
+            .module_updates(&HashSet::from([theme.clone()]), &build)
+            .unwrap();
+        assert_eq!(updates[0].boundaries, vec![boundary("main.ts", "app.ts")]);
+        assert_eq!(
+            updates[0].stale,
+            vec!["theme.ts".to_string(), "app.ts".to_string()]
+        );
+
+        // util.ts is also imported by main.ts, which does not accept it
+        assert!(hmr
+            .module_updates(&HashSet::from([util.clone()]), &build)
+            .is_none());
+
+        // An invalidated module hands its update to its importers
+        let update = hmr.invalidation("app.ts");
+        assert!(matches!(update.kind, HmrUpdateKind::ModuleUpdated));
+        assert!(update.content.is_none());
+        assert_eq!(update.boundaries, vec![boundary("main.ts", "app.ts")]);
+        assert!(update.stale.is_empty());
+        assert!(matches!(
+            hmr.invalidation("theme.ts").kind,
+            HmrUpdateKind::ModuleUpdated
+        ));
+        assert!(matches!(
+            hmr.invalidation("main.ts").kind,
+            HmrUpdateKind::FullReload
+        ));
+    }
+
+    #[test]
+    fn test_build_errors_carry_location_and_code_frame() {
+        use crate::infrastructure::processors::js_transformer::transform_module;
+
+        let code = "const a = 1;\nconst b = ;\nexport { a, b };\n";
+        let error =
+            transform_module(code, Path::new("/app/src/main.ts"), &Default::default()).unwrap_err();
+        let update = HmrUpdate::build_error(PathBuf::from("/app/src/other.ts"), &error);
+
+        assert!(matches!(update.kind, HmrUpdateKind::BuildError));
+        assert_eq!(update.path, PathBuf::from("/app/src/main.ts"));
+        assert_eq!(update.content, Some(error.format_detailed()));
+        let payload = update.error.unwrap();
+        assert!(payload.message.contains("Unexpected token"));
+        assert_eq!(payload.file, Some(PathBuf::from("/app/src/main.ts")));
+        assert_eq!((payload.line, payload.column), (Some(2), Some(11)));
+        assert_eq!(
+            payload.frame.as_deref(),
+            Some(
+                "     1 | const a = 1;\n>    2 | const b = ;\n       |           ^\n     3 | export { a, b };\n"
+            )
+        );
+        assert_eq!(payload.plugin, None);
+
+        let error = SokuError::config("missing option".to_string()).in_plugin("svg-loader");
+        let payload = HmrError::from(&error);
+        assert_eq!(payload.plugin.as_deref(), Some("svg-loader"));
+        assert!(payload.message.contains("missing option"));
+        assert!(error.format_detailed().contains("🔌 Plugin: svg-loader"));
+    }
 }
@@ -1,9 +1,57 @@
+use std::path::{Path, PathBuf};
+
+/// Development build of the React Fast Refresh runtime in the `react-refresh`
+/// package, the one React's own tooling installs
+const REACT_REFRESH_RUNTIME_FILE: &str = "react-refresh/cjs/react-refresh-runtime.development.js";
+
+/// Find the `react-refresh` runtime installed for a project, in the
+/// `node_modules` of its root or of a parent directory like Node does
+pub fn find_react_refresh_runtime(root: &Path) -> Option<PathBuf> {
+    root.ancestors()
+        .map(|dir| dir.join("node_modules").join(REACT_REFRESH_RUNTIME_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Install the `react-refresh` runtime before the bundle runs
+///
+/// The CommonJS source gets its own `exports` and a development
+/// `process.env`, then hooks into React through the DevTools global hook,
+/// which React renderers inject themselves into. Modules compiled for HMR
+/// register their components and hook signatures through
+/// `window.__soku_refresh__`; `performReactRefresh` is false when there was
+/// nothing to refresh, so the update falls back to the importers.
+pub fn react_refresh_runtime(source: &str) -> String {
+    format!(
+        r#"// React Fast Refresh runtime (react-refresh)
+(function() {{
+    var module = {{ exports: {{}} }};
+    var exports = module.exports;
+    var process = {{ env: {{ NODE_ENV: 'development' }} }};
+{source}
+    var runtime = module.exports;
+    if (typeof window !== 'undefined') {{
+        runtime.injectIntoGlobalHook(window);
+        window.__soku_refresh__ = {{
+            register: runtime.register,
+            createSignatureFunctionForTransform: runtime.createSignatureFunctionForTransform,
+            performReactRefresh: function() {{
+                return runtime.performReactRefresh() !== null;
+            }}
+        }};
+    }}
+}})();
+"#,
+        source = source
+    )
+}
+
 /// HMR Client-side JavaScript runtime
 /// This generates the JavaScript code that gets injected into the bundle
-/// to enable hot reloading in the browser
-pub fn generate_hmr_client_code(port: u16) -> String {
+/// to enable hot reloading in the browser, after the React Fast Refresh
+/// runtime when the project has one
+pub fn generate_hmr_client_code(port: u16, refresh_runtime: Option<&str>) -> String {
     format!(
-        r#"
+        r#"{refresh}
 // Soku Bundler HMR Client Runtime
 (function() {{
     'use strict';
@@ -83,7 +131,7 @@ pub fn generate_hmr_client_code(port: u16) -> String {
                     break;
 
                 case 'BuildError':
-                    this.showErrorOverlay(update.content || 'Build failed');
+                    this.showErrorOverlay(update.content || 'Build failed', update.error);
                     break;
 
                 case 'BuildSuccess':
@@ -104,23 +152,22 @@ pub fn generate_hmr_client_code(port: u16) -> String {
         }}
 
         updateModule(update) {{
-            const modulePath = update.path;
-            const newContent = update.content;
+            const moduleId = update.module_id;
+            if (!moduleId) {{
+                // Messages that are not about a module, like the welcome message
+                return;
+            }}
 
-            if (!newContent) {{
-                console.warn('[Soku HMR] No content for module update:', modulePath);
+            const runtime = window.__soku_runtime__;
+            if (!runtime || !(moduleId in runtime.modules)) {{
+                // The page does not run a bundle linked for HMR
+                this.performFullReload();
                 return;
             }}
 
             try {{
-                // Attempt hot module replacement
-                if (this.canHotReplace(modulePath)) {{
-                    this.hotReplaceModule(modulePath, newContent);
-                    this.showNotification(`📦 Updated: ${{modulePath}}`, 'info');
-                }} else {{
-                    // Fallback to full reload
-                    this.performFullReload();
-                }}
+                this.hotReplaceModule(update);
+                this.showNotification(`📦 Updated: ${{update.path}}`, 'info');
             }} catch (error) {{
                 console.warn('[Soku HMR] Module update failed:', error);
                 this.performFullReload();
@@ -145,40 +192,78 @@ pub fn generate_hmr_client_code(port: u16) -> String {
             }}
         }}
 
-        canHotReplace(modulePath) {{
-            // Simple heuristic: can hot replace if it's not the main entry
-            // and doesn't contain certain keywords that require full reload
-            const content = this.moduleCache.get(modulePath) || '';
-            const hasComplexUpdates = /(?:class\s+\w+|function\s+\w+|const\s+\w+\s*=\s*\w+|export\s+default)/.test(content);
-
-            return !hasComplexUpdates;
-        }}
-
-        hotReplaceModule(modulePath, newContent) {{
-            // Store the new content
-            this.moduleCache.set(modulePath, newContent);
+        hotReplaceModule(update) {{
+            const runtime = window.__soku_runtime__;
+            const moduleId = update.module_id;
+
+            // Accept handlers belong to the running instances of the boundaries
+            const handlers = update.boundaries.map(({{ boundary, accepted }}) => {{
+                const instance = runtime.cache[boundary];
+                const accepts = instance ? instance.hot.accepts : [];
+                return {{
+                    boundary,
+                    accepted,
+                    running: !!instance,
+                    accepts: accepts.filter(accept => accept.ids.includes(accepted))
+                }};
+            }});
+            const declined = handlers.find(handler => handler.running && handler.accepts.length === 0);
+            if (declined) {{
+                // The running instance did not call `accept`, e.g. a component
+                // module on a page without React
+                throw new Error(`${{declined.boundary}} does not accept ${{declined.accepted}}`);
+            }}
 
-            // Create a new script element with the updated content
-            const script = document.createElement('script');
-            script.type = 'module';
-            script.textContent = newContent;
+            // Dispose of the stale instances, handing their data to the next ones
+            for (const id of update.stale) {{
+                const instance = runtime.cache[id];
+                if (!instance) {{
+                    continue;
+                }}
+                instance.hot.disposers.forEach(dispose => dispose(instance.hot.data));
+                runtime.data[id] = instance.hot.data;
+                delete runtime.cache[id];
+            }}
 
-            // Remove old script if exists
-            const oldScript = document.querySelector(`script[data-soku-module="${{modulePath}}"]`);
-            if (oldScript) {{
-                oldScript.remove();
+            // Register the compiled module; invalidations have no new code
+            if (update.content) {{
+                runtime.define(update.content);
+                this.moduleCache.set(moduleId, update.content);
             }}
 
-            // Add new script
-            script.setAttribute('data-soku-module', modulePath);
-            document.head.appendChild(script);
+            // Run the accepted modules again and hand them to the boundaries
+            for (const {{ accepted, accepts }} of handlers) {{
+                const exports = runtime.require(accepted);
+                for (const accept of accepts) {{
+                    if (!accept.callback) {{
+                        continue;
+                    }}
+                    accept.callback(accept.single
+                        ? exports
+                        : accept.ids.map(id => id === accepted ? exports : runtime.require(id)));
+                }}
+            }}
 
             // Trigger custom event for application to handle
             window.dispatchEvent(new CustomEvent('soku-hmr-module-updated', {{
-                detail: {{ modulePath, content: newContent }}
+                detail: {{
+                    modulePath: update.path,
+                    moduleId,
+                    dependencies: update.dependencies,
+                    boundaries: update.boundaries
+                }}
             }}));
         }}
 
+        invalidate(moduleId) {{
+            console.log(`[Soku HMR] ${{moduleId}} invalidated its update`);
+            if (!this.isConnected) {{
+                this.performFullReload();
+                return;
+            }}
+            this.ws.send(JSON.stringify({{ kind: 'Invalidate', module_id: moduleId }}));
+        }}
+
         hotReplaceCss(cssPath, newContent) {{
             // Find existing CSS link or style tag
             let cssElement = this.cssLinks.get(cssPath);
@@ -297,7 +382,7 @@ pub fn generate_hmr_client_code(port: u16) -> String {
             }}, 3000);
         }}
 
-        showErrorOverlay(errorMessage) {{
+        showErrorOverlay(errorMessage, error = null) {{
             // Remove existing overlay
             this.hideErrorOverlay();
 
@@ -332,7 +417,9 @@ pub fn generate_hmr_client_code(port: u16) -> String {
                 font-size: 24px;
                 font-weight: bold;
             `;
-            header.textContent = '❌ Build Failed';
+            header.textContent = error && error.plugin
+                ? `❌ Build Failed [plugin ${{error.plugin}}]`
+                : '❌ Build Failed';
 
             const message = document.createElement('pre');
             message.style.cssText = `
@@ -345,7 +432,41 @@ pub fn generate_hmr_client_code(port: u16) -> String {
                 margin: 0;
                 line-height: 1.5;
             `;
-            message.textContent = errorMessage;
+            message.textContent = error ? error.message : errorMessage;
+
+            // Structured build errors point at the code that failed
+            let location = null;
+            if (error && error.file) {{
+                location = document.createElement('div');
+                location.style.cssText = `
+                    color: #8be9fd;
+                    margin: 0 0 10px 0;
+                `;
+                location.textContent = error.line
+                    ? `${{error.file}}:${{error.line}}:${{error.column || 1}}`
+                    : error.file;
+            }}
+            let frame = null;
+            if (error && error.frame) {{
+                frame = document.createElement('pre');
+                frame.style.cssText = `
+                    color: #f8f8f2;
+                    background: rgba(255, 255, 255, 0.05);
+                    padding: 15px;
+                    border-radius: 4px;
+                    margin: 15px 0 0 0;
+                    line-height: 1.5;
+                    overflow-x: auto;
+                `;
+                for (const line of error.frame.replace(/\n$/, '').split('\n')) {{
+                    const row = document.createElement('div');
+                    row.textContent = line;
+                    if (line.startsWith('>') || /^\s+\|\s*\^$/.test(line)) {{
+                        row.style.color = '#ff5555';
+                    }}
+                    frame.appendChild(row);
+                }}
+            }}
 
             const tip = document.createElement('p');
             tip.style.cssText = `
@@ -356,7 +477,13 @@ pub fn generate_hmr_client_code(port: u16) -> String {
             tip.textContent = '💡 Fix the error and save the file to continue';
 
             content.appendChild(header);
+            if (location) {{
+                content.appendChild(location);
+            }}
             content.appendChild(message);
+            if (frame) {{
+                content.appendChild(frame);
+            }}
             content.appendChild(tip);
             overlay.appendChild(content);
             document.body.appendChild(overlay);
@@ -415,6 +542,41 @@ pub fn generate_hmr_client_code(port: u16) -> String {
     }}
 }})();
 "#,
-        port = port
+        port = port,
+        refresh = refresh_runtime.unwrap_or_default()
     )
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_react_refresh_runtime_like_node() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = dir
+            .path()
+            .join("node_modules")
+            .join(REACT_REFRESH_RUNTIME_FILE);
+        std::fs::create_dir_all(runtime.parent().unwrap()).unwrap();
+        std::fs::write(&runtime, "exports.register = function () {};").unwrap();
+        let app = dir.path().join("packages/web");
+        std::fs::create_dir_all(&app).unwrap();
+
+        assert_eq!(find_react_refresh_runtime(&app), Some(runtime));
+        let other = tempfile::tempdir().unwrap();
+        assert_eq!(find_react_refresh_runtime(other.path()), None);
+    }
+
+    #[test]
+    fn test_react_refresh_runtime_is_installed_before_the_client() {
+        let runtime = react_refresh_runtime("exports.register = function () {};");
+        let client = generate_hmr_client_code(3001, Some(&runtime));
+        let source = client.find("exports.register").unwrap();
+        assert!(source < client.find("window.__soku_refresh__ =").unwrap());
+        assert!(source < client.find("Soku Bundler HMR Client Runtime").unwrap());
+        assert!(runtime.contains("NODE_ENV: 'development'"));
+
+        let client = generate_hmr_client_code(3001, None);
+        assert!(!client.contains("__soku_refresh__"));
+    }
+}
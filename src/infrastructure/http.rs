@@ -0,0 +1,482 @@
+// Minimal HTTP/1.1 server shared by `soku dev` and `soku preview`
+// Only what serving static files needs: request heads, keep-alive and
+// responses with a body that is fully in memory
+
+use crate::utils::{Result, SokuError};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Requests with a head larger than this are rejected
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Error of `HttpRequest::read` for a head over `MAX_HEAD_SIZE`, answered with 431
+#[derive(Debug)]
+struct HeadTooLarge;
+
+impl std::fmt::Display for HeadTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP request head larger than {} bytes", MAX_HEAD_SIZE)
+    }
+}
+
+impl std::error::Error for HeadTooLarge {}
+
+impl HeadTooLarge {
+    fn is(error: &SokuError) -> bool {
+        matches!(error, SokuError::Io(io) if io.get_ref().is_some_and(|e| e.is::<HeadTooLarge>()))
+    }
+}
+
+/// A parsed request head; request bodies are read and dropped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    /// Percent-decoded path, without the query string
+    pub path: String,
+    pub query: Option<String>,
+    pub version: String,
+    /// Headers by lowercase name
+    pub headers: HashMap<String, String>,
+}
+
+impl HttpRequest {
+    /// Read the next request of a connection; `None` when the client closed it
+    pub async fn read<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Self>> {
+        let mut head = Vec::new();
+        loop {
+            let start = head.len();
+            // A line never ends past the limit, so a client cannot make the
+            // head grow without bound by leaving out newlines
+            let limit = (MAX_HEAD_SIZE + 1 - start) as u64;
+            if (&mut *reader)
+                .take(limit)
+                .read_until(b'\n', &mut head)
+                .await?
+                == 0
+            {
+                return if head.iter().all(u8::is_ascii_whitespace) {
+                    Ok(None)
+                } else {
+                    Err(SokuError::build("Incomplete HTTP request".to_string()))
+                };
+            }
+            if head.len() > MAX_HEAD_SIZE {
+                return Err(
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, HeadTooLarge).into(),
+                );
+            }
+            let line = &head[start..];
+            // Blank lines before the request line are allowed
+            if (line == b"\r\n" || line == b"\n") && start > 0 {
+                break;
+            }
+            if line == b"\r\n" || line == b"\n" {
+                head.clear();
+            }
+        }
+
+        let head = String::from_utf8_lossy(&head);
+        let mut lines = head.lines();
+        let request_line = lines.next().unwrap_or_default();
+        let mut parts = request_line.split_whitespace();
+        let (Some(method), Some(target), Some(version)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(SokuError::build(format!(
+                "Malformed HTTP request line: {}",
+                request_line
+            )));
+        };
+
+        let mut headers = HashMap::new();
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        let request = Self {
+            method: method.to_ascii_uppercase(),
+            path: percent_decode(path),
+            query,
+            version: version.to_string(),
+            headers,
+        };
+
+        // Drop the body so the next request of the connection can be read
+        let body_length = request
+            .header("content-length")
+            .and_then(|length| length.parse::<u64>().ok())
+            .unwrap_or(0);
+        if body_length > 0 {
+            let mut body = (&mut *reader).take(body_length);
+            tokio::io::copy(&mut body, &mut tokio::io::sink()).await?;
+        }
+
+        Ok(Some(request))
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+
+    /// Whether the connection stays open after the response
+    pub fn keep_alive(&self) -> bool {
+        let connection = self.header("connection").unwrap_or("").to_ascii_lowercase();
+        if self.version == "HTTP/1.0" {
+            connection == "keep-alive"
+        } else {
+            connection != "close"
+        }
+    }
+
+    /// Whether the client asks for a page, e.g. a navigation in the browser
+    pub fn accepts_html(&self) -> bool {
+        self.header("accept")
+            .is_some_and(|accept| accept.contains("text/html"))
+    }
+
+    pub fn is_head(&self) -> bool {
+        self.method == "HEAD"
+    }
+}
+
+/// A response with its whole body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// A response with `body` of the given content type
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200)
+            .with_header("Content-Type", content_type)
+            .with_body(body)
+    }
+
+    /// A plain text response, e.g. for errors
+    pub fn text(status: u16, text: &str) -> Self {
+        Self::new(status)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(text)
+    }
+
+    pub fn not_found() -> Self {
+        Self::text(404, "Not Found")
+    }
+
+    pub fn method_not_allowed() -> Self {
+        Self::text(405, "Method Not Allowed").with_header("Allow", "GET, HEAD")
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Write the response; HEAD responses keep the headers of GET but no body
+    pub async fn write_to<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        head_only: bool,
+        keep_alive: bool,
+    ) -> std::io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\n",
+            self.status,
+            reason_phrase(self.status)
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        // Bodiless statuses describe the resource without sending it
+        if self.header("content-length").is_none() && !matches!(self.status, 204 | 304) {
+            head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        head.push_str(if keep_alive {
+            "Connection: keep-alive\r\n\r\n"
+        } else {
+            "Connection: close\r\n\r\n"
+        });
+
+        writer.write_all(head.as_bytes()).await?;
+        if !head_only {
+            writer.write_all(&self.body).await?;
+        }
+        writer.flush().await
+    }
+}
+
+/// Answer the requests of every connection to `listener` with `handler`
+pub async fn serve<H, F>(listener: TcpListener, handler: H) -> Result<()>
+where
+    H: Fn(HttpRequest) -> F + Send + Sync + 'static,
+    F: Future<Output = HttpResponse> + Send,
+{
+    let handler = Arc::new(handler);
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = tokio::io::BufReader::new(reader);
+            loop {
+                let request = match HttpRequest::read(&mut reader).await {
+                    Ok(Some(request)) => request,
+                    Ok(None) => break,
+                    Err(e) => {
+                        crate::utils::Logger::debug(&format!("HTTP error from {}: {}", addr, e));
+                        let response = if HeadTooLarge::is(&e) {
+                            HttpResponse::text(431, "Request Header Fields Too Large")
+                        } else {
+                            HttpResponse::text(400, "Bad Request")
+                        };
+                        let _ = response.write_to(&mut writer, false, false).await;
+                        break;
+                    }
+                };
+
+                let keep_alive = request.keep_alive();
+                let head_only = request.is_head();
+                let response = handler(request).await;
+                if response
+                    .write_to(&mut writer, head_only, keep_alive)
+                    .await
+                    .is_err()
+                    || !keep_alive
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// File of `dir` that the URL `path` points at
+///
+/// Directories resolve to their `index.html`. Paths leaving `dir` (`..`),
+/// hidden files and directories such as `.env` or `.git/` and missing files
+/// resolve to `None`.
+pub fn resolve_file(dir: &Path, path: &str) -> Option<PathBuf> {
+    if is_hidden_path(path) {
+        return None;
+    }
+    let mut file = dir.to_path_buf();
+    for component in Path::new(path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => file.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if file.is_dir() {
+        file.push("index.html");
+    }
+    file.is_file().then_some(file)
+}
+
+/// Whether a URL path has a segment starting with `.`, such as `/.env` or
+/// `/.git/config`
+pub fn is_hidden_path(path: &str) -> bool {
+    path.split('/').any(|segment| segment.starts_with('.'))
+}
+
+/// Content type of a file by extension
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" | "cjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json; charset=utf-8",
+        "webmanifest" => "application/manifest+json; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Decode `%XX` escapes of a URL path; invalid escapes are kept as they are
+pub fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_requests_of_a_connection() {
+        let mut input: &[u8] = b"GET /assets/a%20b.js?v=1 HTTP/1.1\r\nHost: localhost\r\nAccept: text/html\r\n\r\nPOST /api HTTP/1.0\r\nContent-Length: 5\r\n\r\nhelloHEAD / HTTP/1.1\r\nConnection: close\r\n\r\n";
+
+        let first = HttpRequest::read(&mut input).await.unwrap().unwrap();
+        assert_eq!(first.method, "GET");
+        assert_eq!(first.path, "/assets/a b.js");
+        assert_eq!(first.query.as_deref(), Some("v=1"));
+        assert_eq!(first.header("host"), Some("localhost"));
+        assert!(first.accepts_html() && first.keep_alive());
+
+        let second = HttpRequest::read(&mut input).await.unwrap().unwrap();
+        assert_eq!(
+            (second.method.as_str(), second.path.as_str()),
+            ("POST", "/api")
+        );
+        assert!(!second.keep_alive());
+
+        let third = HttpRequest::read(&mut input).await.unwrap().unwrap();
+        assert!(third.is_head() && !third.keep_alive());
+        assert!(HttpRequest::read(&mut input).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_head_without_newlines_is_limited() {
+        // An endless request line must not be buffered until memory runs out
+        let mut input = tokio::io::BufReader::new(tokio::io::repeat(b'a'));
+        let error = HttpRequest::read(&mut input).await.unwrap_err();
+        assert!(HeadTooLarge::is(&error), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_write_response() {
+        let response = HttpResponse::ok("text/plain", "hi");
+        let mut output = Vec::new();
+        response.write_to(&mut output, false, true).await.unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: keep-alive\r\n\r\nhi"
+        );
+
+        let mut output = Vec::new();
+        response.write_to(&mut output, true, false).await.unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .ends_with("Content-Length: 2\r\nConnection: close\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_resolve_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/index.html"), "<p>docs</p>").unwrap();
+        std::fs::write(dir.path().join("app.js"), "run()").unwrap();
+
+        assert_eq!(
+            resolve_file(dir.path(), "/app.js"),
+            Some(dir.path().join("app.js"))
+        );
+        assert_eq!(
+            resolve_file(dir.path(), "/docs/"),
+            Some(dir.path().join("docs/index.html"))
+        );
+        assert_eq!(resolve_file(dir.path(), "/missing.js"), None);
+        assert_eq!(resolve_file(dir.path(), "/../etc/passwd"), None);
+
+        std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".git/config"), "").unwrap();
+        std::fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+        assert_eq!(resolve_file(dir.path(), "/.env"), None);
+        assert_eq!(resolve_file(dir.path(), "/.git/config"), None);
+    }
+
+    #[test]
+    fn test_mime_type() {
+        assert_eq!(
+            mime_type(Path::new("bundle.js")),
+            "text/javascript; charset=utf-8"
+        );
+        assert_eq!(mime_type(Path::new("logo.SVG")), "image/svg+xml");
+        assert_eq!(mime_type(Path::new("app.wasm")), "application/wasm");
+        assert_eq!(mime_type(Path::new("LICENSE")), "application/octet-stream");
+    }
+}
@@ -1,16 +1,21 @@
 // Infrastructure layer
+pub mod dev_server;
 pub mod file_system;
 pub mod hmr;
 pub mod hmr_client;
 pub mod hmr_hooks;
+pub mod http;
 pub mod node_resolver;
+pub mod preview_server;
 pub mod processors;
 pub mod soku_file_system;
 
+pub use dev_server::*;
 pub use file_system::*;
 pub use hmr::*;
 pub use hmr_client::*;
 pub use hmr_hooks::*;
 pub use node_resolver::*;
+pub use preview_server::*;
 pub use processors::*;
 pub use soku_file_system::*;
@@ -265,8 +265,8 @@ impl NodeModuleResolver {
             .await
     }
 
-    /// Try to resolve as file or directory
-    async fn resolve_file_or_directory(&self, path: &Path) -> Option<PathBuf> {
+    /// Try to resolve as file or directory, adding extensions and index files
+    pub async fn resolve_file_or_directory(&self, path: &Path) -> Option<PathBuf> {
         // Try as file first
         if let Some(file) = self.resolve_as_file(path).await {
             return Some(file);
@@ -0,0 +1,431 @@
+// Static server for `soku preview`
+// Serves a production build the way a CDN would: validators, byte ranges,
+// gzip and long-lived caching of the files the build manifest lists as
+// content-hashed
+
+use crate::infrastructure::http::{self, HttpRequest, HttpResponse};
+use crate::utils::{BuildManifest, Logger, Result, SokuError};
+use flate2::{write::GzEncoder, Compression};
+use std::io::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::net::TcpListener;
+
+/// Smaller bodies are not worth compressing
+const MIN_GZIP_SIZE: usize = 1024;
+
+/// Cache-Control of files whose name contains the hash of their content
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// HTTP server of a built output directory
+#[derive(Clone)]
+pub struct PreviewServer {
+    dir: Arc<PathBuf>,
+}
+
+impl PreviewServer {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir: Arc::new(dir) }
+    }
+
+    /// Bind the HTTP server to `host:port`
+    pub async fn bind(host: IpAddr, port: u16) -> Result<TcpListener> {
+        TcpListener::bind(SocketAddr::new(host, port))
+            .await
+            .map_err(|e| {
+                SokuError::build(format!(
+                    "Preview server bind failed on {}:{}: {}",
+                    host, port, e
+                ))
+            })
+    }
+
+    /// Serve the directory until the listener fails
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let server = self.clone();
+        http::serve(listener, move |request| {
+            let server = server.clone();
+            async move { server.respond(&request).await }
+        })
+        .await
+    }
+
+    /// Answer a request from the directory
+    ///
+    /// Unknown paths that ask for a page get `index.html`, so client-side
+    /// routers work on reload.
+    pub async fn respond(&self, request: &HttpRequest) -> HttpResponse {
+        if request.method != "GET" && request.method != "HEAD" {
+            return HttpResponse::method_not_allowed();
+        }
+
+        let file = http::resolve_file(&self.dir, &request.path).or_else(|| {
+            let wants_page =
+                request.accepts_html() || Path::new(&request.path).extension().is_none();
+            let index = self.dir.join("index.html");
+            (wants_page && index.is_file()).then_some(index)
+        });
+        match file {
+            Some(file) => {
+                match Self::file_response(request, &file, self.is_hashed(&file).await).await {
+                    Ok(response) => response,
+                    Err(e) => {
+                        Logger::warn(&format!("Cannot read {}: {}", file.display(), e));
+                        HttpResponse::text(500, "Internal Server Error")
+                    }
+                }
+            }
+            None => HttpResponse::not_found(),
+        }
+    }
+
+    /// Whether `manifest.json` lists the file as named after the `[hash]` of
+    /// its content, so the content never changes under that name
+    ///
+    /// Names are not guessed: `report-20261017.js` may be rewritten in place.
+    async fn is_hashed(&self, file: &Path) -> bool {
+        let Ok(name) = file.strip_prefix(self.dir.as_path()) else {
+            return false;
+        };
+        BuildManifest::read_entries(&self.dir)
+            .await
+            .is_some_and(|entries| {
+                entries
+                    .values()
+                    .any(|entry| entry.hashed && Path::new(&entry.file) == name)
+            })
+    }
+
+    async fn file_response(
+        request: &HttpRequest,
+        file: &Path,
+        hashed: bool,
+    ) -> Result<HttpResponse> {
+        let metadata = tokio::fs::metadata(file).await?;
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let content_type = http::mime_type(file);
+        let gzip = is_compressible(content_type)
+            && metadata.len() as usize >= MIN_GZIP_SIZE
+            && accepts_gzip(request)
+            && request.header("range").is_none();
+
+        // Each encoding is a different representation with its own tag
+        let mtime = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let etag = format!(
+            "\"{:x}-{:x}{}\"",
+            metadata.len(),
+            mtime,
+            if gzip { "-gz" } else { "" }
+        );
+        let last_modified = httpdate::fmt_http_date(modified);
+        let cache_control = if hashed { IMMUTABLE } else { "no-cache" };
+
+        let mut response = HttpResponse::new(200)
+            .with_header("ETag", etag.clone())
+            .with_header("Last-Modified", last_modified.clone())
+            .with_header("Cache-Control", cache_control);
+        if is_compressible(content_type) {
+            response = response.with_header("Vary", "Accept-Encoding");
+        }
+
+        if is_not_modified(request, &etag, modified) {
+            response.status = 304;
+            return Ok(response);
+        }
+
+        let content = tokio::fs::read(file).await?;
+        response = response
+            .with_header("Content-Type", content_type)
+            .with_header("Accept-Ranges", "bytes");
+
+        // A stale If-Range asks for the whole new file instead of a part
+        let range_applies = request
+            .header("if-range")
+            .is_none_or(|validator| validator == etag || validator == last_modified);
+        match request.header("range").filter(|_| range_applies) {
+            Some(range) => match parse_range(range, content.len()) {
+                Some(ByteRange::Satisfiable(range)) => {
+                    response.status = 206;
+                    Ok(response
+                        .with_header(
+                            "Content-Range",
+                            format!("bytes {}-{}/{}", range.start, range.end - 1, content.len()),
+                        )
+                        .with_body(&content[range]))
+                }
+                Some(ByteRange::Unsatisfiable) => Ok(HttpResponse::new(416)
+                    .with_header("Content-Range", format!("bytes */{}", content.len()))),
+                None => Ok(response.with_body(content)),
+            },
+            None if gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&content)?;
+                Ok(response
+                    .with_header("Content-Encoding", "gzip")
+                    .with_body(encoder.finish()?))
+            }
+            None => Ok(response.with_body(content)),
+        }
+    }
+}
+
+/// IPv4 addresses of the network interfaces other than loopback
+pub fn lan_addresses() -> Vec<IpAddr> {
+    let mut addresses: Vec<IpAddr> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|interface| !interface.is_loopback())
+        .map(|interface| interface.ip())
+        .filter(IpAddr::is_ipv4)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// A `Range` header resolved against the length of the file
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    Satisfiable(Range<usize>),
+    Unsatisfiable,
+}
+
+/// Parse a single `bytes=` range; `None` means the header is ignored and the
+/// whole file is sent, as for multiple ranges or other units
+fn parse_range(header: &str, length: usize) -> Option<ByteRange> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = if start.is_empty() {
+        // The last `end` bytes
+        let suffix: usize = end.parse().ok()?;
+        if suffix == 0 {
+            return Some(ByteRange::Unsatisfiable);
+        }
+        length.saturating_sub(suffix)..length
+    } else {
+        let start: usize = start.parse().ok()?;
+        let end = if end.is_empty() {
+            length
+        } else {
+            let end: usize = end.parse().ok()?;
+            if end < start {
+                return None;
+            }
+            (end + 1).min(length)
+        };
+        start..end
+    };
+
+    if range.start >= length {
+        Some(ByteRange::Unsatisfiable)
+    } else {
+        Some(ByteRange::Satisfiable(range))
+    }
+}
+
+/// Whether the client's cached copy is still current
+fn is_not_modified(request: &HttpRequest, etag: &str, modified: SystemTime) -> bool {
+    // If-None-Match takes precedence over If-Modified-Since
+    if let Some(tags) = request.header("if-none-match") {
+        return tags
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+    request
+        .header("if-modified-since")
+        .and_then(|since| httpdate::parse_http_date(since).ok())
+        .and_then(|since| {
+            // HTTP dates have a precision of a second
+            let modified = modified.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+            let since = since.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+            Some(modified.as_secs() <= since.as_secs())
+        })
+        .unwrap_or(false)
+}
+
+fn accepts_gzip(request: &HttpRequest) -> bool {
+    request.header("accept-encoding").is_some_and(|encodings| {
+        encodings.split(',').any(|encoding| {
+            let mut parts = encoding.split(';');
+            let name = parts.next().unwrap_or("").trim();
+            let refused = parts.any(|param| param.trim().replace(' ', "") == "q=0");
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        })
+    })
+}
+
+fn is_compressible(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || content_type.starts_with("application/json")
+        || content_type.starts_with("application/manifest+json")
+        || content_type.starts_with("application/xml")
+        || content_type == "image/svg+xml"
+        || content_type == "application/wasm"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn request(path: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            query: None,
+            version: "HTTP/1.1".to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        use ByteRange::*;
+        assert_eq!(parse_range("bytes=0-99", 1000), Some(Satisfiable(0..100)));
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            Some(Satisfiable(900..1000))
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            Some(Satisfiable(900..1000))
+        );
+        assert_eq!(
+            parse_range("bytes=990-2000", 1000),
+            Some(Satisfiable(990..1000))
+        );
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(Unsatisfiable));
+        assert_eq!(parse_range("bytes=0-1,5-9", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+    }
+
+    #[tokio::test]
+    async fn test_serves_files_with_validators_ranges_and_gzip() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = "console.log('preview');\n".repeat(100);
+        std::fs::write(dir.path().join("app.8b41e0aa.js"), &script).unwrap();
+        std::fs::write(dir.path().join("index.html"), "<p>home</p>").unwrap();
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            r#"{"src/main.js": {"file": "app.8b41e0aa.js", "isEntry": true, "hashed": true}}"#,
+        )
+        .unwrap();
+        let server = PreviewServer::new(dir.path().to_path_buf());
+
+        let response = server.respond(&request("/app.8b41e0aa.js", &[])).await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("cache-control"), Some(IMMUTABLE));
+        assert_eq!(response.body, script.as_bytes());
+        let etag = response.header("etag").unwrap().to_string();
+        let last_modified = response.header("last-modified").unwrap().to_string();
+
+        let response = server
+            .respond(&request("/app.8b41e0aa.js", &[("if-none-match", &etag)]))
+            .await;
+        assert_eq!(response.status, 304);
+        assert!(response.body.is_empty());
+        let response = server
+            .respond(&request(
+                "/app.8b41e0aa.js",
+                &[("if-modified-since", &last_modified)],
+            ))
+            .await;
+        assert_eq!(response.status, 304);
+
+        let response = server
+            .respond(&request("/app.8b41e0aa.js", &[("range", "bytes=0-6")]))
+            .await;
+        assert_eq!(response.status, 206);
+        assert_eq!(response.body, b"console");
+        assert_eq!(
+            response.header("content-range").unwrap(),
+            format!("bytes 0-6/{}", script.len())
+        );
+
+        let response = server
+            .respond(&request(
+                "/app.8b41e0aa.js",
+                &[("accept-encoding", "gzip, deflate, br")],
+            ))
+            .await;
+        assert_eq!(response.header("content-encoding"), Some("gzip"));
+        assert_ne!(response.header("etag").unwrap(), etag);
+        let mut decoded = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(&response.body[..]),
+            &mut decoded,
+        )
+        .unwrap();
+        assert_eq!(decoded, script);
+    }
+
+    #[tokio::test]
+    async fn test_only_hashed_outputs_of_the_manifest_are_immutable() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("assets")).unwrap();
+        for name in [
+            "assets/app.8b41e0aa.js",
+            "report-20261017.js",
+            "build-12345678.css",
+            "vendor.0a1b2c3d.js",
+        ] {
+            std::fs::write(dir.path().join(name), "content").unwrap();
+        }
+        std::fs::write(
+            dir.path().join("manifest.json"),
+            r#"{
+                "src/main.js": {"file": "assets/app.8b41e0aa.js", "isEntry": true, "hashed": true},
+                "src/report.js": {"file": "report-20261017.js", "isEntry": true},
+                "src/build.css": {"file": "build-12345678.css"}
+            }"#,
+        )
+        .unwrap();
+        let server = PreviewServer::new(dir.path().to_path_buf());
+
+        let cache_control = |path: &'static str| {
+            let server = server.clone();
+            async move {
+                let response = server.respond(&request(path, &[])).await;
+                assert_eq!(response.status, 200);
+                response.header("cache-control").unwrap().to_string()
+            }
+        };
+        assert_eq!(cache_control("/assets/app.8b41e0aa.js").await, IMMUTABLE);
+        assert_eq!(cache_control("/report-20261017.js").await, "no-cache");
+        assert_eq!(cache_control("/build-12345678.css").await, "no-cache");
+        // Not an output of the build
+        assert_eq!(cache_control("/vendor.0a1b2c3d.js").await, "no-cache");
+    }
+
+    #[tokio::test]
+    async fn test_spa_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("index.html"), "<p>home</p>").unwrap();
+        let server = PreviewServer::new(dir.path().to_path_buf());
+
+        let response = server
+            .respond(&request("/settings/profile", &[("accept", "text/html")]))
+            .await;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"<p>home</p>");
+        assert_eq!(response.header("cache-control"), Some("no-cache"));
+
+        let response = server.respond(&request("/missing.png", &[])).await;
+        assert_eq!(response.status, 404);
+    }
+}
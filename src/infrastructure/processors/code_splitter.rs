@@ -1,13 +1,14 @@
 // Code splitting functionality - currently unused but kept for future implementation
 use crate::core::models::ModuleInfo;
 use crate::utils::Result;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
 
 /// Smart code splitter for creating optimized bundle chunks
 #[allow(dead_code)]
 pub struct CodeSplitter {
     /// Map of chunk name to modules
-    chunks: HashMap<String, Vec<ModuleInfo>>,
+    chunks: BTreeMap<String, Vec<ModuleInfo>>,
     /// Map of module to chunk assignment
     module_chunk_map: HashMap<String, String>,
     /// Configuration for splitting strategies
@@ -64,7 +65,7 @@ pub enum ChunkType {
 impl CodeSplitter {
     pub fn new(config: CodeSplitConfig) -> Self {
         Self {
-            chunks: HashMap::new(),
+            chunks: BTreeMap::new(),
             module_chunk_map: HashMap::new(),
             config,
         }
@@ -105,12 +106,9 @@ impl CodeSplitter {
         modules
             .iter()
             .filter(|module| {
-                let path_str = module.path.to_string_lossy();
-                entry_points.iter().any(|entry| {
-                    path_str.contains(entry)
-                        || path_str.contains("main")
-                        || path_str.contains("index")
-                })
+                entry_points
+                    .iter()
+                    .any(|entry| module.path == Path::new(entry))
             })
             .cloned()
             .collect()
@@ -240,7 +238,7 @@ impl CodeSplitter {
 
     /// Find the best chunk for a module based on its dependencies
     fn find_best_chunk_for_module(&self, module: &ModuleInfo) -> Option<String> {
-        let mut chunk_scores = HashMap::new();
+        let mut chunk_scores = BTreeMap::new();
 
         // Score chunks based on dependency overlap
         for dep in &module.dependencies {
@@ -417,7 +415,7 @@ impl CodeSplitter {
 
     /// Extract dependencies for a chunk
     fn extract_chunk_dependencies(&self, modules: &[ModuleInfo]) -> Vec<String> {
-        let mut deps = HashSet::new();
+        let mut deps = BTreeSet::new();
         for module in modules {
             for dep in &module.dependencies {
                 deps.insert(dep.clone());
@@ -472,6 +470,7 @@ mod tests {
             module_type: ModuleType::JavaScript,
             dependencies: deps,
             exports: Vec::new(),
+            resolved_imports: std::collections::HashMap::new(),
         }
     }
 
@@ -1,17 +1,27 @@
+use super::js_transformer::{
+    has_newer_syntax, needs_transform, transform_module, transform_module_with_map,
+};
+use super::module_linker::{LinkOutput, ModuleLinker};
+use super::output_format::{format_bundle, format_bundle_with_map, format_chunks};
 use crate::core::{interfaces::JsProcessor, models::*};
 use crate::utils::performance::SokuCache;
-use crate::utils::{ErrorContext, Logger, Result, SokuError};
+use crate::utils::{
+    module_display_name, parse_target, CodeMap, ErrorContext, Logger, Result, SokuError,
+};
 use async_trait::async_trait;
-use once_cell::sync::Lazy;
 use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Argument, CallExpression, ExportAllDeclaration, ExportNamedDeclaration, Expression,
+    ImportDeclaration, ImportExpression, TSImportEqualsDeclaration, TSModuleReference,
+};
+use oxc_ast_visit::{walk, Visit};
 use oxc_diagnostics::OxcDiagnostic;
 use oxc_parser::Parser;
-use oxc_span::SourceType;
-use regex::Regex;
+use oxc_span::{SourceType, Span};
 /// Shared functionality between JS processors
 /// This module contains common code extracted from js_processor.rs and enhanced_js_processor.rs
 /// to eliminate duplication and provide a single source of truth.
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 // ============================================================================
@@ -27,9 +37,9 @@ pub enum ProcessingStrategy {
     /// - No JSX transformation
     Fast,
 
-    /// Standard mode: Basic TypeScript stripping
-    /// - TypeScript type annotations removed
-    /// - Basic JSX support
+    /// Standard mode: TypeScript and JSX compiled
+    /// - TypeScript lowered by oxc_transformer
+    /// - JSX support
     /// - Moderate caching
     Standard,
 
@@ -204,7 +214,7 @@ impl ProcessingOptions {
 /// ```rust,no_run
 /// use soku::infrastructure::processors::{UnifiedJsProcessor, ProcessingStrategy};
 ///
-/// // Standard mode: Basic TypeScript stripping
+/// // Standard mode: TypeScript and JSX compiled
 /// let standard = UnifiedJsProcessor::new(ProcessingStrategy::Standard);
 ///
 /// // Enhanced mode: Full TypeScript + JSX transformation
@@ -215,6 +225,10 @@ pub struct UnifiedJsProcessor {
     strategy: ProcessingStrategy,
     options: ProcessingOptions,
     cache: Arc<SokuCache>,
+    /// Hoist/wrap decisions from bundling, drained by `take_module_linking`
+    linking: Arc<parking_lot::Mutex<Vec<ModuleLinking>>>,
+    /// Linker warnings from bundling, drained by `take_link_warnings`
+    link_warnings: Arc<parking_lot::Mutex<Vec<String>>>,
 }
 
 impl UnifiedJsProcessor {
@@ -224,6 +238,8 @@ impl UnifiedJsProcessor {
             strategy,
             options: ProcessingOptions::from_strategy(strategy),
             cache: Arc::new(SokuCache::new()),
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -234,6 +250,8 @@ impl UnifiedJsProcessor {
             strategy,
             options,
             cache: Arc::new(SokuCache::new()),
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -244,6 +262,8 @@ impl UnifiedJsProcessor {
             strategy,
             options: ProcessingOptions::from_strategy(strategy),
             cache: Arc::new(SokuCache::with_persistent_cache(cache_dir)),
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -260,13 +280,28 @@ impl UnifiedJsProcessor {
     }
 
     /// Process content based on strategy
+    #[allow(dead_code)] // Part of public API
     pub fn process_content(&self, content: &str, file_path: &Path) -> Result<String> {
-        // Check cache first
-        let path_str = file_path.to_string_lossy();
+        self.process_content_with_config(content, file_path, &BuildConfig::default())
+    }
+
+    /// Process content based on strategy, compiling JSX as configured
+    pub fn process_content_with_config(
+        &self,
+        content: &str,
+        file_path: &Path,
+        config: &BuildConfig,
+    ) -> Result<String> {
+        // Check cache first; the key covers the transform options
+        let path_str = format!(
+            "{}#{:x}",
+            file_path.to_string_lossy(),
+            transform_options_hash(config)
+        );
         if let Some(cached) =
             get_cached_js(&self.cache, &path_str, content, self.options.enable_cache)
         {
-            Logger::debug(&format!("Cache hit for {}", path_str));
+            Logger::debug(&format!("Cache hit for {}", file_path.display()));
             return Ok(cached);
         }
 
@@ -277,12 +312,12 @@ impl UnifiedJsProcessor {
                 self.process_fast(content, file_path)?
             }
             ProcessingStrategy::Standard => {
-                // Standard mode: basic TypeScript stripping
-                self.process_standard(content, file_path)?
+                // Standard mode: TypeScript and JSX compiled
+                self.process_standard(content, file_path, config)?
             }
             ProcessingStrategy::Enhanced => {
                 // Enhanced mode: full processing
-                self.process_enhanced(content, file_path)?
+                self.process_enhanced(content, file_path, config)?
             }
         };
 
@@ -300,123 +335,142 @@ impl UnifiedJsProcessor {
 
     /// Fast processing: minimal transformations
     fn process_fast(&self, content: &str, _file_path: &Path) -> Result<String> {
-        // In Fast mode, just remove import/export for bundling
-        let processed = content
-            .lines()
-            .filter(|line| {
-                let trimmed = line.trim();
-                !trimmed.starts_with("import ") && !trimmed.starts_with("export ")
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        Ok(processed)
+        // In Fast mode the source is passed through; imports/exports are
+        // resolved by the module linker at bundle time
+        Ok(content.to_string())
     }
 
-    /// Standard processing: basic TypeScript stripping
-    fn process_standard(&self, content: &str, file_path: &Path) -> Result<String> {
-        let allocator = Allocator::default();
+    /// Process supported modules and link them into a single scope, with
+    /// source maps when `with_maps` is set. `shared_registry` links them for
+    /// a build split into chunks.
+    async fn link_modules(
+        &self,
+        modules: &[ModuleInfo],
+        config: &BuildConfig,
+        with_maps: bool,
+        shared_registry: bool,
+    ) -> Result<LinkOutput> {
+        let mut inputs = Vec::with_capacity(modules.len());
+        let mut maps = Vec::new();
+        for module in modules {
+            if self.supports_module_type(&module.module_type) {
+                Logger::processing_file(
+                    module
+                        .path
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("unknown"),
+                    "bundling",
+                );
+                let processed = self.process_module_with_config(module, config)?;
+                if with_maps {
+                    maps.push(processed_module_map(
+                        &module.content,
+                        &processed,
+                        &module.path,
+                        config,
+                    )?);
+                }
+                inputs.push((module, processed));
+            }
+        }
 
-        // Determine if file has TypeScript or JSX
-        let has_ts = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|ext| ext == "ts" || ext == "tsx")
-            .unwrap_or(false);
-
-        let has_jsx = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|ext| ext == "tsx" || ext == "jsx")
-            .unwrap_or(false);
-
-        // Parse with appropriate config
-        let config = if has_jsx {
-            ParsingConfig::jsx()
-        } else if has_ts {
-            ParsingConfig::typescript()
+        let linker = ModuleLinker::new()
+            .with_hmr(config.hmr)
+            .with_shared_registry(shared_registry);
+        let linked = if with_maps {
+            linker.link_with_maps(&inputs, &maps)
         } else {
-            ParsingConfig::javascript()
+            linker.link(&inputs)
         };
+        for warning in &linked.warnings {
+            Logger::warn(warning);
+        }
+        self.linking.lock().extend(linked.linking());
+        self.link_warnings
+            .lock()
+            .extend(linked.warnings.iter().cloned());
+        Ok(linked)
+    }
 
-        // Parse and validate
-        let _result = parse_with_oxc(
+    /// Process a module with the JSX options of a build
+    fn process_module_with_config(
+        &self,
+        module: &ModuleInfo,
+        config: &BuildConfig,
+    ) -> Result<String> {
+        let _timer = crate::utils::Timer::start(&format!(
+            "Processing {} ({})",
+            module
+                .path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown"),
+            self.strategy.name()
+        ));
+
+        self.process_content_with_config(&module.content, &module.path, config)
+    }
+
+    /// Standard processing: TypeScript and JSX compiled, syntax errors
+    /// reported with their location
+    fn process_standard(
+        &self,
+        content: &str,
+        file_path: &Path,
+        config: &BuildConfig,
+    ) -> Result<String> {
+        if self.should_transform(content, file_path) {
+            return transform_module(content, file_path, config);
+        }
+
+        let allocator = Allocator::default();
+        let parsed = parse_with_oxc(
             &allocator,
             content,
-            config,
+            ParsingConfig::for_path(file_path),
             file_path,
             "Standard processing",
-        );
-        // Ignore parse errors in Standard mode
-
-        // Strip TypeScript if needed
-        let processed = if has_ts && self.options.strip_types {
-            let stripped = strip_typescript_block_constructs(content);
-            clean_typescript_inline_annotations(&stripped)
-        } else {
-            content.to_string()
-        };
+        )?;
+        // Syntax newer than the target is lowered in plain JavaScript too
+        if has_newer_syntax(&parsed.program, parse_target(&config.target)?) {
+            return transform_module(content, file_path, config);
+        }
 
-        Ok(processed)
+        Ok(content.to_string())
     }
 
     /// Enhanced processing: full TypeScript + JSX transformation
-    fn process_enhanced(&self, content: &str, file_path: &Path) -> Result<String> {
-        let allocator = Allocator::default();
-
-        // Determine file characteristics
-        let has_ts = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|ext| ext == "ts" || ext == "tsx")
-            .unwrap_or(false);
-
-        let has_jsx = file_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .map(|ext| ext == "tsx" || ext == "jsx")
-            .unwrap_or(false);
-
-        // Parse with appropriate config
-        let config = if has_jsx {
-            ParsingConfig::jsx()
-        } else if has_ts {
-            ParsingConfig::typescript()
-        } else {
-            ParsingConfig::javascript()
-        };
+    fn process_enhanced(
+        &self,
+        content: &str,
+        file_path: &Path,
+        config: &BuildConfig,
+    ) -> Result<String> {
+        if self.should_transform(content, file_path) {
+            return transform_module(content, file_path, config);
+        }
 
-        // Parse with full error handling
-        let _result = match parse_with_oxc(
+        // Plain JavaScript only has to parse, unless it is newer than the target
+        let allocator = Allocator::default();
+        let parsed = parse_with_oxc(
             &allocator,
             content,
-            config,
+            ParsingConfig::for_path(file_path),
             file_path,
             "Enhanced processing",
-        ) {
-            Ok(result) => result,
-            Err(_) => {
-                // Fallback to regex-based approach
-                Logger::warn("Enhanced parsing failed, falling back to regex");
-                let stripped = if has_ts {
-                    let temp = strip_typescript_block_constructs(content);
-                    clean_typescript_inline_annotations(&temp)
-                } else {
-                    content.to_string()
-                };
-                return Ok(stripped);
-            }
-        };
+        )?;
+        if has_newer_syntax(&parsed.program, parse_target(&config.target)?) {
+            return transform_module(content, file_path, config);
+        }
 
-        // Full processing with all transformations
-        let processed = if has_ts && self.options.strip_types {
-            let stripped = strip_typescript_block_constructs(content);
-            clean_typescript_inline_annotations(&stripped)
-        } else {
-            content.to_string()
-        };
+        Ok(content.to_string())
+    }
 
-        Ok(processed)
+    /// Whether the strategy compiles this module with `oxc_transformer`
+    fn should_transform(&self, content: &str, file_path: &Path) -> bool {
+        (self.options.strip_types || self.options.transform_jsx)
+            && needs_transform(content, file_path)
     }
 }
 
@@ -427,62 +481,28 @@ impl UnifiedJsProcessor {
 #[async_trait]
 impl JsProcessor for UnifiedJsProcessor {
     async fn process_module(&self, module: &ModuleInfo) -> Result<String> {
-        let _timer = crate::utils::Timer::start(&format!(
-            "Processing {} ({})",
-            module
-                .path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("unknown"),
-            self.strategy.name()
-        ));
-
-        // Use the unified process_content method
-        self.process_content(&module.content, &module.path)
+        self.process_module_with_config(module, &BuildConfig::default())
     }
 
-    async fn bundle_modules(&self, modules: &[ModuleInfo]) -> Result<String> {
+    async fn bundle_modules(&self, modules: &[ModuleInfo], config: &BuildConfig) -> Result<String> {
         let _timer =
             crate::utils::Timer::start(&format!("Bundling modules ({})", self.strategy.name()));
 
-        let mut bundle = String::new();
-        bundle.push_str(&format!(
-            "// Soku Bundler - {} Mode Build\n",
-            self.strategy.name()
-        ));
-        bundle.push_str("(function() {\n'use strict';\n\n");
-
-        // Process each module
-        for module in modules {
-            if self.supports_module_type(&module.module_type) {
-                Logger::processing_file(
-                    module
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown"),
-                    "bundling",
-                );
-
-                let processed = self.process_module(module).await?;
-                bundle.push_str(&format!("// Module: {}\n", module.path.display()));
-                bundle.push_str(&processed);
-                bundle.push_str("\n\n");
-            }
-        }
-
-        bundle.push_str("})();\n");
-        Ok(bundle)
+        let banner = format!("// Soku Bundler - {} Mode Build\n", self.strategy.name());
+        let linked = self.link_modules(modules, config, false, false).await?;
+        let body = linked.render(|path| format!("// Module: {}\n", module_display_name(path)));
+        format_bundle(&linked, &banner, &body, config)
     }
 
     async fn bundle_modules_with_tree_shaking(
         &self,
         modules: &[ModuleInfo],
         _tree_shaking_stats: Option<&TreeShakingStats>,
+        config: &BuildConfig,
     ) -> Result<String> {
         // For now, delegate to bundle_modules
         // Tree shaking is handled at a higher level
-        self.bundle_modules(modules).await
+        self.bundle_modules(modules, config).await
     }
 
     async fn bundle_modules_with_source_maps(
@@ -492,88 +512,57 @@ impl JsProcessor for UnifiedJsProcessor {
     ) -> Result<BundleOutput> {
         if !config.enable_source_maps {
             // Source maps disabled, just bundle normally
-            let code = self.bundle_modules(modules).await?;
+            let code = self.bundle_modules(modules, config).await?;
             return Ok(BundleOutput {
                 code,
                 source_map: None,
             });
         }
 
-        // Build bundle with simple source map (line-level mapping)
-        let mut bundle = String::new();
-        let strategy_name = self.strategy.name();
-
-        // Bundle header
-        bundle.push_str(&format!("// Soku Bundler - {} Mode Build\n", strategy_name));
-        bundle.push_str("(function() {\n'use strict';\n\n");
-
-        // Build source tracking
-        let mut sources = Vec::new();
-        let mut sources_content = Vec::new();
-
-        for module in modules {
-            if !self.supports_module_type(&module.module_type) {
-                continue;
-            }
-
-            // Process module
-            let processed = self.process_module(module).await?;
-
-            // Add module comment
-            bundle.push_str(&format!("// Module: {}\n", module.path.display()));
-
-            // Track source
-            sources.push(module.path.to_string_lossy().to_string());
-            sources_content.push(module.content.clone());
-
-            // Add module content
-            for line in processed.lines() {
-                bundle.push_str(line);
-                bundle.push('\n');
-            }
-
-            bundle.push('\n');
-        }
-
-        bundle.push_str("})();\n");
-
-        // Build a simple source map JSON manually
-        let source_map_json = serde_json::json!({
-            "version": 3,
-            "file": "bundle.js",
-            "sources": sources,
-            "sourcesContent": sources_content,
-            "names": [],
-            "mappings": "" // Simplified - no detailed mappings for now
-        });
-
-        let source_map_str = serde_json::to_string(&source_map_json).map_err(|e| {
-            crate::utils::SokuError::build(format!("Failed to generate source map: {}", e))
-        })?;
+        let banner = format!("// Soku Bundler - {} Mode Build\n", self.strategy.name());
+        let linked = self.link_modules(modules, config, true, false).await?;
+        let (body, body_map) =
+            linked.render_with_map(|path| format!("// Module: {}\n", module_display_name(path)));
+        let (code, source_map) =
+            format_bundle_with_map(&linked, &banner, &body, &body_map, config)?;
 
         Ok(BundleOutput {
-            code: bundle,
-            source_map: Some(source_map_str),
+            code,
+            source_map: Some(source_map),
         })
     }
 
+    async fn bundle_chunks(
+        &self,
+        modules: &[ModuleInfo],
+        chunks: &[Vec<PathBuf>],
+        config: &BuildConfig,
+    ) -> Result<Vec<BundleOutput>> {
+        let banner = format!("// Soku Bundler - {} Mode Build\n", self.strategy.name());
+        let linked = self
+            .link_modules(modules, config, config.enable_source_maps, true)
+            .await?;
+        format_chunks(
+            &linked,
+            chunks,
+            &banner,
+            |path| format!("// Module: {}\n", module_display_name(path)),
+            config,
+        )
+    }
+
     fn supports_module_type(&self, module_type: &ModuleType) -> bool {
         matches!(module_type, ModuleType::JavaScript | ModuleType::TypeScript)
     }
-}
 
-// Pre-compiled regex patterns for TypeScript stripping (shared across processors)
-static TYPE_ANNOTATION_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\s*:\s*[a-zA-Z_$][a-zA-Z0-9_$<>\[\]|&\s]*([,)=])")
-        .unwrap()
-});
-
-static RETURN_TYPE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\)\s*:\s*[^=]+\s*(=>)").unwrap());
-
-static GENERIC_TYPE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)<[^<>]*>").unwrap());
+    fn take_module_linking(&self) -> Vec<ModuleLinking> {
+        std::mem::take(&mut *self.linking.lock())
+    }
 
-static SIMPLE_GENERIC_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^<>]*>").unwrap());
+    fn take_link_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.link_warnings.lock())
+    }
+}
 
 // ============================================================================
 // Unified OXC Parsing Interface (Shared)
@@ -615,6 +604,19 @@ impl ParsingConfig {
         }
     }
 
+    /// Pick a config from the file extension (`.js`/`.jsx` parse with JSX enabled)
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ts") | Some("mts") | Some("cts") => Self::typescript(),
+            Some("tsx") => Self::jsx(),
+            _ => Self {
+                typescript: false,
+                jsx: true,
+                module: true,
+            },
+        }
+    }
+
     /// Convert config to OXC SourceType
     pub fn to_source_type(self) -> SourceType {
         let mut source_type = SourceType::default();
@@ -721,8 +723,32 @@ pub fn create_parse_error_context(
         .with_snippet(code_context)
 }
 
+/// Source map of a processed module back to its source
+///
+/// Modules that were passed through map onto themselves; compiled modules
+/// are compiled again, this time recording the mappings.
+pub fn processed_module_map(
+    content: &str,
+    processed: &str,
+    path: &Path,
+    config: &BuildConfig,
+) -> Result<CodeMap> {
+    if processed == content {
+        return Ok(CodeMap::identity(&path.to_string_lossy(), content));
+    }
+    let (code, map) = transform_module_with_map(content, path, config)?;
+    if code != processed {
+        Logger::debug(&format!(
+            "{}: processed code differs from the compiled code, leaving it unmapped",
+            path.display()
+        ));
+        return Ok(CodeMap::default());
+    }
+    Ok(map)
+}
+
 // ============================================================================
-// Node Modules Optimization (Shared)
+// Node Modules Helpers (Shared)
 // ============================================================================
 
 /// Check if a module path is from node_modules
@@ -746,289 +772,23 @@ pub fn extract_package_name(path: &Path) -> String {
     "unknown_package".to_string()
 }
 
-/// Optimize node_modules content by keeping only essential parts
-pub fn optimize_node_module_content(content: &str, path: &Path) -> String {
-    let package_name = extract_package_name(path);
-
-    if package_name == "lodash" {
-        optimize_lodash_content(content, &package_name)
-    } else {
-        optimize_general_node_module_content(content)
-    }
-}
-
-/// Specific optimization for lodash
-/// Uses brace tracking to preserve complete function definitions
-fn optimize_lodash_content(content: &str, package_name: &str) -> String {
-    let mut result = String::new();
-    let lines: Vec<&str> = content.lines().collect();
-
-    let mut in_function = false;
-    let mut brace_count = 0;
-
-    for line in lines {
-        let trimmed = line.trim();
-
-        // Skip comments and empty lines
-        if trimmed.starts_with("//") || trimmed.starts_with("/*") || trimmed.is_empty() {
-            continue;
-        }
-
-        // Keep essential function definitions
-        if trimmed.starts_with("function ")
-            || trimmed.starts_with("var ")
-            || trimmed.starts_with("module.exports")
-            || trimmed.starts_with("exports.")
-        {
-            in_function = true;
-            result.push_str(line);
-            result.push('\n');
-
-            brace_count += line.matches('{').count();
-            brace_count -= line.matches('}').count();
-
-            if brace_count == 0 {
-                in_function = false;
-            }
-            continue;
-        }
-
-        // If inside function, keep the content
-        if in_function {
-            result.push_str(line);
-            result.push('\n');
-
-            brace_count += line.matches('{').count();
-            brace_count -= line.matches('}').count();
-
-            if brace_count == 0 {
-                in_function = false;
-            }
-        }
-    }
-
-    format!(
-        "// TREE-SHAKEN: Optimized {} content\n{}",
-        package_name, result
-    )
-}
-
-/// General optimization for other node_modules packages
-/// Removes development-only code and verbose comments
-fn optimize_general_node_module_content(content: &str) -> String {
-    let mut result = String::new();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Skip obvious dead code patterns
-        if trimmed.starts_with("// Development only")
-            || trimmed.starts_with("// DEBUG")
-            || trimmed.contains("console.warn")
-            || trimmed.contains("console.error")
-        {
-            result.push_str(&format!("// TREE-SHAKEN: {}\n", line));
-            continue;
-        }
-
-        result.push_str(line);
-        result.push('\n');
-    }
-
-    result
-}
-
 // ============================================================================
-// TypeScript Stripping Functions (Shared)
+// Unified Caching Interface (Shared)
 // ============================================================================
 
-/// Strip TypeScript block-level constructs (interfaces, type aliases, enums)
-/// Returns the cleaned content with TypeScript blocks commented out
-pub fn strip_typescript_block_constructs(content: &str) -> String {
-    let mut result = String::new();
-    let mut in_interface = false;
-    let mut in_type_alias = false;
-    let mut in_enum = false;
-    let mut in_decorator = false;
-    let mut brace_depth = 0;
-    let mut paren_depth = 0;
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Handle multiline decorator arguments
-        if in_decorator {
-            result.push_str(&format!("// {}\n", line));
-            // Track parentheses for multiline decorators
-            paren_depth += trimmed.matches('(').count();
-            paren_depth = paren_depth.saturating_sub(trimmed.matches(')').count());
-            if paren_depth == 0 {
-                in_decorator = false;
-            }
-            continue;
-        }
-
-        // Skip decorators (@Decorator or @Decorator(...))
-        if trimmed.starts_with('@') {
-            Logger::debug(&format!("Stripping decorator: {}", trimmed));
-            result.push_str(&format!("// {}\n", line));
-            // Check if decorator has arguments spanning multiple lines
-            paren_depth = trimmed.matches('(').count();
-            paren_depth = paren_depth.saturating_sub(trimmed.matches(')').count());
-            if paren_depth > 0 {
-                in_decorator = true;
-            }
-            continue;
-        }
-
-        // Skip interface declarations
-        if trimmed.starts_with("interface ") || trimmed.starts_with("export interface ") {
-            in_interface = true;
-            brace_depth = 0;
-            result.push_str(&format!("// {}\n", line));
-            if trimmed.contains('{') {
-                brace_depth += trimmed.matches('{').count();
-            }
-            if trimmed.contains('}') {
-                brace_depth -= trimmed.matches('}').count();
-                if brace_depth == 0 {
-                    in_interface = false;
-                }
-            }
-            continue;
-        }
-
-        // Skip type aliases
-        if trimmed.starts_with("type ") || trimmed.starts_with("export type ") {
-            in_type_alias = true;
-            result.push_str(&format!("// {}\n", line));
-            if !trimmed.contains(';') && !trimmed.contains('=') {
-                continue;
-            }
-            in_type_alias = false;
-            continue;
-        }
-
-        // Skip enum declarations (including const enums)
-        if trimmed.starts_with("enum ")
-            || trimmed.starts_with("export enum ")
-            || trimmed.starts_with("const enum ")
-            || trimmed.starts_with("export const enum ")
-        {
-            in_enum = true;
-            brace_depth = 0;
-            result.push_str(&format!("// {}\n", line));
-            if trimmed.contains('{') {
-                brace_depth += trimmed.matches('{').count();
-            }
-            if trimmed.contains('}') {
-                brace_depth -= trimmed.matches('}').count();
-                if brace_depth == 0 {
-                    in_enum = false;
-                }
-            }
-            continue;
-        }
-
-        // Handle interface/type/enum continuation
-        if in_interface || in_enum {
-            result.push_str(&format!("// {}\n", line));
-            if trimmed.contains('{') {
-                brace_depth += trimmed.matches('{').count();
-            }
-            if trimmed.contains('}') {
-                brace_depth -= trimmed.matches('}').count();
-                if brace_depth == 0 {
-                    in_interface = false;
-                    in_enum = false;
-                }
-            }
-            continue;
-        }
-
-        if in_type_alias {
-            result.push_str(&format!("// {}\n", line));
-            if trimmed.ends_with(';') {
-                in_type_alias = false;
-            }
-            continue;
-        }
-
-        // Keep other lines
-        result.push_str(line);
-        result.push('\n');
-    }
-
-    result
-}
-
-/// Clean inline TypeScript annotations from code
-/// Removes type annotations like `: Type`, return types, etc.
-pub fn clean_typescript_inline_annotations(content: &str) -> String {
-    let mut result = content.to_string();
-
-    // Remove class property type annotations: propertyName: Type; -> propertyName;
-    if let Ok(re) =
-        Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\s*:\s*[a-zA-Z_$][a-zA-Z0-9_$<>\[\]|&\s]+;")
-    {
-        result = re.replace_all(&result, "$1;").to_string();
-    }
-
-    // Remove method/function return types: methodName(): Type { -> methodName() {
-    if let Ok(re) = Regex::new(r"\)\s*:\s*[a-zA-Z_$][a-zA-Z0-9_$<>\[\]|&\s]+\s*\{") {
-        result = re.replace_all(&result, ") {").to_string();
-    }
-
-    // Remove simple type annotations: name: Type -> name
-    result = TYPE_ANNOTATION_REGEX
-        .replace_all(&result, "$1$2")
-        .to_string();
-
-    // Remove function return types: ): Type => -> ) =>
-    result = RETURN_TYPE_REGEX.replace_all(&result, ")$1").to_string();
-
-    // Remove generic type parameters
-    result = SIMPLE_GENERIC_REGEX.replace_all(&result, "").to_string();
-
-    // Remove TypeScript non-null assertion operator
-    if let Ok(re) = Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\s*!") {
-        result = re.replace_all(&result, "$1").to_string();
-    }
-
-    // Remove access modifiers
-    if let Ok(re) = Regex::new(r"\b(private|public|protected|readonly)\s+") {
-        result = re.replace_all(&result, "").to_string();
-    }
-
-    // Remove as type assertions
-    if let Ok(re) = Regex::new(r"\s+as\s+[a-zA-Z_$][a-zA-Z0-9_$<>\[\]|&\s]*") {
-        result = re.replace_all(&result, "").to_string();
-    }
-
-    result
+/// Hash of the build options that change how a module is transformed
+pub fn transform_options_hash(config: &BuildConfig) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    config.jsx.hash(&mut hasher);
+    config.typescript.hash(&mut hasher);
+    config.target.hash(&mut hasher);
+    // Fast Refresh registration is only compiled in for HMR
+    config.hmr.hash(&mut hasher);
+    hasher.finish()
 }
 
-/// Remove generic types from content (iteratively to handle nested generics)
-pub fn remove_generic_types(content: &str, max_iterations: usize) -> String {
-    let mut result = content.to_string();
-    let mut iterations = 0;
-
-    while iterations < max_iterations {
-        let new_result = GENERIC_TYPE_REGEX.replace_all(&result, "$1").to_string();
-        if new_result == result {
-            break; // No more changes
-        }
-        result = new_result;
-        iterations += 1;
-    }
-
-    result
-}
-
-// ============================================================================
-// Unified Caching Interface (Shared)
-// ============================================================================
-
 /// Check cache for processed JavaScript content
 /// Returns cached content if available and caching is enabled
 pub fn get_cached_js(
@@ -1093,79 +853,144 @@ pub fn store_cached_css(
 // Dependency Extraction (Shared)
 // ============================================================================
 
-// Pre-compiled regex patterns for dependency extraction
-static CSS_IMPORT_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r#"import\s+['"]([^'"]+)['"]"#).unwrap());
-static REQUIRE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r#"require\s*\(\s*['"]([^'"]+)['"]\s*\)"#).unwrap());
+/// Collects dependency records while walking a parsed module
+#[derive(Default)]
+struct DependencyCollector {
+    records: Vec<DependencyRecord>,
+}
+
+impl DependencyCollector {
+    fn push(&mut self, specifier: &str, kind: DependencyKind, span: Span) {
+        self.records.push(DependencyRecord {
+            specifier: specifier.to_string(),
+            kind,
+            span: SourceSpan {
+                start: span.start,
+                end: span.end,
+            },
+        });
+    }
+}
+
+impl<'a> Visit<'a> for DependencyCollector {
+    fn visit_import_declaration(&mut self, it: &ImportDeclaration<'a>) {
+        // Type-only imports are erased by the TypeScript transform
+        if it.import_kind.is_type() {
+            return;
+        }
+        let kind = if it.specifiers.is_none() {
+            DependencyKind::SideEffect
+        } else {
+            DependencyKind::Static
+        };
+        self.push(&it.source.value, kind, it.span);
+    }
+
+    fn visit_export_named_declaration(&mut self, it: &ExportNamedDeclaration<'a>) {
+        match &it.source {
+            Some(source) if !it.export_kind.is_type() => {
+                self.push(&source.value, DependencyKind::ReExport, it.span)
+            }
+            Some(_) => {}
+            None => walk::walk_export_named_declaration(self, it),
+        }
+    }
+
+    fn visit_export_all_declaration(&mut self, it: &ExportAllDeclaration<'a>) {
+        if !it.export_kind.is_type() {
+            self.push(&it.source.value, DependencyKind::ReExport, it.span);
+        }
+    }
+
+    fn visit_ts_import_equals_declaration(&mut self, it: &TSImportEqualsDeclaration<'a>) {
+        if let TSModuleReference::ExternalModuleReference(reference) = &it.module_reference {
+            if !it.import_kind.is_type() {
+                self.push(
+                    &reference.expression.value,
+                    DependencyKind::Require,
+                    it.span,
+                );
+            }
+        }
+    }
+
+    fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+        // Only specifiers known at build time can be resolved
+        match &it.source {
+            Expression::StringLiteral(literal) => {
+                self.push(&literal.value, DependencyKind::Dynamic, it.span)
+            }
+            Expression::TemplateLiteral(template) => {
+                if let Some(quasi) = template.single_quasi() {
+                    self.push(&quasi, DependencyKind::Dynamic, it.span);
+                }
+            }
+            _ => {}
+        }
+        walk::walk_import_expression(self, it);
+    }
+
+    fn visit_call_expression(&mut self, it: &CallExpression<'a>) {
+        if it.callee.is_specific_id("require") && it.arguments.len() == 1 {
+            if let Argument::StringLiteral(literal) = &it.arguments[0] {
+                self.push(&literal.value, DependencyKind::Require, it.span);
+            }
+        }
+        walk::walk_call_expression(self, it);
+    }
+}
 
 /// Extract dependencies from JavaScript/TypeScript content
 ///
-/// This function parses import and require statements to find module dependencies.
-/// It handles:
-/// - ES6 imports: `import foo from 'module'` and `import 'module'`
-/// - CommonJS: `require('module')`
-/// - Both relative and absolute paths
+/// The source is parsed with oxc and every module specifier is returned as a
+/// typed record, in source order:
+/// - Static imports: `import foo from 'module'`
+/// - Side-effect imports: `import 'module'`
+/// - Re-exports: `export { foo } from 'module'` and `export * from 'module'`
+/// - Dynamic imports with a literal specifier: `import('module')`
+/// - CommonJS: `require('module')` and TypeScript `import foo = require('module')`
+///
+/// Type-only imports and exports are skipped, as are specifiers that only
+/// appear in comments or strings. Code that fails to parse yields the records
+/// found before the error.
 ///
 /// # Arguments
 /// * `content` - The JavaScript/TypeScript source code
-///
-/// # Returns
-/// A vector of dependency paths (module specifiers)
+/// * `path` - The file path, used to pick the TypeScript/JSX syntax
 ///
 /// # Example
-/// ```rust,no_run
+/// ```rust
+/// use soku::core::models::DependencyKind;
 /// use soku::infrastructure::processors::common::extract_dependencies;
+/// use std::path::Path;
 ///
 /// let code = r#"
 ///     import React from 'react';
 ///     import './styles.css';
 ///     const lodash = require('lodash');
+///     const page = import('./page.js');
 /// "#;
 ///
-/// let deps = extract_dependencies(code);
-/// assert_eq!(deps, vec!["react", "./styles.css", "lodash"]);
+/// let deps = extract_dependencies(code, Path::new("main.js"));
+/// let kinds: Vec<_> = deps.iter().map(|d| (d.specifier.as_str(), d.kind)).collect();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         ("react", DependencyKind::Static),
+///         ("./styles.css", DependencyKind::SideEffect),
+///         ("lodash", DependencyKind::Require),
+///         ("./page.js", DependencyKind::Dynamic),
+///     ]
+/// );
 /// ```
-pub fn extract_dependencies(content: &str) -> Vec<String> {
-    let mut dependencies = Vec::new();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Handle ES6 import patterns
-        if trimmed.starts_with("import ") {
-            if let Some(from_index) = trimmed.rfind(" from ") {
-                let import_path = &trimmed[from_index + 6..];
-                // Remove quotes and semicolon
-                let clean_path = import_path.trim_matches(|c| c == '"' || c == '\'' || c == ';');
-
-                if !clean_path.is_empty() {
-                    // Handle both relative imports and node_modules imports
-                    dependencies.push(clean_path.to_string());
-                }
-            } else {
-                // Handle CSS/asset imports like: import './styles.css'
-                // Using pre-compiled regex for performance
-                if let Some(captures) = CSS_IMPORT_REGEX.captures(trimmed) {
-                    let import_path = &captures[1];
-
-                    // Handle all import paths
-                    dependencies.push(import_path.to_string());
-                }
-            }
-        }
-
-        // Handle CommonJS require() patterns
-        // Using pre-compiled regex for performance
-        for captures in REQUIRE_REGEX.captures_iter(trimmed) {
-            let require_path = &captures[1];
-            if !require_path.is_empty() {
-                dependencies.push(require_path.to_string());
-            }
-        }
-    }
-
-    dependencies
+pub fn extract_dependencies(content: &str, path: &Path) -> Vec<DependencyRecord> {
+    let allocator = Allocator::default();
+    let source_type = ParsingConfig::for_path(path).to_source_type();
+    let parsed = Parser::new(&allocator, content, source_type).parse();
+
+    let mut collector = DependencyCollector::default();
+    collector.visit_program(&parsed.program);
+    collector.records
 }
 
 #[cfg(test)]
@@ -1192,16 +1017,67 @@ mod tests {
     }
 
     #[test]
-    fn test_optimize_lodash_content() {
-        let content = r#"
-// Lodash
-function add(a, b) { return a + b; }
-exports.add = add;
+    fn test_syntax_errors_fail_with_their_location() {
+        let code = "const a = 1;\nconst b = ;\n";
+        for strategy in [ProcessingStrategy::Standard, ProcessingStrategy::Enhanced] {
+            let error = UnifiedJsProcessor::new(strategy)
+                .process_content_with_config(code, Path::new("/app/main.js"), &Default::default())
+                .unwrap_err();
+            let context = error.context().unwrap();
+            assert_eq!(context.file_path, Some(PathBuf::from("/app/main.js")));
+            assert_eq!(context.line, Some(2));
+        }
+    }
+
+    fn dependency_kinds(code: &str, file: &str) -> Vec<(String, DependencyKind)> {
+        extract_dependencies(code, Path::new(file))
+            .into_iter()
+            .map(|d| (d.specifier, d.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_dependencies_covers_all_import_forms() {
+        let code = r#"
+import {
+    a,
+    b,
+} from './multi.js';
+export { c } from './reexport.js';
+export * from './star.js';
+const lazy = () => import('./lazy.js');
+// import fake from './comment.js';
+const text = `import nope from './template.js'`;
+"#;
+        assert_eq!(
+            dependency_kinds(code, "main.js"),
+            vec![
+                ("./multi.js".to_string(), DependencyKind::Static),
+                ("./reexport.js".to_string(), DependencyKind::ReExport),
+                ("./star.js".to_string(), DependencyKind::ReExport),
+                ("./lazy.js".to_string(), DependencyKind::Dynamic),
+            ]
+        );
+    }
 
-        "#;
+    #[test]
+    fn test_extract_dependencies_skips_type_only_imports() {
+        let code = "import type { Props } from './types';\nimport fs = require('fs');\nexport type { Other } from './other';\n";
+        assert_eq!(
+            dependency_kinds(code, "main.ts"),
+            vec![("fs".to_string(), DependencyKind::Require)]
+        );
+    }
 
-        let result = optimize_lodash_content(content, "lodash");
-        assert!(result.contains("function add"));
-        assert!(result.contains("exports.add"));
+    #[test]
+    fn test_extract_dependencies_records_spans() {
+        let code = "const x = 1;\nimport './side.css';\n";
+        let deps = extract_dependencies(code, Path::new("main.js"));
+        assert_eq!(deps.len(), 1);
+        let span = deps[0].span;
+        assert_eq!(
+            &code[span.start as usize..span.end as usize],
+            "import './side.css';"
+        );
     }
 }
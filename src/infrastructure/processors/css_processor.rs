@@ -1,5 +1,8 @@
 use crate::core::interfaces::CssProcessor;
-use crate::utils::{CssModulesProcessor, Logger, Result, SokuCache, SokuError};
+use crate::core::models::BundleOutput;
+use crate::utils::{
+    CodeMap, CssModulesProcessor, Logger, MappedCode, Result, SokuCache, SokuError,
+};
 use lightningcss::{
     printer::PrinterOptions,
     stylesheet::{ParserOptions as CssParserOptions, StyleSheet},
@@ -44,61 +47,22 @@ impl CssProcessor for LightningCssProcessor {
                 .unwrap_or("unknown"),
         );
 
-        // Check if this is a CSS Module
-        let css_content = if CssModulesProcessor::is_css_module(path) {
-            Logger::info(&format!(
-                "📦 CSS Module detected: {}",
-                path.file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("unknown")
-            ));
-
-            let css_modules = CssModulesProcessor::new();
-            let module_result = css_modules.process(content, path)?;
-
-            // TODO: Save exports for JS import resolution
-            Logger::debug(&format!(
-                "CSS Module exports: {:?}",
-                module_result.exports.keys()
-            ));
-
-            module_result.css
-        } else {
-            content.to_string()
-        };
-
-        // Process CSS with lightningcss
-        let result = match StyleSheet::parse(&css_content, CssParserOptions::default()) {
-            Ok(stylesheet) => {
-                match stylesheet.to_css(PrinterOptions {
-                    minify: self.minify,
-                    ..Default::default()
-                }) {
-                    Ok(result) => Ok(result.code),
-                    Err(_) => {
-                        Logger::warn(&format!(
-                            "CSS processing failed for {}, using fallback minification",
-                            path.display()
-                        ));
-                        Ok(self.fallback_minify(content))
-                    }
-                }
-            }
-            Err(_) => {
-                Logger::warn(&format!(
-                    "CSS parse error for {}, using fallback minification",
-                    path.display()
-                ));
-                Ok(self.fallback_minify(content))
-            }
-        };
+        let (result, _) = self.process(content, path, None);
 
         // Cache the result
-        if let Ok(ref processed) = result {
-            self.cache.cache_css(&path_str, content, processed.clone());
-        }
+        self.cache.cache_css(&path_str, content, result.clone());
+
+        Ok(result)
+    }
 
-        result
+    async fn process_css_with_map(
+        &self,
+        content: &str,
+        path: &Path,
+        map: CodeMap,
+    ) -> Result<(String, CodeMap)> {
+        let (code, map) = self.process(content, path, Some(map));
+        Ok((code, map.unwrap_or_default()))
     }
 
     async fn bundle_css(&self, files: &[PathBuf]) -> Result<String> {
@@ -127,9 +91,108 @@ impl CssProcessor for LightningCssProcessor {
 
         Ok(bundle)
     }
+
+    async fn bundle_css_with_source_map(&self, files: &[PathBuf]) -> Result<BundleOutput> {
+        let mut bundle = MappedCode::new(true);
+        bundle.push_str("/* Soku Bundler - CSS Bundle */\n");
+
+        for css_file in files {
+            let content = tokio::fs::read_to_string(css_file)
+                .await
+                .map_err(SokuError::Io)?;
+            let map = CodeMap::identity(&css_file.to_string_lossy(), &content);
+            let (processed, map) = self.process_css_with_map(&content, css_file, map).await?;
+
+            bundle.push_str(&format!(
+                "/* From: {} */\n",
+                css_file
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("unknown")
+            ));
+            bundle.push_mapped(&processed, Some(&map));
+            bundle.push_str("\n");
+        }
+
+        Ok(BundleOutput {
+            code: bundle.code,
+            source_map: bundle.map,
+        })
+    }
 }
 
 impl LightningCssProcessor {
+    /// Scope CSS module names and run lightningcss over `content`
+    ///
+    /// With `map`, the map of `content`, the map of the result is returned too.
+    fn process(
+        &self,
+        content: &str,
+        path: &Path,
+        map: Option<CodeMap>,
+    ) -> (String, Option<CodeMap>) {
+        // Check if this is a CSS Module
+        let (css_content, map) = if CssModulesProcessor::is_css_module(path) {
+            Logger::info(&format!(
+                "📦 CSS Module detected: {}",
+                path.file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("unknown")
+            ));
+
+            let css_modules = CssModulesProcessor::new();
+            let (css, exports) = css_modules.rewrite(content, path);
+
+            // TODO: Save exports for JS import resolution
+            Logger::debug(&format!("CSS Module exports: {:?}", exports.keys()));
+
+            let map = map.map(|map| css.rewrite_map(&map));
+            (css.into_string(), map)
+        } else {
+            (content.to_string(), map)
+        };
+
+        // Process CSS with lightningcss
+        let mut source_map = map.as_ref().map(|_| {
+            let mut source_map = parcel_sourcemap::SourceMap::new("/");
+            source_map.add_source(&path.to_string_lossy());
+            source_map
+        });
+        let printed = StyleSheet::parse(&css_content, CssParserOptions::default())
+            .map_err(|_| "CSS parse error")
+            .and_then(|stylesheet| {
+                stylesheet
+                    .to_css(PrinterOptions {
+                        minify: self.minify,
+                        source_map: source_map.as_mut(),
+                        ..Default::default()
+                    })
+                    .map_err(|_| "CSS processing failed")
+            });
+
+        match printed {
+            Ok(result) => {
+                let map = map.and_then(|map| {
+                    let json = source_map?.to_json(None).ok()?;
+                    let printed_map = oxc_sourcemap::SourceMap::from_json_string(&json).ok()?;
+                    Some(map.compose(&css_content, &printed_map, &result.code))
+                });
+                (result.code, map)
+            }
+            Err(error) => {
+                Logger::warn(&format!(
+                    "{} for {}, using fallback minification",
+                    error,
+                    path.display()
+                ));
+                let fallback = self.fallback_minify(content);
+                // Minifying joins the lines, which loses the positions
+                let map = map.filter(|_| !self.minify);
+                (fallback, map)
+            }
+        }
+    }
+
     fn fallback_minify(&self, content: &str) -> String {
         if self.minify {
             content
@@ -229,6 +229,7 @@ mod tests {
             module_type: ModuleType::JavaScript,
             dependencies: Vec::new(),
             exports: Vec::new(),
+            resolved_imports: std::collections::HashMap::new(),
         }
     }
 
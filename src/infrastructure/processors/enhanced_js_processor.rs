@@ -1,13 +1,16 @@
 #![allow(dead_code)] // Enhanced JS processor - advanced features, may not all be used yet
 
+use super::common::processed_module_map;
+use super::output_format::{format_bundle, format_bundle_with_map, format_chunks};
 use crate::core::{interfaces::JsProcessor, models::*};
-use crate::utils::{Logger, Result, SokuCache, SokuError};
+use crate::utils::{module_display_name, CodeMap, Logger, Result, SokuCache, SokuError};
 use oxc_allocator::Allocator;
-use oxc_ast::ast;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 // Note: Regex patterns now live in common.rs to avoid duplication
 
+const BUNDLE_BANNER: &str = "// Soku Bundler - Enhanced TypeScript/JavaScript Build\n";
+
 /// Enhanced JavaScript/TypeScript processor with advanced caching and optimizations
 ///
 /// **DEPRECATED**: This processor is maintained for backward compatibility.
@@ -33,6 +36,10 @@ use std::sync::Arc;
 pub struct EnhancedJsProcessor {
     cache: Arc<SokuCache>,
     enable_cache: bool,
+    /// Hoist/wrap decisions from bundling, drained by `take_module_linking`
+    linking: Arc<parking_lot::Mutex<Vec<ModuleLinking>>>,
+    /// Linker warnings from bundling, drained by `take_link_warnings`
+    link_warnings: Arc<parking_lot::Mutex<Vec<String>>>,
 }
 
 impl EnhancedJsProcessor {
@@ -40,6 +47,8 @@ impl EnhancedJsProcessor {
         Self {
             cache: Arc::new(SokuCache::new()),
             enable_cache: true,
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -47,6 +56,8 @@ impl EnhancedJsProcessor {
         Self {
             cache: Arc::new(SokuCache::new()),
             enable_cache: false,
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -54,6 +65,8 @@ impl EnhancedJsProcessor {
         Self {
             cache: Arc::new(SokuCache::with_persistent_cache(cache_dir)),
             enable_cache: true,
+            linking: Arc::default(),
+            link_warnings: Arc::default(),
         }
     }
 
@@ -68,647 +81,20 @@ impl EnhancedJsProcessor {
                 .unwrap_or("unknown")
         ));
 
-        let file_extension = module
-            .path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("");
-
-        if file_extension == "tsx" {
-            Logger::processing_typescript("TSX/JSX component (AST-based)");
-            // For TSX files, we need both TypeScript stripping AND JSX transformation
-            let processed = self.process_jsx_content(&module.content, &module.path)?;
-            Logger::debug(&format!("JSX processed output:\n{}", processed));
-            Ok(processed)
-        } else {
-            Logger::processing_typescript(&format!(
-                "TypeScript {} (AST-based)",
-                module
-                    .path
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("unknown")
-            ));
-            // For regular TS files, just strip TypeScript types
-            let processed = self.ast_typescript_transform(&module.content, &module.path)?;
-            Ok(processed)
-        }
-    }
-
-    /// Process JSX/TSX content using AST-based transformation
-    fn process_jsx_content(&self, content: &str, file_path: &Path) -> Result<String> {
-        let allocator = Allocator::default();
-
-        // Parse with unified interface (with fallback to regex on error)
-        let result = match super::common::parse_with_oxc(
-            &allocator,
-            content,
-            super::common::ParsingConfig::jsx(),
-            file_path,
-            "JSX/TSX parsing failed",
-        ) {
-            Ok(result) => result,
-            Err(_) => {
-                Logger::warn("Falling back to regex-based approach for JSX processing");
-                // Fall back to regex-based approach
-                let type_stripped = self.strip_typescript_types(content);
-                return Ok(self.convert_jsx_to_js(&type_stripped));
-            }
-        };
-
-        // Transform JSX AST to JavaScript
-        let transformed = self.transform_jsx_ast(&result.program, content);
-        Ok(transformed)
-    }
-
-    /// Transform JSX AST to JavaScript function calls
-    fn transform_jsx_ast(&self, _program: &ast::Program, original_content: &str) -> String {
-        // For initial AST-based JSX transformation
-        // This will be more accurate than regex parsing
-
-        let mut result = String::new();
-
-        for line in original_content.lines() {
-            let js_line = self.transform_jsx_line(line);
-            result.push_str(&js_line);
-            result.push('\n');
-        }
-
-        result
-    }
-
-    /// Transform a single line of JSX to JavaScript
-    fn transform_jsx_line(&self, line: &str) -> String {
-        let mut result = line.to_string();
-
-        // Clean TypeScript annotations first
-        result = self.clean_typescript_annotations(&result);
-
-        // Transform JSX elements to createElement calls
-        // This is a simplified transformation - full JSX would need more complex AST walking
-
-        // Handle simple JSX elements: <div>content</div> -> React.createElement('div', null, 'content')
-        // Fixed: Removed backreference \1, match with general closing tag pattern
-        if let Ok(re) = regex::Regex::new(r"<(\w+)>([^<]*)</\w+>") {
-            result = re
-                .replace_all(&result, "React.createElement('$1', null, '$2')")
-                .to_string();
-        }
-
-        // Handle self-closing JSX: <div /> -> React.createElement('div', null)
-        if let Ok(re) = regex::Regex::new(r"<(\w+)\s*/>") {
-            result = re
-                .replace_all(&result, "React.createElement('$1', null)")
-                .to_string();
-        }
-
-        // For complex JSX with props, fall back to null for now
-        if let Ok(re) = regex::Regex::new(r"<[^>]*\s[^>]*>") {
-            result = re.replace_all(&result, "null").to_string();
-        }
-
-        result
-    }
-
-    /// Convert JSX syntax to JavaScript function calls (improved regex approach)
-    fn convert_jsx_to_js(&self, content: &str) -> String {
-        Logger::debug(&format!("JSX input content:\n{}", content));
-        let mut result = content.to_string();
-
-        // First strip TypeScript types
-        result = self.strip_typescript_types(&result);
-        Logger::debug(&format!("After TypeScript stripping:\n{}", result));
-
-        // Transform simple lowercase elements: <div>text</div> (single line)
-        // Fixed: Removed backreference \1, match with general lowercase closing tag
-        if let Ok(re) = regex::Regex::new(
-            r#"<([a-z][a-zA-Z0-9]*)\s*([^>]*?)>\s*([^<>]*?)\s*</[a-z][a-zA-Z0-9]*>"#,
-        ) {
-            let callback = |caps: &regex::Captures| {
-                let element = &caps[1];
-                let props = caps.get(2).map_or("", |m| m.as_str()).trim();
-                let children = caps.get(3).map_or("", |m| m.as_str()).trim();
-
-                let props_obj = if props.is_empty() {
-                    "null".to_string()
-                } else {
-                    self.parse_jsx_props_simple(props)
-                };
-
-                let children_str = if children.is_empty() {
-                    ""
-                } else {
-                    &format!(", \"{}\"", children)
-                };
-
-                let replacement = format!(
-                    "React.createElement(\"{}\", {}{children_str})",
-                    element, props_obj
-                );
-                if let Some(original) = caps.get(0) {
-                    Logger::debug(&format!(
-                        "JSX transform: {} -> {}",
-                        original.as_str(),
-                        replacement
-                    ));
-                }
-                replacement
-            };
-            result = re.replace_all(&result, callback).to_string();
-        }
-
-        // Transform self-closing lowercase elements: <input type="text" />
-        if let Ok(re) = regex::RegexBuilder::new(r#"<([a-z][a-zA-Z0-9]*)\s*([^/>]*?)\s*/\s*>"#)
-            .dot_matches_new_line(true)
-            .build()
-        {
-            let callback = |caps: &regex::Captures| {
-                let element = &caps[1];
-                let props = caps.get(2).map_or("", |m| m.as_str()).trim();
-
-                let props_obj = if props.is_empty() {
-                    "null".to_string()
-                } else {
-                    self.parse_jsx_props_simple(props)
-                };
-
-                format!("React.createElement(\"{}\", {})", element, props_obj)
-            };
-            result = re.replace_all(&result, callback).to_string();
-        }
-
-        // Transform self-closing component tags: <Component prop={value} />
-        if let Ok(re) = regex::RegexBuilder::new(r#"<([A-Z][a-zA-Z0-9.]*)\s*([^/>]*?)\s*/\s*>"#)
-            .dot_matches_new_line(true)
-            .build()
-        {
-            let callback = |caps: &regex::Captures| {
-                let component = &caps[1];
-                let props = caps.get(2).map_or("", |m| m.as_str()).trim();
-
-                let props_obj = if props.is_empty() {
-                    "null".to_string()
-                } else {
-                    self.parse_jsx_props_simple(props)
-                };
-
-                format!("React.createElement({}, {})", component, props_obj)
-            };
-            result = re.replace_all(&result, callback).to_string();
-        }
-
-        // Transform simple component elements: <Component>content</Component> (single line)
-        // Fixed: Removed backreference \1, match with general uppercase closing tag
-        if let Ok(re) = regex::Regex::new(
-            r#"<([A-Z][a-zA-Z0-9.]*)\s*([^>]*?)>\s*([^<>]*?)\s*</[A-Z][a-zA-Z0-9.]*>"#,
-        ) {
-            let callback = |caps: &regex::Captures| {
-                let component = &caps[1];
-                let props = caps.get(2).map_or("", |m| m.as_str()).trim();
-                let children = caps.get(3).map_or("", |m| m.as_str()).trim();
-
-                let props_obj = if props.is_empty() {
-                    "null".to_string()
-                } else {
-                    self.parse_jsx_props_simple(props)
-                };
-
-                let children_str = if children.is_empty() {
-                    ""
-                } else {
-                    &format!(", {}", children)
-                };
-
-                format!(
-                    "React.createElement({}, {}{children_str})",
-                    component, props_obj
-                )
-            };
-            result = re.replace_all(&result, callback).to_string();
-        }
-
-        Logger::debug(&format!("Final JSX output:\n{}", result));
-        result
-    }
-
-    /// Simplified JSX props parsing for initial implementation
-    fn parse_jsx_props_simple(&self, props: &str) -> String {
-        if props.trim().is_empty() {
-            return "null".to_string();
-        }
-
-        // For now, return a simple object with the props as-is
-        // This needs improvement for production use
-        let cleaned_props = props
-            .replace("={", ": ")
-            .replace("}", "")
-            .replace("=\"", ": \"");
-
-        format!("{{{}}}", cleaned_props)
-    }
-
-    /// Enhanced JSX props parsing
-    fn parse_jsx_props_enhanced(&self, props: &str) -> String {
-        if props.trim().is_empty() {
-            return "null".to_string();
-        }
-
-        let mut prop_pairs = Vec::new();
-
-        // Handle various prop patterns
-        // Pattern 1: prop="string value"
-        if let Ok(re) = regex::Regex::new(r#"([a-zA-Z][a-zA-Z0-9]*)\s*=\s*"([^"]*)""#) {
-            for caps in re.captures_iter(props) {
-                let prop_name = &caps[1];
-                let value = &caps[2];
-                prop_pairs.push(format!("{}: \"{}\"", prop_name, value));
-            }
-        }
-
-        // Pattern 2: prop='string value'
-        if let Ok(re) = regex::Regex::new(r#"([a-zA-Z][a-zA-Z0-9]*)\s*=\s*'([^']*)'"#) {
-            for caps in re.captures_iter(props) {
-                let prop_name = &caps[1];
-                let value = &caps[2];
-                if !prop_pairs
-                    .iter()
-                    .any(|p| p.starts_with(&format!("{}:", prop_name)))
-                {
-                    prop_pairs.push(format!("{}: \"{}\"", prop_name, value));
-                }
-            }
-        }
-
-        // Pattern 3: prop={expression}
-        if let Ok(re) = regex::Regex::new(r#"([a-zA-Z][a-zA-Z0-9]*)\s*=\s*\{([^}]*)\}"#) {
-            for caps in re.captures_iter(props) {
-                let prop_name = &caps[1];
-                let expression = &caps[2];
-                if !prop_pairs
-                    .iter()
-                    .any(|p| p.starts_with(&format!("{}:", prop_name)))
-                {
-                    prop_pairs.push(format!("{}: {}", prop_name, expression));
-                }
-            }
-        }
-
-        // Pattern 4: boolean props (just the name)
-        // Fixed: Removed negative look-ahead (?!...), filter manually instead
-        if let Ok(re) = regex::Regex::new(r#"\b([a-zA-Z][a-zA-Z0-9]*)\b"#) {
-            for caps in re.captures_iter(props) {
-                let prop_name = &caps[1];
-                // Check if this prop is followed by '=' in original string
-                if let Some(full_match) = caps.get(0) {
-                    let after_match = &props[full_match.end()..];
-                    if !after_match.trim_start().starts_with('=')
-                        && !prop_pairs
-                            .iter()
-                            .any(|p| p.starts_with(&format!("{}:", prop_name)))
-                    {
-                        prop_pairs.push(format!("{}: true", prop_name));
-                    }
-                }
-            }
-        }
-
-        if prop_pairs.is_empty() {
-            "null".to_string()
-        } else {
-            format!("{{{}}}", prop_pairs.join(", "))
-        }
-    }
-
-    /// AST-based TypeScript transformation using oxc parser
-    fn ast_typescript_transform(&self, content: &str, file_path: &Path) -> Result<String> {
-        let allocator = Allocator::default();
-
-        // Parse with unified interface (with fallback to regex on error)
-        let result = match super::common::parse_with_oxc(
-            &allocator,
-            content,
-            super::common::ParsingConfig::typescript(),
-            file_path,
-            "TypeScript parsing failed",
-        ) {
-            Ok(result) => result,
-            Err(_) => {
-                Logger::warn("Falling back to regex-based approach for TypeScript processing");
-                // Fall back to fast regex-based approach if AST parsing fails
-                return Ok(self.fast_typescript_strip(content));
-            }
-        };
-
-        // For now, use a simple approach to extract JavaScript from AST
-        // This is more robust than regex for handling complex TypeScript
-        let transformed = self.extract_javascript_from_ast(&result.program, content);
-        Logger::debug(&format!("AST transformed output: {}", transformed));
-
-        Ok(transformed)
-    }
-
-    /// Extract JavaScript code from TypeScript AST
-    fn extract_javascript_from_ast(
-        &self,
-        _program: &ast::Program,
-        original_content: &str,
-    ) -> String {
-        // For initial implementation, perform selective stripping based on AST validation
-        // This ensures we only transform syntactically valid TypeScript
-
-        // Use the fast regex-based approach which is more robust
-        // TODO: Implement proper AST-based transformation later
-        self.fast_typescript_strip(original_content)
-    }
-
-    /// Parse JSX props into JavaScript object notation
-    fn parse_jsx_props(&self, props: &str) -> String {
-        if props.trim().is_empty() {
-            return "null".to_string();
-        }
-
-        let mut prop_pairs = Vec::new();
-
-        // Simple regex to match prop="value" or prop={expression}
-        if let Ok(re) =
-            regex::Regex::new(r#"([a-zA-Z][a-zA-Z0-9]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|\{([^}]*)\})"#)
-        {
-            for caps in re.captures_iter(props) {
-                let prop_name = &caps[1];
-                let value = if let Some(quoted) = caps.get(2).or(caps.get(3)) {
-                    format!("\"{}\"", quoted.as_str())
-                } else if let Some(expr) = caps.get(4) {
-                    expr.as_str().to_string()
-                } else {
-                    "true".to_string()
-                };
-                prop_pairs.push(format!("{}: {}", prop_name, value));
-            }
-        }
-
-        if prop_pairs.is_empty() {
-            "null".to_string()
-        } else {
-            format!("{{{}}}", prop_pairs.join(", "))
-        }
-    }
-
-    /// Fast TypeScript type stripping for fallback scenarios
-    fn fast_typescript_strip(&self, content: &str) -> String {
-        let mut lines = Vec::new();
-        let mut in_declaration = false;
-        let mut brace_depth = 0;
-
-        for line in content.lines() {
-            let trimmed = line.trim();
-
-            // Skip decorators (both @Decorator and @Decorator())
-            if trimmed.starts_with('@') {
-                Logger::debug(&format!("Stripping decorator: {}", trimmed));
-                continue;
-            }
-
-            // Skip import statements
-            if trimmed.starts_with("import ") {
-                continue;
-            }
-
-            // Skip single-line type aliases
-            if (trimmed.starts_with("type ") || trimmed.starts_with("export type "))
-                && trimmed.contains("=")
-                && trimmed.ends_with(";")
-                && !trimmed.contains("{")
-            {
-                continue;
-            }
-
-            // Check if we're starting a multi-line TypeScript declaration
-            if trimmed.starts_with("interface ")
-                || trimmed.starts_with("export interface ")
-                || trimmed.starts_with("type ") && trimmed.contains("=") && trimmed.contains("{")
-                || trimmed.starts_with("export type ")
-                    && trimmed.contains("=")
-                    && trimmed.contains("{")
-                || trimmed.starts_with("enum ")
-                || trimmed.starts_with("export enum ")
-                || trimmed.starts_with("const enum ")
-                || trimmed.starts_with("export const enum ")
-            {
-                in_declaration = true;
-                if trimmed.contains('{') {
-                    brace_depth = trimmed.matches('{').count() - trimmed.matches('}').count();
-                }
-                // Check if it's a single-line declaration
-                if brace_depth == 0 && trimmed.ends_with("}") {
-                    in_declaration = false;
-                }
-                continue;
-            }
-
-            // If we're in a declaration, track braces and skip content
-            if in_declaration {
-                if trimmed.contains('{') {
-                    brace_depth += trimmed.matches('{').count();
-                }
-                if trimmed.contains('}') {
-                    brace_depth -= trimmed.matches('}').count();
-                    if brace_depth == 0 {
-                        in_declaration = false;
-                    }
-                }
-                continue;
-            }
-
-            // Basic type annotation cleaning for function parameters
-            let mut cleaned = line.to_string();
-
-            // Remove type annotations and return types using shared function
-            cleaned = super::common::clean_typescript_inline_annotations(&cleaned);
-
-            lines.push(cleaned);
-        }
-
-        lines.join("\n")
-    }
-
-    /// Advanced TypeScript type stripping with comprehensive support
-    fn strip_typescript_types(&self, content: &str) -> String {
-        // Use the fast approach by default to avoid performance issues
-        self.fast_typescript_strip(content)
-    }
-
-    /// Clean TypeScript annotations from a single line of code
-    fn clean_typescript_annotations(&self, line: &str) -> String {
-        let mut result = line.to_string();
-
-        // Fix common syntax errors first
-
-        // Fix malformed function calls: onCountChange.(value) -> onCountChange(value)
-        if let Ok(re) = regex::Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\.\(([^)]*)\)") {
-            result = re.replace_all(&result, "$1($2)").to_string();
-        }
-
-        // Fix template literal syntax: ${variant $disabled -> ${variant} ${disabled
-        if let Ok(re) =
-            regex::Regex::new(r"\$\{([a-zA-Z_$][a-zA-Z0-9_$]*)\s+\$([a-zA-Z_$][a-zA-Z0-9_$]*)")
-        {
-            result = re.replace_all(&result, "${$1} ${$2").to_string();
-        }
-
-        // Clean destructuring parameters with types: ({ text, onClick }: Props) -> ({ text, onClick })
-        if let Ok(re) = regex::Regex::new(r"\(\s*\{([^}]*)\}\s*:\s*[^)]*\)") {
-            result = re.replace_all(&result, "({ $1 })").to_string();
-        }
-
-        // Clean regular parameters with types: (text, onClick, disabled = false, variant = 'primary' : ButtonProps) -> (text, onClick, disabled = false, variant = 'primary')
-        if let Ok(re) = regex::Regex::new(r"\(([^)]*?)\s*:\s*[^)]*\)\s*=>") {
-            result = re.replace_all(&result, "($1) =>").to_string();
-        }
-
-        // Clean function parameters inline: text: string, -> text,
-        if let Ok(re) = regex::Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\s*:\s*[^,)=]+([,)])") {
-            result = re.replace_all(&result, "$1$2").to_string();
-        }
-
-        // Clean variable declarations: let x: number = 5 -> let x = 5
-        if let Ok(re) =
-            regex::Regex::new(r"(let|const|var)\s+([a-zA-Z_$][a-zA-Z0-9_$]*)\s*:\s*[^=]+(\s*=)")
-        {
-            result = re.replace_all(&result, "$1 $2$3").to_string();
-        }
-
-        // Clean function return types: function foo(): Type -> function foo()
-        if let Ok(re) = regex::Regex::new(
-            r"(function\s+[a-zA-Z_$][a-zA-Z0-9_$]*\s*\([^)]*\))\s*:\s*[^{]+(\s*\{)",
-        ) {
-            result = re.replace_all(&result, "$1$2").to_string();
-        }
-
-        // Clean arrow function return types: (): Type => -> () =>
-        if let Ok(re) = regex::Regex::new(r"\)\s*:\s*[^=]+(\s*=>)") {
-            result = re.replace_all(&result, ")$1").to_string();
-        }
-
-        // Add missing braces for arrow functions - DISABLED due to syntax errors
-        // if let Ok(re) = regex::Regex::new(r"\)\s*=>\s*$") {
-        //     result = re.replace_all(&result, ") => {").to_string();
-        // }
-        // if let Ok(re) = regex::Regex::new(r"\{\s*$\s*;\s*$") {
-        //     result = re.replace_all(&result, "};\n").to_string();
-        // }
-
-        // Remove generic type parameters
-        if let Ok(re) = regex::Regex::new(r"<[^<>]*>") {
-            result = re.replace_all(&result, "").to_string();
-        }
-
-        // Remove TypeScript non-null assertion operator
-        if let Ok(re) = regex::Regex::new(r"([a-zA-Z_$][a-zA-Z0-9_$]*)\s*!") {
-            result = re.replace_all(&result, "$1").to_string();
-        }
-
-        // Remove access modifiers
-        if let Ok(re) = regex::Regex::new(r"\b(private|public|protected|readonly)\s+") {
-            result = re.replace_all(&result, "").to_string();
-        }
-
-        // Remove as type assertions
-        if let Ok(re) = regex::Regex::new(r"\s+as\s+[a-zA-Z_$][a-zA-Z0-9_$<>\[\]|&\s]*") {
-            result = re.replace_all(&result, "").to_string();
-        }
-
-        result
-    }
-
-    /// Clean function parameters of TypeScript types
-    fn clean_function_parameters(&self, content: &str) -> String {
-        let mut result = content.to_string();
-
-        // Clean destructuring parameters with types: ({ text, onClick }: Props) -> ({ text, onClick })
-        if let Ok(re) = regex::Regex::new(r"\(\s*\{([^}]*)\}\s*:\s*[^)]+\)") {
-            result = re.replace_all(&result, "({$1})").to_string();
-        }
-
-        // Clean regular typed parameters: (x: number, y: string) -> (x, y)
-        if let Ok(re) = regex::Regex::new(r"\(([^)]*)\)") {
-            result = re
-                .replace_all(&result, |caps: &regex::Captures| {
-                    let params = &caps[1];
-                    if params.trim().is_empty() {
-                        return "()".to_string();
-                    }
-
-                    // Handle destructuring separately from regular params
-                    if params.contains('{') && !params.contains(':') {
-                        // Already processed destructuring above
-                        return format!("({})", params);
-                    }
-
-                    let cleaned_params: Vec<String> = params
-                        .split(',')
-                        .map(|param| {
-                            let trimmed = param.trim();
-
-                            // Handle destructuring: { text, onClick }: Props -> { text, onClick }
-                            if trimmed.starts_with('{') {
-                                if let Some(colon_pos) = trimmed.find(':') {
-                                    let destructured = trimmed[..colon_pos].trim();
-                                    return destructured.to_string();
-                                }
-                                return trimmed.to_string();
-                            }
-
-                            // Extract parameter name before colon: "x: number" -> "x"
-                            if let Some(colon_pos) = trimmed.find(':') {
-                                let param_name = trimmed[..colon_pos].trim();
-                                // Handle optional parameters: "x?" -> "x"
-                                let clean_name = param_name.trim_end_matches('?');
-                                // Handle default values: "disabled = false" -> keep as is
-                                clean_name.to_string()
-                            } else {
-                                // Keep parameters that don't have types
-                                trimmed.trim_end_matches('?').to_string()
-                            }
-                        })
-                        .collect();
-
-                    format!("({})", cleaned_params.join(", "))
-                })
-                .to_string();
-        }
-
-        result
-    }
-
-    /// Clean generic types recursively
-    fn clean_generic_types(&self, content: &str) -> String {
-        const MAX_ITERATIONS: usize = 10; // Prevent infinite loops
-        super::common::remove_generic_types(content, MAX_ITERATIONS)
-    }
-
-    /// Clean export statements while preserving JavaScript functionality
-    fn clean_export_statement(&self, line: &str) -> String {
-        let mut result = line.to_string();
-
-        // Handle export function with types: export function foo(x: number): string -> export function foo(x)
-        if result.contains("export function") {
-            result = self.clean_typescript_annotations(&result);
-        }
-
-        // Handle export const with types: export const x: number = 5 -> export const x = 5
-        if result.contains("export const")
-            || result.contains("export let")
-            || result.contains("export var")
-        {
-            result = self.clean_typescript_annotations(&result);
-        }
-
-        // Handle export class with types
-        if result.contains("export class") {
-            result = self.clean_typescript_annotations(&result);
-        }
-
-        result
+        Logger::processing_typescript(&format!(
+            "TypeScript {} (AST-based)",
+            module
+                .path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+        ));
+        // TypeScript and TSX are compiled by oxc_transformer
+        super::js_transformer::transform_module(
+            &module.content,
+            &module.path,
+            &BuildConfig::default(),
+        )
     }
 
     /// Enhanced JavaScript processing with optimizations
@@ -724,18 +110,8 @@ impl EnhancedJsProcessor {
         );
         // Ignore parse errors, just log warnings (already done in parse_with_oxc)
 
-        // Simple processing: remove import/export statements for bundling
-        let processed = module
-            .content
-            .lines()
-            .filter(|line| {
-                let trimmed = line.trim();
-                !trimmed.starts_with("import ") && !trimmed.starts_with("export ")
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        Ok(processed)
+        // Imports/exports are kept: the module linker resolves them at bundle time
+        Ok(module.content.clone())
     }
 }
 
@@ -783,61 +159,26 @@ impl JsProcessor for EnhancedJsProcessor {
         result
     }
 
-    async fn bundle_modules(&self, modules: &[ModuleInfo]) -> Result<String> {
+    async fn bundle_modules(&self, modules: &[ModuleInfo], config: &BuildConfig) -> Result<String> {
         let _timer = crate::utils::Timer::start("Enhanced bundling modules");
 
-        let mut bundle = String::new();
-        bundle.push_str("// Soku Bundler - Enhanced TypeScript/JavaScript Build\n");
-        bundle.push_str("(function() {\n'use strict';\n\n");
+        let inputs = self.process_inputs(modules).await?;
+        let linked = self.link(&inputs, None, config, false);
+        let body = linked.render(|path| Self::module_header(modules, path));
 
-        // Process modules with enhanced performance
-        for module in modules {
-            if self.supports_module_type(&module.module_type) {
-                Logger::processing_file(
-                    module
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown"),
-                    match module.module_type {
-                        ModuleType::TypeScript => "Enhanced TS",
-                        ModuleType::JavaScript => "Enhanced JS",
-                        _ => "Enhanced",
-                    },
-                );
-
-                let processed = self.process_module(module).await?;
-                bundle.push_str(&format!(
-                    "// Module: {} ({})\n",
-                    module.path.display(),
-                    match module.module_type {
-                        ModuleType::TypeScript => "TypeScript → JavaScript",
-                        ModuleType::JavaScript => "JavaScript",
-                        _ => "Unknown",
-                    }
-                ));
-                bundle.push_str(&processed);
-                bundle.push_str("\n\n");
-            }
-        }
-
-        bundle.push_str("})();\n");
-        Ok(bundle)
+        format_bundle(&linked, BUNDLE_BANNER, &body, config)
     }
 
     async fn bundle_modules_with_tree_shaking(
         &self,
         modules: &[ModuleInfo],
         _tree_shaking_stats: Option<&TreeShakingStats>,
+        config: &BuildConfig,
     ) -> Result<String> {
         let _timer = crate::utils::Timer::start(
             "Enhanced bundling with tree shaking and node_modules optimization",
         );
 
-        let mut bundle = String::new();
-        bundle.push_str("// Soku Bundler - Enhanced Build with Node Modules Tree Shaking\n");
-        bundle.push_str("(function() {\n'use strict';\n\n");
-
         // Separate node_modules from local modules for different processing
         let (local_modules, node_modules): (Vec<_>, Vec<_>) = modules
             .iter()
@@ -849,42 +190,41 @@ impl JsProcessor for EnhancedJsProcessor {
             node_modules.len()
         ));
 
-        // Process local modules first
+        let mut inputs = Vec::with_capacity(modules.len());
         for module in local_modules {
-            let processed = self.process_module(module).await?;
-            bundle.push_str(&format!("// Module: {}\n", module.path.display()));
-            bundle.push_str(&processed);
-            bundle.push_str("\n\n");
+            inputs.push((module, self.process_module(module).await?));
         }
 
-        // Process node_modules with optimization
-        if !node_modules.is_empty() {
-            bundle.push_str("// === NODE MODULES (Tree Shaken) ===\n");
-
-            for module in node_modules {
-                Logger::processing_file(
-                    module
-                        .path
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown"),
-                    "tree shaking node_modules",
-                );
-
-                let processed = self.optimize_node_module_content(&module.content, &module.path);
-
-                bundle.push_str(&format!(
-                    "// Node Module: {}\n",
-                    self.extract_package_name(&module.path)
-                ));
-                bundle.push_str(&processed);
-                bundle.push_str("\n\n");
-            }
+        // node_modules are plain JavaScript (often CommonJS) and are linked as-is
+        for module in node_modules {
+            Logger::processing_file(
+                module
+                    .path
+                    .file_name()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("unknown"),
+                "bundling node_modules",
+            );
+            inputs.push((module, module.content.clone()));
         }
 
-        bundle.push_str("})();\n");
+        // Modules are emitted in dependency order, so node_modules end up
+        // before the local code that imports them
+        let linked = self.link(&inputs, None, config, false);
+        let body = linked.render(|path| {
+            if self.is_node_modules_path(path) {
+                format!("// Node Module: {}\n", self.extract_package_name(path))
+            } else {
+                format!("// Module: {}\n", module_display_name(path))
+            }
+        });
 
-        Ok(bundle)
+        format_bundle(
+            &linked,
+            "// Soku Bundler - Enhanced Build with Node Modules Tree Shaking\n",
+            &body,
+            config,
+        )
     }
 
     async fn bundle_modules_with_source_maps(
@@ -892,32 +232,149 @@ impl JsProcessor for EnhancedJsProcessor {
         modules: &[ModuleInfo],
         config: &BuildConfig,
     ) -> Result<BundleOutput> {
-        // For now, use a simple implementation that delegates to regular bundling
-        // TODO: Implement proper source maps for enhanced TypeScript processing
-        if config.enable_source_maps {
-            let code = self.bundle_modules(modules).await?;
-            Ok(BundleOutput {
-                code: format!("{}\n//# sourceMappingURL=bundle.js.map", code),
-                source_map: Some(
-                    r#"{"version":3,"sources":["enhanced"],"names":[],"mappings":"AAAA"}"#
-                        .to_string(),
-                ),
-            })
-        } else {
-            let code = self.bundle_modules(modules).await?;
-            Ok(BundleOutput {
+        if !config.enable_source_maps {
+            let code = self.bundle_modules(modules, config).await?;
+            return Ok(BundleOutput {
                 code,
                 source_map: None,
-            })
+            });
         }
+
+        let inputs = self.process_inputs(modules).await?;
+        let maps = Self::input_maps(&inputs)?;
+        let linked = self.link(&inputs, Some(&maps), config, false);
+        let (body, body_map) = linked.render_with_map(|path| Self::module_header(modules, path));
+        let (code, source_map) =
+            format_bundle_with_map(&linked, BUNDLE_BANNER, &body, &body_map, config)?;
+
+        Ok(BundleOutput {
+            code,
+            source_map: Some(source_map),
+        })
+    }
+
+    async fn bundle_chunks(
+        &self,
+        modules: &[ModuleInfo],
+        chunks: &[Vec<PathBuf>],
+        config: &BuildConfig,
+    ) -> Result<Vec<BundleOutput>> {
+        let inputs = self.process_inputs(modules).await?;
+        let maps = if config.enable_source_maps {
+            Some(Self::input_maps(&inputs)?)
+        } else {
+            None
+        };
+        let linked = self.link(&inputs, maps.as_deref(), config, true);
+        format_chunks(
+            &linked,
+            chunks,
+            BUNDLE_BANNER,
+            |path| Self::module_header(modules, path),
+            config,
+        )
     }
 
     fn supports_module_type(&self, module_type: &ModuleType) -> bool {
         matches!(module_type, ModuleType::JavaScript | ModuleType::TypeScript)
     }
+
+    fn take_module_linking(&self) -> Vec<ModuleLinking> {
+        std::mem::take(&mut *self.linking.lock())
+    }
+
+    fn take_link_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.link_warnings.lock())
+    }
 }
 
 impl EnhancedJsProcessor {
+    /// Process the modules this processor supports, in order
+    async fn process_inputs<'a>(
+        &self,
+        modules: &'a [ModuleInfo],
+    ) -> Result<Vec<(&'a ModuleInfo, String)>> {
+        let mut inputs = Vec::with_capacity(modules.len());
+        for module in modules {
+            if self.supports_module_type(&module.module_type) {
+                Logger::processing_file(
+                    module
+                        .path
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("unknown"),
+                    match module.module_type {
+                        ModuleType::TypeScript => "Enhanced TS",
+                        ModuleType::JavaScript => "Enhanced JS",
+                        _ => "Enhanced",
+                    },
+                );
+
+                inputs.push((module, self.process_module(module).await?));
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// Maps of the processed modules back to their sources
+    fn input_maps(inputs: &[(&ModuleInfo, String)]) -> Result<Vec<CodeMap>> {
+        // TypeScript is compiled with the default options, see `process_typescript`
+        inputs
+            .iter()
+            .map(|(module, processed)| {
+                processed_module_map(
+                    &module.content,
+                    processed,
+                    &module.path,
+                    &BuildConfig::default(),
+                )
+            })
+            .collect()
+    }
+
+    /// Comment labelling a module in the bundle
+    fn module_header(modules: &[ModuleInfo], path: &Path) -> String {
+        format!(
+            "// Module: {} ({})\n",
+            module_display_name(path),
+            match modules
+                .iter()
+                .find(|m| m.path == path)
+                .map(|m| &m.module_type)
+            {
+                Some(ModuleType::TypeScript) => "TypeScript → JavaScript",
+                Some(ModuleType::JavaScript) => "JavaScript",
+                _ => "Unknown",
+            }
+        )
+    }
+
+    /// Link processed modules into a single scope, logging linker warnings.
+    /// `shared_registry` links them for a build split into chunks.
+    fn link(
+        &self,
+        inputs: &[(&ModuleInfo, String)],
+        maps: Option<&[CodeMap]>,
+        config: &BuildConfig,
+        shared_registry: bool,
+    ) -> super::module_linker::LinkOutput {
+        let linker = super::module_linker::ModuleLinker::new()
+            .with_hmr(config.hmr)
+            .with_shared_registry(shared_registry);
+        let linked = match maps {
+            Some(maps) => linker.link_with_maps(inputs, maps),
+            None => linker.link(inputs),
+        };
+        for warning in &linked.warnings {
+            Logger::warn(warning);
+        }
+        self.linking.lock().extend(linked.linking());
+        self.link_warnings
+            .lock()
+            .extend(linked.warnings.iter().cloned());
+        linked
+    }
+
     /// Check if a module path is from node_modules
     fn is_node_modules_path(&self, path: &std::path::Path) -> bool {
         super::common::is_node_modules_path(path)
@@ -927,11 +384,6 @@ impl EnhancedJsProcessor {
     fn extract_package_name(&self, path: &std::path::Path) -> String {
         super::common::extract_package_name(path)
     }
-
-    /// Optimize node_modules content by keeping only essential parts
-    fn optimize_node_module_content(&self, content: &str, path: &std::path::Path) -> String {
-        super::common::optimize_node_module_content(content, path)
-    }
 }
 
 impl Default for EnhancedJsProcessor {
@@ -974,6 +426,7 @@ const items: Array<string> = ['a', 'b'];
             module_type: ModuleType::TypeScript,
             dependencies: vec![],
             exports: vec![],
+            resolved_imports: std::collections::HashMap::new(),
         };
 
         let result = processor.process_module(&module).await.unwrap();
@@ -992,8 +445,10 @@ const items: Array<string> = ['a', 'b'];
         assert!(result.contains("console.log"));
 
         // Check that some type stripping occurred (spacing may vary)
-        assert!(result.contains("let count") && result.contains("42")); // Should have ": number" removed
-        assert!(result.contains("const items") && result.contains("['a', 'b']"));
+        // Should have ": number" removed
+        assert!(result.contains("let count") && result.contains("42"));
+        // The output is printed by oxc_codegen, which uses double quotes
+        assert!(result.contains("const items") && result.contains(r#"["a", "b"]"#));
         // Should have "Array<string>" removed
     }
 
@@ -1007,6 +462,7 @@ const items: Array<string> = ['a', 'b'];
             module_type: ModuleType::TypeScript,
             dependencies: vec![],
             exports: vec![],
+            resolved_imports: std::collections::HashMap::new(),
         };
 
         // First processing
@@ -1044,6 +500,7 @@ const Counter = ({ title, count }: Props) => {
             module_type: ModuleType::TypeScript,
             dependencies: vec![],
             exports: vec![],
+            resolved_imports: std::collections::HashMap::new(),
         };
 
         let result = processor.process_module(&module).await.unwrap();
@@ -1093,6 +550,7 @@ class MyService {
             module_type: ModuleType::TypeScript,
             dependencies: vec![],
             exports: vec![],
+            resolved_imports: std::collections::HashMap::new(),
         };
 
         let result = processor.process_module(&module).await.unwrap();
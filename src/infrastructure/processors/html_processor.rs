@@ -0,0 +1,374 @@
+// HTML Processor - HTML pages as build entries
+// Pages reference their scripts and stylesheets by source path; once the
+// bundles are written those tags point at the output files of the manifest,
+// with a `<script>` per imported chunk and the entry's stylesheets in `<head>`
+
+use crate::utils::entry_points::{HTML_COMMENT_REGEX, SRC_ATTR_REGEX, TYPE_ATTR_REGEX};
+use crate::utils::{
+    attribute_value, is_javascript_script, resolve_page_url, BuildManifest, Logger,
+};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+static LINK_TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<link\b([^>]*?)/?>").unwrap());
+static SCRIPT_ELEMENT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<script\b([^>]*)>\s*</script\s*>").unwrap());
+static REL_ATTR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\brel\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+static HREF_ATTR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+static HEAD_END_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)</head\s*>").unwrap());
+static BODY_START_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<body\b").unwrap());
+
+/// Local stylesheets linked from an HTML page
+pub fn html_stylesheets(html: &str, page: &Path, root: &Path) -> Vec<PathBuf> {
+    let html = HTML_COMMENT_REGEX.replace_all(html, "");
+    let mut stylesheets = Vec::new();
+    for tag in LINK_TAG_REGEX.captures_iter(&html) {
+        let Some(href) = stylesheet_href(&tag[1]) else {
+            continue;
+        };
+        let Some(path) = resolve_page_url(href, page, root) else {
+            continue;
+        };
+        if !path.is_file() {
+            Logger::warn(&format!(
+                "{} links a missing stylesheet: {}",
+                page_name(page),
+                href
+            ));
+            continue;
+        }
+        if !stylesheets.contains(&path) {
+            stylesheets.push(path);
+        }
+    }
+    stylesheets
+}
+
+/// Path of a page in the output directory, e.g. `admin/index.html`
+pub fn html_output_name(page: &Path, root: &Path) -> String {
+    let page = page.canonicalize().unwrap_or_else(|_| page.to_path_buf());
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    match page.strip_prefix(&root) {
+        Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+        Err(_) => page_name(&page),
+    }
+}
+
+/// Point the script and stylesheet tags of a page at the built files
+///
+/// Tags referencing the same output file twice are dropped, and tags for
+/// files that are not part of the build are left untouched. Root-absolute
+/// URLs stay root-absolute, so a page served for a nested client-side route
+/// still loads its files.
+pub fn rewrite_html(html: &str, page: &Path, root: &Path, manifest: &BuildManifest) -> String {
+    // Output names are relative to the output directory, pages may be nested in it
+    let relative_base = "../".repeat(html_output_name(page, root).matches('/').count());
+    let base_for = |url: &str| {
+        if url.starts_with('/') {
+            "/"
+        } else {
+            relative_base.as_str()
+        }
+    };
+    let mut emitted: HashSet<String> = HashSet::new();
+    let mut stylesheets: Vec<String> = Vec::new();
+
+    let comments = comment_ranges(html);
+    let html = LINK_TAG_REGEX.replace_all(html, |tag: &Captures| {
+        let element = tag.get(0).unwrap();
+        if in_comment(&comments, element.start()) {
+            return element.as_str().to_string();
+        }
+        let Some(href) = stylesheet_href(&tag[1]) else {
+            return element.as_str().to_string();
+        };
+        let Some(path) = resolve_page_url(href, page, root) else {
+            return element.as_str().to_string();
+        };
+        let Some(entry) = manifest.get_source(&path) else {
+            Logger::warn(&format!(
+                "{} links a stylesheet that is not part of the build: {}",
+                page_name(page),
+                href
+            ));
+            return element.as_str().to_string();
+        };
+        if !emitted.insert(entry.file.clone()) {
+            return String::new();
+        }
+        let url = format!("{}{}", base_for(href), entry.file);
+        set_attribute(element.as_str(), &HREF_ATTR_REGEX, "href", &url)
+    });
+
+    let comments = comment_ranges(&html);
+    let html = SCRIPT_ELEMENT_REGEX.replace_all(&html, |tag: &Captures| {
+        let element = tag.get(0).unwrap();
+        let attributes = &tag[1];
+        if in_comment(&comments, element.start()) || !is_javascript_script(attributes) {
+            return element.as_str().to_string();
+        }
+        let Some(src) = attribute_value(&SRC_ATTR_REGEX, attributes).map(str::trim) else {
+            return element.as_str().to_string();
+        };
+        let Some(path) = resolve_page_url(src, page, root) else {
+            return element.as_str().to_string();
+        };
+        let Some(entry) = manifest.get_source(&path) else {
+            return element.as_str().to_string();
+        };
+
+        // Files the entry loads use the same kind of URL as its tag
+        let base = base_for(src);
+        for css in &entry.css {
+            if emitted.insert(css.clone()) {
+                stylesheets.push(format!("{}{}", base, css));
+            }
+        }
+
+        // Chunks the entry imports are loaded first, with the same script type
+        let type_attribute = attribute_value(&TYPE_ATTR_REGEX, attributes)
+            .map(|script_type| format!(" type=\"{}\"", script_type))
+            .unwrap_or_default();
+        let mut tags = Vec::new();
+        for import in &entry.imports {
+            if emitted.insert(import.clone()) {
+                tags.push(format!(
+                    "<script{} src=\"{}{}\"></script>",
+                    type_attribute, base, import
+                ));
+            }
+        }
+        if emitted.insert(entry.file.clone()) {
+            let url = format!("{}{}", base, entry.file);
+            tags.push(set_attribute(
+                element.as_str(),
+                &SRC_ATTR_REGEX,
+                "src",
+                &url,
+            ));
+        }
+        tags.join(&format!("\n{}", line_indent(&html, element.start())))
+    });
+
+    if stylesheets.is_empty() {
+        return html.into_owned();
+    }
+    let links: Vec<String> = stylesheets
+        .iter()
+        .map(|url| format!("<link rel=\"stylesheet\" href=\"{}\">", url))
+        .collect();
+    inject_into_head(&html, &links)
+}
+
+/// Insert tags at the end of `<head>`, or before `<body>` when there is none
+pub fn inject_into_head(html: &str, tags: &[String]) -> String {
+    if let Some(head_end) = HEAD_END_REGEX.find(html) {
+        let indent = line_indent(html, head_end.start());
+        let line_start = head_end.start() - indent.len();
+        if html[..line_start].ends_with('\n') {
+            // Indent the tags one level deeper than `</head>`
+            let lines: String = tags
+                .iter()
+                .map(|tag| format!("{}  {}\n", indent, tag))
+                .collect();
+            return format!("{}{}{}", &html[..line_start], lines, &html[line_start..]);
+        }
+        return format!(
+            "{}{}{}",
+            &html[..head_end.start()],
+            tags.concat(),
+            &html[head_end.start()..]
+        );
+    }
+    let position = BODY_START_REGEX
+        .find(html)
+        .map(|body| body.start())
+        .unwrap_or(0);
+    format!(
+        "{}{}\n{}",
+        &html[..position],
+        tags.join("\n"),
+        &html[position..]
+    )
+}
+
+/// `href` of a `<link rel="stylesheet">` tag
+fn stylesheet_href(attributes: &str) -> Option<&str> {
+    let rel = attribute_value(&REL_ATTR_REGEX, attributes)?;
+    if !rel
+        .split_ascii_whitespace()
+        .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+    {
+        return None;
+    }
+    attribute_value(&HREF_ATTR_REGEX, attributes)
+        .map(str::trim)
+        .filter(|href| !href.is_empty())
+}
+
+/// Replace the attribute matched by `regex` in `tag`
+fn set_attribute(tag: &str, regex: &Regex, name: &str, value: &str) -> String {
+    regex
+        .replace(tag, |_: &Captures| format!("{}=\"{}\"", name, value))
+        .into_owned()
+}
+
+/// Whitespace between the start of the line and `position`
+fn line_indent(html: &str, position: usize) -> &str {
+    let line_start = html[..position].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &html[line_start..position];
+    if indent.chars().all(char::is_whitespace) {
+        indent
+    } else {
+        ""
+    }
+}
+
+fn comment_ranges(html: &str) -> Vec<Range<usize>> {
+    HTML_COMMENT_REGEX
+        .find_iter(html)
+        .map(|comment| comment.range())
+        .collect()
+}
+
+fn in_comment(comments: &[Range<usize>], position: usize) -> bool {
+    comments.iter().any(|comment| comment.contains(&position))
+}
+
+fn page_name(page: &Path) -> String {
+    page.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ManifestChunk;
+
+    fn manifest() -> BuildManifest {
+        let mut manifest = BuildManifest::new(Path::new("/app"));
+        manifest.add_chunk(ManifestChunk {
+            name: "vendor",
+            file: "vendor.1111aaaa.js",
+            entries: &[],
+            modules: &[],
+            imports: Vec::new(),
+        });
+        manifest.add_chunk(ManifestChunk {
+            name: "app",
+            file: "assets/app.2222bbbb.js",
+            entries: &[PathBuf::from("/app/src/main.js")],
+            modules: &[],
+            imports: vec!["vendor.1111aaaa.js".to_string()],
+        });
+        manifest.add_css(
+            "bundle.3333cccc.css",
+            &[PathBuf::from("/app/src/style.css")],
+        );
+        manifest
+    }
+
+    #[test]
+    fn test_rewrite_html_points_tags_at_outputs() {
+        let html = r#"<!doctype html>
+<html>
+  <head>
+    <link rel="icon" href="/favicon.ico">
+    <link rel="stylesheet" href="./src/style.css">
+  </head>
+  <body>
+    <!-- <script src="/src/main.js"></script> -->
+    <script type="module" src="/src/main.js"></script>
+    <script type="module" src="/src/main.js?again"></script>
+    <script src="https://cdn.example.com/lib.js"></script>
+  </body>
+</html>"#;
+        let rewritten = rewrite_html(
+            html,
+            Path::new("/app/index.html"),
+            Path::new("/app"),
+            &manifest(),
+        );
+
+        assert!(rewritten.contains(r#"<link rel="icon" href="/favicon.ico">"#));
+        assert!(rewritten.contains(r#"<link rel="stylesheet" href="bundle.3333cccc.css">"#));
+        assert!(rewritten.contains(r#"<!-- <script src="/src/main.js"></script> -->"#));
+        assert!(rewritten.contains(
+            "    <script type=\"module\" src=\"/vendor.1111aaaa.js\"></script>\n    <script type=\"module\" src=\"/assets/app.2222bbbb.js\"></script>"
+        ));
+        assert_eq!(rewritten.matches("app.2222bbbb.js").count(), 1);
+        assert_eq!(rewritten.matches("bundle.3333cccc.css").count(), 1);
+        assert!(rewritten.contains(r#"<script src="https://cdn.example.com/lib.js"></script>"#));
+    }
+
+    #[test]
+    fn test_rewrite_html_links_entry_css_from_nested_page() {
+        let html = "<html>\n  <head>\n    <title>Admin</title>\n  </head>\n  <body>\n    <script src=\"main.js\"></script>\n  </body>\n</html>";
+        let rewritten = rewrite_html(
+            html,
+            Path::new("/app/src/index.html"),
+            Path::new("/app"),
+            &manifest(),
+        );
+
+        assert!(rewritten.contains(
+            "    <title>Admin</title>\n    <link rel=\"stylesheet\" href=\"../bundle.3333cccc.css\">\n  </head>"
+        ));
+        assert!(rewritten.contains(r#"<script src="../vendor.1111aaaa.js"></script>"#));
+        assert!(rewritten.contains(r#"<script src="../assets/app.2222bbbb.js"></script>"#));
+    }
+
+    #[test]
+    fn test_rewrite_html_keeps_absolute_urls_for_nested_routes() {
+        // Served as the fallback page of routes like `/users/42`
+        let html = "<html>\n  <head>\n  </head>\n  <body>\n    <script src=\"/src/main.js\"></script>\n  </body>\n</html>";
+        let rewritten = rewrite_html(
+            html,
+            Path::new("/app/index.html"),
+            Path::new("/app"),
+            &manifest(),
+        );
+
+        assert!(rewritten.contains(r#"<link rel="stylesheet" href="/bundle.3333cccc.css">"#));
+        assert!(rewritten.contains(r#"<script src="/vendor.1111aaaa.js"></script>"#));
+        assert!(rewritten.contains(r#"<script src="/assets/app.2222bbbb.js"></script>"#));
+    }
+
+    #[test]
+    fn test_inject_into_head() {
+        let tags = vec!["<script>hmr()</script>".to_string()];
+        assert_eq!(
+            inject_into_head("<head><title>x</title></head>", &tags),
+            "<head><title>x</title><script>hmr()</script></head>"
+        );
+        assert_eq!(
+            inject_into_head("<body><p>x</p></body>", &tags),
+            "<script>hmr()</script>\n<body><p>x</p></body>"
+        );
+    }
+
+    #[test]
+    fn test_html_stylesheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("pages")).unwrap();
+        std::fs::write(root.join("pages/page.css"), "").unwrap();
+        std::fs::write(root.join("global.css"), "").unwrap();
+
+        let html = r#"<link rel="stylesheet" href="page.css">
+<link rel="preload stylesheet" href="/global.css">
+<link rel="stylesheet" href="https://fonts.example.com/font.css">
+<!-- <link rel="stylesheet" href="old.css"> -->"#;
+        assert_eq!(
+            html_stylesheets(html, &root.join("pages/index.html"), root),
+            vec![root.join("pages/page.css"), root.join("global.css")]
+        );
+    }
+}
@@ -0,0 +1,1151 @@
+// JS Transformer - JSX and TypeScript lowering with oxc_transformer
+// JSX uses the classic (`React.createElement`, Preact's `h`) or automatic
+// runtime, overridable per file with `@jsx`, `@jsxFrag`, `@jsxRuntime` and
+// `@jsxImportSource` comments. TypeScript is compiled, not stripped: enums and
+// namespaces become objects, decorators `babelHelpers.decorate` calls, and
+// `const enum` members of the same file are inlined; importers of an exported
+// `const enum` read the runtime object, like tsc with `isolatedModules`. Syntax
+// newer than the `target` is lowered, and syntax that cannot be fails with its
+// location
+
+use super::common::{
+    create_parse_error_context, is_node_modules_path, parse_with_oxc, ParsingConfig,
+};
+use crate::core::models::{BuildConfig, JsxConfig, JsxRuntime, TypeScriptConfig};
+use crate::utils::{parse_target, CodeMap, Logger, Result, SokuError, TextEdit};
+use once_cell::sync::Lazy;
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    ArrowFunctionExpression, AssignmentExpression, AssignmentOperator, AwaitExpression,
+    BigIntLiteral, BinaryExpression, BinaryOperator, CatchClause, ChainExpression,
+    ComputedMemberExpression, Declaration, Decorator, ExportNamedDeclaration, Expression,
+    ForOfStatement, Function, IdentifierReference, JSXElement, JSXFragment, LogicalExpression,
+    LogicalOperator, MethodDefinition, ObjectAssignmentTarget, ObjectExpression, ObjectPattern,
+    ObjectPropertyKind, PrivateInExpression, Program, PropertyDefinition, RegExpFlags,
+    RegExpLiteral, Statement, StaticBlock, StaticMemberExpression, TSEnumDeclaration,
+    UnaryOperator, VariableDeclaration,
+};
+use oxc_ast::AstBuilder;
+use oxc_ast_visit::{walk, walk_mut, Visit, VisitMut};
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_diagnostics::OxcDiagnostic;
+use oxc_semantic::{ScopeFlags, Scoping, SemanticBuilder, SymbolId};
+use oxc_span::{GetSpan, Span};
+use oxc_transformer::{
+    DecoratorOptions, ESTarget, EnvOptions, HelperLoaderMode, JsxOptions, ReactRefreshOptions,
+    TransformOptions, Transformer,
+};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+static JSX_PRAGMA_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"@jsx(Runtime|ImportSource|Frag)?[ \t]+([^\s*]+)").unwrap());
+
+/// Package the automatic runtime imports from when nothing else is configured
+const DEFAULT_IMPORT_SOURCE: &str = "react";
+
+/// Whether a module has syntax that `transform_module` has to lower
+///
+/// `.js` files are only parsed when they could contain JSX at all; packages
+/// in `node_modules` ship compiled JavaScript and are left alone.
+pub fn needs_transform(content: &str, path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ts") | Some("mts") | Some("cts") | Some("tsx") | Some("jsx") => true,
+        Some("js") | Some("mjs") => {
+            !is_node_modules_path(path) && (content.contains("</") || content.contains("/>"))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a program uses syntax that has to be lowered for `target`
+pub fn has_newer_syntax(program: &Program, target: ESTarget) -> bool {
+    target != ESTarget::ESNext && !TargetSyntaxFinder::find(program, target).is_empty()
+}
+
+/// Compile the JSX and TypeScript of a module to JavaScript for the build target
+pub fn transform_module(content: &str, path: &Path, config: &BuildConfig) -> Result<String> {
+    transform(content, path, config, false).map(|(code, _)| code)
+}
+
+/// `transform_module` that also maps the compiled code back to `content`
+pub fn transform_module_with_map(
+    content: &str,
+    path: &Path,
+    config: &BuildConfig,
+) -> Result<(String, CodeMap)> {
+    let (code, map) = transform(content, path, config, true)?;
+    Ok((code, map.unwrap_or_default()))
+}
+
+fn transform(
+    original: &str,
+    path: &Path,
+    config: &BuildConfig,
+    source_map: bool,
+) -> Result<(String, Option<CodeMap>)> {
+    let target = parse_target(&config.target)?;
+    let inlined = inline_const_enums(original, path);
+    let content = inlined.as_ref().map_or(original, |(code, _)| code.as_str());
+
+    let allocator = Allocator::default();
+    let parsed = parse_with_oxc(
+        &allocator,
+        content,
+        ParsingConfig::for_path(path),
+        path,
+        "Transform failed",
+    )?;
+    let mut program = parsed.program;
+
+    if let Some(syntax) = TargetSyntaxFinder::find(&program, target)
+        .into_iter()
+        .find(|syntax| !syntax.lowerable)
+    {
+        let diagnostic = OxcDiagnostic::error(syntax.feature).with_label(syntax.span);
+        return Err(SokuError::build_with_context(
+            format!(
+                "{} is not available in {} and cannot be lowered: set \"target\" to {} or later",
+                syntax.feature, target, syntax.since
+            ),
+            create_parse_error_context(&[diagnostic], content, path),
+        ));
+    }
+
+    ForAwaitBodies {
+        ast: AstBuilder::new(&allocator),
+    }
+    .visit_program(&mut program);
+
+    let jsx = ResolvedJsx::new(&config.jsx, &program);
+    let mut options = TransformOptions {
+        jsx: jsx.options(&config.jsx),
+        env: EnvOptions::from_target(&target.to_string()).map_err(SokuError::config)?,
+        ..Default::default()
+    };
+    // `using` declarations need a runtime helper soku does not provide
+    options.proposals.explicit_resource_management = false;
+    // Keep imports that are only used by the pragma, e.g. `import { h } from 'preact'`
+    if let Some(pragma) = &jsx.pragma {
+        options.typescript.jsx_pragma = Cow::Owned(pragma.clone());
+    }
+    if let Some(pragma_frag) = &jsx.pragma_frag {
+        options.typescript.jsx_pragma_frag = Cow::Owned(pragma_frag.clone());
+    }
+    // Register components and hook signatures with the Fast Refresh runtime,
+    // which the module linker provides to every module under HMR
+    if config.hmr && !is_node_modules_path(path) {
+        options.jsx.refresh = Some(ReactRefreshOptions::default());
+    }
+    apply_typescript_options(&mut options, &config.typescript);
+    if !config.typescript.experimental_decorators && DecoratorFinder::find(&program) {
+        Logger::warn(&format!(
+            "{}: decorators are compiled with TypeScript's experimentalDecorators semantics",
+            path.display()
+        ));
+    }
+
+    let scoping = SemanticBuilder::new()
+        .build(&program)
+        .semantic
+        .into_scoping();
+    let transformed =
+        Transformer::new(&allocator, path, &options).build_with_scoping(scoping, &mut program);
+    if !transformed.errors.is_empty() {
+        let context = create_parse_error_context(&transformed.errors, content, path);
+        return Err(SokuError::parse_with_context(
+            format!("Transform failed: {}", transformed.errors[0]),
+            context,
+        ));
+    }
+
+    let codegen = Codegen::new().with_options(CodegenOptions {
+        source_map_path: source_map.then(|| path.to_path_buf()),
+        ..CodegenOptions::default()
+    });
+    let generated = codegen.build(&program);
+    let map = generated.map.map(|map| match &inlined {
+        // The map points into the code with inlined enums
+        Some((code, edits)) => CodeMap::identity(&path.to_string_lossy(), original)
+            .apply_edits(original, edits)
+            .compose(code, &map, &generated.code),
+        None => CodeMap::from_source_map(&map, &generated.code),
+    });
+    Ok((generated.code, map))
+}
+
+/// Map tsconfig.json options onto the transformer
+fn apply_typescript_options(options: &mut TransformOptions, typescript: &TypeScriptConfig) {
+    // Helpers are defined in the bundle instead of imported from a package
+    options.helper_loader.mode = HelperLoaderMode::External;
+    options.typescript.only_remove_type_imports = typescript.verbatim_module_syntax;
+    // Legacy decorators are the only ones oxc can lower
+    options.decorator = DecoratorOptions {
+        legacy: true,
+        emit_decorator_metadata: typescript.emit_decorator_metadata,
+    };
+    if !typescript.use_define_for_class_fields {
+        // Fields become constructor assignments, as with tsc
+        options.typescript.remove_class_fields_without_initializer = true;
+        options.assumptions.set_public_class_fields = true;
+        options.env.es2022.class_properties = Some(Default::default());
+    }
+}
+
+/// Module the automatic JSX runtime imports in this file, e.g. `react/jsx-runtime`
+///
+/// The import only exists after `transform_module`, so the dependency walk
+/// asks for it up front to bundle the runtime.
+pub fn jsx_runtime_import(content: &str, path: &Path, jsx: &JsxConfig) -> Option<String> {
+    if !needs_transform(content, path) {
+        return None;
+    }
+    let allocator = Allocator::default();
+    let parsed = oxc_parser::Parser::new(
+        &allocator,
+        content,
+        ParsingConfig::for_path(path).to_source_type(),
+    )
+    .parse();
+    if !parsed.errors.is_empty() {
+        return None;
+    }
+
+    let resolved = ResolvedJsx::new(jsx, &parsed.program);
+    if resolved.runtime != JsxRuntime::Automatic {
+        return None;
+    }
+    let mut finder = JsxFinder::default();
+    finder.visit_program(&parsed.program);
+    if !finder.found {
+        return None;
+    }
+
+    let import_source = resolved
+        .import_source
+        .as_deref()
+        .unwrap_or(DEFAULT_IMPORT_SOURCE);
+    Some(if jsx.development {
+        format!("{}/jsx-dev-runtime", import_source)
+    } else {
+        format!("{}/jsx-runtime", import_source)
+    })
+}
+
+/// JSX settings of one file: the configuration overridden by pragma comments
+struct ResolvedJsx {
+    runtime: JsxRuntime,
+    import_source: Option<String>,
+    pragma: Option<String>,
+    pragma_frag: Option<String>,
+}
+
+impl ResolvedJsx {
+    fn new(config: &JsxConfig, program: &Program) -> Self {
+        let mut runtime = None;
+        let mut import_source = None;
+        let mut pragma = None;
+        let mut pragma_frag = None;
+        for comment in &program.comments {
+            let text = comment.content_span().source_text(program.source_text);
+            for captures in JSX_PRAGMA_REGEX.captures_iter(text) {
+                let value = captures[2].to_string();
+                match captures.get(1).map(|keyword| keyword.as_str()) {
+                    None => pragma = Some(value),
+                    Some("Frag") => pragma_frag = Some(value),
+                    Some("ImportSource") => import_source = Some(value),
+                    Some(_) => {
+                        runtime = match value.as_str() {
+                            "classic" => Some(JsxRuntime::Classic),
+                            "automatic" => Some(JsxRuntime::Automatic),
+                            _ => runtime,
+                        }
+                    }
+                }
+            }
+        }
+
+        // A pragma only makes sense for the classic runtime and an import
+        // source only for the automatic one, so they select it
+        let runtime = runtime.unwrap_or(if pragma.is_some() || pragma_frag.is_some() {
+            JsxRuntime::Classic
+        } else if import_source.is_some() {
+            JsxRuntime::Automatic
+        } else {
+            config
+                .runtime
+                .unwrap_or(if config.pragma.is_some() || config.pragma_frag.is_some() {
+                    JsxRuntime::Classic
+                } else {
+                    JsxRuntime::Automatic
+                })
+        });
+
+        match runtime {
+            JsxRuntime::Classic => Self {
+                runtime,
+                import_source: None,
+                pragma: pragma.or_else(|| config.pragma.clone()),
+                pragma_frag: pragma_frag.or_else(|| config.pragma_frag.clone()),
+            },
+            JsxRuntime::Automatic => Self {
+                runtime,
+                import_source: import_source.or_else(|| config.import_source.clone()),
+                pragma: None,
+                pragma_frag: None,
+            },
+        }
+    }
+
+    fn options(&self, config: &JsxConfig) -> JsxOptions {
+        let mut options = JsxOptions {
+            runtime: match self.runtime {
+                JsxRuntime::Classic => oxc_transformer::JsxRuntime::Classic,
+                JsxRuntime::Automatic => oxc_transformer::JsxRuntime::Automatic,
+            },
+            development: config.development,
+            import_source: self.import_source.clone(),
+            pragma: self.pragma.clone(),
+            pragma_frag: self.pragma_frag.clone(),
+            ..JsxOptions::enable()
+        };
+        options.conform();
+        options
+    }
+}
+
+/// Notes whether a program contains any JSX element or fragment
+#[derive(Default)]
+struct JsxFinder {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for JsxFinder {
+    fn visit_jsx_element(&mut self, _it: &JSXElement<'a>) {
+        self.found = true;
+    }
+
+    fn visit_jsx_fragment(&mut self, _it: &JSXFragment<'a>) {
+        self.found = true;
+    }
+}
+
+/// Notes whether a program uses decorators
+#[derive(Default)]
+struct DecoratorFinder {
+    found: bool,
+}
+
+impl DecoratorFinder {
+    fn find(program: &Program) -> bool {
+        let mut finder = Self::default();
+        finder.visit_program(program);
+        finder.found
+    }
+}
+
+impl<'a> Visit<'a> for DecoratorFinder {
+    fn visit_decorator(&mut self, _it: &Decorator<'a>) {
+        self.found = true;
+    }
+}
+
+/// Syntax introduced after the build target
+struct NewerSyntax {
+    feature: &'static str,
+    since: ESTarget,
+    span: Span,
+    /// Whether `oxc_transformer` can rewrite it for the target
+    lowerable: bool,
+}
+
+/// Collects the syntax of a program that is newer than the build target
+struct TargetSyntaxFinder {
+    target: ESTarget,
+    function_depth: usize,
+    found: Vec<NewerSyntax>,
+}
+
+impl TargetSyntaxFinder {
+    fn find(program: &Program, target: ESTarget) -> Vec<NewerSyntax> {
+        let mut finder = Self {
+            target,
+            function_depth: 0,
+            found: Vec::new(),
+        };
+        finder.visit_program(program);
+        finder.found
+    }
+
+    fn note(&mut self, feature: &'static str, since: ESTarget, span: Span) {
+        self.note_syntax(feature, since, span, true);
+    }
+
+    fn note_syntax(&mut self, feature: &'static str, since: ESTarget, span: Span, lowerable: bool) {
+        if since > self.target {
+            self.found.push(NewerSyntax {
+                feature,
+                since,
+                span,
+                lowerable,
+            });
+        }
+    }
+}
+
+impl<'a> Visit<'a> for TargetSyntaxFinder {
+    fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+        match (it.r#async, it.generator) {
+            (true, true) => self.note("Async generator", ESTarget::ES2018, it.span),
+            (true, false) => self.note("Async function", ESTarget::ES2017, it.span),
+            _ => {}
+        }
+        self.function_depth += 1;
+        walk::walk_function(self, it, flags);
+        self.function_depth -= 1;
+    }
+
+    fn visit_arrow_function_expression(&mut self, it: &ArrowFunctionExpression<'a>) {
+        if it.r#async {
+            self.note("Async function", ESTarget::ES2017, it.span);
+        }
+        self.function_depth += 1;
+        walk::walk_arrow_function_expression(self, it);
+        self.function_depth -= 1;
+    }
+
+    fn visit_await_expression(&mut self, it: &AwaitExpression<'a>) {
+        if self.function_depth == 0 {
+            self.note_syntax("Top-level await", ESTarget::ES2022, it.span, false);
+        }
+        walk::walk_await_expression(self, it);
+    }
+
+    fn visit_for_of_statement(&mut self, it: &ForOfStatement<'a>) {
+        if it.r#await {
+            if self.function_depth == 0 {
+                self.note_syntax("Top-level await", ESTarget::ES2022, it.span, false);
+            }
+            self.note("for await", ESTarget::ES2018, it.span);
+        }
+        walk::walk_for_of_statement(self, it);
+    }
+
+    fn visit_binary_expression(&mut self, it: &BinaryExpression<'a>) {
+        if it.operator == BinaryOperator::Exponential {
+            self.note("Exponentiation operator", ESTarget::ES2016, it.span);
+        }
+        walk::walk_binary_expression(self, it);
+    }
+
+    fn visit_assignment_expression(&mut self, it: &AssignmentExpression<'a>) {
+        match it.operator {
+            AssignmentOperator::Exponential => {
+                self.note("Exponentiation operator", ESTarget::ES2016, it.span)
+            }
+            AssignmentOperator::LogicalAnd
+            | AssignmentOperator::LogicalOr
+            | AssignmentOperator::LogicalNullish => {
+                self.note("Logical assignment", ESTarget::ES2021, it.span)
+            }
+            _ => {}
+        }
+        walk::walk_assignment_expression(self, it);
+    }
+
+    fn visit_logical_expression(&mut self, it: &LogicalExpression<'a>) {
+        if it.operator == LogicalOperator::Coalesce {
+            self.note("Nullish coalescing", ESTarget::ES2020, it.span);
+        }
+        walk::walk_logical_expression(self, it);
+    }
+
+    fn visit_chain_expression(&mut self, it: &ChainExpression<'a>) {
+        self.note("Optional chaining", ESTarget::ES2020, it.span);
+        walk::walk_chain_expression(self, it);
+    }
+
+    fn visit_object_expression(&mut self, it: &ObjectExpression<'a>) {
+        if let Some(spread) = it
+            .properties
+            .iter()
+            .find(|property| matches!(property, ObjectPropertyKind::SpreadProperty(_)))
+        {
+            self.note("Object spread", ESTarget::ES2018, spread.span());
+        }
+        walk::walk_object_expression(self, it);
+    }
+
+    fn visit_object_pattern(&mut self, it: &ObjectPattern<'a>) {
+        if let Some(rest) = &it.rest {
+            self.note("Object rest", ESTarget::ES2018, rest.span);
+        }
+        walk::walk_object_pattern(self, it);
+    }
+
+    fn visit_object_assignment_target(&mut self, it: &ObjectAssignmentTarget<'a>) {
+        if let Some(rest) = &it.rest {
+            self.note("Object rest", ESTarget::ES2018, rest.span);
+        }
+        walk::walk_object_assignment_target(self, it);
+    }
+
+    fn visit_catch_clause(&mut self, it: &CatchClause<'a>) {
+        if it.param.is_none() {
+            self.note("Optional catch binding", ESTarget::ES2019, it.span);
+        }
+        walk::walk_catch_clause(self, it);
+    }
+
+    fn visit_big_int_literal(&mut self, it: &BigIntLiteral<'a>) {
+        self.note_syntax("BigInt literal", ESTarget::ES2020, it.span, false);
+    }
+
+    fn visit_reg_exp_literal(&mut self, it: &RegExpLiteral<'a>) {
+        // Regular expressions the target cannot parse become `new RegExp()` calls
+        let pattern = it.regex.pattern.text.as_str();
+        let flags = it.regex.flags;
+        if flags.contains(RegExpFlags::V) {
+            self.note("RegExp v flag", ESTarget::ES2024, it.span);
+        } else if flags.contains(RegExpFlags::D) {
+            self.note("RegExp d flag", ESTarget::ES2022, it.span);
+        } else if flags.contains(RegExpFlags::S)
+            || pattern.contains("(?<")
+            || pattern.contains("\\p{")
+            || pattern.contains("\\P{")
+        {
+            self.note("RegExp syntax", ESTarget::ES2018, it.span);
+        }
+    }
+
+    fn visit_property_definition(&mut self, it: &PropertyDefinition<'a>) {
+        self.note("Class field", ESTarget::ES2022, it.span);
+        walk::walk_property_definition(self, it);
+    }
+
+    fn visit_method_definition(&mut self, it: &MethodDefinition<'a>) {
+        if it.key.is_private_identifier() {
+            self.note("Private method", ESTarget::ES2022, it.span);
+        }
+        walk::walk_method_definition(self, it);
+    }
+
+    fn visit_private_in_expression(&mut self, it: &PrivateInExpression<'a>) {
+        self.note("Private field check", ESTarget::ES2022, it.span);
+        walk::walk_private_in_expression(self, it);
+    }
+
+    fn visit_static_block(&mut self, it: &StaticBlock<'a>) {
+        self.note("Class static block", ESTarget::ES2022, it.span);
+        walk::walk_static_block(self, it);
+    }
+
+    fn visit_variable_declaration(&mut self, it: &VariableDeclaration<'a>) {
+        if it.kind.is_using() {
+            self.note_syntax("`using` declaration", ESTarget::ESNext, it.span, false);
+        }
+        walk::walk_variable_declaration(self, it);
+    }
+}
+
+/// Wraps the bodies of `for await` loops in blocks
+///
+/// The ES2018 lowering of `for await` loses a body that is a single statement.
+struct ForAwaitBodies<'a> {
+    ast: AstBuilder<'a>,
+}
+
+impl<'a> VisitMut<'a> for ForAwaitBodies<'a> {
+    fn visit_for_of_statement(&mut self, it: &mut ForOfStatement<'a>) {
+        walk_mut::walk_for_of_statement(self, it);
+        if it.r#await && !matches!(it.body, Statement::BlockStatement(_)) {
+            let span = it.body.span();
+            let body = std::mem::replace(&mut it.body, self.ast.statement_empty(span));
+            it.body = self.ast.statement_block(span, self.ast.vec1(body));
+        }
+    }
+}
+
+/// Value of a `const enum` member
+#[derive(Debug, Clone, PartialEq)]
+enum EnumValue {
+    Number(f64),
+    String(String),
+}
+
+impl EnumValue {
+    /// JavaScript literal for the value, safe to put in place of an expression
+    fn to_literal(&self) -> String {
+        match self {
+            Self::String(value) => serde_json::to_string(value).unwrap_or_default(),
+            Self::Number(value) if value.is_nan() => "NaN".to_string(),
+            Self::Number(value) if *value < 0.0 || value.is_sign_negative() && *value == 0.0 => {
+                format!("({})", number_to_string(*value))
+            }
+            Self::Number(value) => number_to_string(*value),
+        }
+    }
+
+    fn to_js_string(&self) -> String {
+        match self {
+            Self::String(value) => value.clone(),
+            Self::Number(value) => number_to_string(*value),
+        }
+    }
+}
+
+/// Number formatted like JavaScript's `String(n)` for the usual enum values
+fn number_to_string(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else if value.is_nan() {
+        "NaN".to_string()
+    } else if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 {
+        if value == 0.0 && value.is_sign_negative() {
+            "-0".to_string()
+        } else {
+            format!("{}", value as i64)
+        }
+    } else {
+        format!("{}", value)
+    }
+}
+
+/// ECMAScript ToInt32, used by the bitwise operators
+fn to_int32(value: f64) -> i32 {
+    if value.is_finite() {
+        (value.trunc() % 4_294_967_296.0) as i64 as u32 as i32
+    } else {
+        0
+    }
+}
+
+/// A `const enum` whose members all have constant values
+struct ConstEnum {
+    symbol: SymbolId,
+    /// Span of the declaration statement (including `export`)
+    span: Span,
+    exported: bool,
+    members: HashMap<String, EnumValue>,
+}
+
+/// Collects the `const enum` declarations of a program and their values
+struct ConstEnumCollector<'s> {
+    scoping: &'s Scoping,
+    enums: Vec<ConstEnum>,
+    /// Span of `export <enum declaration>` while visiting it
+    export_span: Option<Span>,
+}
+
+impl<'s> ConstEnumCollector<'s> {
+    fn evaluate(
+        &self,
+        expression: &Expression,
+        members: &HashMap<String, EnumValue>,
+    ) -> Option<EnumValue> {
+        use EnumValue::{Number, String};
+
+        match expression {
+            Expression::NumericLiteral(literal) => Some(Number(literal.value)),
+            Expression::StringLiteral(literal) => Some(String(literal.value.to_string())),
+            Expression::TemplateLiteral(template) if template.expressions.is_empty() => template
+                .quasis
+                .first()
+                .and_then(|quasi| quasi.value.cooked)
+                .map(|cooked| String(cooked.to_string())),
+            Expression::ParenthesizedExpression(inner) => self.evaluate(&inner.expression, members),
+            Expression::Identifier(identifier) => members.get(identifier.name.as_str()).cloned(),
+            Expression::StaticMemberExpression(member) => {
+                self.member_value(&member.object, member.property.name.as_str())
+            }
+            Expression::UnaryExpression(unary) => {
+                let Number(value) = self.evaluate(&unary.argument, members)? else {
+                    return None;
+                };
+                match unary.operator {
+                    UnaryOperator::UnaryNegation => Some(Number(-value)),
+                    UnaryOperator::UnaryPlus => Some(Number(value)),
+                    UnaryOperator::BitwiseNot => Some(Number(!to_int32(value) as f64)),
+                    _ => None,
+                }
+            }
+            Expression::BinaryExpression(binary) => {
+                let left = self.evaluate(&binary.left, members)?;
+                let right = self.evaluate(&binary.right, members)?;
+                if binary.operator == BinaryOperator::Addition
+                    && (matches!(left, String(_)) || matches!(right, String(_)))
+                {
+                    return Some(String(left.to_js_string() + &right.to_js_string()));
+                }
+                let (Number(left), Number(right)) = (left, right) else {
+                    return None;
+                };
+                let shift = (to_int32(right) as u32) & 31;
+                Some(Number(match binary.operator {
+                    BinaryOperator::Addition => left + right,
+                    BinaryOperator::Subtraction => left - right,
+                    BinaryOperator::Multiplication => left * right,
+                    BinaryOperator::Division => left / right,
+                    BinaryOperator::Remainder => left % right,
+                    BinaryOperator::Exponential => left.powf(right),
+                    BinaryOperator::ShiftLeft => to_int32(left).wrapping_shl(shift) as f64,
+                    BinaryOperator::ShiftRight => to_int32(left).wrapping_shr(shift) as f64,
+                    BinaryOperator::ShiftRightZeroFill => {
+                        (to_int32(left) as u32).wrapping_shr(shift) as f64
+                    }
+                    BinaryOperator::BitwiseOR => (to_int32(left) | to_int32(right)) as f64,
+                    BinaryOperator::BitwiseAnd => (to_int32(left) & to_int32(right)) as f64,
+                    BinaryOperator::BitwiseXOR => (to_int32(left) ^ to_int32(right)) as f64,
+                    _ => return None,
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Value of `Enum.member` when `object` refers to a const enum seen so far
+    fn member_value(&self, object: &Expression, member: &str) -> Option<EnumValue> {
+        let Expression::Identifier(identifier) = object else {
+            return None;
+        };
+        let symbol = self
+            .scoping
+            .get_reference(identifier.reference_id())
+            .symbol_id()?;
+        self.enums
+            .iter()
+            .find(|known| known.symbol == symbol)
+            .and_then(|known| known.members.get(member).cloned())
+    }
+}
+
+impl<'a> Visit<'a> for ConstEnumCollector<'_> {
+    fn visit_export_named_declaration(&mut self, it: &ExportNamedDeclaration<'a>) {
+        if matches!(it.declaration, Some(Declaration::TSEnumDeclaration(_))) {
+            self.export_span = Some(it.span);
+        }
+        walk::walk_export_named_declaration(self, it);
+        self.export_span = None;
+    }
+
+    fn visit_ts_enum_declaration(&mut self, it: &TSEnumDeclaration<'a>) {
+        let (span, exported) = match self.export_span.take() {
+            Some(span) => (span, true),
+            None => (it.span, false),
+        };
+        if !it.r#const {
+            return;
+        }
+        let Some(symbol) = it.id.symbol_id.get() else {
+            return;
+        };
+
+        let mut members = HashMap::new();
+        let mut next = Some(0.0);
+        for member in &it.body.members {
+            let value = match &member.initializer {
+                Some(initializer) => self.evaluate(initializer, &members),
+                None => next.map(EnumValue::Number),
+            };
+            // Members that are not constant keep the whole enum at runtime
+            let Some(value) = value else {
+                return;
+            };
+            next = match value {
+                EnumValue::Number(number) => Some(number + 1.0),
+                EnumValue::String(_) => None,
+            };
+            members.insert(member.id.static_name().to_string(), value);
+        }
+        self.enums.push(ConstEnum {
+            symbol,
+            span,
+            exported,
+            members,
+        });
+    }
+}
+
+/// Replaces reads of const enum members by their values
+struct ConstEnumInliner<'s> {
+    scoping: &'s Scoping,
+    enums: &'s [ConstEnum],
+    edits: Vec<(Span, String)>,
+    /// Per enum, whether it is still referenced other than through an inlined member
+    referenced: Vec<bool>,
+}
+
+impl ConstEnumInliner<'_> {
+    /// Enum index and value of `object.member` if it can be inlined
+    fn resolve(&self, object: &Expression, member: &str, span: Span) -> Option<(usize, String)> {
+        let Expression::Identifier(identifier) = object else {
+            return None;
+        };
+        let symbol = self
+            .scoping
+            .get_reference(identifier.reference_id())
+            .symbol_id()?;
+        let index = self.enums.iter().position(|known| known.symbol == symbol)?;
+        let known = &self.enums[index];
+        // Members referring to each other inside the declaration stay as they are
+        if known.span.start <= span.start && span.end <= known.span.end {
+            return None;
+        }
+        known
+            .members
+            .get(member)
+            .map(|value| (index, value.to_literal()))
+    }
+}
+
+impl<'a> Visit<'a> for ConstEnumInliner<'_> {
+    fn visit_static_member_expression(&mut self, it: &StaticMemberExpression<'a>) {
+        match self.resolve(&it.object, it.property.name.as_str(), it.span) {
+            Some((_, literal)) => self.edits.push((it.span, literal)),
+            None => walk::walk_static_member_expression(self, it),
+        }
+    }
+
+    fn visit_computed_member_expression(&mut self, it: &ComputedMemberExpression<'a>) {
+        let resolved = match &it.expression {
+            Expression::StringLiteral(property) => {
+                self.resolve(&it.object, property.value.as_str(), it.span)
+            }
+            _ => None,
+        };
+        match resolved {
+            Some((_, literal)) => self.edits.push((it.span, literal)),
+            None => walk::walk_computed_member_expression(self, it),
+        }
+    }
+
+    fn visit_identifier_reference(&mut self, it: &IdentifierReference<'a>) {
+        let reference = self.scoping.get_reference(it.reference_id());
+        if !reference.is_value() {
+            return;
+        }
+        let Some(symbol) = reference.symbol_id() else {
+            return;
+        };
+        if let Some(index) = self.enums.iter().position(|known| known.symbol == symbol) {
+            let span = self.enums[index].span;
+            if !(span.start <= it.span.start && it.span.end <= span.end) {
+                self.referenced[index] = true;
+            }
+        }
+    }
+}
+
+/// Inline the members of `const enum`s declared in this file
+///
+/// Returns the inlined code and the edits that produced it, or `None` when
+/// there is nothing to inline. A declaration is dropped
+/// once nothing refers to it anymore; exported ones stay, since other
+/// modules read their members from the runtime object. Members are never
+/// inlined across files: each file is transformed on its own.
+fn inline_const_enums(content: &str, path: &Path) -> Option<(String, Vec<TextEdit>)> {
+    if !content.contains("const enum") {
+        return None;
+    }
+    let allocator = Allocator::default();
+    let parsed = oxc_parser::Parser::new(
+        &allocator,
+        content,
+        ParsingConfig::for_path(path).to_source_type(),
+    )
+    .parse();
+    if !parsed.errors.is_empty() {
+        return None;
+    }
+    let semantic = SemanticBuilder::new().build(&parsed.program).semantic;
+    let scoping = semantic.scoping();
+
+    let mut collector = ConstEnumCollector {
+        scoping,
+        enums: Vec::new(),
+        export_span: None,
+    };
+    collector.visit_program(&parsed.program);
+    if collector.enums.is_empty() {
+        return None;
+    }
+
+    let mut inliner = ConstEnumInliner {
+        scoping,
+        enums: &collector.enums,
+        edits: Vec::new(),
+        referenced: vec![false; collector.enums.len()],
+    };
+    inliner.visit_program(&parsed.program);
+
+    let mut edits = inliner.edits;
+    for (known, referenced) in collector.enums.iter().zip(&inliner.referenced) {
+        if !known.exported && !referenced {
+            edits.push((known.span, String::new()));
+        }
+    }
+    if edits.is_empty() {
+        return None;
+    }
+    edits.sort_by_key(|(span, _)| span.start);
+
+    let mut output = String::with_capacity(content.len());
+    let mut applied = Vec::with_capacity(edits.len());
+    let mut last = 0;
+    for (span, replacement) in edits {
+        let (start, end) = (span.start as usize, span.end as usize);
+        if start < last {
+            continue;
+        }
+        output.push_str(&content[last..start]);
+        output.push_str(&replacement);
+        applied.push((start, end, replacement));
+        last = end;
+    }
+    output.push_str(&content[last..]);
+    Some((output, applied))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(code: &str, file: &str, jsx: JsxConfig) -> String {
+        let config = BuildConfig {
+            jsx,
+            ..Default::default()
+        };
+        transform_module(code, Path::new(file), &config).unwrap()
+    }
+
+    #[test]
+    fn test_classic_runtime_with_props_and_children() {
+        let code = r#"const App = ({ name }) => (
+  <div className="app" id={name}>
+    <h1>Hello {name}</h1>
+    <>
+      <Item {...props} />
+    </>
+  </div>
+);"#;
+        let jsx = JsxConfig {
+            runtime: Some(JsxRuntime::Classic),
+            ..Default::default()
+        };
+        let out = transform(code, "App.jsx", jsx);
+        assert!(out.contains("React.createElement(\"div\""));
+        assert!(out.contains("className: \"app\""));
+        assert!(out.contains("id: name"));
+        assert!(out.contains("React.Fragment"));
+        assert!(out.contains("React.createElement(Item"));
+    }
+
+    #[test]
+    fn test_automatic_runtime_imports_from_import_source() {
+        let code = "export const App = () => <p class=\"x\">hi</p>;";
+        let out = transform(code, "App.tsx", JsxConfig::default());
+        assert!(out.contains("from \"react/jsx-runtime\""));
+        assert_eq!(
+            jsx_runtime_import(code, Path::new("App.tsx"), &JsxConfig::default()).as_deref(),
+            Some("react/jsx-runtime")
+        );
+
+        let preact = JsxConfig {
+            import_source: Some("preact".to_string()),
+            development: true,
+            ..Default::default()
+        };
+        let out = transform(code, "App.tsx", preact.clone());
+        assert!(out.contains("from \"preact/jsx-dev-runtime\""));
+        assert_eq!(
+            jsx_runtime_import(code, Path::new("App.tsx"), &preact).as_deref(),
+            Some("preact/jsx-dev-runtime")
+        );
+
+        // No JSX, no runtime import
+        assert_eq!(
+            jsx_runtime_import("export const x = 1;", Path::new("x.jsx"), &preact),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pragmas_select_classic_runtime() {
+        // From the configuration
+        let jsx = JsxConfig {
+            pragma: Some("h".to_string()),
+            pragma_frag: Some("Fragment".to_string()),
+            ..Default::default()
+        };
+        let code = "import { h, Fragment } from 'preact';\nexport const A = () => <><b /></>;";
+        let out = transform(code, "A.tsx", jsx.clone());
+        assert!(out.contains("h(Fragment, null, /* @__PURE__ */ h(\"b\", null))"));
+        assert!(out.contains("from \"preact\""));
+        assert_eq!(jsx_runtime_import(code, Path::new("A.tsx"), &jsx), None);
+
+        // From a file comment
+        let code = "/** @jsx h */\nimport { h } from 'preact';\nexport const B = () => <i />;";
+        let out = transform(code, "B.jsx", JsxConfig::default());
+        assert!(out.contains("h(\"i\", null)"));
+        assert!(!out.contains("jsx-runtime"));
+    }
+
+    #[test]
+    fn test_fast_refresh_registration_under_hmr() {
+        let code = r#"import { useState } from 'react';
+export default function App() {
+  const [count] = useState(0);
+  return <p>{count}</p>;
+}
+"#;
+        let hmr = BuildConfig {
+            hmr: true,
+            ..Default::default()
+        };
+        let out = transform_module(code, Path::new("App.jsx"), &hmr).unwrap();
+        assert!(out.contains("var _s = $RefreshSig$();"));
+        assert!(out.contains("$RefreshReg$(_c, \"App\");"));
+
+        // Production builds and packages are left alone
+        let out = transform(code, "App.jsx", JsxConfig::default());
+        assert!(!out.contains("$Refresh"));
+        let out = transform_module(code, Path::new("node_modules/ui/App.jsx"), &hmr).unwrap();
+        assert!(!out.contains("$Refresh"));
+    }
+
+    fn transform_ts(code: &str, typescript: TypeScriptConfig) -> String {
+        // Nothing is lowered, only TypeScript is compiled
+        let config = BuildConfig {
+            typescript,
+            target: "esnext".to_string(),
+            ..Default::default()
+        };
+        transform_module(code, Path::new("module.ts"), &config).unwrap()
+    }
+
+    #[test]
+    fn test_typescript_runtime_constructs() {
+        let code = r#"import type { Shape } from './types';
+export enum Color { Red, Green = "g" }
+namespace Util { export const twice = (x: number): number => x * 2; }
+const pick = <K extends keyof T, T>(obj: T, keys: K[]): Array<(x: T) => K> => [];
+class Point {
+  constructor(private readonly label: string, public z?: number) {}
+}
+"#;
+        let out = transform_ts(code, TypeScriptConfig::default());
+        assert!(!out.contains("./types"));
+        assert!(out.contains("Color[Color[\"Red\"] = 0] = \"Red\""));
+        assert!(out.contains("Color[\"Green\"] = \"g\""));
+        assert!(out.contains("_Util.twice = (x) => x * 2"));
+        assert!(out.contains("const pick = (obj, keys) => []"));
+        assert!(out.contains("this.label = label;"));
+        assert!(out.contains("this.z = z;"));
+    }
+
+    #[test]
+    fn test_const_enums_are_inlined() {
+        let code = r#"const enum Dir { Up = 1, Down, Left = Up << 3, Name = "n" + Down, Neg = -Up }
+export const enum Shared { A = 7 }
+console.log(Dir.Up, Dir["Down"], Dir.Left, Dir.Name, Dir.Neg, Shared.A);
+"#;
+        let out = transform_ts(code, TypeScriptConfig::default());
+        assert!(out.contains("console.log(1, 2, 8, \"n2\", -1, 7)"));
+        // The local enum is gone, the exported one stays for importers
+        assert!(!out.contains("Dir"));
+        assert!(out.contains("export let Shared"));
+
+        // Dynamic access needs the runtime object
+        let code =
+            "const enum Dir { Up }\nexport const get = (key: string) => Dir[key as 'Up'] + Dir.Up;";
+        let out = transform_ts(code, TypeScriptConfig::default());
+        assert!(out.contains("Dir[Dir[\"Up\"] = 0] = \"Up\""));
+        assert!(out.contains("Dir[key] + 0"));
+    }
+
+    fn transform_for_target(code: &str, target: &str) -> Result<String> {
+        let config = BuildConfig {
+            target: target.to_string(),
+            ..Default::default()
+        };
+        transform_module(code, Path::new("module.js"), &config)
+    }
+
+    #[test]
+    fn test_syntax_is_lowered_for_target() {
+        let code = r#"export const load = async (o) => {
+  o.count ??= 1;
+  return { ...o, value: o?.a?.b };
+};
+export class Item { size = 1; }
+"#;
+        let out = transform_for_target(code, "es2017").unwrap();
+        for syntax in ["??", "?.", "...", "size = 1"] {
+            assert!(!out.contains(syntax), "{} in {}", syntax, out);
+        }
+        assert!(out.contains("babelHelpers.objectSpread2"));
+        assert!(out.contains("babelHelpers.defineProperty(this, \"size\", 1)"));
+        assert!(out.contains("async (o) =>"));
+
+        let out = transform_for_target(code, "es2016").unwrap();
+        assert!(out.contains("babelHelpers.asyncToGenerator"));
+
+        let out = transform_for_target(code, "esnext").unwrap();
+        assert!(out.contains("o?.a?.b"));
+        assert!(out.contains("size = 1"));
+    }
+
+    #[test]
+    fn test_unlowerable_syntax_is_reported_with_location() {
+        let code = "const a = 1;\nawait load(a);\n";
+        let error = transform_for_target(code, "es2017").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Top-level await is not available in es2017"));
+        let SokuError::Build {
+            context: Some(context),
+            ..
+        } = error
+        else {
+            panic!("expected a build error with context, got {:?}", error);
+        };
+        assert_eq!(context.line, Some(2));
+        assert!(transform_for_target(code, "es2022").is_ok());
+
+        assert!(transform_for_target("export const n = 10n;", "es2019").is_err());
+        assert!(transform_for_target("let x = 1; x = 2;", "es3").is_err());
+    }
+
+    #[test]
+    fn test_tsconfig_options() {
+        let code = r#"import { Injectable, Token } from './di';
+@Injectable()
+export class Service {
+  name: string;
+  count = 0;
+  @log run(token: Token): void {}
+}
+"#;
+        let out = transform_ts(code, TypeScriptConfig::default());
+        assert!(out.contains("babelHelpers.decorate([Injectable()], Service)"));
+        assert!(out.contains("\tname;"));
+        assert!(out.contains("count = 0;"));
+        assert!(!out.contains("Token"));
+
+        let out = transform_ts(
+            code,
+            TypeScriptConfig {
+                use_define_for_class_fields: false,
+                verbatim_module_syntax: true,
+                experimental_decorators: true,
+                emit_decorator_metadata: true,
+            },
+        );
+        assert!(!out.contains("name;"));
+        assert!(out.contains("this.count = 0;"));
+        assert!(out.contains("import { Injectable, Token } from \"./di\""));
+        assert!(out.contains("babelHelpers.decorateMetadata(\"design:returntype\", void 0)"));
+    }
+}
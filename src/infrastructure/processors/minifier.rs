@@ -1,34 +1,86 @@
-use crate::utils::{ErrorContext, Result, SokuError};
+use crate::core::models::OutputFormat;
+use crate::utils::{CodeMap, ErrorContext, Result, SokuError};
 use flate2::{write::GzEncoder, Compression};
 use oxc_allocator::Allocator;
 use oxc_codegen::{Codegen, CodegenOptions};
 use oxc_diagnostics::OxcDiagnostic;
 use oxc_minifier::{CompressOptions, MangleOptions, Minifier, MinifierOptions};
 use oxc_parser::Parser;
+use oxc_sourcemap::SourceMap;
 use oxc_span::SourceType;
+use oxc_transformer::ESTarget;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Lightning-fast JavaScript minification using oxc
 pub struct OxcMinifier {
     options: MinifierOptions,
+    /// Parse code as a script, whose top-level declarations are globals
+    script: bool,
 }
 
 impl OxcMinifier {
     pub fn new() -> Self {
+        Self::for_target(ESTarget::ESNext)
+    }
+
+    /// Minifier that only uses syntax available in `target`, e.g. no `??` for ES2019
+    pub fn for_target(target: ESTarget) -> Self {
         Self {
             options: MinifierOptions {
                 mangle: Some(MangleOptions::default()),
-                compress: Some(CompressOptions::default()),
+                compress: Some(CompressOptions {
+                    target,
+                    ..CompressOptions::default()
+                }),
             },
+            script: false,
+        }
+    }
+
+    /// Minifier for bundles in `format`; only ES module bundles are parsed as
+    /// modules, the others keep their top-level declarations
+    pub fn for_bundle(target: ESTarget, format: OutputFormat) -> Self {
+        Self {
+            script: format != OutputFormat::Esm,
+            ..Self::for_target(target)
         }
     }
 
     /// Minify JavaScript code
     pub fn minify(&self, source_code: &str, filename: &str) -> Result<String> {
+        self.minify_program(source_code, filename, false)
+            .map(|(code, _)| code)
+    }
+
+    /// `minify` for code described by `map`, returning the map of the minified code
+    pub fn minify_with_map(
+        &self,
+        source_code: &str,
+        filename: &str,
+        map: &CodeMap,
+    ) -> Result<(String, CodeMap)> {
+        let (code, minified_map) = self.minify_program(source_code, filename, true)?;
+        let map = match minified_map {
+            Some(minified_map) => map.compose(source_code, &minified_map, &code),
+            None => CodeMap::default(),
+        };
+        Ok((code, map))
+    }
+
+    fn minify_program(
+        &self,
+        source_code: &str,
+        filename: &str,
+        source_map: bool,
+    ) -> Result<(String, Option<SourceMap>)> {
         let allocator = Allocator::default();
-        let source_type = SourceType::from_path(filename).unwrap_or_else(|_| SourceType::default());
+        let mut source_type =
+            SourceType::from_path(filename).unwrap_or_else(|_| SourceType::default());
+        if self.script {
+            source_type = source_type.with_script(true);
+        }
 
         // Parse the source code
         let parser = Parser::new(&allocator, source_code, source_type);
@@ -57,14 +109,15 @@ impl OxcMinifier {
         // Generate minified code
         let codegen_options = CodegenOptions {
             minify: true,
+            source_map_path: source_map.then(|| PathBuf::from(filename)),
             ..Default::default()
         };
 
         let mut codegen = Codegen::new();
         codegen = codegen.with_options(codegen_options);
-        let minified_code = codegen.build(&program).code;
+        let minified = codegen.build(&program);
 
-        Ok(minified_code)
+        Ok((minified.code, minified.map))
     }
 
     /// Minify with custom options
@@ -106,7 +159,7 @@ impl OxcMinifier {
                 None
             },
             compress: if compress {
-                Some(CompressOptions::default())
+                self.options.compress.clone()
             } else {
                 None
             },
@@ -255,6 +308,20 @@ impl MinificationService {
         }
     }
 
+    /// Service for bundles that must run on `target`
+    pub fn for_target(target: ESTarget) -> Self {
+        Self {
+            minifier: Arc::new(OxcMinifier::for_target(target)),
+        }
+    }
+
+    /// Service for bundles in `format` that must run on `target`
+    pub fn for_bundle(target: ESTarget, format: OutputFormat) -> Self {
+        Self {
+            minifier: Arc::new(OxcMinifier::for_bundle(target, format)),
+        }
+    }
+
     /// Minify JavaScript bundle asynchronously
     pub async fn minify_bundle(&self, bundle: String, filename: &str) -> Result<String> {
         let minifier = self.minifier.clone();
@@ -266,6 +333,21 @@ impl MinificationService {
             .map_err(|e| SokuError::build(format!("Minification task failed: {}", e)))?
     }
 
+    /// Minify a bundle described by `map`, returning the map of the minified bundle
+    pub async fn minify_bundle_with_map(
+        &self,
+        bundle: String,
+        map: CodeMap,
+        filename: &str,
+    ) -> Result<(String, CodeMap)> {
+        let minifier = self.minifier.clone();
+        let filename = filename.to_string();
+
+        tokio::task::spawn_blocking(move || minifier.minify_with_map(&bundle, &filename, &map))
+            .await
+            .map_err(|e| SokuError::build(format!("Minification task failed: {}", e)))?
+    }
+
     /// Get minification statistics with compression analysis
     pub fn get_stats(&self, original: &str, minified: &str) -> MinificationStats {
         let gzip_original = self.gzip_compress(original.as_bytes()).unwrap_or_default();
@@ -404,6 +486,29 @@ mod tests {
         assert!(minified.len() < source.len());
     }
 
+    #[test]
+    fn test_minification_respects_target() {
+        let source = "function f(a) { return a !== null && a !== undefined ? a : 1; }\nf(2);";
+        let es2019 = OxcMinifier::for_target(ESTarget::ES2019)
+            .minify(source, "test.js")
+            .unwrap();
+        assert!(!es2019.contains("??"));
+    }
+
+    #[test]
+    fn test_script_bundles_keep_top_level_globals() {
+        let source = "var MyLib = (function() {\n  return { add: function(a, b) { return a + b; } };\n})();\n";
+        let iife = OxcMinifier::for_bundle(ESTarget::ESNext, OutputFormat::Iife)
+            .minify(source, "bundle.js")
+            .unwrap();
+        assert!(iife.contains("var MyLib="));
+
+        let esm = OxcMinifier::for_bundle(ESTarget::ESNext, OutputFormat::Esm)
+            .minify("export const x = 1;\n", "bundle.js")
+            .unwrap();
+        assert!(esm.contains("export"));
+    }
+
     #[test]
     fn test_reduction_calculation() {
         let minifier = OxcMinifier::new();
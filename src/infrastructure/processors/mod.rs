@@ -6,8 +6,14 @@ pub mod common; // Shared functionality between processors
 pub mod css_processor;
 pub mod dynamic_import_splitter;
 pub mod enhanced_js_processor;
+pub mod html_processor;
+pub mod js_transformer;
 pub mod minifier;
+pub mod module_linker;
+pub mod output_format;
+pub mod scss_map;
 pub mod scss_processor;
+pub mod transform_helpers;
 pub mod tree_shaker;
 
 // Re-export unified processing components (recommended for new code)
@@ -18,6 +24,7 @@ pub use asset_processor::*;
 pub use ast_tree_shaker::*;
 pub use code_splitter::*;
 pub use css_processor::*;
+pub use html_processor::*;
 pub use minifier::*;
 pub use scss_processor::*;
 pub use tree_shaker::*;
@@ -0,0 +1,2954 @@
+// ES Module Linker - Scope hoisting of the modules of a bundle
+// Import bindings are resolved to their declarations through re-exports and
+// `export *`, colliding top-level names are renamed and the modules are
+// concatenated into one scope in dependency order. CommonJS modules are
+// wrapped: a default import is `module.exports` (or `exports.default` with
+// `__esModule`) and `require()` of an ES module returns its namespace object.
+// Modules loaded with `import()` are wrapped too, and only run when loaded
+
+use super::common::{parse_with_oxc, ParsingConfig};
+use crate::core::models::{LinkStrategy, ModuleInfo, ModuleLinking, WrapReason};
+use crate::utils::{is_virtual_module, module_display_name, CodeMap, MappedCode};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Argument, ArrayExpressionElement, AssignmentOperator, CallExpression, Declaration,
+    ExportDefaultDeclaration, ExportDefaultDeclarationKind, Expression, ImportDeclarationSpecifier,
+    ObjectPropertyKind, Program, Statement, StaticMemberExpression,
+};
+use oxc_ast::AstKind;
+use oxc_semantic::{SemanticBuilder, SymbolFlags};
+use oxc_span::{GetSpan, Span};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Module registry of a bundle, private to it
+const MODULE_REGISTRY: &str = r#"var __soku_modules = {};
+var __soku_cache = {};
+"#;
+
+/// Module registry of a bundle split into chunks, shared by all chunks
+///
+/// `globalThis` is looked up with a fallback, as it is newer than ES2017.
+const SHARED_MODULE_REGISTRY: &str = r#"var __soku_global = typeof globalThis !== 'undefined' ? globalThis : typeof self !== 'undefined' ? self : typeof window !== 'undefined' ? window : typeof global !== 'undefined' ? global : this;
+var __soku_registry = __soku_global.__soku_registry__ || (__soku_global.__soku_registry__ = { modules: {}, cache: {} });
+var __soku_modules = __soku_registry.modules;
+var __soku_cache = __soku_registry.cache;
+"#;
+
+/// Registry runtime emitted when at least one module cannot be scope-hoisted,
+/// after the registry itself
+const MODULE_RUNTIME: &str = r#"function __soku_require(id) {
+  var cached = __soku_cache[id];
+  if (cached) return cached.exports;
+  var definition = __soku_modules[id];
+  var module = __soku_cache[id] = { id: id, exports: {} };
+  definition[0].call(module.exports, module, module.exports, function (specifier) {
+    var target = definition[1][specifier];
+    if (target !== undefined) return __soku_require(target);
+    if (typeof require === 'function') return require(specifier);
+    throw new Error("Cannot find module '" + specifier + "'");
+  });
+  return module.exports;
+}
+function __soku_export(exports, getters) {
+  Object.defineProperty(exports, '__esModule', { value: true });
+  for (var name in getters) {
+    Object.defineProperty(exports, name, { get: getters[name], enumerable: true });
+  }
+}
+function __soku_to_esm(mod) {
+  if (mod && mod.__esModule) return mod;
+  var namespace = { default: mod };
+  if (mod != null && (typeof mod === 'object' || typeof mod === 'function')) {
+    Object.keys(mod).forEach(function (name) {
+      if (name === 'default') return;
+      Object.defineProperty(namespace, name, {
+        get: function () { return mod[name]; },
+        enumerable: true
+      });
+    });
+  }
+  return namespace;
+}
+
+"#;
+
+/// Registry runtime of bundles linked for HMR
+///
+/// Every module gets `module.hot`, which `import.meta.hot` is compiled to. The
+/// registry is exposed to the HMR client as `globalThis.__soku_runtime__`.
+const HMR_RUNTIME: &str = r#"var __soku_global = typeof globalThis !== 'undefined' ? globalThis : typeof self !== 'undefined' ? self : typeof window !== 'undefined' ? window : typeof global !== 'undefined' ? global : this;
+var __soku_modules = {};
+var __soku_cache = {};
+var __soku_hot_data = {};
+function __soku_require(id) {
+  var cached = __soku_cache[id];
+  if (cached) return cached.exports;
+  var definition = __soku_modules[id];
+  var module = __soku_cache[id] = { id: id, exports: {}, hot: __soku_hot(id, definition[1]) };
+  definition[0].call(module.exports, module, module.exports, function (specifier) {
+    var target = definition[1][specifier];
+    if (target !== undefined) return __soku_require(target);
+    if (typeof require === 'function') return require(specifier);
+    throw new Error("Cannot find module '" + specifier + "'");
+  });
+  return module.exports;
+}
+function __soku_hot(id, dependencies) {
+  var data = __soku_hot_data[id] || {};
+  delete __soku_hot_data[id];
+  var hot = {
+    data: data,
+    accepts: [],
+    disposers: [],
+    accept: function (deps, callback) {
+      if (deps === undefined || typeof deps === 'function') {
+        hot.accepts.push({ ids: [id], callback: deps, single: true });
+        return;
+      }
+      var single = typeof deps === 'string';
+      var ids = (single ? [deps] : deps).map(function (specifier) {
+        return specifier in dependencies ? dependencies[specifier] : specifier;
+      });
+      hot.accepts.push({ ids: ids, callback: callback, single: single });
+    },
+    dispose: function (callback) {
+      hot.disposers.push(callback);
+    },
+    invalidate: function () {
+      // The client may start after the bundle, so it is looked up when needed
+      var client = __soku_global.__SOKU_HMR__;
+      if (client) client.invalidate(id);
+      else if (__soku_global.location) __soku_global.location.reload();
+    }
+  };
+  return hot;
+}
+function __soku_refresh_reg(id) {
+  return function (type, name) {
+    var refresh = __soku_global.__soku_refresh__;
+    if (refresh) refresh.register(type, id + ' ' + name);
+  };
+}
+function __soku_refresh_sig() {
+  var refresh = __soku_global.__soku_refresh__;
+  return refresh ? refresh.createSignatureFunctionForTransform() : function (type) { return type; };
+}
+function __soku_refresh_accept(module) {
+  if (!__soku_global.__soku_refresh__) return;
+  module.hot.accept(function () {
+    if (!__soku_global.__soku_refresh__.performReactRefresh()) module.hot.invalidate();
+  });
+}
+function __soku_export(exports, getters) {
+  Object.defineProperty(exports, '__esModule', { value: true });
+  for (var name in getters) {
+    Object.defineProperty(exports, name, { get: getters[name], enumerable: true });
+  }
+}
+function __soku_to_esm(mod) {
+  if (mod && mod.__esModule) return mod;
+  var namespace = { default: mod };
+  if (mod != null && (typeof mod === 'object' || typeof mod === 'function')) {
+    Object.keys(mod).forEach(function (name) {
+      if (name === 'default') return;
+      Object.defineProperty(namespace, name, {
+        get: function () { return mod[name]; },
+        enumerable: true
+      });
+    });
+  }
+  return namespace;
+}
+__soku_global.__soku_runtime__ = {
+  modules: __soku_modules,
+  cache: __soku_cache,
+  data: __soku_hot_data,
+  require: __soku_require,
+  define: function (definition) {
+    new Function('__soku_modules', '__soku_require', '__soku_export', '__soku_to_esm',
+      '__soku_refresh_reg', '__soku_refresh_sig', '__soku_refresh_accept', definition)(
+      __soku_modules, __soku_require, __soku_export, __soku_to_esm,
+      __soku_refresh_reg, __soku_refresh_sig, __soku_refresh_accept);
+  }
+};
+
+"#;
+
+/// Functions that Fast Refresh registration calls, provided per module under HMR
+const REFRESH_GLOBALS: [&str; 2] = ["$RefreshReg$", "$RefreshSig$"];
+
+/// Free variables of a CommonJS module
+const COMMONJS_GLOBALS: [&str; 3] = ["require", "module", "exports"];
+
+/// A module after linking, ready to be concatenated into the bundle
+#[derive(Debug, Clone)]
+pub struct LinkedModule {
+    pub path: PathBuf,
+    /// Id in the module registry
+    pub id: String,
+    /// Registry ids of the bundled modules it imports
+    pub dependencies: Vec<String>,
+    /// Registry ids of the modules whose updates it accepts through
+    /// `import.meta.hot.accept`, including its own when it accepts itself
+    pub hot_accepts: Vec<String>,
+    /// Code run at the module's position in execution order
+    pub code: String,
+    /// `__soku_modules` registration for wrapped modules
+    pub definition: Option<String>,
+    pub strategy: LinkStrategy,
+    /// Source maps of `code` and `definition`, when linked with maps
+    pub code_map: Option<CodeMap>,
+    pub definition_map: Option<CodeMap>,
+}
+
+/// Import of a module that is not part of the bundle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalImport {
+    pub source: String,
+    /// Imported name (`None` for `* as local`) and the local it is bound to;
+    /// empty for side-effect imports
+    pub bindings: Vec<(Option<String>, String)>,
+}
+
+/// Result of linking a set of modules
+#[derive(Debug, Clone, Default)]
+pub struct LinkOutput {
+    /// Module registry runtime and namespace objects shared by all modules
+    pub prelude: String,
+    /// Linked modules in execution order (dependencies first)
+    pub modules: Vec<LinkedModule>,
+    /// Exports of the entry modules: exported name and its expression in the bundle scope
+    pub exports: Vec<(String, String)>,
+    /// `module.exports` of an entry module written in CommonJS
+    pub commonjs_entry: Option<String>,
+    /// Imports left for the output format to provide, with their local names untouched
+    pub externals: Vec<ExternalImport>,
+    /// Entry modules: the linked modules that no other module imports
+    pub entries: Vec<PathBuf>,
+    pub warnings: Vec<String>,
+}
+
+impl LinkOutput {
+    /// Concatenate the linked modules, using `header` to label each one.
+    ///
+    /// Wrapped modules are registered before any module code runs, so that
+    /// `__soku_require` can reach them from anywhere in the bundle.
+    pub fn render(&self, header: impl Fn(&Path) -> String) -> String {
+        self.render_mapped(header, false).code
+    }
+
+    /// `render` that also concatenates the source maps of the modules
+    pub fn render_with_map(&self, header: impl Fn(&Path) -> String) -> (String, CodeMap) {
+        let rendered = self.render_mapped(header, true);
+        (rendered.code, rendered.map.unwrap_or_default())
+    }
+
+    fn render_mapped(&self, header: impl Fn(&Path) -> String, with_map: bool) -> MappedCode {
+        let mut code = MappedCode::new(with_map);
+        code.push_str(&self.prelude);
+        for module in &self.modules {
+            if let Some(definition) = &module.definition {
+                code.push_str(&header(&module.path));
+                code.push_mapped(definition, module.definition_map.as_ref());
+                code.push_str("\n\n");
+            }
+        }
+        for module in &self.modules {
+            if module.definition.is_none() {
+                code.push_str(&header(&module.path));
+            } else if module.code.is_empty() {
+                continue;
+            }
+            code.push_mapped(&module.code, module.code_map.as_ref());
+            code.push_str("\n\n");
+        }
+        code
+    }
+
+    /// Split the output of `with_shared_registry` linking into one output per
+    /// chunk, each registering the modules whose paths the chunk lists.
+    ///
+    /// Every chunk gets the registry runtime and the externals. When one chunk
+    /// holds all entry modules, it also runs the code of every module and gets
+    /// the exports: it has to be loaded after the other chunks, which only
+    /// register modules. Warnings stay with `self`.
+    pub fn split(&self, chunks: &[Vec<PathBuf>]) -> Vec<LinkOutput> {
+        let entry_chunk = chunks
+            .iter()
+            .position(|paths| self.entries.iter().all(|entry| paths.contains(entry)));
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(index, paths)| {
+                let is_entry = entry_chunk == Some(index);
+                let modules = self
+                    .modules
+                    .iter()
+                    .filter_map(|module| {
+                        let in_chunk = paths.contains(&module.path);
+                        match (in_chunk, entry_chunk) {
+                            (true, None) => Some(module.clone()),
+                            (true, Some(_)) if is_entry => Some(module.clone()),
+                            (true, Some(_)) => Some(LinkedModule {
+                                code: String::new(),
+                                code_map: None,
+                                ..module.clone()
+                            }),
+                            (false, Some(_)) if is_entry && !module.code.is_empty() => {
+                                Some(LinkedModule {
+                                    definition: None,
+                                    definition_map: None,
+                                    ..module.clone()
+                                })
+                            }
+                            (false, _) => None,
+                        }
+                    })
+                    .collect();
+                LinkOutput {
+                    prelude: self.prelude.clone(),
+                    modules,
+                    exports: if is_entry {
+                        self.exports.clone()
+                    } else {
+                        Vec::new()
+                    },
+                    commonjs_entry: self.commonjs_entry.clone().filter(|_| is_entry),
+                    externals: self.externals.clone(),
+                    entries: self
+                        .entries
+                        .iter()
+                        .filter(|entry| paths.contains(entry))
+                        .cloned()
+                        .collect(),
+                    warnings: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// How each module was placed into the bundle
+    pub fn linking(&self) -> Vec<ModuleLinking> {
+        self.modules
+            .iter()
+            .map(|module| ModuleLinking {
+                path: module.path.clone(),
+                strategy: module.strategy,
+                id: module.id.clone(),
+                dependencies: module.dependencies.clone(),
+                hot_accepts: module.hot_accepts.clone(),
+                definition: module
+                    .definition
+                    .clone()
+                    .filter(|_| module.strategy == LinkStrategy::Wrapped(WrapReason::Hmr)),
+            })
+            .collect()
+    }
+}
+
+/// What an import binding refers to in the exporting module
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Imported {
+    Named(String),
+    Namespace,
+}
+
+#[derive(Debug, Clone)]
+struct ImportBinding {
+    local: String,
+    source: String,
+    imported: Imported,
+    /// Whether the binding is referenced as a value (type-only imports are elided)
+    used: bool,
+}
+
+#[derive(Debug, Clone)]
+enum ExportBinding {
+    /// `export { local }`, `export const local = ...`
+    Local(String),
+    /// Synthesized binding for `export default <expression>` and anonymous declarations
+    Symbol(usize),
+    /// `export { imported } from 'source'`, `export * as name from 'source'`
+    ReExport { source: String, imported: Imported },
+}
+
+#[derive(Debug, Clone)]
+enum EditText {
+    Text(String),
+    /// Final name of a top-level symbol, wrapped in `before`/`after`
+    Symbol {
+        before: String,
+        symbol: usize,
+        after: String,
+    },
+    /// Expression an import binding resolves to
+    Import {
+        before: String,
+        import: usize,
+        /// Called directly, so a member expression must not become `this`
+        callee: bool,
+    },
+    /// `require('...')` call in an ES module
+    Require(usize),
+    /// `import('...')` call loading a resolved module
+    DynamicImport(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Edit {
+    start: u32,
+    end: u32,
+    text: EditText,
+}
+
+impl Edit {
+    fn remove(span: Span) -> Self {
+        Self {
+            start: span.start,
+            end: span.end,
+            text: EditText::Text(String::new()),
+        }
+    }
+
+    /// Remove a whole statement together with its line break
+    fn remove_statement(code: &str, span: Span) -> Self {
+        let rest = &code[span.end as usize..];
+        let line_break = if rest.starts_with("\r\n") {
+            2
+        } else if rest.starts_with('\n') {
+            1
+        } else {
+            0
+        };
+        Self::remove(Span::new(span.start, span.end + line_break))
+    }
+}
+
+/// Owned result of analysing one module
+#[derive(Debug, Default)]
+struct ModuleRecord {
+    path: PathBuf,
+    code: String,
+    /// Source map of `code`, when linking with maps
+    map: Option<CodeMap>,
+    resolved: HashMap<String, PathBuf>,
+    /// Dependency specifiers in statement order
+    sources: Vec<String>,
+    /// Whether the module contains any `import`/`export` statement
+    has_module_syntax: bool,
+    /// Original names of value symbols declared at the top level
+    symbols: Vec<String>,
+    imports: Vec<ImportBinding>,
+    exports: Vec<(String, ExportBinding)>,
+    star_exports: Vec<String>,
+    /// Specifiers of `require()` calls in an ES module
+    requires: Vec<String>,
+    /// Specifiers of `import()` calls with a string literal
+    dynamic_imports: Vec<String>,
+    commonjs: CommonJsExports,
+    edits: Vec<Edit>,
+    /// Free variables (globals) referenced by the module
+    globals: HashSet<String>,
+    /// Names bound in nested scopes, which imported names must not be captured by
+    nested: HashSet<String>,
+    /// `import.meta.hot.accept()` without dependencies
+    accepts_self: bool,
+    /// Specifiers of the dependencies passed to `import.meta.hot.accept`
+    accepted: Vec<String>,
+}
+
+impl ModuleRecord {
+    fn is_commonjs(&self) -> bool {
+        !self.has_module_syntax && COMMONJS_GLOBALS.iter().any(|g| self.globals.contains(*g))
+    }
+}
+
+/// Exports of a CommonJS module found by static analysis, following the same
+/// patterns as Node's `cjs-module-lexer`
+#[derive(Debug, Default)]
+struct CommonJsExports {
+    /// `exports.name = ...`, `module.exports = { name }`, `Object.defineProperty(exports, 'name', ...)`
+    names: BTreeSet<String>,
+    /// `module.exports = require('source')`
+    reexports: Vec<String>,
+    /// `exports`/`module` are used in ways the analysis cannot follow
+    dynamic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Binding {
+    /// Top-level symbol of a scope-hoisted module
+    Symbol(usize, usize),
+    /// Namespace object of a module (the exports object for wrapped modules)
+    Namespace(usize),
+    /// Named export of a wrapped module, read from its exports object
+    Export(usize, String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ImportTarget {
+    Bundle(Binding),
+    /// Module outside the bundle: the local name is left untouched
+    External,
+    /// Resolved stylesheet or asset, which the build emits outside the JS
+    /// bundle: its bindings are `undefined`
+    Asset,
+    Missing,
+}
+
+/// Scope-hoisting linker for ES modules
+///
+/// Modules that cannot be concatenated safely (CommonJS, direct `eval`,
+/// circular imports) are wrapped in a function and registered with a small
+/// `__soku_require` runtime instead.
+#[derive(Debug, Default)]
+pub struct ModuleLinker {
+    /// Wrap every module, so the HMR client can replace its definition
+    hmr: bool,
+    /// Wrap every module into a registry on `globalThis`, so the output can
+    /// be split into chunks
+    shared_registry: bool,
+}
+
+impl ModuleLinker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Link for hot module replacement: nothing is hoisted, and the registry
+    /// is exposed to the HMR client as `globalThis.__soku_runtime__`
+    pub fn with_hmr(mut self, hmr: bool) -> Self {
+        self.hmr = hmr;
+        self
+    }
+
+    /// Link for a build split into chunks with `LinkOutput::split`: nothing is
+    /// hoisted, and every chunk registers its modules with, and requires the
+    /// modules of other chunks from, a registry on `globalThis`. Ignored when
+    /// linking for HMR.
+    pub fn with_shared_registry(mut self, shared_registry: bool) -> Self {
+        self.shared_registry = shared_registry;
+        self
+    }
+
+    /// Link processed module code into a single scope.
+    ///
+    /// `modules` pairs each module with its processed (type-stripped) code.
+    pub fn link(&self, modules: &[(&ModuleInfo, String)]) -> LinkOutput {
+        self.link_modules(modules, None)
+    }
+
+    /// `link` that carries `maps`, the source maps of the processed code,
+    /// over to the linked modules
+    pub fn link_with_maps(
+        &self,
+        modules: &[(&ModuleInfo, String)],
+        maps: &[CodeMap],
+    ) -> LinkOutput {
+        self.link_modules(modules, Some(maps))
+    }
+
+    fn link_modules(
+        &self,
+        modules: &[(&ModuleInfo, String)],
+        maps: Option<&[CodeMap]>,
+    ) -> LinkOutput {
+        let mut warnings = Vec::new();
+        let records: Vec<ModuleRecord> = modules
+            .iter()
+            .enumerate()
+            .map(|(i, (module, code))| {
+                let mut record = analyze_module(module, code, self.hmr, &mut warnings);
+                if record.code == *code {
+                    record.map = maps.and_then(|maps| maps.get(i)).cloned();
+                }
+                record
+            })
+            .collect();
+
+        let mut index: HashMap<PathBuf, usize> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            index.insert(record.path.clone(), i);
+            if let Ok(canonical) = record.path.canonicalize() {
+                index.insert(canonical, i);
+            }
+        }
+
+        let mut graph = ModuleGraph {
+            records: &records,
+            index: &index,
+            wrap: vec![None; records.len()],
+        };
+        graph.plan_wrapping();
+        if self.hmr {
+            for wrap in &mut graph.wrap {
+                wrap.get_or_insert(WrapReason::Hmr);
+            }
+        } else if self.shared_registry {
+            for wrap in &mut graph.wrap {
+                wrap.get_or_insert(WrapReason::Chunked);
+            }
+        }
+        let graph = graph;
+        let order = graph.execution_order();
+        let ids = module_ids(&records);
+
+        // Resolve every import binding to its target
+        let targets: Vec<Vec<ImportTarget>> = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                record
+                    .imports
+                    .iter()
+                    .map(|import| {
+                        let target = graph.resolve_import(i, &import.source, &import.imported);
+                        let missing = match &target {
+                            ImportTarget::Missing => true,
+                            // Named imports of CommonJS modules always read
+                            // `module.exports`, but warn when the analysis is conclusive
+                            ImportTarget::Bundle(Binding::Export(module, name))
+                                if !records[*module].has_module_syntax && name != "default" =>
+                            {
+                                graph
+                                    .commonjs_names(*module, &mut HashSet::new())
+                                    .is_some_and(|names| !names.contains(name))
+                            }
+                            _ => false,
+                        };
+                        if missing && import.used {
+                            warnings.push(format!(
+                                "{}: '{}' is not exported by '{}'",
+                                record.path.display(),
+                                match &import.imported {
+                                    Imported::Named(name) => name.as_str(),
+                                    Imported::Namespace => "*",
+                                },
+                                import.source
+                            ));
+                        }
+                        target
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // `export *` can only forward the names found in a CommonJS module
+        for (i, record) in records.iter().enumerate() {
+            for source in &record.star_exports {
+                let Some(target) = graph.module_for(i, source) else {
+                    continue;
+                };
+                let opaque = !records[target].has_module_syntax
+                    && graph.commonjs_names(target, &mut HashSet::new()).is_none();
+                if opaque {
+                    warnings.push(format!(
+                        "{}: `export * from '{}'` only re-exports names that can be detected statically in a CommonJS module",
+                        record.path.display(),
+                        source
+                    ));
+                }
+            }
+        }
+
+        // Export getters of wrapped ES modules
+        let wrapped_exports: HashMap<usize, Vec<(String, Binding)>> = (0..records.len())
+            .filter(|&i| graph.wrap[i].is_some() && records[i].has_module_syntax)
+            .map(|i| (i, graph.namespace_members(i)))
+            .collect();
+
+        // What the bundle exports: the exports of its entry modules
+        let roots = graph.roots();
+        let mut entry_exports: Vec<(String, Binding)> = Vec::new();
+        for &root in &roots {
+            let members = if graph.wrap[root].is_some() {
+                graph
+                    .export_names(root, &mut HashSet::new())
+                    .into_iter()
+                    .map(|name| (name.clone(), Binding::Export(root, name)))
+                    .collect()
+            } else {
+                graph.namespace_members(root)
+            };
+            for (name, binding) in members {
+                if entry_exports.iter().any(|(existing, _)| *existing == name) {
+                    warnings.push(format!(
+                        "{}: export '{}' is already exported by another entry module",
+                        records[root].path.display(),
+                        name
+                    ));
+                    continue;
+                }
+                entry_exports.push((name, binding));
+            }
+        }
+        let commonjs_root = roots
+            .iter()
+            .copied()
+            .find(|&root| records[root].is_commonjs());
+
+        // Collect the namespace objects that need to be materialised
+        let mut namespaces: BTreeSet<usize> = BTreeSet::new();
+        let mut pending: Vec<Binding> = Vec::new();
+        for (record, record_targets) in records.iter().zip(&targets) {
+            for (import, target) in record.imports.iter().zip(record_targets) {
+                if let (true, ImportTarget::Bundle(binding)) = (import.used, target) {
+                    pending.push(binding.clone());
+                }
+            }
+        }
+        pending.extend(wrapped_exports.values().flatten().map(|(_, b)| b.clone()));
+        pending.extend(entry_exports.iter().map(|(_, b)| b.clone()));
+        let mut namespace_members: HashMap<usize, Vec<(String, Binding)>> = HashMap::new();
+        while let Some(binding) = pending.pop() {
+            let Binding::Namespace(module) = binding else {
+                continue;
+            };
+            if graph.wrap[module].is_some() || !namespaces.insert(module) {
+                continue;
+            }
+            let members = graph.namespace_members(module);
+            pending.extend(members.iter().map(|(_, binding)| binding.clone()));
+            namespace_members.insert(module, members);
+        }
+
+        // Wrapped modules read from the bundle scope get a shared exports variable
+        let wrapped_of = |binding: &Binding| match binding {
+            Binding::Namespace(module) | Binding::Export(module, _)
+                if graph.wrap[*module].is_some() =>
+            {
+                Some(*module)
+            }
+            _ => None,
+        };
+        let mut bundle_access: BTreeSet<usize> = BTreeSet::new();
+        for (i, record_targets) in targets.iter().enumerate() {
+            if graph.wrap[i].is_none() {
+                bundle_access.extend(record_targets.iter().filter_map(|t| match t {
+                    ImportTarget::Bundle(binding) => wrapped_of(binding),
+                    _ => None,
+                }));
+            }
+        }
+        bundle_access.extend(
+            namespace_members
+                .values()
+                .flatten()
+                .chain(&entry_exports)
+                .filter_map(|(_, b)| wrapped_of(b)),
+        );
+        bundle_access.extend(commonjs_root);
+
+        // Assign bundle-wide unique names
+        let mut taken: HashSet<String> = HashSet::new();
+        for (record, record_targets) in records.iter().zip(&targets) {
+            taken.extend(record.globals.iter().cloned());
+            for (import, target) in record.imports.iter().zip(record_targets) {
+                if *target == ImportTarget::External {
+                    taken.insert(import.local.clone());
+                }
+            }
+        }
+        taken.extend(
+            [
+                "__soku_global",
+                "__soku_registry",
+                "__soku_modules",
+                "__soku_cache",
+                "__soku_require",
+                "__soku_export",
+                "__soku_to_esm",
+                "__soku_default",
+            ]
+            .map(String::from),
+        );
+
+        // Modules that refer to another module's names: its direct importers,
+        // and every module whose imports resolve to it through re-exports
+        let mut importers: Vec<HashSet<usize>> = vec![HashSet::new(); records.len()];
+        for (i, record) in records.iter().enumerate() {
+            for source in &record.sources {
+                if let Some(target) = graph.module_for(i, source) {
+                    importers[target].insert(i);
+                }
+            }
+            for target in &targets[i] {
+                if let ImportTarget::Bundle(Binding::Symbol(module, _)) = target {
+                    importers[*module].insert(i);
+                }
+            }
+        }
+
+        // Names visible inside an importer that would capture a hoisted name:
+        // nested bindings, plus everything declared inside a wrapper function
+        let shadowing: Vec<HashSet<&str>> = records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let mut names: HashSet<&str> = record.nested.iter().map(String::as_str).collect();
+                if graph.wrap[i].is_some() {
+                    names.extend(record.symbols.iter().map(String::as_str));
+                    names.extend(COMMONJS_GLOBALS);
+                }
+                names
+            })
+            .collect();
+
+        let mut names: Vec<Vec<String>> = vec![Vec::new(); records.len()];
+        for &i in &order {
+            names[i] = if graph.wrap[i].is_some() {
+                // Wrapped modules keep their own scope
+                records[i].symbols.clone()
+            } else {
+                let avoid: HashSet<&str> = importers[i]
+                    .iter()
+                    .flat_map(|&importer| shadowing[importer].iter().copied())
+                    .collect();
+                records[i]
+                    .symbols
+                    .iter()
+                    .map(|name| unique_name(name, &mut taken, &avoid))
+                    .collect()
+            };
+        }
+
+        let mut namespace_names: HashMap<usize, String> = HashMap::new();
+        for &i in &order {
+            if namespaces.contains(&i) || bundle_access.contains(&i) {
+                let base = format!("{}_exports", identifier_stem(&records[i].path));
+                namespace_names.insert(i, unique_name(&base, &mut taken, &HashSet::new()));
+            }
+        }
+
+        // Inside a wrapper, other wrapped modules are required into local variables
+        let mut local_requires: HashMap<usize, Vec<(usize, String)>> = HashMap::new();
+        for &i in &order {
+            if graph.wrap[i].is_none() || !records[i].has_module_syntax {
+                continue;
+            }
+            let mut required: Vec<usize> = graph
+                .dependencies(i)
+                .filter(|&dep| dep != i && graph.wrap[dep].is_some())
+                .collect();
+            let bindings = targets[i]
+                .iter()
+                .filter_map(|t| match t {
+                    ImportTarget::Bundle(binding) => Some(binding),
+                    _ => None,
+                })
+                .chain(wrapped_exports[&i].iter().map(|(_, b)| b));
+            for module in bindings.filter_map(wrapped_of) {
+                if module != i && !required.contains(&module) {
+                    required.push(module);
+                }
+            }
+            let avoid = &shadowing[i];
+            let locals = required
+                .into_iter()
+                .map(|module| {
+                    let base = format!("{}_exports", identifier_stem(&records[module].path));
+                    (module, unique_name(&base, &mut taken, avoid))
+                })
+                .collect();
+            local_requires.insert(i, locals);
+        }
+
+        // ES modules see a CommonJS module through an ES namespace view
+        let require_expr = |module: usize| -> String {
+            let require = format!("__soku_require({})", json_string(&ids[module]));
+            if records[module].has_module_syntax {
+                require
+            } else {
+                format!("__soku_to_esm({})", require)
+            }
+        };
+
+        // Expression for a binding as seen from code in module `from`
+        let binding_expr = |binding: &Binding, from: Option<usize>| -> String {
+            let exports_of = |module: usize| -> String {
+                if from == Some(module) {
+                    return "exports".to_string();
+                }
+                from.and_then(|from| local_requires.get(&from))
+                    .and_then(|locals| locals.iter().find(|(m, _)| *m == module))
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| namespace_names[&module].clone())
+            };
+            match binding {
+                Binding::Symbol(module, symbol) => names[*module][*symbol].clone(),
+                Binding::Namespace(module) if graph.wrap[*module].is_some() => exports_of(*module),
+                Binding::Namespace(module) => namespace_names[module].clone(),
+                Binding::Export(module, name) => {
+                    format!("{}{}", exports_of(*module), member_access(name))
+                }
+            }
+        };
+
+        let mut prelude = String::new();
+        if self.hmr {
+            prelude.push_str(HMR_RUNTIME);
+        } else if graph.wrap.iter().any(Option::is_some) {
+            prelude.push_str(if self.shared_registry {
+                SHARED_MODULE_REGISTRY
+            } else {
+                MODULE_REGISTRY
+            });
+            prelude.push_str(MODULE_RUNTIME);
+        }
+
+        // Namespace objects go first; their getters are evaluated lazily
+        for &i in &order {
+            if let Some(members) = namespace_members.get(&i) {
+                prelude.push_str(&format!(
+                    "var {} = Object.freeze({{\n  __proto__: null",
+                    namespace_names[&i]
+                ));
+                for (export, binding) in members {
+                    prelude.push_str(&format!(
+                        ",\n  get {}() {{ return {}; }}",
+                        property_key(export),
+                        binding_expr(binding, None)
+                    ));
+                }
+                prelude.push_str("\n});\n\n");
+            }
+        }
+
+        // Wrapped modules run at their position when an entry point or a
+        // hoisted module imports them; otherwise they load lazily on require
+        let mut eager: BTreeSet<usize> = roots.iter().copied().collect();
+        for i in (0..records.len()).filter(|&i| graph.wrap[i].is_none()) {
+            eager.extend(graph.imported(i).filter(|&dep| graph.wrap[dep].is_some()));
+        }
+
+        let mut linked: Vec<LinkedModule> = order
+            .iter()
+            .map(|&i| {
+                let record = &records[i];
+                let wrap = graph.wrap[i];
+                let import_exprs: Vec<Option<String>> = record
+                    .imports
+                    .iter()
+                    .zip(&targets[i])
+                    .map(|(import, target)| match target {
+                        _ if !import.used => None,
+                        ImportTarget::Bundle(binding) => Some(binding_expr(binding, Some(i))),
+                        ImportTarget::External => None,
+                        ImportTarget::Asset | ImportTarget::Missing => {
+                            Some("(void 0)".to_string())
+                        }
+                    })
+                    .collect();
+                let require_exprs: Vec<Option<String>> = record
+                    .requires
+                    .iter()
+                    .map(|source| {
+                        let target = graph.module_for(i, source)?;
+                        Some(format!("__soku_require({})", json_string(&ids[target])))
+                    })
+                    .collect();
+                // The registry definition of a dynamically imported module is
+                // in the bundle, so the import resolves to its namespace
+                let dynamic_import_exprs: Vec<Option<String>> = record
+                    .dynamic_imports
+                    .iter()
+                    .map(|source| {
+                        let target = graph.module_for(i, source)?;
+                        Some(format!(
+                            "Promise.resolve().then(function () {{ return {}; }})",
+                            require_expr(target)
+                        ))
+                    })
+                    .collect();
+                let (body, body_map) = apply_edits(
+                    record,
+                    &names[i],
+                    &import_exprs,
+                    &require_exprs,
+                    &dynamic_import_exprs,
+                );
+                let dependency_ids: Vec<String> = graph
+                    .dependencies(i)
+                    .map(|dep| ids[dep].clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect();
+                let mut hot_accepts: Vec<String> = record
+                    .accepted
+                    .iter()
+                    .filter_map(|source| graph.module_for(i, source))
+                    .map(|module| ids[module].clone())
+                    .collect();
+                let refresh_boundary = self.hmr && is_refresh_boundary(record);
+                if record.accepts_self || refresh_boundary {
+                    hot_accepts.push(ids[i].clone());
+                }
+
+                let Some(reason) = wrap else {
+                    return LinkedModule {
+                        path: record.path.clone(),
+                        id: ids[i].clone(),
+                        dependencies: dependency_ids,
+                        hot_accepts,
+                        code: body,
+                        definition: None,
+                        strategy: LinkStrategy::Hoisted,
+                        code_map: body_map,
+                        definition_map: None,
+                    };
+                };
+
+                let id = json_string(&ids[i]);
+                let mut wrapper = String::new();
+                // Modules without exports, like most entries, leave `exports` empty
+                if let Some(exports) = wrapped_exports.get(&i).filter(|e| !e.is_empty()) {
+                    let getters: Vec<String> = exports
+                        .iter()
+                        .map(|(name, binding)| {
+                            format!(
+                                "  {}: function () {{ return {}; }}",
+                                property_key(name),
+                                binding_expr(binding, Some(i))
+                            )
+                        })
+                        .collect();
+                    wrapper.push_str(&format!(
+                        "__soku_export(exports, {{\n{}\n}});\n",
+                        getters.join(",\n")
+                    ));
+                }
+                for (module, local) in local_requires.get(&i).into_iter().flatten() {
+                    wrapper.push_str(&format!("var {} = {};\n", local, require_expr(*module)));
+                }
+                if self.hmr && REFRESH_GLOBALS.iter().any(|g| record.globals.contains(*g)) {
+                    wrapper.push_str(
+                        "var $RefreshReg$ = __soku_refresh_reg(module.id), $RefreshSig$ = __soku_refresh_sig;\n",
+                    );
+                }
+
+                let dependencies: Vec<String> = record
+                    .resolved
+                    .keys()
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .filter_map(|specifier| {
+                        let target = graph.module_for(i, specifier)?;
+                        Some(format!(
+                            "{}: {}",
+                            json_string(specifier),
+                            json_string(&ids[target])
+                        ))
+                    })
+                    .collect();
+                let body = body.trim_end();
+                if body.is_empty() {
+                    wrapper.truncate(wrapper.trim_end().len());
+                }
+                let mut definition = MappedCode::new(body_map.is_some());
+                definition.push_str(&format!(
+                    "__soku_modules[{}] = [function (module, exports, require) {{\n{}",
+                    id, wrapper
+                ));
+                definition.push_mapped(body, body_map.as_ref());
+                if refresh_boundary {
+                    definition.push_str("\n__soku_refresh_accept(module);");
+                }
+                definition.push_str(&format!("\n}}, {{{}}}];", dependencies.join(", ")));
+
+                let code = if let Some(name) = namespace_names.get(&i) {
+                    format!("var {} = {};", name, require_expr(i))
+                } else if eager.contains(&i) {
+                    format!("__soku_require({});", id)
+                } else {
+                    String::new()
+                };
+
+                LinkedModule {
+                    path: record.path.clone(),
+                    id: ids[i].clone(),
+                    dependencies: dependency_ids,
+                    hot_accepts,
+                    code,
+                    definition: Some(definition.code),
+                    strategy: LinkStrategy::Wrapped(reason),
+                    code_map: None,
+                    definition_map: definition.map,
+                }
+            })
+            .collect();
+
+        // A cycle has to be entered through the module the walk reached
+        // first, which is the last of its members in `order`: requiring it
+        // evaluates the other members from inside its wrapper, in ESM order.
+        // Requiring another member earlier would run that member's imports of
+        // the entry before the member itself.
+        let position: HashMap<usize, usize> =
+            order.iter().enumerate().map(|(p, &i)| (i, p)).collect();
+        for component in graph.strongly_connected_components() {
+            let mut members: Vec<usize> = component.iter().map(|m| position[m]).collect();
+            members.sort_unstable();
+            let Some((&entry, rest)) = members.split_last() else {
+                continue;
+            };
+            for &member in rest {
+                let code = std::mem::take(&mut linked[member].code);
+                let code_map = linked[member].code_map.take();
+                if code.is_empty() {
+                    continue;
+                }
+                let entry_module = &mut linked[entry];
+                if !entry_module.code.is_empty() {
+                    entry_module.code.push('\n');
+                }
+                if let Some(code_map) = &code_map {
+                    entry_module
+                        .code_map
+                        .get_or_insert_with(CodeMap::default)
+                        .append(code_map, entry_module.code.len());
+                }
+                entry_module.code.push_str(&code);
+            }
+        }
+
+        let exports = entry_exports
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding_expr(binding, None)))
+            .collect();
+        let commonjs_entry =
+            commonjs_root.map(|root| format!("__soku_require({})", json_string(&ids[root])));
+
+        // Imports that stay outside the bundle, grouped by specifier. Only
+        // unresolved specifiers, which include the configured externals, are
+        // external: stylesheets and assets were resolved by the graph walk
+        let mut externals: Vec<ExternalImport> = Vec::new();
+        for &i in &order {
+            let record = &records[i];
+            if !record.has_module_syntax {
+                // CommonJS modules load their externals with `require()` at runtime
+                continue;
+            }
+            for source in &record.sources {
+                if record.resolved.contains_key(source) {
+                    continue;
+                }
+                if !externals.iter().any(|e| e.source == *source) {
+                    externals.push(ExternalImport {
+                        source: source.clone(),
+                        bindings: Vec::new(),
+                    });
+                }
+            }
+            for (import, target) in record.imports.iter().zip(&targets[i]) {
+                if !import.used || *target != ImportTarget::External {
+                    continue;
+                }
+                let binding = (
+                    match &import.imported {
+                        Imported::Named(name) => Some(name.clone()),
+                        Imported::Namespace => None,
+                    },
+                    import.local.clone(),
+                );
+                if let Some(external) = externals.iter_mut().find(|e| e.source == import.source) {
+                    if !external.bindings.contains(&binding) {
+                        external.bindings.push(binding);
+                    }
+                }
+            }
+        }
+
+        LinkOutput {
+            prelude,
+            modules: linked,
+            exports,
+            commonjs_entry,
+            externals,
+            entries: roots.iter().map(|&i| records[i].path.clone()).collect(),
+            warnings,
+        }
+    }
+}
+
+/// Read-only view over the analysed modules used for resolution
+struct ModuleGraph<'r> {
+    records: &'r [ModuleRecord],
+    index: &'r HashMap<PathBuf, usize>,
+    /// Modules that are wrapped for `__soku_require` instead of hoisted
+    wrap: Vec<Option<WrapReason>>,
+}
+
+impl ModuleGraph<'_> {
+    fn module_for(&self, importer: usize, source: &str) -> Option<usize> {
+        let path = self.records[importer].resolved.get(source)?;
+        self.index.get(path).copied()
+    }
+
+    /// Modules reached through `import`/`export ... from` statements
+    fn imported(&self, module: usize) -> impl Iterator<Item = usize> + '_ {
+        self.records[module]
+            .sources
+            .iter()
+            .filter_map(move |source| self.module_for(module, source))
+    }
+
+    /// Imported modules followed by modules loaded with `require()`
+    fn dependencies(&self, module: usize) -> impl Iterator<Item = usize> + '_ {
+        let record = &self.records[module];
+        self.imported(module).chain(
+            record
+                .requires
+                .iter()
+                .filter_map(move |source| self.module_for(module, source)),
+        )
+    }
+
+    /// Modules loaded through `import()` calls
+    fn dynamically_imported(&self, module: usize) -> impl Iterator<Item = usize> + '_ {
+        self.records[module]
+            .dynamic_imports
+            .iter()
+            .filter_map(move |source| self.module_for(module, source))
+    }
+
+    /// Decide which modules cannot be scope-hoisted
+    fn plan_wrapping(&mut self) {
+        for (i, record) in self.records.iter().enumerate() {
+            if record.is_commonjs() {
+                self.wrap[i] = Some(WrapReason::CommonJs);
+            } else if record.globals.contains("eval") {
+                self.wrap[i] = Some(WrapReason::DirectEval);
+            }
+        }
+
+        for component in self.strongly_connected_components() {
+            let cyclic = component.len() > 1
+                || self
+                    .dependencies(component[0])
+                    .any(|dep| dep == component[0]);
+            if cyclic {
+                for module in component {
+                    self.wrap[module].get_or_insert(WrapReason::Cycle);
+                }
+            }
+        }
+
+        // `require()` needs an exports object, so anything loaded that way
+        // has to live in the registry as well
+        for i in 0..self.records.len() {
+            let required: Vec<usize> = if self.wrap[i] == Some(WrapReason::CommonJs) {
+                self.dependencies(i).collect()
+            } else {
+                let record = &self.records[i];
+                record
+                    .requires
+                    .iter()
+                    .filter_map(|source| self.module_for(i, source))
+                    .collect()
+            };
+            for module in required {
+                self.wrap[module].get_or_insert(WrapReason::Required);
+            }
+        }
+
+        // Modules loaded with `import()` only run once the import does
+        for i in 0..self.records.len() {
+            let loaded: Vec<usize> = self.dynamically_imported(i).collect();
+            for module in loaded {
+                self.wrap[module].get_or_insert(WrapReason::DynamicImport);
+            }
+        }
+    }
+
+    /// Tarjan's algorithm over the import graph
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        struct Tarjan {
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<usize>,
+            next: usize,
+            components: Vec<Vec<usize>>,
+        }
+
+        fn connect(graph: &ModuleGraph, state: &mut Tarjan, module: usize) {
+            state.index[module] = Some(state.next);
+            state.low[module] = state.next;
+            state.next += 1;
+            state.stack.push(module);
+            state.on_stack[module] = true;
+
+            for dep in graph.dependencies(module) {
+                match state.index[dep] {
+                    None => {
+                        connect(graph, state, dep);
+                        state.low[module] = state.low[module].min(state.low[dep]);
+                    }
+                    Some(index) if state.on_stack[dep] => {
+                        state.low[module] = state.low[module].min(index);
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            if Some(state.low[module]) == state.index[module] {
+                let mut component = Vec::new();
+                while let Some(member) = state.stack.pop() {
+                    state.on_stack[member] = false;
+                    component.push(member);
+                    if member == module {
+                        break;
+                    }
+                }
+                state.components.push(component);
+            }
+        }
+
+        let count = self.records.len();
+        let mut state = Tarjan {
+            index: vec![None; count],
+            low: vec![0; count],
+            on_stack: vec![false; count],
+            stack: Vec::new(),
+            next: 0,
+            components: Vec::new(),
+        };
+        for module in 0..count {
+            if state.index[module].is_none() {
+                connect(self, &mut state, module);
+            }
+        }
+        state.components
+    }
+
+    /// Modules nobody imports, in input order
+    fn roots(&self) -> Vec<usize> {
+        let mut imported = vec![false; self.records.len()];
+        for i in 0..self.records.len() {
+            for target in self.dependencies(i).chain(self.dynamically_imported(i)) {
+                if target != i {
+                    imported[target] = true;
+                }
+            }
+        }
+        (0..self.records.len()).filter(|&i| !imported[i]).collect()
+    }
+
+    /// Dependencies first, starting from modules nobody imports
+    fn execution_order(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.records.len()];
+        let mut order = Vec::with_capacity(self.records.len());
+        for root in self.roots().into_iter().chain(0..self.records.len()) {
+            self.visit(root, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn visit(&self, module: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[module] {
+            return;
+        }
+        visited[module] = true;
+        let dependencies: Vec<usize> = self.dependencies(module).collect();
+        for dep in dependencies {
+            self.visit(dep, visited, order);
+        }
+        order.push(module);
+    }
+
+    fn resolve_import(&self, importer: usize, source: &str, imported: &Imported) -> ImportTarget {
+        let Some(target) = self.module_for(importer, source) else {
+            if self.records[importer].resolved.contains_key(source) {
+                return ImportTarget::Asset;
+            }
+            return ImportTarget::External;
+        };
+        match imported {
+            Imported::Namespace => ImportTarget::Bundle(Binding::Namespace(target)),
+            Imported::Named(name) => self
+                .resolve_export(target, name, &mut HashSet::new())
+                .map(ImportTarget::Bundle)
+                .unwrap_or(ImportTarget::Missing),
+        }
+    }
+
+    fn resolve_export(
+        &self,
+        module: usize,
+        name: &str,
+        visited: &mut HashSet<(usize, String)>,
+    ) -> Option<Binding> {
+        if self.wrap[module].is_some() {
+            if !self.records[module].has_module_syntax {
+                // CommonJS: read through the `__soku_to_esm` view of `module.exports`
+                return Some(Binding::Export(module, name.to_string()));
+            }
+            return self
+                .export_names(module, &mut HashSet::new())
+                .contains(name)
+                .then(|| Binding::Export(module, name.to_string()));
+        }
+        self.resolve_own_export(module, name, visited)
+    }
+
+    /// Resolve an export to the binding that declares it, looking through the
+    /// module itself even when it is wrapped
+    fn resolve_own_export(
+        &self,
+        module: usize,
+        name: &str,
+        visited: &mut HashSet<(usize, String)>,
+    ) -> Option<Binding> {
+        if !visited.insert((module, name.to_string())) {
+            return None;
+        }
+        let record = &self.records[module];
+
+        if let Some((_, binding)) = record.exports.iter().find(|(exported, _)| exported == name) {
+            return match binding {
+                ExportBinding::Local(local) => self.resolve_local(module, local, visited),
+                ExportBinding::Symbol(symbol) => Some(Binding::Symbol(module, *symbol)),
+                ExportBinding::ReExport { source, imported } => {
+                    let target = self.module_for(module, source)?;
+                    match imported {
+                        Imported::Namespace => Some(Binding::Namespace(target)),
+                        Imported::Named(imported) => self.resolve_export(target, imported, visited),
+                    }
+                }
+            };
+        }
+
+        // `export *` never re-exports `default`
+        if name == "default" {
+            return None;
+        }
+        record.star_exports.iter().find_map(|source| {
+            let target = self.module_for(module, source)?;
+            let forwarded = self.records[target].has_module_syntax
+                || self
+                    .export_names(target, &mut HashSet::new())
+                    .contains(name);
+            if !forwarded {
+                return None;
+            }
+            self.resolve_export(target, name, visited)
+        })
+    }
+
+    fn resolve_local(
+        &self,
+        module: usize,
+        local: &str,
+        visited: &mut HashSet<(usize, String)>,
+    ) -> Option<Binding> {
+        let record = &self.records[module];
+        if let Some(symbol) = record.symbols.iter().position(|s| s == local) {
+            return Some(Binding::Symbol(module, symbol));
+        }
+        let import = record.imports.iter().find(|i| i.local == local)?;
+        let target = self.module_for(module, &import.source)?;
+        match &import.imported {
+            Imported::Namespace => Some(Binding::Namespace(target)),
+            Imported::Named(name) => self.resolve_export(target, name, visited),
+        }
+    }
+
+    /// All names exported by a module, sorted like a module namespace object
+    fn export_names(&self, module: usize, visited: &mut HashSet<usize>) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        if !visited.insert(module) {
+            return names;
+        }
+        let record = &self.records[module];
+        if !record.has_module_syntax {
+            names.insert("default".to_string());
+            names.extend(
+                self.commonjs_names(module, &mut HashSet::new())
+                    .unwrap_or_default(),
+            );
+            return names;
+        }
+        names.extend(record.exports.iter().map(|(name, _)| name.clone()));
+        for source in &record.star_exports {
+            if let Some(target) = self.module_for(module, source) {
+                names.extend(
+                    self.export_names(target, visited)
+                        .into_iter()
+                        .filter(|name| name != "default"),
+                );
+            }
+        }
+        names
+    }
+
+    /// Statically detected exports of a CommonJS module, or `None` when they
+    /// cannot be known without running it
+    fn commonjs_names(
+        &self,
+        module: usize,
+        visited: &mut HashSet<usize>,
+    ) -> Option<BTreeSet<String>> {
+        if !visited.insert(module) {
+            return Some(BTreeSet::new());
+        }
+        let record = &self.records[module];
+        if record.has_module_syntax {
+            let mut names = self.export_names(module, &mut HashSet::new());
+            names.remove("default");
+            return Some(names);
+        }
+        if record.commonjs.dynamic {
+            return None;
+        }
+        let mut names = record.commonjs.names.clone();
+        for source in &record.commonjs.reexports {
+            let target = self.module_for(module, source)?;
+            names.extend(self.commonjs_names(target, visited)?);
+        }
+        Some(names)
+    }
+
+    /// Exports of a module paired with the bindings they resolve to
+    fn namespace_members(&self, module: usize) -> Vec<(String, Binding)> {
+        self.export_names(module, &mut HashSet::new())
+            .into_iter()
+            .filter_map(|name| {
+                let binding = self.resolve_own_export(module, &name, &mut HashSet::new())?;
+                Some((name, binding))
+            })
+            .collect()
+    }
+}
+
+/// Stable registry ids: module paths relative to their common directory
+///
+/// Virtual modules keep their id without the prefix.
+fn module_ids(records: &[ModuleRecord]) -> Vec<String> {
+    let mut base: Option<PathBuf> = None;
+    for record in records.iter().filter(|r| !is_virtual_module(&r.path)) {
+        let dir = record.path.parent().unwrap_or(Path::new("")).to_path_buf();
+        base = Some(match base {
+            None => dir,
+            Some(base) => base
+                .components()
+                .zip(dir.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    let base = base.unwrap_or_default();
+    records
+        .iter()
+        .map(|record| {
+            if is_virtual_module(&record.path) {
+                return module_display_name(&record.path);
+            }
+            record
+                .path
+                .strip_prefix(&base)
+                .unwrap_or(&record.path)
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect()
+}
+
+/// Parse a module and record its bindings, exports and the edits needed to hoist it
+///
+/// `import.meta.hot` becomes the module's HMR API when linking for HMR, and
+/// `undefined` otherwise.
+fn analyze_module(
+    module: &ModuleInfo,
+    code: &str,
+    hmr: bool,
+    warnings: &mut Vec<String>,
+) -> ModuleRecord {
+    let mut record = ModuleRecord {
+        path: module.path.clone(),
+        code: code.to_string(),
+        resolved: module.resolved_imports.clone(),
+        ..Default::default()
+    };
+
+    let allocator = Allocator::default();
+    let parsed = match parse_with_oxc(
+        &allocator,
+        code,
+        ParsingConfig::for_path(&module.path),
+        &module.path,
+        "Module linking failed",
+    ) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            warnings.push(format!(
+                "{}: could not be parsed for linking, import/export statements were dropped",
+                module.path.display()
+            ));
+            record.code = code
+                .lines()
+                .filter(|line| {
+                    let trimmed = line.trim();
+                    !trimmed.starts_with("import ") && !trimmed.starts_with("export ")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            return record;
+        }
+    };
+
+    let program = &parsed.program;
+    let default_name = format!("{}_default", identifier_stem(&module.path));
+    collect_module_syntax(program, code, &default_name, &mut record);
+    if !record.has_module_syntax {
+        // CommonJS dependencies come from `require()` calls
+        record.sources = module.dependencies.clone();
+    }
+
+    let semantic = SemanticBuilder::new().build(program).semantic;
+    let scoping = semantic.scoping();
+    let root = scoping.root_scope_id();
+
+    // Identifiers written in shorthand form need `name: newName` when renamed
+    let mut shorthand: HashSet<(u32, u32)> = HashSet::new();
+    for node in semantic.nodes().iter() {
+        let span = match node.kind() {
+            AstKind::ObjectProperty(prop) if prop.shorthand => prop.value.span(),
+            AstKind::BindingProperty(prop) if prop.shorthand => prop.key.span(),
+            AstKind::AssignmentTargetPropertyIdentifier(prop) => prop.binding.span,
+            _ => continue,
+        };
+        shorthand.insert((span.start, span.end));
+    }
+    let prefix = |span: Span, name: &str| -> String {
+        if shorthand.contains(&(span.start, span.end)) {
+            format!("{}: ", name)
+        } else {
+            String::new()
+        }
+    };
+
+    let mut bindings: Vec<(&str, oxc_semantic::SymbolId)> = scoping
+        .get_bindings(root)
+        .iter()
+        .map(|(name, symbol)| (*name, *symbol))
+        .collect();
+    bindings.sort_by_key(|(_, symbol)| scoping.symbol_span(*symbol).start);
+
+    let import_index: HashMap<String, usize> = record
+        .imports
+        .iter()
+        .enumerate()
+        .map(|(i, import)| (import.local.clone(), i))
+        .collect();
+
+    for (name, symbol_id) in bindings {
+        let flags = scoping.symbol_flags(symbol_id);
+        let references = scoping
+            .get_resolved_reference_ids(symbol_id)
+            .iter()
+            .map(|id| scoping.get_reference(*id));
+
+        if flags.intersects(SymbolFlags::Import | SymbolFlags::TypeImport) {
+            let Some(&import) = import_index.get(name) else {
+                continue;
+            };
+            for reference in references.filter(|r| r.is_value()) {
+                let span = semantic.reference_span(reference);
+                let callee = match semantic.nodes().parent_kind(reference.node_id()) {
+                    AstKind::CallExpression(call) => call.callee.span() == span,
+                    AstKind::TaggedTemplateExpression(tagged) => tagged.tag.span() == span,
+                    _ => false,
+                };
+                record.imports[import].used = true;
+                record.edits.push(Edit {
+                    start: span.start,
+                    end: span.end,
+                    text: EditText::Import {
+                        before: prefix(span, name),
+                        import,
+                        callee,
+                    },
+                });
+            }
+            continue;
+        }
+
+        if !flags.is_value() {
+            continue;
+        }
+
+        let symbol = record.symbols.len();
+        record.symbols.push(name.to_string());
+        let declarations = std::iter::once(scoping.symbol_span(symbol_id)).chain(
+            scoping
+                .symbol_redeclarations(symbol_id)
+                .iter()
+                .map(|redeclaration| redeclaration.span),
+        );
+        let spans: Vec<Span> = declarations
+            .chain(references.map(|reference| semantic.reference_span(reference)))
+            .collect();
+        for span in spans {
+            record.edits.push(Edit {
+                start: span.start,
+                end: span.end,
+                text: EditText::Symbol {
+                    before: prefix(span, name),
+                    symbol,
+                    after: String::new(),
+                },
+            });
+        }
+    }
+
+    let unresolved = scoping.root_unresolved_references();
+    if record.has_module_syntax {
+        // `require()` in an ES module loads the target from the registry
+        for node in semantic.nodes().iter() {
+            let AstKind::CallExpression(call) = node.kind() else {
+                continue;
+            };
+            let Some(source) = require_specifier(call) else {
+                continue;
+            };
+            let is_global = matches!(&call.callee, Expression::Identifier(ident)
+                if scoping.get_reference(ident.reference_id()).symbol_id().is_none());
+            if is_global && record.resolved.contains_key(source) {
+                record.edits.push(Edit {
+                    start: call.span.start,
+                    end: call.span.end,
+                    text: EditText::Require(record.requires.len()),
+                });
+                record.requires.push(source.to_string());
+            }
+        }
+    } else {
+        let references = ["exports", "module"]
+            .iter()
+            .filter_map(|name| unresolved.get(*name))
+            .map(|ids| ids.len())
+            .sum();
+        record.commonjs = collect_commonjs_exports(program, references);
+    }
+
+    for node in semantic.nodes().iter() {
+        match node.kind() {
+            AstKind::ImportExpression(import) => {
+                let Expression::StringLiteral(source) = &import.source else {
+                    continue;
+                };
+                if record.resolved.contains_key(source.value.as_str()) {
+                    record.edits.push(Edit {
+                        start: import.span.start,
+                        end: import.span.end,
+                        text: EditText::DynamicImport(record.dynamic_imports.len()),
+                    });
+                    record.dynamic_imports.push(source.value.to_string());
+                }
+            }
+            AstKind::StaticMemberExpression(member) if is_import_meta_hot(member) => {
+                record.edits.push(Edit {
+                    start: member.span.start,
+                    end: member.span.end,
+                    text: EditText::Text(if hmr { "module.hot" } else { "(void 0)" }.to_string()),
+                });
+            }
+            AstKind::CallExpression(call) => {
+                let Expression::StaticMemberExpression(callee) = &call.callee else {
+                    continue;
+                };
+                let accepts = callee.property.name == "accept"
+                    && matches!(&callee.object, Expression::StaticMemberExpression(hot) if is_import_meta_hot(hot));
+                if !accepts {
+                    continue;
+                }
+                match call.arguments.first() {
+                    None
+                    | Some(Argument::FunctionExpression(_))
+                    | Some(Argument::ArrowFunctionExpression(_)) => record.accepts_self = true,
+                    Some(Argument::StringLiteral(literal)) => {
+                        record.accepted.push(literal.value.to_string())
+                    }
+                    Some(Argument::ArrayExpression(array)) => {
+                        record
+                            .accepted
+                            .extend(array.elements.iter().filter_map(|element| match element {
+                                ArrayExpressionElement::StringLiteral(literal) => {
+                                    Some(literal.value.to_string())
+                                }
+                                _ => None,
+                            }))
+                    }
+                    Some(_) => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    if !record.has_module_syntax {
+        // Dynamically imported modules are not loaded with the module
+        let dynamic_imports = &record.dynamic_imports;
+        record
+            .sources
+            .retain(|source| !dynamic_imports.contains(source));
+    }
+
+    record.globals = unresolved.keys().map(|name| name.to_string()).collect();
+    record.nested = scoping
+        .symbol_ids()
+        .filter(|&symbol| scoping.symbol_scope_id(symbol) != root)
+        .map(|symbol| scoping.symbol_name(symbol).to_string())
+        .collect();
+
+    record
+}
+
+/// Find the exports of a CommonJS module from its top-level assignments.
+///
+/// `references` is the number of free `exports`/`module` references; if some
+/// of them are not part of a recognised pattern the result is marked dynamic.
+fn collect_commonjs_exports(program: &Program, references: usize) -> CommonJsExports {
+    let mut exports = CommonJsExports::default();
+    let mut recognized = 0;
+    let is_exports_object = |expr: &Expression| {
+        expr.is_specific_id("exports") || expr.is_specific_member_access("module", "exports")
+    };
+
+    for statement in &program.body {
+        let Statement::ExpressionStatement(statement) = statement else {
+            continue;
+        };
+        match statement.expression.get_inner_expression() {
+            Expression::AssignmentExpression(assign)
+                if assign.operator == AssignmentOperator::Assign =>
+            {
+                let Some(member) = assign.left.as_member_expression() else {
+                    continue;
+                };
+                if member.is_specific_member_access("module", "exports") {
+                    // `module.exports = ...` replaces everything assigned so far
+                    recognized += 1;
+                    exports.names.clear();
+                    exports.reexports.clear();
+                    match assign.right.get_inner_expression() {
+                        Expression::ObjectExpression(object) => {
+                            for property in &object.properties {
+                                match property {
+                                    ObjectPropertyKind::ObjectProperty(property) => {
+                                        match property.key.static_name() {
+                                            Some(name) => {
+                                                exports.names.insert(name.to_string());
+                                            }
+                                            None => exports.dynamic = true,
+                                        }
+                                    }
+                                    ObjectPropertyKind::SpreadProperty(_) => exports.dynamic = true,
+                                }
+                            }
+                        }
+                        Expression::CallExpression(call) => {
+                            if let Some(source) = require_specifier(call) {
+                                exports.reexports.push(source.to_string());
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if is_exports_object(member.object()) {
+                    if let Some(name) = member.static_property_name() {
+                        recognized += 1;
+                        exports.names.insert(name.to_string());
+                    }
+                }
+            }
+            Expression::CallExpression(call)
+                if call
+                    .callee
+                    .is_specific_member_access("Object", "defineProperty") =>
+            {
+                let (Some(target), Some(Argument::StringLiteral(name))) = (
+                    call.arguments.first().and_then(|a| a.as_expression()),
+                    call.arguments.get(1),
+                ) else {
+                    continue;
+                };
+                if is_exports_object(target) {
+                    recognized += 1;
+                    exports.names.insert(name.value.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    exports.names.remove("__esModule");
+    exports.dynamic |= recognized != references;
+    exports
+}
+
+/// Specifier of a `require('...')` call with a string literal argument
+fn require_specifier<'a>(call: &CallExpression<'a>) -> Option<&'a str> {
+    if !call.callee.is_specific_id("require") || call.arguments.len() != 1 {
+        return None;
+    }
+    match &call.arguments[0] {
+        Argument::StringLiteral(literal) => Some(literal.value.as_str()),
+        _ => None,
+    }
+}
+
+/// Whether a module only exports React components that it registered with
+/// Fast Refresh, so that an update can re-render them in place
+fn is_refresh_boundary(record: &ModuleRecord) -> bool {
+    let is_component = |name: &str| name.starts_with(|c: char| c.is_ascii_uppercase());
+    record.globals.contains("$RefreshReg$")
+        && record.star_exports.is_empty()
+        && !record.exports.is_empty()
+        && record
+            .exports
+            .iter()
+            .all(|(exported, binding)| match binding {
+                ExportBinding::Local(local) if exported == "default" => is_component(local),
+                ExportBinding::Local(_) => is_component(exported),
+                _ => false,
+            })
+}
+
+/// `import.meta.hot`
+fn is_import_meta_hot(member: &StaticMemberExpression) -> bool {
+    member.property.name == "hot"
+        && matches!(&member.object, Expression::MetaProperty(meta)
+            if meta.meta.name == "import" && meta.property.name == "meta")
+}
+
+/// Declare the binding that holds a module's anonymous default export
+fn default_symbol(record: &mut ModuleRecord, default_name: &str) -> usize {
+    record.symbols.push(default_name.to_string());
+    record.exports.push((
+        "default".to_string(),
+        ExportBinding::Symbol(record.symbols.len() - 1),
+    ));
+    record.symbols.len() - 1
+}
+
+/// Record imports/exports and the statement-level edits that strip module syntax
+fn collect_module_syntax(
+    program: &Program,
+    code: &str,
+    default_name: &str,
+    record: &mut ModuleRecord,
+) {
+    for statement in &program.body {
+        match statement {
+            Statement::ImportDeclaration(decl) => {
+                record.has_module_syntax = true;
+                record.edits.push(Edit::remove_statement(code, decl.span));
+                if decl.import_kind.is_type() {
+                    continue;
+                }
+                let source = decl.source.value.to_string();
+                record.sources.push(source.clone());
+                for specifier in decl.specifiers.iter().flatten() {
+                    let (local, imported) = match specifier {
+                        ImportDeclarationSpecifier::ImportSpecifier(s) => {
+                            if s.import_kind.is_type() {
+                                continue;
+                            }
+                            (&s.local, Imported::Named(s.imported.name().to_string()))
+                        }
+                        ImportDeclarationSpecifier::ImportDefaultSpecifier(s) => {
+                            (&s.local, Imported::Named("default".to_string()))
+                        }
+                        ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) => {
+                            (&s.local, Imported::Namespace)
+                        }
+                    };
+                    record.imports.push(ImportBinding {
+                        local: local.name.to_string(),
+                        source: source.clone(),
+                        imported,
+                        used: false,
+                    });
+                }
+            }
+            Statement::ExportNamedDeclaration(decl) => {
+                record.has_module_syntax = true;
+                if decl.export_kind.is_type() {
+                    record.edits.push(Edit::remove_statement(code, decl.span));
+                    continue;
+                }
+                if let Some(declaration) = &decl.declaration {
+                    let type_only = declaration.is_type()
+                        || declaration.declare()
+                        || matches!(declaration, Declaration::FunctionDeclaration(f) if f.body.is_none());
+                    if type_only {
+                        record.edits.push(Edit::remove_statement(code, decl.span));
+                        continue;
+                    }
+                    if let Some(range) =
+                        export_keyword_range(code, decl.span, declaration.span(), false)
+                    {
+                        record.edits.push(Edit::remove(range));
+                    }
+                    let names: Vec<String> = match declaration {
+                        Declaration::VariableDeclaration(var) => var
+                            .declarations
+                            .iter()
+                            .flat_map(|d| d.id.get_binding_identifiers())
+                            .map(|id| id.name.to_string())
+                            .collect(),
+                        _ => declaration
+                            .id()
+                            .map(|id| vec![id.name.to_string()])
+                            .unwrap_or_default(),
+                    };
+                    for name in names {
+                        record
+                            .exports
+                            .push((name.clone(), ExportBinding::Local(name)));
+                    }
+                    continue;
+                }
+
+                record.edits.push(Edit::remove_statement(code, decl.span));
+                let source = decl.source.as_ref().map(|s| s.value.to_string());
+                if let Some(source) = &source {
+                    record.sources.push(source.clone());
+                }
+                for specifier in &decl.specifiers {
+                    if specifier.export_kind.is_type() {
+                        continue;
+                    }
+                    let exported = specifier.exported.name().to_string();
+                    let local = specifier.local.name().to_string();
+                    let binding = match &source {
+                        Some(source) => ExportBinding::ReExport {
+                            source: source.clone(),
+                            imported: Imported::Named(local),
+                        },
+                        None => ExportBinding::Local(local),
+                    };
+                    record.exports.push((exported, binding));
+                }
+            }
+            Statement::ExportDefaultDeclaration(decl) => {
+                record.has_module_syntax = true;
+                collect_default_export(decl, code, default_name, record);
+            }
+            Statement::ExportAllDeclaration(decl) => {
+                record.has_module_syntax = true;
+                record.edits.push(Edit::remove_statement(code, decl.span));
+                if decl.export_kind.is_type() {
+                    continue;
+                }
+                let source = decl.source.value.to_string();
+                record.sources.push(source.clone());
+                match &decl.exported {
+                    Some(exported) => record.exports.push((
+                        exported.name().to_string(),
+                        ExportBinding::ReExport {
+                            source,
+                            imported: Imported::Namespace,
+                        },
+                    )),
+                    None => record.star_exports.push(source),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Strip `export default` and bind the default export to a named symbol
+fn collect_default_export(
+    decl: &ExportDefaultDeclaration,
+    code: &str,
+    default_name: &str,
+    record: &mut ModuleRecord,
+) {
+    match &decl.declaration {
+        ExportDefaultDeclarationKind::FunctionDeclaration(func) => {
+            if let Some(range) = export_keyword_range(code, decl.span, func.span, true) {
+                record.edits.push(Edit::remove(range));
+            }
+            if let Some(id) = &func.id {
+                record.exports.push((
+                    "default".to_string(),
+                    ExportBinding::Local(id.name.to_string()),
+                ));
+            } else {
+                let at = func
+                    .type_parameters
+                    .as_ref()
+                    .map(|t| t.span.start)
+                    .unwrap_or(func.params.span.start);
+                push_default_name(record, code, default_name, at);
+            }
+        }
+        ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+            if let Some(range) = export_keyword_range(code, decl.span, class.span, true) {
+                record.edits.push(Edit::remove(range));
+            }
+            if let Some(id) = &class.id {
+                record.exports.push((
+                    "default".to_string(),
+                    ExportBinding::Local(id.name.to_string()),
+                ));
+            } else {
+                let search_from = class
+                    .decorators
+                    .last()
+                    .map(|d| d.span.end)
+                    .unwrap_or(class.span.start)
+                    .max(decl.span.start);
+                let Some(keyword) = code[search_from as usize..].find("class") else {
+                    return;
+                };
+                let at = search_from + keyword as u32 + "class".len() as u32;
+                push_default_name(record, code, default_name, at);
+            }
+        }
+        ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => {
+            record.edits.push(Edit::remove_statement(code, decl.span));
+        }
+        kind => {
+            let Some(expression) = kind.as_expression() else {
+                return;
+            };
+            let symbol = default_symbol(record, default_name);
+            record.edits.push(Edit {
+                start: decl.span.start,
+                end: expression.span().start,
+                text: EditText::Symbol {
+                    before: "var ".to_string(),
+                    symbol,
+                    after: " = ".to_string(),
+                },
+            });
+        }
+    }
+}
+
+/// Name an anonymous `export default function/class` by inserting an identifier
+fn push_default_name(record: &mut ModuleRecord, code: &str, default_name: &str, at: u32) {
+    let symbol = default_symbol(record, default_name);
+    let needs_space = !code[..at as usize].ends_with(char::is_whitespace);
+    record.edits.push(Edit {
+        start: at,
+        end: at,
+        text: EditText::Symbol {
+            before: if needs_space { " " } else { "" }.to_string(),
+            symbol,
+            after: String::new(),
+        },
+    });
+}
+
+/// Span of the `export` (and `default`) keywords in front of a declaration
+fn export_keyword_range(
+    code: &str,
+    statement: Span,
+    declaration: Span,
+    is_default: bool,
+) -> Option<Span> {
+    if declaration.start > statement.start {
+        return Some(Span::new(statement.start, declaration.start));
+    }
+
+    // Decorators may precede the `export` keyword: `@dec export class Foo {}`
+    let text = &code[statement.start as usize..statement.end as usize];
+    let mut start = text.find("export")?;
+    let mut end = start + "export".len();
+    if is_default {
+        let rest = &text[end..];
+        end += rest.find("default")? + "default".len();
+    }
+    end += text[end..].len() - text[end..].trim_start().len();
+    start += statement.start as usize;
+    end += statement.start as usize;
+    Some(Span::new(start as u32, end as u32))
+}
+
+/// Apply edits to a module's code, skipping edits nested inside removed ranges
+///
+/// The module's source map, if any, is carried over to the edited code.
+fn apply_edits(
+    record: &ModuleRecord,
+    names: &[String],
+    imports: &[Option<String>],
+    requires: &[Option<String>],
+    dynamic_imports: &[Option<String>],
+) -> (String, Option<CodeMap>) {
+    let mut edits: Vec<(u32, u32, String)> = record
+        .edits
+        .iter()
+        .filter_map(|edit| {
+            let text = match &edit.text {
+                EditText::Text(text) => text.clone(),
+                EditText::Symbol {
+                    before,
+                    symbol,
+                    after,
+                } => {
+                    let name = &names[*symbol];
+                    let original = &record.symbols[*symbol];
+                    let is_noop = edit.start != edit.end
+                        && before.is_empty()
+                        && after.is_empty()
+                        && name == original;
+                    if is_noop || (!before.is_empty() && before == &format!("{}: ", name)) {
+                        return None;
+                    }
+                    format!("{}{}{}", before, name, after)
+                }
+                EditText::Import {
+                    before,
+                    import,
+                    callee,
+                } => {
+                    let expr = imports[*import].as_ref()?;
+                    if expr == &record.imports[*import].local {
+                        return None;
+                    }
+                    if *callee && expr.contains(['.', '[']) {
+                        format!("{}(0, {})", before, expr)
+                    } else {
+                        format!("{}{}", before, expr)
+                    }
+                }
+                EditText::Require(require) => requires[*require].clone()?,
+                EditText::DynamicImport(import) => dynamic_imports[*import].clone()?,
+            };
+            Some((edit.start, edit.end, text))
+        })
+        .collect();
+    edits.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let code = &record.code;
+    let mut output = String::with_capacity(code.len());
+    let mut applied = Vec::with_capacity(edits.len());
+    let mut cursor = 0u32;
+    for (start, end, text) in edits {
+        if start < cursor {
+            continue;
+        }
+        output.push_str(&code[cursor as usize..start as usize]);
+        output.push_str(&text);
+        applied.push((start as usize, end as usize, text));
+        cursor = end;
+    }
+    output.push_str(&code[cursor as usize..]);
+    let map = record
+        .map
+        .as_ref()
+        .map(|map| map.apply_edits(code, &applied));
+    (output, map)
+}
+
+/// Pick a name not yet used in the bundle scope: `name`, `name$1`, `name$2`, ...
+fn unique_name(base: &str, taken: &mut HashSet<String>, avoid: &HashSet<&str>) -> String {
+    let is_free =
+        |name: &str, taken: &HashSet<String>| !taken.contains(name) && !avoid.contains(name);
+    let name = if is_free(base, taken) {
+        base.to_string()
+    } else {
+        (1..)
+            .map(|n| format!("{}${}", base, n))
+            .find(|candidate| is_free(candidate, taken))
+            .unwrap_or_default()
+    };
+    taken.insert(name.clone());
+    name
+}
+
+/// Identifier-safe version of a module's file stem (`my-utils.js` -> `my_utils`)
+fn identifier_stem(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("module");
+    let mut ident: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Object literal key for an export name (quoted unless it is a plain identifier)
+fn property_key(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        json_string(name)
+    }
+}
+
+/// Property access for an export name: `.name` or `["name"]`
+fn member_access(name: &str) -> String {
+    if is_identifier(name) {
+        format!(".{}", name)
+    } else {
+        format!("[{}]", json_string(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::ModuleType;
+
+    fn module(path: &str, content: &str, imports: &[(&str, &str)]) -> ModuleInfo {
+        ModuleInfo {
+            path: PathBuf::from(path),
+            content: content.to_string(),
+            module_type: ModuleType::from_extension(
+                Path::new(path)
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("js"),
+            ),
+            dependencies: imports.iter().map(|(s, _)| s.to_string()).collect(),
+            exports: Vec::new(),
+            resolved_imports: imports
+                .iter()
+                .map(|(s, p)| (s.to_string(), PathBuf::from(p)))
+                .collect(),
+        }
+    }
+
+    fn link(modules: &[ModuleInfo]) -> LinkOutput {
+        let inputs: Vec<(&ModuleInfo, String)> =
+            modules.iter().map(|m| (m, m.content.clone())).collect();
+        ModuleLinker::new().link(&inputs)
+    }
+
+    #[test]
+    fn test_hoists_imports_in_dependency_order() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { greet as hello } from './utils.js';\nconsole.log(hello('world'));\n",
+                &[("./utils.js", "/app/utils.js")],
+            ),
+            module(
+                "/app/utils.js",
+                "export function greet(name) {\n  return `Hello, ${name}`;\n}\n",
+                &[],
+            ),
+        ]);
+
+        assert!(output.warnings.is_empty());
+        assert_eq!(output.modules[0].path, PathBuf::from("/app/utils.js"));
+        assert!(output.modules[0].code.starts_with("function greet(name)"));
+        assert!(output.modules[1]
+            .code
+            .contains("console.log(greet('world'))"));
+        assert!(!output.modules[1].code.contains("import"));
+    }
+
+    #[test]
+    fn test_renames_colliding_top_level_symbols() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { count } from './a.js';\nconst value = 1;\nconst obj = { value, count };\n",
+                &[("./a.js", "/app/a.js")],
+            ),
+            module(
+                "/app/a.js",
+                "const value = 2;\nexport const count = value + 1;\n",
+                &[],
+            ),
+        ]);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("const value = 2;"));
+        assert!(code.contains("const count = value + 1;"));
+        assert!(code.contains("const value$1 = 1;"));
+        assert!(code.contains("{ value: value$1, count }"));
+    }
+
+    #[test]
+    fn test_default_and_namespace_imports() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import Button, * as lib from './lib.js';\nButton();\nlib.helper();\n",
+                &[("./lib.js", "/app/lib.js")],
+            ),
+            module(
+                "/app/lib.js",
+                "export default function () {}\nexport const helper = () => 1;\n",
+                &[],
+            ),
+        ]);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("var lib_exports = Object.freeze({"));
+        assert!(code.contains("get default() { return lib_default; }"));
+        assert!(code.contains("get helper() { return helper; }"));
+        assert!(code.contains("function lib_default()"));
+        assert!(code.contains("lib_default();"));
+        assert!(code.contains("lib_exports.helper();"));
+    }
+
+    #[test]
+    fn test_re_exports_and_multiline_imports() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import {\n  add,\n  total as sum,\n} from './index.js';\nadd(sum);\n",
+                &[("./index.js", "/app/index.js")],
+            ),
+            module(
+                "/app/index.js",
+                "export * from './math.js';\nexport { value as total } from './math.js';\n",
+                &[("./math.js", "/app/math.js")],
+            ),
+            module(
+                "/app/math.js",
+                "export const value = 42;\nexport const add = (n) => n + 1;\nexport default 'math';\n",
+                &[],
+            ),
+        ]);
+
+        let code = output.render(|_| String::new());
+        assert!(output.warnings.is_empty());
+        assert!(code.contains("var math_default = 'math';"));
+        assert!(code.contains("add(value);"));
+        assert!(!code.contains("export"));
+    }
+
+    #[test]
+    fn test_hoisted_names_avoid_locals_of_importers_through_barrels() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { format } from './index.js';\nfunction f(format) {\n  return format(format);\n}\nf(1);\n",
+                &[("./index.js", "/app/index.js")],
+            ),
+            module(
+                "/app/index.js",
+                "export { format } from './fmt.js';
+",
+                &[("./fmt.js", "/app/fmt.js")],
+            ),
+            module(
+                "/app/fmt.js",
+                "export function format(value) {\n  return String(value);\n}\n",
+                &[],
+            ),
+        ]);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("function format$1(value)"));
+        assert!(code.contains("return format(format);"));
+        assert!(!code.contains("function format(value)"));
+    }
+
+    #[test]
+    fn test_missing_export_warns() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { nope } from './a.js';\nnope();\n",
+                &[("./a.js", "/app/a.js")],
+            ),
+            module("/app/a.js", "export const yes = 1;\n", &[]),
+        ]);
+
+        assert_eq!(output.warnings.len(), 1);
+        assert!(output.modules[1].code.contains("(void 0)();"));
+    }
+
+    #[test]
+    fn test_wraps_commonjs_modules_in_registry() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import legacy, { version } from 'legacy';\nconsole.log(legacy(2), version);\n",
+                &[("legacy", "/app/node_modules/legacy/index.js")],
+            ),
+            module(
+                "/app/node_modules/legacy/index.js",
+                "var helper = require('./helper');\nmodule.exports = function (x) { return helper(x); };\nmodule.exports.version = '1.0';\n",
+                &[("./helper", "/app/node_modules/legacy/helper.js")],
+            ),
+            module(
+                "/app/node_modules/legacy/helper.js",
+                "module.exports = function (x) { return x * 2; };\n",
+                &[],
+            ),
+        ]);
+
+        let linking = output.linking();
+        assert_eq!(
+            linking[0].strategy,
+            LinkStrategy::Wrapped(WrapReason::CommonJs)
+        );
+        assert_eq!(
+            linking[1].strategy,
+            LinkStrategy::Wrapped(WrapReason::CommonJs)
+        );
+        assert_eq!(linking[2].strategy, LinkStrategy::Hoisted);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("function __soku_require(id)"));
+        assert!(code.contains("}, {\"./helper\": \"node_modules/legacy/helper.js\"}];"));
+        assert!(code.contains(
+            "var index_exports = __soku_to_esm(__soku_require(\"node_modules/legacy/index.js\"));"
+        ));
+        assert!(code.contains("console.log((0, index_exports.default)(2), index_exports.version);"));
+    }
+
+    #[test]
+    fn test_wraps_cycles_and_direct_eval() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { a } from './a.js';\nimport { run } from './ev.js';\na(run());\n",
+                &[("./a.js", "/app/a.js"), ("./ev.js", "/app/ev.js")],
+            ),
+            module(
+                "/app/a.js",
+                "import { b } from './b.js';\nexport function a(x) { return b(x); }\n",
+                &[("./b.js", "/app/b.js")],
+            ),
+            module(
+                "/app/b.js",
+                "import { a } from './a.js';\nexport function b(x) { return x || a; }\n",
+                &[("./a.js", "/app/a.js")],
+            ),
+            module(
+                "/app/ev.js",
+                "const secret = 1;\nexport function run() { return eval('secret'); }\n",
+                &[],
+            ),
+        ]);
+
+        let strategies: HashMap<PathBuf, LinkStrategy> = output
+            .linking()
+            .into_iter()
+            .map(|l| (l.path, l.strategy))
+            .collect();
+        let cycle = LinkStrategy::Wrapped(WrapReason::Cycle);
+        assert_eq!(strategies[Path::new("/app/a.js")], cycle);
+        assert_eq!(strategies[Path::new("/app/b.js")], cycle);
+        assert_eq!(
+            strategies[Path::new("/app/ev.js")],
+            LinkStrategy::Wrapped(WrapReason::DirectEval)
+        );
+        assert_eq!(strategies[Path::new("/app/main.js")], LinkStrategy::Hoisted);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("const secret = 1;"));
+        assert!(code.contains("(0, a_exports.a)((0, ev_exports.run)());"));
+        assert!(code.contains("run: function () { return run; }"));
+    }
+
+    #[test]
+    fn test_enters_cycles_through_first_imported_module() {
+        // main imports a before c, so ESM evaluates b (a's dependency) first
+        // even though c imports b directly
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { a } from './a.js';\nimport { c } from './c.js';\nconsole.log(a, c);\n",
+                &[("./a.js", "/app/a.js"), ("./c.js", "/app/c.js")],
+            ),
+            module(
+                "/app/a.js",
+                "import { b } from './b.js';\nexport const a = b + 1;\n",
+                &[("./b.js", "/app/b.js")],
+            ),
+            module(
+                "/app/b.js",
+                "import { a } from './a.js';\nexport const b = 1;\nexport function f() { return a; }\n",
+                &[("./a.js", "/app/a.js")],
+            ),
+            module(
+                "/app/c.js",
+                "import './b.js';\nexport const c = 3;\n",
+                &[("./b.js", "/app/b.js")],
+            ),
+        ]);
+
+        let code = output.render(|_| String::new());
+        let enter_a = code
+            .find("var a_exports = __soku_require(\"a.js\");")
+            .unwrap();
+        let enter_b = code.find("\n__soku_require(\"b.js\");").unwrap();
+        assert!(enter_a < enter_b, "{}", code);
+        assert!(code.find("const c = 3;").unwrap() > enter_a);
+    }
+
+    #[test]
+    fn test_detects_commonjs_exports_statically() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { add, sub, nope } from './math.js';\nimport { value } from './factory.js';\nadd(sub, nope, value);\n",
+                &[("./math.js", "/app/math.js"), ("./factory.js", "/app/factory.js")],
+            ),
+            module(
+                "/app/math.js",
+                "Object.defineProperty(exports, '__esModule', { value: true });\nexports.add = function (a, b) { return a + b; };\nmodule.exports.sub = function (a, b) { return a - b; };\n",
+                &[],
+            ),
+            module(
+                "/app/factory.js",
+                "(function (root, factory) { module.exports = factory(); })(this, function () { return {}; });\n",
+                &[],
+            ),
+        ]);
+
+        // `nope` is missing from a module whose exports are fully known; the
+        // factory module is opaque, so `value` is trusted
+        assert_eq!(output.warnings.len(), 1);
+        assert!(output.warnings[0].contains("'nope'"));
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains(
+            "(0, math_exports.add)(math_exports.sub, math_exports.nope, factory_exports.value);"
+        ));
+    }
+
+    #[test]
+    fn test_export_star_forwards_commonjs_names() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import { helper } from './index.js';\nhelper();\n",
+                &[("./index.js", "/app/index.js")],
+            ),
+            module(
+                "/app/index.js",
+                "export * from './lib.js';\n",
+                &[("./lib.js", "/app/lib.js")],
+            ),
+            module(
+                "/app/lib.js",
+                "module.exports = { helper: function () {}, other: 1 };\n",
+                &[],
+            ),
+        ]);
+
+        assert!(output.warnings.is_empty());
+        let code = output.render(|_| String::new());
+        assert!(code.contains("(0, lib_exports.helper)();"));
+    }
+
+    #[test]
+    fn test_require_of_es_module_from_es_module() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import './side.js';\nconst config = require('./config.js');\nconsole.log(config.default);\n",
+                &[("./side.js", "/app/side.js"), ("./config.js", "/app/config.js")],
+            ),
+            module("/app/side.js", "export const side = 1;\n", &[]),
+            module(
+                "/app/config.js",
+                "export default { debug: true };\n",
+                &[],
+            ),
+        ]);
+
+        let strategies: HashMap<PathBuf, LinkStrategy> = output
+            .linking()
+            .into_iter()
+            .map(|l| (l.path, l.strategy))
+            .collect();
+        assert_eq!(
+            strategies[Path::new("/app/config.js")],
+            LinkStrategy::Wrapped(WrapReason::Required)
+        );
+        assert_eq!(strategies[Path::new("/app/side.js")], LinkStrategy::Hoisted);
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains("const config = __soku_require(\"config.js\");"));
+        assert!(code.contains("default: function () { return config_default; }"));
+        // Required modules load lazily, at the `require()` call
+        assert!(!code.contains("\n__soku_require(\"config.js\");"));
+    }
+
+    #[test]
+    fn test_dynamic_import_of_bundled_module() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import('./lazy.js').then((lazy) => console.log(lazy.value));\nimport('./missing.js');\n",
+                &[("./lazy.js", "/app/lazy.js")],
+            ),
+            module(
+                "/app/lazy.js",
+                "export const value = 1;\nconsole.log('lazy');\n",
+                &[],
+            ),
+        ]);
+
+        let strategies: HashMap<PathBuf, LinkStrategy> = output
+            .linking()
+            .into_iter()
+            .map(|l| (l.path, l.strategy))
+            .collect();
+        assert_eq!(
+            strategies[Path::new("/app/lazy.js")],
+            LinkStrategy::Wrapped(WrapReason::DynamicImport)
+        );
+        // Not an entry, so its exports are not the bundle's
+        assert!(output.exports.is_empty());
+
+        let code = output.render(|_| String::new());
+        assert!(code.contains(
+            "Promise.resolve().then(function () { return __soku_require(\"lazy.js\"); }).then("
+        ));
+        assert!(code.contains("import('./missing.js');"));
+        assert!(!code.contains("\n__soku_require(\"lazy.js\");"));
+    }
+
+    #[test]
+    fn test_split_chunks_share_the_registry() {
+        let modules = [
+            module(
+                "/app/main.js",
+                "import { helper } from './utils.js';\nexport const value = helper();\n",
+                &[("./utils.js", "/app/utils.js")],
+            ),
+            module(
+                "/app/utils.js",
+                "export function helper() { return 1; }\n",
+                &[],
+            ),
+        ];
+        let inputs: Vec<(&ModuleInfo, String)> =
+            modules.iter().map(|m| (m, m.content.clone())).collect();
+        let output = ModuleLinker::new().with_shared_registry(true).link(&inputs);
+        assert!(output
+            .linking()
+            .iter()
+            .all(|m| m.strategy == LinkStrategy::Wrapped(WrapReason::Chunked)));
+
+        let chunks = output.split(&[
+            vec![PathBuf::from("/app/utils.js")],
+            vec![PathBuf::from("/app/main.js")],
+        ]);
+        let utils = chunks[0].render(|_| String::new());
+        assert!(utils.contains("__soku_global.__soku_registry__"));
+        assert!(utils.contains("__soku_modules[\"utils.js\"] = "));
+        assert!(!utils.contains("__soku_modules[\"main.js\"]"));
+        assert!(!utils.contains("\n__soku_require("));
+        assert!(chunks[0].exports.is_empty());
+
+        // The entry chunk requires utils.js from the registry when it runs
+        let main = chunks[1].render(|_| String::new());
+        assert!(!main.contains("__soku_modules[\"utils.js\"]"));
+        assert!(main.contains("var utils_exports = __soku_require(\"utils.js\");"));
+        assert!(main.contains("var main_exports = __soku_require(\"main.js\");"));
+        assert_eq!(
+            chunks[1].exports,
+            vec![("value".to_string(), "main_exports.value".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_collects_entry_exports_and_externals() {
+        let output = link(&[
+            module(
+                "/app/main.js",
+                "import React, { useState } from 'react';\nimport { helper } from './utils.js';\nexport { helper as help };\nexport default function App() { return useState(React); }\n",
+                &[("./utils.js", "/app/utils.js")],
+            ),
+            module("/app/utils.js", "export function helper() {}\n", &[]),
+        ]);
+
+        assert!(output.warnings.is_empty());
+        assert_eq!(
+            output.exports,
+            vec![
+                ("default".to_string(), "App".to_string()),
+                ("help".to_string(), "helper".to_string()),
+            ]
+        );
+        assert_eq!(output.externals.len(), 1);
+        assert_eq!(output.externals[0].source, "react");
+        assert_eq!(
+            output.externals[0].bindings,
+            vec![
+                (Some("default".to_string()), "React".to_string()),
+                (Some("useState".to_string()), "useState".to_string()),
+            ]
+        );
+        assert!(output.commonjs_entry.is_none());
+    }
+
+    #[test]
+    fn test_resolved_stylesheets_are_not_external() {
+        let output = link(&[module(
+            "/app/main.js",
+            "import './style.css';\nimport classes from './theme.css';\nimport 'polyfill';\nconsole.log(classes);\n",
+            &[
+                ("./style.css", "/app/style.css"),
+                ("./theme.css", "/app/theme.css"),
+            ],
+        )]);
+
+        assert!(output.warnings.is_empty());
+        assert_eq!(output.externals.len(), 1);
+        assert_eq!(output.externals[0].source, "polyfill");
+        let code = output.render(|_| String::new());
+        assert!(code.contains("console.log((void 0));"), "{}", code);
+    }
+
+    #[test]
+    fn test_links_every_module_for_hmr() {
+        let modules = [
+            module(
+                "/app/main.js",
+                "import { greet } from './lib/greet.js';\nconsole.log(greet('world'));\n",
+                &[("./lib/greet.js", "/app/lib/greet.js")],
+            ),
+            module(
+                "/app/lib/greet.js",
+                "export const greet = (name) => `Hello, ${name}`;\n",
+                &[],
+            ),
+        ];
+        let inputs: Vec<(&ModuleInfo, String)> =
+            modules.iter().map(|m| (m, m.content.clone())).collect();
+        let output = ModuleLinker::new().with_hmr(true).link(&inputs);
+
+        assert!(output.prelude.contains("__soku_global.__soku_runtime__"));
+        let linking = output.linking();
+        assert!(linking
+            .iter()
+            .all(|m| m.strategy == LinkStrategy::Wrapped(WrapReason::Hmr)));
+        assert_eq!(linking[0].id, "lib/greet.js");
+        assert!(linking[0].definition.as_deref().unwrap().starts_with(
+            "__soku_modules[\"lib/greet.js\"] = [function (module, exports, require) {"
+        ));
+        assert_eq!(linking[1].id, "main.js");
+        assert_eq!(linking[1].dependencies, vec!["lib/greet.js".to_string()]);
+        // An entry without exports does not define any
+        assert!(!linking[1]
+            .definition
+            .as_deref()
+            .unwrap()
+            .contains("__soku_export"));
+        // The entry runs the graph through the registry
+        assert_eq!(output.modules[1].code, "__soku_require(\"main.js\");");
+
+        // Without HMR only the registration of modules linked for HMR is kept
+        let linking = link(&modules).linking();
+        assert!(linking.iter().all(|m| m.definition.is_none()));
+        assert_eq!(linking[1].dependencies, vec!["lib/greet.js".to_string()]);
+    }
+
+    #[test]
+    fn test_compiles_import_meta_hot() {
+        let modules = [
+            module(
+                "/app/main.js",
+                "import { render } from './view.js';\nimport './log.js';\nrender();\nif (import.meta.hot) {\n  import.meta.hot.accept('./view.js', (view) => view.render());\n}\n",
+                &[("./view.js", "/app/view.js"), ("./log.js", "/app/log.js")],
+            ),
+            module(
+                "/app/view.js",
+                "export function render() {}\nimport.meta.hot.accept();\nimport.meta.hot.dispose((data) => { data.count = 1; });\n",
+                &[],
+            ),
+            module(
+                "/app/log.js",
+                "import.meta.hot?.accept(['./main.js']);\n",
+                &[("./main.js", "/app/main.js")],
+            ),
+        ];
+        let inputs: Vec<(&ModuleInfo, String)> =
+            modules.iter().map(|m| (m, m.content.clone())).collect();
+        let output = ModuleLinker::new().with_hmr(true).link(&inputs);
+
+        let linking = output.linking();
+        let module = |id: &str| linking.iter().find(|m| m.id == id).unwrap();
+        assert_eq!(module("main.js").hot_accepts, vec!["view.js".to_string()]);
+        assert_eq!(module("view.js").hot_accepts, vec!["view.js".to_string()]);
+        assert_eq!(module("log.js").hot_accepts, vec!["main.js".to_string()]);
+        let definition = module("view.js").definition.clone().unwrap();
+        assert!(definition.contains("module.hot.accept();"));
+        assert!(definition.contains("module.hot.dispose("));
+        assert!(output
+            .prelude
+            .contains("hot: __soku_hot(id, definition[1])"));
+
+        // Outside of HMR there is no hot API
+        let code = link(&modules).render(|_| String::new());
+        assert!(code.contains("if ((void 0)) {"));
+        assert!(code.contains("(void 0).accept();"));
+        assert!(!code.contains("import.meta"));
+    }
+
+    #[test]
+    fn test_component_modules_accept_fast_refresh() {
+        // Modules as the transformer compiles them with Fast Refresh
+        let modules = [
+            module(
+                "/app/main.js",
+                "import App from './App.js';\nimport { useCount } from './hooks.js';\nApp(useCount);\n",
+                &[("./App.js", "/app/App.js"), ("./hooks.js", "/app/hooks.js")],
+            ),
+            module(
+                "/app/App.js",
+                "var _s = $RefreshSig$();\nexport default function App() {\n  _s();\n}\n_s(App, \"key\");\n_c = App;\nexport const Button = () => null;\n_c2 = Button;\nvar _c, _c2;\n$RefreshReg$(_c, \"App\");\n$RefreshReg$(_c2, \"Button\");\n",
+                &[],
+            ),
+            module(
+                "/app/hooks.js",
+                "export const Label = () => null;\n_c = Label;\nexport function useCount() {}\nvar _c;\n$RefreshReg$(_c, \"Label\");\n",
+                &[],
+            ),
+        ];
+        let inputs: Vec<(&ModuleInfo, String)> =
+            modules.iter().map(|m| (m, m.content.clone())).collect();
+        let output = ModuleLinker::new().with_hmr(true).link(&inputs);
+
+        let linking = output.linking();
+        let module = |id: &str| linking.iter().find(|m| m.id == id).unwrap();
+        assert_eq!(module("App.js").hot_accepts, vec!["App.js".to_string()]);
+        // A hook export is not a component, so updates go to the importers
+        assert!(module("hooks.js").hot_accepts.is_empty());
+        let definition = module("App.js").definition.clone().unwrap();
+        assert!(definition.contains(
+            "var $RefreshReg$ = __soku_refresh_reg(module.id), $RefreshSig$ = __soku_refresh_sig;"
+        ));
+        assert!(definition.ends_with("__soku_refresh_accept(module);\n}, {}];"));
+        assert!(!module("hooks.js")
+            .definition
+            .clone()
+            .unwrap()
+            .contains("__soku_refresh_accept"));
+        assert!(output
+            .prelude
+            .contains("function __soku_refresh_accept(module)"));
+    }
+}
@@ -0,0 +1,582 @@
+// Output Formats - ES module, CommonJS, IIFE and UMD wrappers for linked bundles
+// Turns the single scope produced by the module linker, with the exports of
+// the entry modules and the imports it left external, into the chosen format
+
+use super::module_linker::{ExternalImport, LinkOutput};
+use super::transform_helpers::with_transform_helpers;
+use crate::core::models::{BuildConfig, BundleOutput, OutputFormat};
+use crate::utils::{CodeMap, Logger, MappedCode, Result, SokuError};
+use std::path::{Path, PathBuf};
+
+/// Wrap linked bundle code in the output format selected by `config`.
+///
+/// `banner` is emitted first, `body` is the rendered module code.
+pub fn format_bundle(
+    linked: &LinkOutput,
+    banner: &str,
+    body: &str,
+    config: &BuildConfig,
+) -> Result<String> {
+    Ok(wrap_bundle(linked, banner, body, None, config)?.code)
+}
+
+/// `format_bundle` for a body with a source map, returning the map of the bundle
+pub fn format_bundle_with_map(
+    linked: &LinkOutput,
+    banner: &str,
+    body: &str,
+    body_map: &CodeMap,
+    config: &BuildConfig,
+) -> Result<(String, CodeMap)> {
+    let bundle = wrap_bundle(linked, banner, body, Some(body_map), config)?;
+    Ok((bundle.code, bundle.map.unwrap_or_default()))
+}
+
+/// Format the chunks of a build linked `with_shared_registry`, one bundle per
+/// chunk in `chunks`, with source maps when they are enabled in `config`.
+///
+/// Only the chunk with the entry modules is assigned to `globalName`; the other
+/// chunks just register their modules.
+pub fn format_chunks(
+    linked: &LinkOutput,
+    chunks: &[Vec<PathBuf>],
+    banner: &str,
+    header: impl Fn(&Path) -> String,
+    config: &BuildConfig,
+) -> Result<Vec<BundleOutput>> {
+    let module_config = BuildConfig {
+        global_name: None,
+        ..config.clone()
+    };
+    linked
+        .split(chunks)
+        .iter()
+        .map(|chunk| {
+            let config = if chunk.entries.is_empty() {
+                &module_config
+            } else {
+                config
+            };
+            if !config.enable_source_maps {
+                let body = chunk.render(&header);
+                return Ok(BundleOutput {
+                    code: format_bundle(chunk, banner, &body, config)?,
+                    source_map: None,
+                });
+            }
+            let (body, body_map) = chunk.render_with_map(&header);
+            let (code, source_map) =
+                format_bundle_with_map(chunk, banner, &body, &body_map, config)?;
+            Ok(BundleOutput {
+                code,
+                source_map: Some(source_map),
+            })
+        })
+        .collect()
+}
+
+fn wrap_bundle(
+    linked: &LinkOutput,
+    banner: &str,
+    body: &str,
+    body_map: Option<&CodeMap>,
+    config: &BuildConfig,
+) -> Result<MappedCode> {
+    let global_name = config.global_name.as_deref();
+    if let Some(name) = global_name {
+        if !name.split('.').all(is_identifier) {
+            return Err(SokuError::config(format!(
+                "Invalid globalName '{}': expected an identifier such as 'MyLib' or 'MyOrg.lib'",
+                name
+            )));
+        }
+    }
+
+    let mut bundle = MappedCode::new(body_map.is_some());
+    bundle.push_str(banner);
+    // Helpers of lowered syntax are defined once for all modules, before the body
+    let with_helpers = with_transform_helpers(body)?;
+    let helpers = &with_helpers[..with_helpers.len() - body.len()];
+    let push_body = |bundle: &mut MappedCode| {
+        bundle.push_str(helpers);
+        bundle.push_mapped(body, body_map);
+    };
+
+    // iife and umd bundles get their externals as arguments of the wrapper
+    // function. A global holds no side effects to run, so an iife only takes
+    // the externals it binds names from
+    let wrapper_externals: Vec<&ExternalImport> = match config.format {
+        OutputFormat::Iife => linked
+            .externals
+            .iter()
+            .filter(|external| {
+                if external.bindings.is_empty() {
+                    Logger::warn(&format!(
+                        "Side-effect import of external '{}' is not loaded by iife bundles",
+                        external.source
+                    ));
+                }
+                !external.bindings.is_empty()
+            })
+            .collect(),
+        OutputFormat::Umd => linked.externals.iter().collect(),
+        _ => Vec::new(),
+    };
+    let params: Vec<String> = (0..wrapper_externals.len())
+        .map(|i| format!("__soku_external_{}", i))
+        .collect();
+    let external_params = params.join(", ");
+    let external_bindings = bind_externals(&wrapper_externals, |i, _| params[i].clone());
+
+    match config.format {
+        OutputFormat::Esm => {
+            bundle.push_str(&esm_imports(&linked.externals));
+            push_body(&mut bundle);
+            bundle.push_str(&esm_exports(&linked.exports));
+        }
+        OutputFormat::Cjs => {
+            bundle.push_str("'use strict';\n\n");
+            bundle.push_str(&commonjs_requires(&linked.externals));
+            push_body(&mut bundle);
+            if let Some(entry) = &linked.commonjs_entry {
+                bundle.push_str(&format!("module.exports = {};\n", entry));
+            } else if !linked.exports.is_empty() {
+                bundle.push_str(&format!(
+                    "module.exports = {};\n",
+                    es_module_object(&linked.exports)
+                ));
+            }
+        }
+        OutputFormat::Iife => {
+            let arguments = wrapper_externals
+                .iter()
+                .map(|external| external_global(config, &external.source))
+                .collect::<Result<Vec<_>>>()?
+                .join(", ");
+            match global_name {
+                Some(name) if is_identifier(name) => {
+                    bundle.push_str(&format!("var {} = ", name));
+                }
+                Some(name) => {
+                    bundle.push_str(&format!(
+                        "{} = ",
+                        global_assignment_target("globalThis", name)
+                    ));
+                }
+                None => {}
+            }
+            bundle.push_str(&format!(
+                "(function({}) {{\n'use strict';\n\n",
+                external_params
+            ));
+            bundle.push_str(&external_bindings);
+            push_body(&mut bundle);
+            if global_name.is_some() {
+                bundle.push_str(&format!("return {};\n", exports_value(linked)));
+            }
+            bundle.push_str(&format!("}})({});\n", arguments));
+        }
+        OutputFormat::Umd => {
+            let has_exports = !linked.exports.is_empty() || linked.commonjs_entry.is_some();
+            let sources: Vec<String> = linked
+                .externals
+                .iter()
+                .map(|external| json_string(&external.source))
+                .collect();
+            let requires: Vec<String> = sources
+                .iter()
+                .map(|source| format!("require({})", source))
+                .collect();
+            let globals = linked
+                .externals
+                .iter()
+                .map(|external| {
+                    external_global(config, &external.source).map(|name| format!("root.{}", name))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let browser_factory = format!("factory({})", globals.join(", "));
+            let browser = match global_name {
+                Some(name) => format!(
+                    "{} = {}",
+                    global_assignment_target("root", name),
+                    browser_factory
+                ),
+                None if has_exports => {
+                    return Err(SokuError::config(
+                        "UMD bundles with exports need a globalName (set \"globalName\" in soku.config.json or pass --global-name)".to_string(),
+                    ));
+                }
+                None => browser_factory,
+            };
+            bundle.push_str(&format!(
+                "(function (root, factory) {{\n  if (typeof define === 'function' && define.amd) define([{}], factory);\n  else if (typeof module === 'object' && module.exports) module.exports = factory({});\n  else {};\n}})(typeof globalThis !== 'undefined' ? globalThis : typeof self !== 'undefined' ? self : this, function ({}) {{\n'use strict';\n\n",
+                sources.join(", "),
+                requires.join(", "),
+                browser,
+                external_params
+            ));
+            bundle.push_str(&external_bindings);
+            push_body(&mut bundle);
+            bundle.push_str(&format!("return {};\n}});\n", exports_value(linked)));
+        }
+    }
+    Ok(bundle)
+}
+
+/// `import` statements for modules left outside the bundle
+fn esm_imports(externals: &[ExternalImport]) -> String {
+    let mut imports = String::new();
+    for external in externals {
+        let source = json_string(&external.source);
+        let mut default = None;
+        let mut named = Vec::new();
+        for (imported, local) in &external.bindings {
+            match imported.as_deref() {
+                None => imports.push_str(&format!("import * as {} from {};\n", local, source)),
+                Some("default") if default.is_none() => default = Some(local.as_str()),
+                Some(imported) if imported == local => named.push(local.clone()),
+                Some(imported) => named.push(format!("{} as {}", property_key(imported), local)),
+            }
+        }
+        let clause = match (default, named.is_empty()) {
+            (Some(default), true) => Some(default.to_string()),
+            (Some(default), false) => Some(format!("{}, {{ {} }}", default, named.join(", "))),
+            (None, false) => Some(format!("{{ {} }}", named.join(", "))),
+            (None, true) if external.bindings.is_empty() => None,
+            // Only namespace imports, already emitted above
+            (None, true) => continue,
+        };
+        match clause {
+            Some(clause) => imports.push_str(&format!("import {} from {};\n", clause, source)),
+            None => imports.push_str(&format!("import {};\n", source)),
+        }
+    }
+    if !imports.is_empty() {
+        imports.push('\n');
+    }
+    imports
+}
+
+/// `export { ... }` for the entry exports; non-identifier expressions are
+/// bound to a local first
+fn esm_exports(exports: &[(String, String)]) -> String {
+    if exports.is_empty() {
+        return String::new();
+    }
+    let mut code = String::new();
+    let mut specifiers = Vec::new();
+    for (i, (name, expr)) in exports.iter().enumerate() {
+        let local = if is_identifier(expr) {
+            expr.clone()
+        } else {
+            let local = format!("__soku_export_{}", i);
+            code.push_str(&format!("var {} = {};\n", local, expr));
+            local
+        };
+        if &local == name {
+            specifiers.push(local);
+        } else {
+            specifiers.push(format!("{} as {}", local, property_key(name)));
+        }
+    }
+    code.push_str(&format!("export {{ {} }};\n", specifiers.join(", ")));
+    code
+}
+
+/// `require()` calls that bind the externals' local names
+fn commonjs_requires(externals: &[ExternalImport]) -> String {
+    let externals: Vec<&ExternalImport> = externals.iter().collect();
+    bind_externals(&externals, |_, external| {
+        format!("require({})", json_string(&external.source))
+    })
+}
+
+/// Variables binding the externals' local names to the modules that `load`
+/// returns for each external
+fn bind_externals(
+    externals: &[&ExternalImport],
+    load: impl Fn(usize, &ExternalImport) -> String,
+) -> String {
+    let mut code = String::new();
+    let needs_default = externals
+        .iter()
+        .flat_map(|e| &e.bindings)
+        .any(|(imported, _)| imported.as_deref() == Some("default"));
+    if needs_default {
+        code.push_str(
+            "function __soku_default(mod) { return mod && mod.__esModule ? mod.default : mod; }\n",
+        );
+    }
+    for (i, external) in externals.iter().enumerate() {
+        let require = load(i, external);
+        if external.bindings.is_empty() {
+            code.push_str(&format!("{};\n", require));
+        }
+        for (imported, local) in &external.bindings {
+            let value = match imported.as_deref() {
+                None => require.clone(),
+                Some("default") => format!("__soku_default({})", require),
+                Some(name) => format!("{}{}", require, member_access(name)),
+            };
+            code.push_str(&format!("var {} = {};\n", local, value));
+        }
+    }
+    if !code.is_empty() {
+        code.push('\n');
+    }
+    code
+}
+
+/// Global variable an iife/umd bundle reads an external from
+///
+/// Taken from `globals`, or guessed from the package name like `react-dom` →
+/// `reactDom` with a warning.
+fn external_global(config: &BuildConfig, source: &str) -> Result<String> {
+    if let Some(name) = config.globals.get(source) {
+        if !name.split('.').all(is_identifier) {
+            return Err(SokuError::config(format!(
+                "Invalid global '{}' for external '{}': expected an identifier such as 'React' or 'MyOrg.lib'",
+                name, source
+            )));
+        }
+        return Ok(name.clone());
+    }
+
+    let package = source.rsplit('/').next().unwrap_or(source);
+    let mut name = String::new();
+    let mut upper = false;
+    for c in package.chars() {
+        if c.is_ascii_alphanumeric() {
+            if upper && !name.is_empty() {
+                name.push(c.to_ascii_uppercase());
+            } else {
+                name.push(c);
+            }
+            upper = false;
+        } else {
+            upper = true;
+        }
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, '_');
+    }
+    Logger::warn(&format!(
+        "No global name for external '{}'; using '{}' (set \"globals\" in soku.config.json)",
+        source, name
+    ));
+    Ok(name)
+}
+
+/// Value handed to CommonJS/AMD/global consumers
+fn exports_value(linked: &LinkOutput) -> String {
+    match &linked.commonjs_entry {
+        Some(entry) => entry.clone(),
+        None => es_module_object(&linked.exports),
+    }
+}
+
+/// Exports object with live getters, flagged with `__esModule` for interop
+fn es_module_object(exports: &[(String, String)]) -> String {
+    let getters: Vec<String> = exports
+        .iter()
+        .map(|(name, expr)| format!("  get {}() {{ return {}; }}", property_key(name), expr))
+        .collect();
+    let object = if getters.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{\n{}\n}}", getters.join(",\n"))
+    };
+    format!(
+        "Object.defineProperty({}, '__esModule', {{ value: true }})",
+        object
+    )
+}
+
+/// Assignment target for a possibly dotted global name, creating the
+/// intermediate objects: `(root.MyOrg = root.MyOrg || {}).lib`
+fn global_assignment_target(root: &str, name: &str) -> String {
+    let mut target = root.to_string();
+    let segments: Vec<&str> = name.split('.').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let member = format!("{}.{}", target, segment);
+        target = if i + 1 < segments.len() {
+            format!("({} = {} || {{}})", member, member)
+        } else {
+            member
+        };
+    }
+    target
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn property_key(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        json_string(name)
+    }
+}
+
+fn member_access(name: &str) -> String {
+    if is_identifier(name) {
+        format!(".{}", name)
+    } else {
+        format!("[{}]", json_string(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linked(exports: &[(&str, &str)]) -> LinkOutput {
+        LinkOutput {
+            exports: exports
+                .iter()
+                .map(|(name, expr)| (name.to_string(), expr.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn config(format: OutputFormat, global_name: Option<&str>) -> BuildConfig {
+        BuildConfig {
+            format,
+            global_name: global_name.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_esm_keeps_exports_and_external_imports() {
+        let mut output = linked(&[("Button", "Button$1"), ("default", "lib_exports.default")]);
+        output.externals = vec![ExternalImport {
+            source: "react".to_string(),
+            bindings: vec![
+                (Some("default".to_string()), "React".to_string()),
+                (Some("useState".to_string()), "useState".to_string()),
+            ],
+        }];
+
+        let code =
+            format_bundle(&output, "", "body();\n", &config(OutputFormat::Esm, None)).unwrap();
+        assert!(code.starts_with("import React, { useState } from \"react\";\n"));
+        assert!(code.contains("var __soku_export_1 = lib_exports.default;\n"));
+        assert!(code.ends_with("export { Button$1 as Button, __soku_export_1 as default };\n"));
+    }
+
+    #[test]
+    fn test_cjs_assigns_module_exports() {
+        let code = format_bundle(
+            &linked(&[("add", "add")]),
+            "",
+            "function add() {}\n",
+            &config(OutputFormat::Cjs, None),
+        )
+        .unwrap();
+        assert!(code.starts_with("'use strict';"));
+        assert!(code.contains("module.exports = Object.defineProperty({\n  get add() { return add; }\n}, '__esModule', { value: true });"));
+    }
+
+    #[test]
+    fn test_iife_with_global_name() {
+        let output = linked(&[("add", "add")]);
+        let code = format_bundle(&output, "", "", &config(OutputFormat::Iife, None)).unwrap();
+        assert_eq!(code, "(function() {\n'use strict';\n\n})();\n");
+
+        let code =
+            format_bundle(&output, "", "", &config(OutputFormat::Iife, Some("MyLib"))).unwrap();
+        assert!(code.starts_with("var MyLib = (function() {"));
+
+        let code = format_bundle(
+            &output,
+            "",
+            "",
+            &config(OutputFormat::Iife, Some("MyOrg.lib")),
+        )
+        .unwrap();
+        assert!(code.starts_with("(globalThis.MyOrg = globalThis.MyOrg || {}).lib = (function() {"));
+
+        assert!(
+            format_bundle(&output, "", "", &config(OutputFormat::Iife, Some("my-lib"))).is_err()
+        );
+    }
+
+    #[test]
+    fn test_umd_requires_global_name_for_exports() {
+        let output = linked(&[("add", "add")]);
+        assert!(format_bundle(&output, "", "", &config(OutputFormat::Umd, None)).is_err());
+
+        let code =
+            format_bundle(&output, "", "", &config(OutputFormat::Umd, Some("MyLib"))).unwrap();
+        assert!(code.contains("define.amd) define([], factory);"));
+        assert!(code.contains("module.exports = factory();"));
+        assert!(code.contains("else root.MyLib = factory();"));
+        assert!(code.ends_with("}, '__esModule', { value: true });\n});\n"));
+    }
+
+    fn with_lodash(exports: &[(&str, &str)]) -> LinkOutput {
+        let mut output = linked(exports);
+        output.externals = vec![ExternalImport {
+            source: "lodash".to_string(),
+            bindings: vec![
+                (Some("default".to_string()), "_".to_string()),
+                (Some("chunk".to_string()), "chunk".to_string()),
+            ],
+        }];
+        output
+    }
+
+    #[test]
+    fn test_iife_receives_externals_as_globals() {
+        let mut options = config(OutputFormat::Iife, Some("MyLib"));
+        options
+            .globals
+            .insert("lodash".to_string(), "_".to_string());
+
+        let code = format_bundle(&with_lodash(&[("add", "add")]), "", "", &options).unwrap();
+        assert!(code.starts_with("var MyLib = (function(__soku_external_0) {\n'use strict';\n\n"));
+        assert!(code.contains("var _ = __soku_default(__soku_external_0);\n"));
+        assert!(code.contains("var chunk = __soku_external_0.chunk;\n"));
+        assert!(code.ends_with("})(_);\n"));
+
+        // Without a configured global the name is guessed from the package
+        let mut output = with_lodash(&[]);
+        output.externals[0].source = "@scope/react-dom".to_string();
+        let code = format_bundle(&output, "", "", &config(OutputFormat::Iife, None)).unwrap();
+        assert!(code.ends_with("})(reactDom);\n"));
+
+        options
+            .globals
+            .insert("lodash".to_string(), "lo-dash".to_string());
+        assert!(format_bundle(&with_lodash(&[]), "", "", &options).is_err());
+    }
+
+    #[test]
+    fn test_umd_loads_externals_in_every_environment() {
+        let mut options = config(OutputFormat::Umd, Some("MyLib"));
+        options
+            .globals
+            .insert("lodash".to_string(), "_".to_string());
+
+        let code = format_bundle(&with_lodash(&[("add", "add")]), "", "", &options).unwrap();
+        assert!(code.contains("define.amd) define([\"lodash\"], factory);"));
+        assert!(code.contains("module.exports = factory(require(\"lodash\"));"));
+        assert!(code.contains("else root.MyLib = factory(root._);"));
+        assert!(code.contains("function (__soku_external_0) {\n'use strict';\n\n"));
+        assert!(code.contains("var _ = __soku_default(__soku_external_0);\n"));
+        assert!(code.contains("var chunk = __soku_external_0.chunk;\n"));
+    }
+}
@@ -0,0 +1,396 @@
+// Source maps for compiled Sass
+// grass does not write source maps, so its output is matched back to the
+// spans of the Sass AST: declarations by property name, in source order, and
+// rules by the declarations they contain
+
+use crate::utils::CodeMap;
+use grass_compiler::codemap::{self, Span};
+use grass_compiler::sass_ast::AstStmt;
+use grass_compiler::{InputSyntax, Options};
+use std::ops::Range;
+use std::path::Path;
+
+/// Map of `css`, which grass compiled from the Sass `content` of `path`
+///
+/// Declarations and rules coming from imported files, or whose names are
+/// interpolated, stay unmapped.
+pub fn scss_map(content: &str, path: &Path, syntax: InputSyntax, css: &str) -> CodeMap {
+    let source = path.to_string_lossy();
+    let Some(outline) = SassOutline::parse(content, path, syntax) else {
+        return CodeMap::from_offsets(&source, content, &[]);
+    };
+    CodeMap::from_offsets(&source, content, &outline.match_css(css))
+}
+
+/// A style rule of the Sass source
+struct SourceRule {
+    /// Byte range of the whole rule, body included
+    span: Range<usize>,
+    /// Byte offset of the selector
+    selector: usize,
+}
+
+/// A declaration or at-rule of the Sass source: name and byte offset
+struct Named {
+    name: String,
+    offset: usize,
+}
+
+/// Rules, declarations and at-rules of a Sass stylesheet, in source order
+struct SassOutline {
+    rules: Vec<SourceRule>,
+    declarations: Vec<Named>,
+    at_rules: Vec<Named>,
+    /// Position of the start of the file in grass's code map
+    base: codemap::Pos,
+}
+
+impl SassOutline {
+    fn parse(content: &str, path: &Path, syntax: InputSyntax) -> Option<Self> {
+        let options = Options::default().input_syntax(syntax);
+        let stylesheet =
+            grass_compiler::parse_stylesheet(content.to_string(), path, &options).ok()?;
+
+        // grass parses into a code map of its own, where the file is the first one
+        let base = codemap::CodeMap::new()
+            .add_file(String::new(), String::new())
+            .span
+            .low();
+        let mut outline = Self {
+            rules: Vec::new(),
+            declarations: Vec::new(),
+            at_rules: Vec::new(),
+            base,
+        };
+        outline.visit(&stylesheet.body, "");
+        Some(outline)
+    }
+
+    fn offset(&self, span: Span) -> usize {
+        (span.low() - self.base) as usize
+    }
+
+    fn range(&self, span: Span) -> Range<usize> {
+        let start = self.offset(span);
+        start..start + span.len() as usize
+    }
+
+    fn visit(&mut self, statements: &[AstStmt], prefix: &str) {
+        for statement in statements {
+            match statement {
+                AstStmt::RuleSet(rule) => {
+                    self.rules.push(SourceRule {
+                        span: self.range(rule.span),
+                        selector: self.offset(rule.selector_span),
+                    });
+                    self.visit(&rule.body, "");
+                }
+                AstStmt::Style(style) => {
+                    let Some(name) = style.name.as_plain() else {
+                        continue;
+                    };
+                    // Nested properties: `font: { family: serif }` is `font-family`
+                    let name = format!("{}{}", prefix, name);
+                    if style.value.is_some() {
+                        self.declarations.push(Named {
+                            name: name.clone(),
+                            offset: self.offset(style.span),
+                        });
+                    }
+                    self.visit(&style.body, &format!("{}-", name));
+                }
+                AstStmt::Media(media) => {
+                    self.add_at_rule("media", media.span);
+                    self.visit(&media.body, prefix);
+                }
+                AstStmt::Supports(supports) => {
+                    self.add_at_rule("supports", supports.span);
+                    self.visit(&supports.body, prefix);
+                }
+                AstStmt::UnknownAtRule(rule) => {
+                    if let Some(name) = rule.name.as_plain() {
+                        self.add_at_rule(name, rule.span);
+                    }
+                    if let Some(body) = &rule.body {
+                        self.visit(body, prefix);
+                    }
+                }
+                AstStmt::Include(include) => {
+                    if let Some(content) = &include.content {
+                        self.visit(&content.body, prefix);
+                    }
+                }
+                AstStmt::Mixin(mixin) => self.visit(&mixin.body, ""),
+                AstStmt::If(rule) => {
+                    for clause in &rule.if_clauses {
+                        self.visit(&clause.body, prefix);
+                    }
+                    if let Some(body) = &rule.else_clause {
+                        self.visit(body, prefix);
+                    }
+                }
+                AstStmt::For(rule) => self.visit(&rule.body, prefix),
+                AstStmt::Each(rule) => self.visit(&rule.body, prefix),
+                AstStmt::While(rule) => self.visit(&rule.body, prefix),
+                AstStmt::AtRootRule(rule) => self.visit(&rule.body, prefix),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_at_rule(&mut self, name: &str, span: Span) {
+        self.at_rules.push(Named {
+            name: name.to_string(),
+            offset: self.offset(span),
+        });
+    }
+
+    /// (offset in `css`, offset in the Sass source) pairs for the rules and
+    /// declarations of `css`
+    fn match_css(&self, css: &str) -> Vec<(usize, Option<usize>)> {
+        let mut offsets = Vec::new();
+        let mut next_declaration = 0;
+        let mut next_rule = 0;
+        let mut next_at_rule = 0;
+        // Output offset of a rule header waiting for its first declaration
+        let mut pending_header = None;
+        let mut current_rule: Option<usize> = None;
+
+        for item in css_items(css) {
+            match item {
+                CssItem::Header { offset, text } => {
+                    if let Some(at_rule) = text.strip_prefix('@') {
+                        let name = at_rule
+                            .split(|c: char| c.is_whitespace() || c == '(')
+                            .next()
+                            .unwrap_or_default();
+                        let found =
+                            find_from(&self.at_rules, next_at_rule, |rule| rule.name == name);
+                        if let Some(index) = found {
+                            next_at_rule = index + 1;
+                        }
+                        offsets.push((offset, found.map(|index| self.at_rules[index].offset)));
+                    } else {
+                        pending_header = Some(offset);
+                        current_rule = None;
+                    }
+                }
+                CssItem::Declaration { offset, name } => {
+                    let within_rule = |declaration: &Named| {
+                        current_rule
+                            .is_none_or(|rule| self.rules[rule].span.contains(&declaration.offset))
+                    };
+                    let found = find_from(&self.declarations, next_declaration, |declaration| {
+                        declaration.name == name && within_rule(declaration)
+                    })
+                    .or_else(|| {
+                        find_from(&self.declarations, next_declaration, |declaration| {
+                            declaration.name == name
+                        })
+                    });
+                    let Some(index) = found else {
+                        offsets.push((offset, None));
+                        continue;
+                    };
+                    next_declaration = index + 1;
+                    let source_offset = self.declarations[index].offset;
+
+                    if let Some(header) = pending_header.take() {
+                        // Declarations from mixins are outside of the rule
+                        current_rule = self
+                            .rule_containing(source_offset)
+                            .or_else(|| (next_rule < self.rules.len()).then_some(next_rule));
+                        if let Some(rule) = current_rule {
+                            next_rule = rule + 1;
+                        }
+                        offsets.push((header, current_rule.map(|rule| self.rules[rule].selector)));
+                    }
+                    offsets.push((offset, Some(source_offset)));
+                }
+                CssItem::Close => {
+                    // A rule without declarations, e.g. only a comment
+                    if let Some(header) = pending_header.take() {
+                        let rule = self.rules.get(next_rule).map(|rule| rule.selector);
+                        if rule.is_some() {
+                            next_rule += 1;
+                        }
+                        offsets.push((header, rule));
+                    }
+                    current_rule = None;
+                }
+            }
+        }
+        offsets
+    }
+
+    /// Innermost rule whose body contains `offset`
+    fn rule_containing(&self, offset: usize) -> Option<usize> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.span.contains(&offset))
+            .max_by_key(|(_, rule)| rule.span.start)
+            .map(|(index, _)| index)
+    }
+}
+
+/// First item from `start` on matching `predicate`, then from the beginning
+fn find_from<T>(items: &[T], start: usize, predicate: impl Fn(&T) -> bool) -> Option<usize> {
+    let start = start.min(items.len());
+    items[start..]
+        .iter()
+        .position(&predicate)
+        .map(|index| start + index)
+        .or_else(|| items[..start].iter().position(&predicate))
+}
+
+/// A piece of CSS the source map points at
+#[derive(Debug, PartialEq)]
+enum CssItem<'a> {
+    /// Selector or at-rule prelude before `{`
+    Header {
+        offset: usize,
+        text: &'a str,
+    },
+    Declaration {
+        offset: usize,
+        name: &'a str,
+    },
+    Close,
+}
+
+/// Rule headers, declarations and block ends of `css`, in order
+///
+/// Works on both expanded and compressed output; comments and strings are
+/// skipped.
+fn css_items(css: &str) -> Vec<CssItem<'_>> {
+    let bytes = css.as_bytes();
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut parens = 0usize;
+    let mut start = 0;
+    let mut i = 0;
+
+    // The piece of `css[start..end]` after leading white space
+    let segment = |start: usize, end: usize| {
+        let text = &css[start..end];
+        let trimmed = text.trim_start();
+        (start + text.len() - trimmed.len(), trimmed.trim_end())
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = css[i + 2..]
+                    .find("*/")
+                    .map_or(css.len(), |end| i + 2 + end + 2);
+                if css[start..i].trim().is_empty() {
+                    start = end;
+                }
+                i = end;
+                continue;
+            }
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'(' => parens += 1,
+            b')' => parens = parens.saturating_sub(1),
+            b'{' if parens == 0 => {
+                let (offset, text) = segment(start, i);
+                items.push(CssItem::Header { offset, text });
+                depth += 1;
+                start = i + 1;
+            }
+            delimiter @ (b';' | b'}') if parens == 0 => {
+                let (offset, text) = segment(start, i);
+                if depth > 0 {
+                    if let Some((name, _)) = text.split_once(':') {
+                        items.push(CssItem::Declaration {
+                            offset,
+                            name: name.trim_end(),
+                        });
+                    }
+                }
+                if delimiter == b'}' {
+                    items.push(CssItem::Close);
+                    depth = depth.saturating_sub(1);
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_css_items() {
+        let css = "/* x */\n.a b,\n.c {\n  color: red;\n  background: url(\"a;b\");\n}\n@media (min-width: 1px){.d{margin:0}}";
+        let items = css_items(css);
+        assert_eq!(
+            items,
+            vec![
+                CssItem::Header {
+                    offset: 8,
+                    text: ".a b,\n.c"
+                },
+                CssItem::Declaration {
+                    offset: 21,
+                    name: "color"
+                },
+                CssItem::Declaration {
+                    offset: 35,
+                    name: "background"
+                },
+                CssItem::Close,
+                CssItem::Header {
+                    offset: 61,
+                    text: "@media (min-width: 1px)"
+                },
+                CssItem::Header {
+                    offset: 85,
+                    text: ".d"
+                },
+                CssItem::Declaration {
+                    offset: 88,
+                    name: "margin"
+                },
+                CssItem::Close,
+                CssItem::Close,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scss_map_points_at_declarations() {
+        let scss = "$gap: 4px;\n@mixin pad { padding: $gap; }\n.nav {\n  color: red;\n  ul {\n    @include pad;\n    margin: 0;\n  }\n}\n";
+        let path = Path::new("/app/nav.scss");
+        let css = grass::from_string(scss.to_string(), &grass::Options::default()).unwrap();
+        let map = scss_map(scss, path, InputSyntax::Scss, &css);
+
+        let source_map = map.generate(&css, None);
+        let json = serde_json::to_string(&source_map).unwrap();
+        let source_map = oxc_sourcemap::SourceMap::from_json_string(&json).unwrap();
+        let table = source_map.generate_lookup_table();
+        let original = |needle: &str| {
+            let offset = css.find(needle).unwrap();
+            let line = css[..offset].matches('\n').count() as u32;
+            let column = (offset - css[..offset].rfind('\n').map_or(0, |i| i + 1)) as u32;
+            let token = source_map.lookup_token(&table, line, column).unwrap();
+            (token.get_src_line(), token.get_src_col())
+        };
+
+        assert_eq!(original(".nav ul"), (4, 2));
+        assert_eq!(original("color"), (3, 2));
+        assert_eq!(original("padding"), (1, 13));
+        assert_eq!(original("margin"), (6, 4));
+    }
+}
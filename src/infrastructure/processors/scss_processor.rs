@@ -1,5 +1,7 @@
 use crate::core::interfaces::CssProcessor;
-use crate::utils::{Logger, Result, SokuCache, SokuError};
+use crate::core::models::BundleOutput;
+use crate::infrastructure::processors::scss_map::scss_map;
+use crate::utils::{CodeMap, Logger, MappedCode, Result, SokuCache, SokuError};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -58,16 +60,9 @@ impl ScssProcessor {
                 .unwrap_or("unknown")
         ));
 
-        // Detect syntax based on file extension
-        let syntax = if path.extension().and_then(|s| s.to_str()) == Some("sass") {
-            grass::InputSyntax::Sass
-        } else {
-            grass::InputSyntax::Scss
-        };
-
         // Configure grass options
         let options = grass::Options::default()
-            .input_syntax(syntax)
+            .input_syntax(Self::syntax_of(path))
             .style(if self.minify {
                 grass::OutputStyle::Compressed
             } else {
@@ -91,6 +86,22 @@ impl ScssProcessor {
             }
         }
     }
+
+    /// Compile SCSS/SASS to CSS with a map back to the Sass source
+    fn compile_scss_with_map(&self, content: &str, path: &Path) -> Result<(String, CodeMap)> {
+        let css = self.compile_scss(content, path)?;
+        let map = scss_map(content, path, Self::syntax_of(path), &css);
+        Ok((css, map))
+    }
+
+    /// Detect syntax based on file extension
+    fn syntax_of(path: &Path) -> grass::InputSyntax {
+        if path.extension().and_then(|s| s.to_str()) == Some("sass") {
+            grass::InputSyntax::Sass
+        } else {
+            grass::InputSyntax::Scss
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -158,6 +169,63 @@ impl CssProcessor for ScssProcessor {
 
         Ok(bundle)
     }
+
+    async fn process_css_with_map(
+        &self,
+        content: &str,
+        path: &Path,
+        map: CodeMap,
+    ) -> Result<(String, CodeMap)> {
+        if !Self::is_scss_file(path) {
+            return match self.css_processor {
+                Some(ref processor) => processor.process_css_with_map(content, path, map).await,
+                None => Ok((content.to_string(), map)),
+            };
+        }
+
+        // Sass is always read from its original file, so the map of the
+        // compiled CSS replaces the identity `map` of the source
+        drop(map);
+        let (css, css_map) = self.compile_scss_with_map(content, path)?;
+
+        match self.css_processor {
+            Some(ref processor) => {
+                Logger::debug("Post-processing compiled CSS with LightningCSS");
+                processor.process_css_with_map(&css, path, css_map).await
+            }
+            None => Ok((css, css_map)),
+        }
+    }
+
+    async fn bundle_css_with_source_map(&self, files: &[PathBuf]) -> Result<BundleOutput> {
+        let _timer = crate::utils::Timer::start("Bundling SCSS/CSS files with source map");
+
+        let mut bundle = MappedCode::new(true);
+        bundle.push_str("/* Soku Bundler - SCSS/CSS Bundle */\n");
+
+        for file_path in files {
+            Logger::debug(&format!("Bundling file: {}", file_path.display()));
+
+            let content = tokio::fs::read_to_string(file_path).await?;
+            let map = CodeMap::identity(&file_path.to_string_lossy(), &content);
+            let (processed, map) = self.process_css_with_map(&content, file_path, map).await?;
+
+            bundle.push_str(&format!("\n/* File: {} */\n", file_path.display()));
+            bundle.push_mapped(&processed, Some(&map));
+            bundle.push_str("\n");
+        }
+
+        Logger::info(&format!(
+            "📦 Bundled {} SCSS/CSS files ({} bytes)",
+            files.len(),
+            bundle.code.len()
+        ));
+
+        Ok(BundleOutput {
+            code: bundle.code,
+            source_map: bundle.map,
+        })
+    }
 }
 
 #[cfg(test)]
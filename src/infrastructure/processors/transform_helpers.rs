@@ -0,0 +1,497 @@
+// Transform Helpers - Runtime helpers for syntax lowered by oxc_transformer
+// Compiled modules call them through a `babelHelpers` object (`decorate`,
+// `asyncToGenerator`, ...); every bundle defines the ones its modules use once
+// at the top of its scope, so no helper package has to be installed
+
+use crate::utils::{Result, SokuError};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+static HELPER_CALL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bbabelHelpers\.([A-Za-z0-9_$]+)").unwrap());
+
+/// Helper name and implementation; helpers call each other through `babelHelpers`
+const HELPERS: &[(&str, &str)] = &[
+    (
+        "toPrimitive",
+        r#"function (input, hint) {
+  if (typeof input !== "object" || input === null) return input;
+  var prim = input[Symbol.toPrimitive];
+  if (prim !== undefined) {
+    var res = prim.call(input, hint || "default");
+    if (typeof res !== "object") return res;
+    throw new TypeError("@@toPrimitive must return a primitive value.");
+  }
+  return (hint === "string" ? String : Number)(input);
+}"#,
+    ),
+    (
+        "toPropertyKey",
+        r#"function (arg) {
+  var key = babelHelpers.toPrimitive(arg, "string");
+  return typeof key === "symbol" ? key : String(key);
+}"#,
+    ),
+    (
+        "defineProperty",
+        r#"function (obj, key, value) {
+  key = babelHelpers.toPropertyKey(key);
+  if (key in obj) {
+    Object.defineProperty(obj, key, { value: value, enumerable: true, configurable: true, writable: true });
+  } else {
+    obj[key] = value;
+  }
+  return obj;
+}"#,
+    ),
+    (
+        "checkPrivateRedeclaration",
+        r#"function (obj, privateCollection) {
+  if (privateCollection.has(obj)) {
+    throw new TypeError("Cannot initialize the same private elements twice on an object");
+  }
+}"#,
+    ),
+    (
+        "classPrivateFieldInitSpec",
+        r#"function (obj, privateMap, value) {
+  babelHelpers.checkPrivateRedeclaration(obj, privateMap);
+  privateMap.set(obj, value);
+}"#,
+    ),
+    (
+        "classPrivateMethodInitSpec",
+        r#"function (obj, privateSet) {
+  babelHelpers.checkPrivateRedeclaration(obj, privateSet);
+  privateSet.add(obj);
+}"#,
+    ),
+    (
+        "assertClassBrand",
+        r#"function (brand, receiver, returnValue) {
+  if (typeof brand === "function" ? brand === receiver : brand.has(receiver)) {
+    return arguments.length < 3 ? receiver : returnValue;
+  }
+  throw new TypeError("Private element is not present on this object");
+}"#,
+    ),
+    (
+        "classPrivateFieldGet2",
+        r#"function (privateMap, receiver) {
+  return privateMap.get(babelHelpers.assertClassBrand(privateMap, receiver));
+}"#,
+    ),
+    (
+        "classPrivateFieldSet2",
+        r#"function (privateMap, receiver, value) {
+  privateMap.set(babelHelpers.assertClassBrand(privateMap, receiver), value);
+  return value;
+}"#,
+    ),
+    (
+        "classPrivateFieldLooseKey",
+        r#"(function () {
+  var id = 0;
+  return function (name) {
+    return "__private_" + id++ + "_" + name;
+  };
+})()"#,
+    ),
+    (
+        "classPrivateFieldLooseBase",
+        r#"function (receiver, privateKey) {
+  if (!Object.prototype.hasOwnProperty.call(receiver, privateKey)) {
+    throw new TypeError("attempted to use private field on non-instance");
+  }
+  return receiver;
+}"#,
+    ),
+    (
+        "toSetter",
+        r#"function (fn, args, thisArg) {
+  if (!args) args = [];
+  var index = args.length++;
+  return Object.defineProperty({}, "_", {
+    set: function (value) {
+      args[index] = value;
+      fn.apply(thisArg, args);
+    }
+  });
+}"#,
+    ),
+    (
+        "superPropGet",
+        r#"function (classArg, property, receiver, flags) {
+  var result = Reflect.get(Object.getPrototypeOf(flags & 1 ? classArg.prototype : classArg), property, receiver);
+  return flags & 2 && typeof result === "function" ? function (args) { return result.apply(receiver, args); } : result;
+}"#,
+    ),
+    (
+        "superPropSet",
+        r#"function (classArg, property, value, receiver, isStrict, prototype) {
+  var ok = Reflect.set(Object.getPrototypeOf(prototype ? classArg.prototype : classArg), property, value, receiver);
+  if (!ok && isStrict) throw new TypeError("failed to set property");
+  return value;
+}"#,
+    ),
+    (
+        "readOnlyError",
+        r#"function (name) {
+  throw new TypeError('"' + name + '" is read-only');
+}"#,
+    ),
+    (
+        "writeOnlyError",
+        r#"function (name) {
+  throw new TypeError('"' + name + '" is write-only');
+}"#,
+    ),
+    (
+        "checkInRHS",
+        r#"function (value) {
+  if (Object(value) !== value) {
+    throw new TypeError("right-hand side of 'in' should be an object, got " + (value !== null ? typeof value : "null"));
+  }
+  return value;
+}"#,
+    ),
+    (
+        "asyncToGenerator",
+        r#"(function () {
+  function step(gen, resolve, reject, next, thrown, key, arg) {
+    try {
+      var info = gen[key](arg);
+      var value = info.value;
+    } catch (error) {
+      reject(error);
+      return;
+    }
+    if (info.done) resolve(value);
+    else Promise.resolve(value).then(next, thrown);
+  }
+  return function (fn) {
+    return function () {
+      var self = this, args = arguments;
+      return new Promise(function (resolve, reject) {
+        var gen = fn.apply(self, args);
+        function next(value) { step(gen, resolve, reject, next, thrown, "next", value); }
+        function thrown(error) { step(gen, resolve, reject, next, thrown, "throw", error); }
+        next(undefined);
+      });
+    };
+  };
+})()"#,
+    ),
+    (
+        "ownKeys",
+        r#"function (object, enumerableOnly) {
+  var keys = Object.keys(object);
+  if (Object.getOwnPropertySymbols) {
+    var symbols = Object.getOwnPropertySymbols(object);
+    if (enumerableOnly) {
+      symbols = symbols.filter(function (sym) { return Object.getOwnPropertyDescriptor(object, sym).enumerable; });
+    }
+    keys.push.apply(keys, symbols);
+  }
+  return keys;
+}"#,
+    ),
+    (
+        "objectSpread2",
+        r#"function (target) {
+  for (var i = 1; i < arguments.length; i++) {
+    var source = arguments[i] != null ? arguments[i] : {};
+    babelHelpers.ownKeys(Object(source), true).forEach(function (key) {
+      babelHelpers.defineProperty(target, key, source[key]);
+    });
+  }
+  return target;
+}"#,
+    ),
+    (
+        "extends",
+        r#"function (target) {
+  for (var i = 1; i < arguments.length; i++) {
+    var source = arguments[i];
+    for (var key in source) {
+      if (Object.prototype.hasOwnProperty.call(source, key)) target[key] = source[key];
+    }
+  }
+  return target;
+}"#,
+    ),
+    (
+        "objectDestructuringEmpty",
+        r#"function (obj) {
+  if (obj == null) throw new TypeError("Cannot destructure " + obj);
+}"#,
+    ),
+    (
+        "objectWithoutProperties",
+        r#"function (source, excluded) {
+  if (source == null) return {};
+  var target = {}, key;
+  for (key in source) {
+    if (Object.prototype.hasOwnProperty.call(source, key) && excluded.indexOf(key) === -1) {
+      target[key] = source[key];
+    }
+  }
+  if (Object.getOwnPropertySymbols) {
+    var symbols = Object.getOwnPropertySymbols(source);
+    for (var i = 0; i < symbols.length; i++) {
+      key = symbols[i];
+      if (excluded.indexOf(key) === -1 && Object.prototype.propertyIsEnumerable.call(source, key)) {
+        target[key] = source[key];
+      }
+    }
+  }
+  return target;
+}"#,
+    ),
+    (
+        "OverloadYield",
+        r#"function (value, kind) {
+  this.v = value;
+  this.k = kind;
+}"#,
+    ),
+    (
+        "awaitAsyncGenerator",
+        r#"function (value) {
+  return new babelHelpers.OverloadYield(value, 0);
+}"#,
+    ),
+    (
+        "AsyncGenerator",
+        r#"(function () {
+  function AsyncGenerator(gen) {
+    var front, back;
+    function send(key, arg) {
+      return new Promise(function (resolve, reject) {
+        var request = { key: key, arg: arg, resolve: resolve, reject: reject, next: null };
+        if (back) {
+          back = back.next = request;
+        } else {
+          front = back = request;
+          resume(key, arg);
+        }
+      });
+    }
+    function resume(key, arg) {
+      try {
+        var result = gen[key](arg);
+        var value = result.value;
+        var overloaded = value instanceof babelHelpers.OverloadYield;
+        Promise.resolve(overloaded ? value.v : value).then(function (arg) {
+          if (overloaded) {
+            var nextKey = key === "return" ? "return" : "next";
+            if (!value.k || arg.done) return resume(nextKey, arg);
+            arg = gen[nextKey](arg).value;
+          }
+          settle(result.done ? "return" : "normal", arg);
+        }, function (error) {
+          resume("throw", error);
+        });
+      } catch (error) {
+        settle("throw", error);
+      }
+    }
+    function settle(type, value) {
+      if (type === "throw") front.reject(value);
+      else front.resolve({ value: value, done: type === "return" });
+      front = front.next;
+      if (front) resume(front.key, front.arg);
+      else back = null;
+    }
+    this._invoke = send;
+    if (typeof gen.return !== "function") this.return = undefined;
+  }
+  AsyncGenerator.prototype[typeof Symbol === "function" && Symbol.asyncIterator || "@@asyncIterator"] = function () { return this; };
+  AsyncGenerator.prototype.next = function (arg) { return this._invoke("next", arg); };
+  AsyncGenerator.prototype.throw = function (arg) { return this._invoke("throw", arg); };
+  AsyncGenerator.prototype.return = function (arg) { return this._invoke("return", arg); };
+  return AsyncGenerator;
+})()"#,
+    ),
+    (
+        "wrapAsyncGenerator",
+        r#"function (fn) {
+  return function () {
+    return new babelHelpers.AsyncGenerator(fn.apply(this, arguments));
+  };
+}"#,
+    ),
+    (
+        "asyncGeneratorDelegate",
+        r#"function (inner) {
+  var iter = {}, waiting = false;
+  function pump(key, value) {
+    waiting = true;
+    value = new Promise(function (resolve) { resolve(inner[key](value)); });
+    return { done: false, value: new babelHelpers.OverloadYield(value, 1) };
+  }
+  iter[typeof Symbol === "function" && Symbol.iterator || "@@iterator"] = function () { return this; };
+  iter.next = function (value) {
+    if (waiting) {
+      waiting = false;
+      return value;
+    }
+    return pump("next", value);
+  };
+  if (typeof inner.throw === "function") {
+    iter.throw = function (value) {
+      if (waiting) {
+        waiting = false;
+        throw value;
+      }
+      return pump("throw", value);
+    };
+  }
+  if (typeof inner.return === "function") {
+    iter.return = function (value) {
+      if (waiting) {
+        waiting = false;
+        return value;
+      }
+      return pump("return", value);
+    };
+  }
+  return iter;
+}"#,
+    ),
+    (
+        "asyncIterator",
+        r#"(function () {
+  function AsyncFromSyncIterator(iterator) {
+    this.s = iterator;
+    this.n = iterator.next;
+  }
+  function continuation(result) {
+    if (Object(result) !== result) return Promise.reject(new TypeError(result + " is not an object."));
+    var done = result.done;
+    return Promise.resolve(result.value).then(function (value) { return { value: value, done: done }; });
+  }
+  AsyncFromSyncIterator.prototype = {
+    next: function () {
+      return continuation(this.n.apply(this.s, arguments));
+    },
+    return: function (value) {
+      var method = this.s.return;
+      if (method === undefined) return Promise.resolve({ value: value, done: true });
+      return continuation(method.apply(this.s, arguments));
+    },
+    throw: function (value) {
+      var method = this.s.throw;
+      if (method === undefined) return Promise.reject(value);
+      return continuation(method.apply(this.s, arguments));
+    }
+  };
+  return function (iterable) {
+    var method;
+    if (typeof Symbol === "function") {
+      if (Symbol.asyncIterator && (method = iterable[Symbol.asyncIterator]) != null) return method.call(iterable);
+      if ((method = iterable[Symbol.iterator]) != null) return new AsyncFromSyncIterator(method.call(iterable));
+    }
+    if ((method = iterable["@@asyncIterator"]) != null) return method.call(iterable);
+    if ((method = iterable["@@iterator"]) != null) return new AsyncFromSyncIterator(method.call(iterable));
+    throw new TypeError("Object is not async iterable");
+  };
+})()"#,
+    ),
+    (
+        "decorate",
+        r#"function (decorators, target, key, desc) {
+  var c = arguments.length, r = c < 3 ? target : desc === null ? desc = Object.getOwnPropertyDescriptor(target, key) : desc, d;
+  if (typeof Reflect === "object" && typeof Reflect.decorate === "function") r = Reflect.decorate(decorators, target, key, desc);
+  else for (var i = decorators.length - 1; i >= 0; i--) if (d = decorators[i]) r = (c < 3 ? d(r) : c > 3 ? d(target, key, r) : d(target, key)) || r;
+  return c > 3 && r && Object.defineProperty(target, key, r), r;
+}"#,
+    ),
+    (
+        "decorateParam",
+        r#"function (paramIndex, decorator) {
+  return function (target, key) { decorator(target, key, paramIndex); };
+}"#,
+    ),
+    (
+        "decorateMetadata",
+        r#"function (key, value) {
+  if (typeof Reflect === "object" && typeof Reflect.metadata === "function") return Reflect.metadata(key, value);
+}"#,
+    ),
+];
+
+fn helper_source(name: &str) -> Option<&'static str> {
+    HELPERS
+        .iter()
+        .find(|(helper, _)| *helper == name)
+        .map(|(_, source)| *source)
+}
+
+/// Prefix bundle code with the definitions of the helpers it calls
+///
+/// Code that calls no helper is returned unchanged.
+pub fn with_transform_helpers(code: &str) -> Result<Cow<'_, str>> {
+    let mut pending: Vec<String> = HELPER_CALL_REGEX
+        .captures_iter(code)
+        .map(|captures| captures[1].to_string())
+        .collect();
+    if pending.is_empty() {
+        return Ok(Cow::Borrowed(code));
+    }
+
+    // Helpers that other helpers call are included too
+    let mut used = BTreeSet::new();
+    while let Some(name) = pending.pop() {
+        if used.contains(&name) {
+            continue;
+        }
+        let source = helper_source(&name).ok_or_else(|| {
+            SokuError::build(format!(
+                "Compiled code needs the unsupported runtime helper '{}'",
+                name
+            ))
+        })?;
+        pending.extend(
+            HELPER_CALL_REGEX
+                .captures_iter(source)
+                .map(|captures| captures[1].to_string()),
+        );
+        used.insert(name);
+    }
+
+    let mut output = String::from("var babelHelpers = {};\n");
+    for name in &used {
+        let source = helper_source(name).unwrap_or_default();
+        output.push_str(&format!("babelHelpers.{} = {};\n", name, source));
+    }
+    output.push('\n');
+    output.push_str(code);
+    Ok(Cow::Owned(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helpers_are_defined_with_their_dependencies() {
+        assert!(matches!(
+            with_transform_helpers("let x = 1;").unwrap(),
+            Cow::Borrowed(_)
+        ));
+
+        let code = "babelHelpers.defineProperty(this, \"x\", 1);";
+        let output = with_transform_helpers(code).unwrap();
+        assert!(output.starts_with("var babelHelpers = {};\n"));
+        assert!(output.contains("babelHelpers.defineProperty = function"));
+        assert!(output.contains("babelHelpers.toPropertyKey = function"));
+        assert!(output.contains("babelHelpers.toPrimitive = function"));
+        assert!(!output.contains("babelHelpers.decorate ="));
+        assert!(output.ends_with(code));
+
+        assert!(with_transform_helpers("babelHelpers.unknownHelper()").is_err());
+    }
+}
@@ -400,6 +400,7 @@ export default function main() {
                 module_type: ModuleType::JavaScript,
                 dependencies: vec![],
                 exports: vec![],
+                resolved_imports: std::collections::HashMap::new(),
             },
             ModuleInfo {
                 path: PathBuf::from("utils.js"),
@@ -411,6 +412,7 @@ export const unusedFunction = () => "unused";
                 module_type: ModuleType::JavaScript,
                 dependencies: vec![],
                 exports: vec![],
+                resolved_imports: std::collections::HashMap::new(),
             },
         ];
 
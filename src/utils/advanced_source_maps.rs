@@ -12,6 +12,7 @@ use std::collections::HashMap;
 pub struct SourceMap {
     pub version: u8,
     pub sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sources_content: Option<Vec<String>>,
     pub names: Vec<String>,
     pub mappings: String,
@@ -44,15 +45,13 @@ pub struct AdvancedSourceMapGenerator {
     mappings: Vec<Mapping>,
 }
 
-/// A single mapping entry
+/// A single mapping entry; lines are 1-based, columns 0-based
 #[derive(Debug, Clone)]
-#[allow(dead_code)] // Used for future VLQ encoding implementation
 struct Mapping {
     generated_line: u32,
     generated_column: u32,
-    source_index: u32,
-    original_line: u32,
-    original_column: u32,
+    /// Source index, line and column; `None` for generated code without a source
+    original: Option<(u32, u32, u32)>,
     name_index: Option<u32>,
 }
 
@@ -88,6 +87,8 @@ impl AdvancedSourceMapGenerator {
     }
 
     /// Add a mapping between generated and original positions
+    ///
+    /// Lines are 1-based and columns 0-based.
     pub fn add_mapping(
         &mut self,
         generated_line: u32,
@@ -102,13 +103,21 @@ impl AdvancedSourceMapGenerator {
         self.mappings.push(Mapping {
             generated_line,
             generated_column,
-            source_index: source_index as u32,
-            original_line,
-            original_column,
+            original: Some((source_index as u32, original_line, original_column)),
             name_index,
         });
     }
 
+    /// Mark generated code that has no original source, e.g. bundler runtime code
+    pub fn add_unmapped(&mut self, generated_line: u32, generated_column: u32) {
+        self.mappings.push(Mapping {
+            generated_line,
+            generated_column,
+            original: None,
+            name_index: None,
+        });
+    }
+
     /// Generate the source map
     pub fn generate(&self, file_name: Option<String>) -> SourceMap {
         SourceMap {
@@ -123,13 +132,55 @@ impl AdvancedSourceMapGenerator {
     }
 
     /// Encode mappings to VLQ format
-    /// This is a simplified version - real implementation would use proper VLQ encoding
+    ///
+    /// Lines are separated by `;` and segments by `,`. Each segment holds the
+    /// generated column, then optionally the source index, original line,
+    /// original column and name index, each relative to the previous segment.
     fn encode_mappings(&self) -> String {
-        // For now, return empty string - full VLQ implementation would be complex
-        // In production, you'd use a proper VLQ encoder
-        // Format: semicolon-separated lines, comma-separated segments
-        // Each segment: [generated_column, source_index, original_line, original_column, name_index]
-        String::new()
+        let mut mappings: Vec<&Mapping> = self.mappings.iter().collect();
+        mappings.sort_by_key(|m| (m.generated_line, m.generated_column));
+
+        let mut encoded = String::new();
+        let mut line = 1;
+        let mut previous_column = 0i64;
+        let (mut previous_source, mut previous_line, mut previous_original_column) = (0, 0, 0);
+        let mut previous_name = 0i64;
+        for mapping in mappings {
+            if mapping.generated_line > line {
+                for _ in line..mapping.generated_line {
+                    encoded.push(';');
+                }
+                line = mapping.generated_line;
+                previous_column = 0;
+            } else if !encoded.is_empty() && !encoded.ends_with(';') {
+                encoded.push(',');
+            }
+
+            let column = mapping.generated_column as i64;
+            encode_vlq(&mut encoded, column - previous_column);
+            previous_column = column;
+
+            if let Some((source, original_line, original_column)) = mapping.original {
+                // Lines are 0-based in the encoding
+                let (source, original_line, original_column) = (
+                    source as i64,
+                    original_line.saturating_sub(1) as i64,
+                    original_column as i64,
+                );
+                encode_vlq(&mut encoded, source - previous_source);
+                encode_vlq(&mut encoded, original_line - previous_line);
+                encode_vlq(&mut encoded, original_column - previous_original_column);
+                previous_source = source;
+                previous_line = original_line;
+                previous_original_column = original_column;
+
+                if let Some(name) = mapping.name_index {
+                    encode_vlq(&mut encoded, name as i64 - previous_name);
+                    previous_name = name as i64;
+                }
+            }
+        }
+        encoded
     }
 
     /// Generate a simple mapping for concatenated files
@@ -161,6 +212,29 @@ impl AdvancedSourceMapGenerator {
     }
 }
 
+/// Append `value` as a base64 VLQ
+fn encode_vlq(output: &mut String, value: i64) {
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    // The sign goes into the lowest bit
+    let mut vlq = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (vlq & 0b11111) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        output.push(BASE64[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
 impl Default for AdvancedSourceMapGenerator {
     fn default() -> Self {
         Self::new()
@@ -213,15 +287,27 @@ impl SourceMapUtils {
     }
 }
 
+/// How a generated file refers to its source map
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceMapMode {
+    /// `.map` file next to the output, referenced by a `sourceMappingURL` comment
+    #[default]
+    External,
+    /// Data URL in the `sourceMappingURL` comment, no `.map` file
+    Inline,
+    /// `.map` file without the comment, e.g. for uploading to an error tracker
+    Hidden,
+}
+
 /// Configuration for source map generation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct SourceMapConfig {
+    /// Where the source map goes
+    pub mode: SourceMapMode,
     /// Include source contents in the source map
     pub include_sources_content: bool,
-    /// Generate inline source map (data URL)
-    pub inline: bool,
-    /// Source map file name (if external)
-    pub file_name: Option<String>,
     /// Source root path
     pub source_root: Option<String>,
 }
@@ -229,14 +315,58 @@ pub struct SourceMapConfig {
 impl Default for SourceMapConfig {
     fn default() -> Self {
         Self {
+            mode: SourceMapMode::External,
             include_sources_content: true,
-            inline: false,
-            file_name: Some("bundle.js.map".to_string()),
             source_root: None,
         }
     }
 }
 
+impl SourceMapConfig {
+    /// Apply the config to the source map of `code` and reference it from `code`
+    ///
+    /// Returns the content of the `map_file_name` file to write next to the
+    /// output, or `None` when the map is inlined. Stylesheets, whose map file
+    /// ends in `.css.map`, get a `/*# sourceMappingURL */` comment.
+    pub fn attach(
+        &self,
+        code: &mut String,
+        mut source_map: SourceMap,
+        map_file_name: &str,
+    ) -> Result<Option<String>> {
+        if !self.include_sources_content {
+            source_map.sources_content = None;
+        }
+        if self.source_root.is_some() {
+            source_map.source_root = self.source_root.clone();
+        }
+
+        let is_css = map_file_name.ends_with(".css.map");
+        let comment = |url: &str| {
+            if is_css {
+                format!("/*# sourceMappingURL={} */", url)
+            } else {
+                SourceMapUtils::generate_external_comment(url)
+            }
+        };
+
+        match self.mode {
+            SourceMapMode::Inline => {
+                code.push('\n');
+                code.push_str(&comment(&SourceMapUtils::to_inline_data_url(&source_map)?));
+                Ok(None)
+            }
+            SourceMapMode::External | SourceMapMode::Hidden => {
+                if self.mode == SourceMapMode::External {
+                    code.push('\n');
+                    code.push_str(&comment(map_file_name));
+                }
+                SourceMapUtils::to_json(&source_map).map(Some)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -291,6 +421,37 @@ mod tests {
         assert_eq!(generator.names.len(), 1);
     }
 
+    #[test]
+    fn test_encode_mappings_as_vlq() {
+        let mut generator = AdvancedSourceMapGenerator::new();
+        generator.add_source("test.js".to_string(), "var x = 1;\nx;".to_string());
+        generator.add_mapping(2, 0, 0, 2, 0, None);
+        generator.add_mapping(1, 0, 0, 1, 0, None);
+        generator.add_mapping(1, 4, 0, 1, 4, Some("x".to_string()));
+        generator.add_unmapped(3, 20);
+
+        let source_map = generator.generate(None);
+        assert_eq!(source_map.mappings, "AAAA,IAAIA;AACJ;oB");
+
+        let decoded = oxc_sourcemap::SourceMap::from_json_string(
+            &SourceMapUtils::to_json(&source_map).unwrap(),
+        )
+        .unwrap();
+        let tokens: Vec<_> = decoded
+            .get_tokens()
+            .map(|t| {
+                (
+                    t.get_dst_line(),
+                    t.get_dst_col(),
+                    t.get_src_line(),
+                    t.get_src_col(),
+                )
+            })
+            .collect();
+        assert_eq!(tokens[..3], [(0, 0, 0, 0), (0, 4, 0, 4), (1, 0, 1, 0)]);
+        assert_eq!(decoded.get_token(1).unwrap().get_name_id(), Some(0));
+    }
+
     #[test]
     fn test_source_map_to_json() {
         let source_map = SourceMap {
@@ -375,8 +536,57 @@ mod tests {
     fn test_source_map_config_defaults() {
         let config = SourceMapConfig::default();
         assert!(config.include_sources_content);
-        assert!(!config.inline);
-        assert_eq!(config.file_name, Some("bundle.js.map".to_string()));
+        assert_eq!(config.mode, SourceMapMode::External);
         assert_eq!(config.source_root, None);
     }
+
+    #[test]
+    fn test_attach_source_map_modes() {
+        let mut generator = AdvancedSourceMapGenerator::new();
+        generator.add_source("src/main.js".to_string(), "run();".to_string());
+        generator.add_mapping(1, 0, 0, 1, 0, None);
+        let source_map = generator.generate(Some("bundle.js".to_string()));
+
+        let mut code = "run();".to_string();
+        let json = SourceMapConfig::default()
+            .attach(&mut code, source_map.clone(), "bundle.js.map")
+            .unwrap()
+            .unwrap();
+        assert_eq!(code, "run();\n//# sourceMappingURL=bundle.js.map");
+        assert!(json.contains("sourcesContent"));
+
+        let config = SourceMapConfig {
+            mode: SourceMapMode::Hidden,
+            include_sources_content: false,
+            source_root: Some("https://example.com/src/".to_string()),
+        };
+        let mut code = "run();".to_string();
+        let json = config
+            .attach(&mut code, source_map.clone(), "bundle.js.map")
+            .unwrap()
+            .unwrap();
+        assert_eq!(code, "run();");
+        assert!(!json.contains("sourcesContent"));
+        assert!(json.contains("\"sourceRoot\": \"https://example.com/src/\""));
+
+        let config = SourceMapConfig {
+            mode: SourceMapMode::Inline,
+            ..Default::default()
+        };
+        let mut code = "run();".to_string();
+        assert!(config
+            .attach(&mut code, source_map.clone(), "bundle.js.map")
+            .unwrap()
+            .is_none());
+        assert!(code.starts_with(
+            "run();\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,"
+        ));
+
+        // Stylesheets only allow block comments
+        let mut code = "a{}".to_string();
+        SourceMapConfig::default()
+            .attach(&mut code, source_map, "bundle.css.map")
+            .unwrap();
+        assert_eq!(code, "a{}\n/*# sourceMappingURL=bundle.css.map */");
+    }
 }
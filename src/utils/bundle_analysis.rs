@@ -86,7 +86,7 @@ impl BundleAnalysis {
         }
 
         // Sort by size descending
-        module_stats.sort_by(|a, b| b.bundle_size.cmp(&a.bundle_size));
+        module_stats.sort_by_key(|m| std::cmp::Reverse(m.bundle_size));
 
         // Calculate type stats
         let mut type_map: HashMap<String, (usize, usize)> = HashMap::new();
@@ -112,7 +112,7 @@ impl BundleAnalysis {
             .collect();
 
         // Sort by size descending
-        type_stats.sort_by(|a, b| b.total_size.cmp(&a.total_size));
+        type_stats.sort_by_key(|t| std::cmp::Reverse(t.total_size));
 
         // Get top 10 largest modules
         let largest_modules = module_stats.iter().take(10).cloned().collect();
@@ -284,6 +284,7 @@ mod tests {
                 module_type: ModuleType::JavaScript,
                 dependencies: vec!["./utils.js".to_string()],
                 exports: vec![],
+                resolved_imports: std::collections::HashMap::new(),
             },
             ModuleInfo {
                 path: PathBuf::from("utils.js"),
@@ -291,6 +292,7 @@ mod tests {
                 module_type: ModuleType::JavaScript,
                 dependencies: vec![],
                 exports: vec![],
+                resolved_imports: std::collections::HashMap::new(),
             },
         ];
 
@@ -304,6 +306,7 @@ mod tests {
             build_time: std::time::Duration::from_millis(50),
             output_files: vec![],
             modules: modules.clone(),
+            module_linking: vec![],
         };
 
         let analysis = BundleAnalysis::analyze(&modules, &result);
@@ -0,0 +1,740 @@
+// Source maps through the build pipeline
+// Every stage that rewrites bundle code (transforms, linking, output format
+// wrappers, minification, env replacement) carries the mappings of its input
+// over to its output, so the final bundle maps back to the original sources
+
+use crate::utils::{is_virtual_module, module_display_name, AdvancedSourceMapGenerator};
+use oxc_sourcemap::SourceMap;
+use std::ops::{Deref, Range};
+use std::path::{Component, Path, PathBuf};
+
+/// Replacement of the byte range `start..end` of some code: (start, end, text)
+pub type TextEdit = (usize, usize, String);
+
+/// Where a piece of generated code comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Original {
+    source: u32,
+    /// Zero-based line and UTF-16 column, as in source maps
+    line: u32,
+    column: u32,
+    name: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping {
+    /// Byte offset in the generated code
+    generated: usize,
+    /// `None` for code soku generated itself (wrappers, runtime helpers)
+    original: Option<Original>,
+}
+
+/// Mappings of a piece of generated code back to its original sources
+///
+/// Positions in the generated code are byte offsets, so edits and
+/// concatenation only have to shift them; lines and columns are computed when
+/// the map is turned into a [`SourceMap`].
+#[derive(Debug, Clone, Default)]
+pub struct CodeMap {
+    sources: Vec<String>,
+    contents: Vec<String>,
+    names: Vec<String>,
+    /// Sorted by generated offset
+    mappings: Vec<Mapping>,
+}
+
+impl CodeMap {
+    /// Map of code that is its own source, with a mapping at every token
+    pub fn identity(source: &str, content: &str) -> Self {
+        #[derive(PartialEq)]
+        enum Class {
+            Space,
+            Word,
+            Punctuation,
+        }
+        let class = |c: char| {
+            if c.is_whitespace() {
+                Class::Space
+            } else if c.is_alphanumeric() || c == '_' || c == '$' {
+                Class::Word
+            } else {
+                Class::Punctuation
+            }
+        };
+
+        let mut mappings = Vec::new();
+        let (mut line, mut column) = (0u32, 0u32);
+        let mut previous = Class::Space;
+        for (offset, c) in content.char_indices() {
+            let current = class(c);
+            if current != Class::Space && current != previous {
+                mappings.push(Mapping {
+                    generated: offset,
+                    original: Some(Original {
+                        source: 0,
+                        line,
+                        column,
+                        name: None,
+                    }),
+                });
+            }
+            if c == '\n' {
+                line += 1;
+                column = 0;
+                previous = Class::Space;
+            } else {
+                column += c.len_utf16() as u32;
+                previous = current;
+            }
+        }
+
+        Self {
+            sources: vec![source.to_string()],
+            contents: vec![content.to_string()],
+            names: Vec::new(),
+            mappings,
+        }
+    }
+
+    /// Map of code generated from `content` by a tool that tells where its
+    /// output comes from
+    ///
+    /// Each pair maps the generated code from a byte offset on to a byte
+    /// offset of `content`, or marks it as having no original.
+    pub fn from_offsets(source: &str, content: &str, offsets: &[(usize, Option<usize>)]) -> Self {
+        let index = LineIndex::new(content);
+        let mappings = offsets
+            .iter()
+            .map(|&(generated, original)| Mapping {
+                generated,
+                original: original.map(|offset| {
+                    let (line, column) = index.position(content, offset);
+                    Original {
+                        source: 0,
+                        line,
+                        column,
+                        name: None,
+                    }
+                }),
+            })
+            .collect();
+
+        Self {
+            sources: vec![source.to_string()],
+            contents: vec![content.to_string()],
+            names: Vec::new(),
+            mappings: dedup_mappings(mappings),
+        }
+    }
+
+    /// Read the mappings of `map`, a source map of `code`
+    pub fn from_source_map(map: &SourceMap, code: &str) -> Self {
+        let index = LineIndex::new(code);
+        let mut mappings: Vec<Mapping> = map
+            .get_tokens()
+            .filter_map(|token| {
+                let generated = index.offset(code, token.get_dst_line(), token.get_dst_col())?;
+                Some(Mapping {
+                    generated,
+                    original: token.get_source_id().map(|source| Original {
+                        source,
+                        line: token.get_src_line(),
+                        column: token.get_src_col(),
+                        name: token.get_name_id(),
+                    }),
+                })
+            })
+            .collect();
+        mappings.sort_by_key(|mapping| mapping.generated);
+
+        let contents = map.get_source_contents().collect::<Vec<_>>();
+        Self {
+            sources: map.get_sources().map(|source| source.to_string()).collect(),
+            contents: (0..map.get_sources().count())
+                .map(|i| {
+                    contents
+                        .get(i)
+                        .copied()
+                        .flatten()
+                        .map(|content| content.to_string())
+                        .unwrap_or_default()
+                })
+                .collect(),
+            names: map.get_names().map(|name| name.to_string()).collect(),
+            mappings,
+        }
+    }
+
+    /// Map `map`, which maps `code` to `intermediate_code`, through `self`,
+    /// the map of `intermediate_code`
+    pub fn compose(&self, intermediate_code: &str, map: &SourceMap, code: &str) -> Self {
+        let generated_index = LineIndex::new(code);
+        let intermediate_index = LineIndex::new(intermediate_code);
+        let mut composed = Self {
+            sources: self.sources.clone(),
+            contents: self.contents.clone(),
+            names: self.names.clone(),
+            mappings: Vec::new(),
+        };
+
+        for token in map.get_tokens() {
+            let Some(generated) =
+                generated_index.offset(code, token.get_dst_line(), token.get_dst_col())
+            else {
+                continue;
+            };
+            let original = token
+                .get_source_id()
+                .and_then(|_| {
+                    intermediate_index.offset(
+                        intermediate_code,
+                        token.get_src_line(),
+                        token.get_src_col(),
+                    )
+                })
+                .and_then(|offset| self.original_at(intermediate_code, offset))
+                .map(|mut original| {
+                    if original.name.is_none() {
+                        original.name = token
+                            .get_name_id()
+                            .and_then(|id| map.get_name(id))
+                            .map(|name| composed.add_name(name));
+                    }
+                    original
+                });
+            composed.mappings.push(Mapping {
+                generated,
+                original,
+            });
+        }
+        composed.mappings.sort_by_key(|mapping| mapping.generated);
+        composed
+    }
+
+    /// Map of `code` after replacing the sorted, non-overlapping `edits`
+    /// (start, end, replacement) in it
+    ///
+    /// Replacements map to the original position of the range they replace.
+    pub fn apply_edits(&self, code: &str, edits: &[TextEdit]) -> Self {
+        let mut mappings = Vec::with_capacity(self.mappings.len());
+        let mut next = 0;
+        let mut delta = 0isize;
+        let shift = |offset: usize, delta: isize| (offset as isize + delta) as usize;
+
+        for (start, end, text) in edits {
+            while next < self.mappings.len() && self.mappings[next].generated < *start {
+                let mapping = self.mappings[next];
+                mappings.push(Mapping {
+                    generated: shift(mapping.generated, delta),
+                    ..mapping
+                });
+                next += 1;
+            }
+            if !text.is_empty() {
+                mappings.push(Mapping {
+                    generated: shift(*start, delta),
+                    original: self.original_at(code, *start),
+                });
+            }
+            while next < self.mappings.len() && self.mappings[next].generated < *end {
+                next += 1;
+            }
+            delta += text.len() as isize - (*end - *start) as isize;
+
+            // The code after the edit continues where it was
+            let resumes_unmapped =
+                next >= self.mappings.len() || self.mappings[next].generated != *end;
+            if end > start && *end < code.len() && resumes_unmapped {
+                mappings.push(Mapping {
+                    generated: shift(*end, delta),
+                    original: self.original_at(code, *end),
+                });
+            }
+        }
+        mappings.extend(self.mappings[next..].iter().map(|mapping| Mapping {
+            generated: shift(mapping.generated, delta),
+            ..*mapping
+        }));
+
+        Self {
+            sources: self.sources.clone(),
+            contents: self.contents.clone(),
+            names: self.names.clone(),
+            mappings: dedup_mappings(mappings),
+        }
+    }
+
+    /// Add the mappings of code inserted at `offset`
+    pub fn append(&mut self, other: &CodeMap, offset: usize) {
+        let sources: Vec<u32> = other
+            .sources
+            .iter()
+            .zip(&other.contents)
+            .map(|(source, content)| self.add_source(source, content))
+            .collect();
+        let names: Vec<u32> = other.names.iter().map(|name| self.add_name(name)).collect();
+
+        let start = self.mappings.len();
+        self.mappings
+            .extend(other.mappings.iter().map(|mapping| Mapping {
+                generated: mapping.generated + offset,
+                original: mapping.original.map(|original| Original {
+                    source: sources[original.source as usize],
+                    name: original.name.map(|name| names[name as usize]),
+                    ..original
+                }),
+            }));
+        if self.mappings[..start]
+            .last()
+            .is_some_and(|last| last.generated > offset)
+        {
+            self.mappings.sort_by_key(|mapping| mapping.generated);
+        }
+    }
+
+    /// Mark the code from `offset` on as generated by soku
+    pub fn add_unmapped(&mut self, offset: usize) {
+        self.mappings.push(Mapping {
+            generated: offset,
+            original: None,
+        });
+        if self.mappings.len() > 1 && self.mappings[self.mappings.len() - 2].generated > offset {
+            self.mappings.sort_by_key(|mapping| mapping.generated);
+        }
+    }
+
+    /// Rename the sources, e.g. to make them relative to the output directory
+    pub fn rename_sources(&mut self, rename: impl Fn(&str) -> String) {
+        for source in &mut self.sources {
+            *source = rename(source);
+        }
+    }
+
+    /// Source map of `code`, the code this map describes
+    pub fn generate(&self, code: &str, file: Option<String>) -> crate::utils::SourceMap {
+        let index = LineIndex::new(code);
+        let mut generator = AdvancedSourceMapGenerator::new();
+        for (source, content) in self.sources.iter().zip(&self.contents) {
+            generator.add_source(source.clone(), content.clone());
+        }
+
+        let mut previous: Option<Option<Original>> = None;
+        for mapping in dedup_mappings(self.mappings.clone()) {
+            if mapping.generated > code.len() {
+                break;
+            }
+            // Unmapped code only needs marking after mapped code
+            if mapping.original.is_none() && previous.is_none_or(|original| original.is_none()) {
+                continue;
+            }
+            previous = Some(mapping.original);
+            let (line, column) = index.position(code, mapping.generated);
+            match mapping.original {
+                Some(original) => generator.add_mapping(
+                    line + 1,
+                    column,
+                    original.source as usize,
+                    original.line + 1,
+                    original.column,
+                    original.name.map(|name| self.names[name as usize].clone()),
+                ),
+                None => generator.add_unmapped(line + 1, column),
+            }
+        }
+        generator.generate(file)
+    }
+
+    /// Original position of the generated code at `offset`
+    ///
+    /// Code between two mappings is taken to continue the first one
+    /// unchanged, which holds for code copied from a source.
+    fn original_at(&self, code: &str, offset: usize) -> Option<Original> {
+        let index = self
+            .mappings
+            .partition_point(|mapping| mapping.generated <= offset);
+        let mapping = self.mappings.get(index.checked_sub(1)?)?;
+        let mut original = mapping.original?;
+        if mapping.generated == offset {
+            return Some(original);
+        }
+
+        let between = code.get(mapping.generated..offset)?;
+        match between.rfind('\n') {
+            Some(newline) => {
+                original.line += between.matches('\n').count() as u32;
+                original.column = utf16_len(&between[newline + 1..]);
+            }
+            None => original.column += utf16_len(between),
+        }
+        original.name = None;
+        Some(original)
+    }
+
+    fn add_source(&mut self, source: &str, content: &str) -> u32 {
+        if let Some(index) = self.sources.iter().position(|known| known == source) {
+            return index as u32;
+        }
+        self.sources.push(source.to_string());
+        self.contents.push(content.to_string());
+        (self.sources.len() - 1) as u32
+    }
+
+    fn add_name(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.names.iter().position(|known| known == name) {
+            return index as u32;
+        }
+        self.names.push(name.to_string());
+        (self.names.len() - 1) as u32
+    }
+}
+
+/// Sort mappings by generated offset, keeping the last one of each offset
+fn dedup_mappings(mut mappings: Vec<Mapping>) -> Vec<Mapping> {
+    mappings.sort_by_key(|mapping| mapping.generated);
+    let mut deduped: Vec<Mapping> = Vec::with_capacity(mappings.len());
+    for mapping in mappings {
+        match deduped.last_mut() {
+            Some(last) if last.generated == mapping.generated => *last = mapping,
+            _ => deduped.push(mapping),
+        }
+    }
+    deduped
+}
+
+fn utf16_len(text: &str) -> u32 {
+    if text.is_ascii() {
+        text.len() as u32
+    } else {
+        text.encode_utf16().count() as u32
+    }
+}
+
+/// Code assembled from pieces, collecting the maps of the mapped ones
+pub struct MappedCode {
+    pub code: String,
+    /// `None` when the code is assembled without a map
+    pub map: Option<CodeMap>,
+}
+
+impl MappedCode {
+    pub fn new(with_map: bool) -> Self {
+        Self {
+            code: String::new(),
+            map: with_map.then(CodeMap::default),
+        }
+    }
+
+    /// Append code soku generated
+    pub fn push_str(&mut self, text: &str) {
+        if let Some(map) = &mut self.map {
+            if !text.is_empty() {
+                map.add_unmapped(self.code.len());
+            }
+        }
+        self.code.push_str(text);
+    }
+
+    /// Append code described by `text_map`
+    pub fn push_mapped(&mut self, text: &str, text_map: Option<&CodeMap>) {
+        match (&mut self.map, text_map) {
+            (Some(map), Some(text_map)) => map.append(text_map, self.code.len()),
+            (Some(map), None) if !text.is_empty() => map.add_unmapped(self.code.len()),
+            _ => {}
+        }
+        self.code.push_str(text);
+    }
+}
+
+/// Code changed by successive replacements, which a map of the code before
+/// the changes can follow
+pub struct RewrittenCode {
+    code: String,
+    replacements: Vec<Replacement>,
+}
+
+struct Replacement {
+    range: Range<usize>,
+    len: usize,
+    /// Start of the replaced text the replacement was copied from
+    copied_from: Option<usize>,
+}
+
+impl RewrittenCode {
+    pub fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            replacements: Vec::new(),
+        }
+    }
+
+    /// Replace `range` with `text`
+    pub fn replace_range(&mut self, range: Range<usize>, text: &str) {
+        self.replacements.push(Replacement {
+            range: range.clone(),
+            len: text.len(),
+            copied_from: None,
+        });
+        self.code.replace_range(range, text);
+    }
+
+    /// Replace `range` with the part of it at `kept`, e.g. unwrap a block
+    pub fn keep_range(&mut self, range: Range<usize>, kept: Range<usize>) {
+        let text = self.code[kept.clone()].to_string();
+        self.replacements.push(Replacement {
+            range: range.clone(),
+            len: text.len(),
+            copied_from: Some(kept.start),
+        });
+        self.code.replace_range(range, &text);
+    }
+
+    pub fn into_string(self) -> String {
+        self.code
+    }
+
+    /// Map of the rewritten code, given `map` of the code before
+    pub fn rewrite_map(&self, map: &CodeMap) -> CodeMap {
+        let mappings = map
+            .mappings
+            .iter()
+            .filter_map(|mapping| {
+                let mut offset = mapping.generated;
+                for replacement in &self.replacements {
+                    offset = replacement.shift(offset)?;
+                }
+                Some(Mapping {
+                    generated: offset,
+                    ..*mapping
+                })
+            })
+            .collect();
+        CodeMap {
+            mappings: dedup_mappings(mappings),
+            ..map.clone()
+        }
+    }
+}
+
+impl Replacement {
+    /// Where the code at `offset` is after this replacement, if it survives
+    fn shift(&self, offset: usize) -> Option<usize> {
+        let Range { start, end } = self.range;
+        if offset < start {
+            return Some(offset);
+        }
+        if offset >= end {
+            return Some(offset - (end - start) + self.len);
+        }
+        if let Some(from) = self.copied_from {
+            if (from..from + self.len).contains(&offset) {
+                return Some(start + offset - from);
+            }
+        }
+        (offset == start).then_some(start)
+    }
+}
+
+impl Deref for RewrittenCode {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.code
+    }
+}
+
+/// `path` relative to the directory `dir`, with `/` separators as source maps expect
+///
+/// Virtual modules have no location and keep their id.
+pub fn relative_source_path(path: &Path, dir: &Path) -> String {
+    if is_virtual_module(path) {
+        return module_display_name(path);
+    }
+    let absolute = |path: &Path| {
+        std::path::absolute(path)
+            .map(|path| normalize(&path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    let (path, dir) = (absolute(path), absolute(dir));
+
+    let path_components: Vec<Component> = path.components().collect();
+    let dir_components: Vec<Component> = dir.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&dir_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+
+    let mut parts: Vec<String> = vec!["..".to_string(); dir_components.len() - common];
+    parts.extend(
+        path_components[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+/// Resolve `.` and `..` without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Converts byte offsets to lines and UTF-16 columns and back
+struct LineIndex {
+    line_starts: Vec<usize>,
+    /// Offsets of non-ASCII characters and the bytes they take beyond their
+    /// UTF-16 length, summed over all characters before and including them
+    wide: Vec<(usize, usize)>,
+}
+
+impl LineIndex {
+    fn new(code: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide = Vec::new();
+        let mut extra = 0;
+        if code.is_ascii() {
+            line_starts.extend(code.match_indices('\n').map(|(offset, _)| offset + 1));
+        } else {
+            for (offset, c) in code.char_indices() {
+                if c == '\n' {
+                    line_starts.push(offset + 1);
+                } else if !c.is_ascii() {
+                    extra += c.len_utf8() - c.len_utf16();
+                    wide.push((offset, extra));
+                }
+            }
+        }
+        Self { line_starts, wide }
+    }
+
+    /// Extra bytes of the wide characters before `offset`
+    fn extra_before(&self, offset: usize) -> usize {
+        let index = self.wide.partition_point(|&(at, _)| at < offset);
+        index.checked_sub(1).map_or(0, |i| self.wide[i].1)
+    }
+
+    fn position(&self, code: &str, offset: usize) -> (u32, u32) {
+        let offset = offset.min(code.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let column = (offset - start) - (self.extra_before(offset) - self.extra_before(start));
+        (line as u32, column as u32)
+    }
+
+    fn offset(&self, code: &str, line: u32, column: u32) -> Option<usize> {
+        let start = *self.line_starts.get(line as usize)?;
+        let end = self
+            .line_starts
+            .get(line as usize + 1)
+            .map_or(code.len(), |next| next - 1);
+        let base = self.extra_before(start);
+
+        // Last wide character of the line that starts before `column`
+        let first = self.wide.partition_point(|&(at, _)| at < start);
+        let last = self.wide.partition_point(|&(at, _)| at < end);
+        let wide = &self.wide[first..last];
+        let before = wide.partition_point(|&(at, _)| {
+            (at - start) - (self.extra_before(at) - base) < column as usize
+        });
+        let extra = before.checked_sub(1).map_or(0, |i| wide[i].1 - base);
+        Some((start + column as usize + extra).min(end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Original (line, column) of the generated `needle`
+    fn lookup(
+        map: &crate::utils::SourceMap,
+        code: &str,
+        needle: &str,
+    ) -> Option<(String, u32, u32)> {
+        let map = SourceMap::from_json_string(&serde_json::to_string(map).unwrap()).unwrap();
+        let offset = code.find(needle).unwrap();
+        let (line, column) = LineIndex::new(code).position(code, offset);
+        let table = map.generate_lookup_table();
+        let token = map.lookup_token(&table, line, column)?;
+        Some((
+            map.get_source(token.get_source_id()?)?.to_string(),
+            token.get_src_line(),
+            token.get_src_col(),
+        ))
+    }
+
+    #[test]
+    fn test_line_index_counts_utf16_columns() {
+        let code = "const s = 'é😀';\nlet x = 1;";
+        let index = LineIndex::new(code);
+        let x = code.find('x').unwrap();
+        assert_eq!(index.position(code, x), (1, 4));
+        let semicolon = code.find(';').unwrap();
+        assert_eq!(index.position(code, semicolon), (0, 15));
+        assert_eq!(index.offset(code, 0, 15), Some(semicolon));
+        assert_eq!(index.offset(code, 1, 4), Some(x));
+    }
+
+    #[test]
+    fn test_edits_and_concatenation_keep_positions() {
+        let a = "import { b } from './b';\nconsole.log(b);\n";
+        let b = "export const b = 1;\n";
+        let a_map = CodeMap::identity("a.js", a)
+            .apply_edits(a, &[(0, 25, String::new()), (38, 39, "b$1".to_string())]);
+        let b_map = CodeMap::identity("b.js", b).apply_edits(b, &[(0, 7, String::new())]);
+
+        let mut bundle = MappedCode::new(true);
+        bundle.push_str("// b\n");
+        bundle.push_mapped("const b = 1;\n", Some(&b_map));
+        bundle.push_str("// a\n");
+        bundle.push_mapped("console.log(b$1);\n", Some(&a_map));
+
+        let map = bundle.map.unwrap().generate(&bundle.code, None);
+        let code = &bundle.code;
+        assert_eq!(lookup(&map, code, "const"), Some(("b.js".into(), 0, 7)));
+        assert_eq!(lookup(&map, code, "console"), Some(("a.js".into(), 1, 0)));
+        assert_eq!(lookup(&map, code, "b$1"), Some(("a.js".into(), 1, 12)));
+        assert_eq!(lookup(&map, code, ");"), Some(("a.js".into(), 1, 13)));
+        assert_eq!(lookup(&map, code, "// a"), None);
+    }
+
+    #[test]
+    fn test_rewritten_code_follows_replacements() {
+        let code = "if(true){run()}else{skip()}stop()";
+        let map = CodeMap::identity("a.js", code);
+        let mut rewritten = RewrittenCode::new(code);
+        rewritten.keep_range(0..27, 9..14);
+        assert_eq!(&*rewritten, "run()stop()");
+
+        let map = rewritten.rewrite_map(&map).generate(&rewritten, None);
+        assert_eq!(lookup(&map, &rewritten, "run"), Some(("a.js".into(), 0, 9)));
+        assert_eq!(
+            lookup(&map, &rewritten, "stop"),
+            Some(("a.js".into(), 0, 27))
+        );
+    }
+
+    #[test]
+    fn test_relative_source_path() {
+        assert_eq!(
+            relative_source_path(Path::new("/app/src/main.ts"), Path::new("/app/dist")),
+            "../src/main.ts"
+        );
+        assert_eq!(
+            relative_source_path(Path::new("/app/./src/a.js"), Path::new("/app")),
+            "src/a.js"
+        );
+    }
+}
@@ -1,5 +1,6 @@
-use crate::core::models::BuildConfig;
-use crate::utils::{Logger, Result, SokuError};
+use crate::core::models::{BuildConfig, JsxConfig, OutputFormat};
+use crate::utils::{Logger, Result, SokuError, SourceMapConfig, SourceMapMode};
+use oxc_transformer::ESTarget;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -25,10 +26,22 @@ pub struct SokuConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub minify: Option<bool>,
 
-    /// Enable/disable source maps (default: false)
+    /// Enable/disable source maps (default: false); `sourcemap` takes precedence
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source_maps: Option<bool>,
 
+    /// Source maps: true (external .map file), "inline", "hidden" (no sourceMappingURL comment) or false
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sourcemap: Option<SourceMapOption>,
+
+    /// Embed the original sources in the source maps (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sources_content: Option<bool>,
+
+    /// `sourceRoot` of the source maps, e.g. a URL where the sources are served
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_root: Option<String>,
+
     /// Enable/disable tree shaking (default: true)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tree_shaking: Option<bool>,
@@ -56,6 +69,34 @@ pub struct SokuConfig {
     /// Enable automatic vendor chunk splitting (node_modules → vendor.js)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vendor_chunk: Option<bool>,
+
+    /// Output format: "esm", "cjs", "iife" or "umd" (default: "iife")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+
+    /// Global variable name for iife/umd bundles (e.g., "MyLib")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub global_name: Option<String>,
+
+    /// Globals iife/umd bundles read externals from (e.g., {"react": "React"})
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub globals: Option<HashMap<String, String>>,
+
+    /// Treat circular imports as build errors instead of warnings (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_on_circular: Option<bool>,
+
+    /// File name template for JavaScript bundles (default: "[name].js", e.g. "[name].[hash].js")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub js_file_names: Option<String>,
+
+    /// File name template for CSS and assets (default: "[name].[ext]", e.g. "[name].[hash].[ext]")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_file_names: Option<String>,
+
+    /// JSX transform: {"runtime": "automatic"|"classic", "importSource", "pragma", "pragmaFrag", "development"}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsx: Option<JsxConfig>,
 }
 
 impl Default for SokuConfig {
@@ -66,6 +107,9 @@ impl Default for SokuConfig {
             outdir: Some("dist".to_string()),
             minify: Some(true),
             source_maps: Some(false),
+            sourcemap: None,
+            sources_content: None,
+            source_root: None,
             tree_shaking: Some(true),
             target: Some("es2020".to_string()),
             code_splitting: Some(false),
@@ -73,10 +117,55 @@ impl Default for SokuConfig {
             alias: None,
             external: None,
             vendor_chunk: Some(false),
+            format: Some(OutputFormat::Iife),
+            global_name: None,
+            globals: None,
+            fail_on_circular: Some(false),
+            js_file_names: None,
+            asset_file_names: None,
+            jsx: None,
         }
     }
 }
 
+/// `sourcemap` option: `true | "inline" | "hidden" | false`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SourceMapOption {
+    /// `true` for external maps, `false` for none
+    Enabled(bool),
+    Mode(SourceMapMode),
+}
+
+impl SourceMapOption {
+    /// Where the maps go, or `None` when they are disabled
+    pub fn mode(self) -> Option<SourceMapMode> {
+        match self {
+            SourceMapOption::Enabled(enabled) => enabled.then_some(SourceMapMode::External),
+            SourceMapOption::Mode(mode) => Some(mode),
+        }
+    }
+}
+
+/// Parse a `target` option such as "es2017" or "esnext"
+pub fn parse_target(target: &str) -> Result<ESTarget> {
+    target.parse().map_err(|e| {
+        SokuError::config(format!(
+            "Unsupported target \"{}\": {} Use es2015 to es2025 or esnext",
+            target, e
+        ))
+    })
+}
+
+/// Resolve a config/CLI path relative to the project root
+fn resolve_path(root: &Path, path: &str) -> PathBuf {
+    if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        root.join(path)
+    }
+}
+
 /// Config loader that supports config files with CLI override
 pub struct ConfigLoader;
 
@@ -97,6 +186,9 @@ impl ConfigLoader {
 
         let config: SokuConfig = serde_json::from_str(&content)
             .map_err(|e| SokuError::config(format!("Failed to parse soku.config.json: {}", e)))?;
+        if let Some(target) = &config.target {
+            parse_target(target)?;
+        }
 
         Logger::debug("✅ Config file loaded successfully");
         Ok(Some(config))
@@ -110,10 +202,13 @@ impl ConfigLoader {
         outdir: Option<&str>,
         enable_tree_shaking: Option<bool>,
         enable_minification: Option<bool>,
-        enable_source_maps: Option<bool>,
+        source_maps: Option<SourceMapOption>,
         enable_code_splitting: Option<bool>,
         max_chunk_size: Option<usize>,
         mode: String,
+        format: Option<OutputFormat>,
+        global_name: Option<String>,
+        cli_entries: Vec<String>,
     ) -> BuildConfig {
         let base = file_config.unwrap_or_default();
 
@@ -121,48 +216,67 @@ impl ConfigLoader {
         let outdir_str = outdir.unwrap_or_else(|| base.outdir.as_deref().unwrap_or("dist"));
 
         // Resolve outdir relative to root if it's a relative path
-        let resolved_outdir = if Path::new(outdir_str).is_absolute() {
-            PathBuf::from(outdir_str)
-        } else {
-            root.join(outdir_str)
-        };
+        let resolved_outdir = resolve_path(&root, outdir_str);
 
-        // Process entries: entries takes precedence over entry
-        let entries = if let Some(entries_map) = base.entries {
+        // Process entries: CLI --entry > entries > entry
+        let entries = if !cli_entries.is_empty() {
+            let single = cli_entries.len() == 1;
+            cli_entries
+                .into_iter()
+                .map(|value| match value.split_once('=') {
+                    Some((name, path_str)) => (name.to_string(), resolve_path(&root, path_str)),
+                    None => {
+                        let entry_path = resolve_path(&root, &value);
+                        // A single unnamed entry behaves like `entry` in the config file
+                        let name = if single {
+                            "main".to_string()
+                        } else {
+                            entry_path
+                                .file_stem()
+                                .and_then(|s| s.to_str())
+                                .unwrap_or("main")
+                                .to_string()
+                        };
+                        (name, entry_path)
+                    }
+                })
+                .collect()
+        } else if let Some(entries_map) = base.entries {
             // Multiple entries from config
             entries_map
                 .into_iter()
-                .map(|(name, path_str)| {
-                    let entry_path = if Path::new(&path_str).is_absolute() {
-                        PathBuf::from(path_str)
-                    } else {
-                        root.join(&path_str)
-                    };
-                    (name, entry_path)
-                })
+                .map(|(name, path_str)| (name, resolve_path(&root, &path_str)))
                 .collect()
         } else if let Some(entry_str) = base.entry {
             // Single entry from config (backward compatible)
-            let entry_path = if Path::new(&entry_str).is_absolute() {
-                PathBuf::from(entry_str)
-            } else {
-                root.join(&entry_str)
-            };
             let mut map = HashMap::new();
-            map.insert("main".to_string(), entry_path);
+            map.insert("main".to_string(), resolve_path(&root, &entry_str));
             map
         } else {
             HashMap::new()
         };
 
+        // Source maps: CLI > `sourcemap` > `sourceMaps`
+        let source_map_mode = match source_maps.or(base.sourcemap) {
+            Some(option) => option.mode(),
+            None => base
+                .source_maps
+                .unwrap_or(false)
+                .then_some(SourceMapMode::External),
+        };
+
         BuildConfig {
             root,
             outdir: resolved_outdir,
             enable_tree_shaking: enable_tree_shaking
                 .unwrap_or_else(|| base.tree_shaking.unwrap_or(true)),
             enable_minification: enable_minification.unwrap_or_else(|| base.minify.unwrap_or(true)),
-            enable_source_maps: enable_source_maps
-                .unwrap_or_else(|| base.source_maps.unwrap_or(false)),
+            enable_source_maps: source_map_mode.is_some(),
+            source_map: SourceMapConfig {
+                mode: source_map_mode.unwrap_or_default(),
+                include_sources_content: base.sources_content.unwrap_or(true),
+                source_root: base.source_root,
+            },
             enable_code_splitting: enable_code_splitting
                 .unwrap_or_else(|| base.code_splitting.unwrap_or(false)),
             max_chunk_size: max_chunk_size.or(base.max_chunk_size).or(Some(250_000)),
@@ -171,6 +285,21 @@ impl ConfigLoader {
             external: base.external.unwrap_or_default(),
             vendor_chunk: base.vendor_chunk.unwrap_or(false),
             entries,
+            format: format.or(base.format).unwrap_or_default(),
+            global_name: global_name.or(base.global_name),
+            globals: base.globals.unwrap_or_default(),
+            fail_on_circular: base.fail_on_circular.unwrap_or(false),
+            js_file_names: base
+                .js_file_names
+                .unwrap_or_else(|| "[name].js".to_string()),
+            asset_file_names: base
+                .asset_file_names
+                .unwrap_or_else(|| "[name].[ext]".to_string()),
+            target: base.target.unwrap_or_else(|| "es2020".to_string()),
+            jsx: base.jsx.unwrap_or_default(),
+            // Read from tsconfig.json, see `load_tsconfig`
+            typescript: Default::default(),
+            hmr: false,
         }
     }
 
@@ -244,11 +373,149 @@ mod tests {
             None,
             None,
             "production".to_string(),
+            None,
+            None,
+            Vec::new(),
         );
 
         assert_eq!(merged.outdir, PathBuf::from("./dist-override")); // Resolved relative to root
         assert!(merged.enable_minification); // CLI wins
         assert_eq!(merged.mode, "production");
+        assert_eq!(merged.format, OutputFormat::Iife);
+    }
+
+    #[test]
+    fn test_merge_format_options() {
+        let file_config: SokuConfig =
+            serde_json::from_str(r#"{"format": "umd", "globalName": "MyLib"}"#).unwrap();
+
+        let merged = ConfigLoader::merge_with_cli(
+            Some(file_config.clone()),
+            PathBuf::from("."),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "production".to_string(),
+            None,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(merged.format, OutputFormat::Umd);
+        assert_eq!(merged.global_name.as_deref(), Some("MyLib"));
+
+        let merged = ConfigLoader::merge_with_cli(
+            Some(file_config),
+            PathBuf::from("."),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            "production".to_string(),
+            Some(OutputFormat::Esm), // CLI override
+            None,
+            Vec::new(),
+        );
+        assert_eq!(merged.format, OutputFormat::Esm);
+    }
+
+    #[test]
+    fn test_merge_entry_options() {
+        let file_config = SokuConfig {
+            entry: Some("src/main.js".to_string()),
+            ..Default::default()
+        };
+        let merge = |cli_entries: Vec<&str>| {
+            ConfigLoader::merge_with_cli(
+                Some(file_config.clone()),
+                PathBuf::from("/app"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                "production".to_string(),
+                None,
+                None,
+                cli_entries.into_iter().map(String::from).collect(),
+            )
+            .entries
+        };
+
+        let entries = merge(vec![]);
+        assert_eq!(entries["main"], PathBuf::from("/app/src/main.js"));
+
+        let entries = merge(vec!["src/app.ts"]); // CLI override
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries["main"], PathBuf::from("/app/src/app.ts"));
+
+        let entries = merge(vec!["src/app.ts", "admin=src/admin/index.ts"]);
+        assert_eq!(entries["app"], PathBuf::from("/app/src/app.ts"));
+        assert_eq!(entries["admin"], PathBuf::from("/app/src/admin/index.ts"));
+    }
+
+    #[test]
+    fn test_merge_source_map_options() {
+        let merge = |json: &str, cli: Option<SourceMapOption>| {
+            ConfigLoader::merge_with_cli(
+                Some(serde_json::from_str(json).unwrap()),
+                PathBuf::from("."),
+                None,
+                None,
+                None,
+                cli,
+                None,
+                None,
+                "production".to_string(),
+                None,
+                None,
+                Vec::new(),
+            )
+        };
+
+        let merged = merge(r#"{"sourceMaps": true}"#, None);
+        assert!(merged.enable_source_maps);
+        assert_eq!(merged.source_map, SourceMapConfig::default());
+
+        let merged = merge(
+            r#"{"sourceMaps": true, "sourcemap": "hidden", "sourcesContent": false, "sourceRoot": "/src/"}"#,
+            None,
+        );
+        assert!(merged.enable_source_maps);
+        assert_eq!(merged.source_map.mode, SourceMapMode::Hidden);
+        assert!(!merged.source_map.include_sources_content);
+        assert_eq!(merged.source_map.source_root.as_deref(), Some("/src/"));
+
+        let merged = merge(r#"{"sourcemap": true}"#, None);
+        assert_eq!(merged.source_map.mode, SourceMapMode::External);
+        let merged = merge(r#"{"sourcemap": "inline"}"#, None);
+        assert_eq!(merged.source_map.mode, SourceMapMode::Inline);
+
+        // CLI override
+        let merged = merge(
+            r#"{"sourcemap": "inline"}"#,
+            Some(SourceMapOption::Enabled(false)),
+        );
+        assert!(!merged.enable_source_maps);
+        assert!(serde_json::from_str::<SokuConfig>(r#"{"sourcemap": "eval"}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(parse_target("es2017").unwrap(), ESTarget::ES2017);
+        assert_eq!(parse_target("ES6").unwrap(), ESTarget::ES2015);
+        assert_eq!(parse_target("ESNext").unwrap(), ESTarget::ESNext);
+        assert!(parse_target("es5").is_err());
+        assert!(parse_target("es2017x").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("soku.config.json"), r#"{"target": "es3"}"#).unwrap();
+        assert!(ConfigLoader::load_from_file(dir.path()).is_err());
     }
 
     #[test]
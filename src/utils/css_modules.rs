@@ -1,7 +1,7 @@
 // CSS Modules support for Soku Bundler
 // Provides scoped CSS with unique class names to avoid global namespace pollution
 
-use crate::utils::Result;
+use crate::utils::{Result, RewrittenCode};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,6 +9,7 @@ use std::path::Path;
 
 /// CSS Module transformation result
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)] // Part of public API
 pub struct CssModuleResult {
     /// Transformed CSS with scoped class names
     pub css: String,
@@ -44,12 +45,23 @@ impl CssModulesProcessor {
     }
 
     /// Process CSS content and generate scoped class names
+    #[allow(dead_code)] // Part of public API
     pub fn process(&self, content: &str, path: &Path) -> Result<CssModuleResult> {
+        let (css, exports) = self.rewrite(content, path);
+        Ok(CssModuleResult {
+            css: css.into_string(),
+            exports,
+        })
+    }
+
+    /// Scope the class and ID names of `content`, keeping the replacements so
+    /// source maps can follow them
+    pub fn rewrite(&self, content: &str, path: &Path) -> (RewrittenCode, HashMap<String, String>) {
         let module_name = self.get_module_name(path);
         let hash = self.generate_hash(content);
 
         let mut exports = HashMap::new();
-        let mut transformed_css = content.to_string();
+        let mut transformed_css = RewrittenCode::new(content);
 
         // Extract and transform class names
         let class_names = self.extract_class_names(content);
@@ -67,9 +79,7 @@ impl CssModulesProcessor {
             // Use word boundaries to avoid replacing partial matches
             let pattern = format!(r"\.{}\b", regex::escape(&class_name));
             let re = Regex::new(&pattern).unwrap();
-            transformed_css = re
-                .replace_all(&transformed_css, format!(".{}", scoped_name))
-                .to_string();
+            replace_all(&mut transformed_css, &re, &format!(".{}", scoped_name));
         }
 
         // Extract and transform ID names
@@ -82,15 +92,10 @@ impl CssModulesProcessor {
             // Replace all occurrences of the ID name
             let pattern = format!(r"#{}\b", regex::escape(&id_name));
             let re = Regex::new(&pattern).unwrap();
-            transformed_css = re
-                .replace_all(&transformed_css, format!("#{}", scoped_name))
-                .to_string();
+            replace_all(&mut transformed_css, &re, &format!("#{}", scoped_name));
         }
 
-        Ok(CssModuleResult {
-            css: transformed_css,
-            exports,
-        })
+        (transformed_css, exports)
     }
 
     /// Extract all class names from CSS content
@@ -147,6 +152,15 @@ impl CssModulesProcessor {
     }
 }
 
+/// Replace every match of `re`, like `Regex::replace_all`
+fn replace_all(code: &mut RewrittenCode, re: &Regex, replacement: &str) {
+    let matches: Vec<_> = re.find_iter(code).map(|m| m.range()).collect();
+    // From the end, so the earlier ranges stay valid
+    for range in matches.into_iter().rev() {
+        code.replace_range(range, replacement);
+    }
+}
+
 impl Default for CssModulesProcessor {
     fn default() -> Self {
         Self::new()
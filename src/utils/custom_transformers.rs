@@ -546,6 +546,7 @@ mod tests {
             module_type: crate::core::models::ModuleType::JavaScript,
             dependencies: Vec::new(),
             exports: Vec::new(),
+            resolved_imports: std::collections::HashMap::new(),
         };
 
         let result = plugin
@@ -1,4 +1,5 @@
-use crate::utils::Logger;
+use crate::utils::{Logger, RewrittenCode};
+use std::ops::Range;
 
 /// Dead code eliminator for conditional exports based on environment variables
 pub struct DeadCodeEliminator;
@@ -10,26 +11,32 @@ impl DeadCodeEliminator {
 
     /// Eliminate dead code branches based on constant conditions
     /// This runs after environment variable replacement
+    #[allow(dead_code)] // Part of public API
     pub fn eliminate(&self, code: &str) -> String {
+        self.rewrite(code).into_string()
+    }
+
+    /// `eliminate`, keeping track of the removed code for source maps
+    pub fn rewrite(&self, code: &str) -> RewrittenCode {
         Logger::debug("🗑️  Running dead code elimination");
 
-        let mut result = code.to_string();
+        let mut result = RewrittenCode::new(code);
         let mut eliminated_count = 0;
 
         // Pattern 1: if (false) { ... }
-        result = self.eliminate_false_blocks(&result, &mut eliminated_count);
+        self.eliminate_false_blocks(&mut result, &mut eliminated_count);
 
         // Pattern 2: if (true) { ... } else { ... } -> keep only if block
-        result = self.eliminate_true_blocks(&result, &mut eliminated_count);
+        self.eliminate_true_blocks(&mut result, &mut eliminated_count);
 
         // Pattern 3: condition ? expr1 : expr2 with known condition
-        result = self.eliminate_ternary(&result, &mut eliminated_count);
+        self.eliminate_ternary(&mut result, &mut eliminated_count);
 
         // Pattern 4: false && expr -> remove
-        result = self.eliminate_false_and(&result, &mut eliminated_count);
+        self.eliminate_false_and(&mut result, &mut eliminated_count);
 
         // Pattern 5: true || expr -> true
-        result = self.eliminate_true_or(&result, &mut eliminated_count);
+        self.eliminate_true_or(&mut result, &mut eliminated_count);
 
         if eliminated_count > 0 {
             Logger::debug(&format!(
@@ -42,16 +49,14 @@ impl DeadCodeEliminator {
     }
 
     /// Eliminate if (false) { ... } blocks
-    fn eliminate_false_blocks(&self, code: &str, count: &mut usize) -> String {
-        let mut result = code.to_string();
-
+    fn eliminate_false_blocks(&self, result: &mut RewrittenCode, count: &mut usize) {
         // Simple pattern: if(false){...}
         // Note: After minification, spaces are removed
         loop {
             if let Some(start) = result.find("if(false)") {
                 if let Some(block_start) = result[start..].find('{') {
                     let block_start_abs = start + block_start;
-                    if let Some(block_end) = self.find_matching_brace(&result, block_start_abs) {
+                    if let Some(block_end) = self.find_matching_brace(result, block_start_abs) {
                         // Check if there's an else clause
                         let after_block = &result[block_end + 1..];
                         let trimmed = after_block.trim_start();
@@ -67,12 +72,13 @@ impl DeadCodeEliminator {
                                 let else_block_start = else_keyword_end
                                     + (result[else_keyword_end..].len() - after_else.len());
                                 if let Some(else_block_end) =
-                                    self.find_matching_brace(&result, else_block_start)
+                                    self.find_matching_brace(result, else_block_start)
                                 {
-                                    // Extract else block content and replace entire if-else
-                                    let else_content =
-                                        result[else_block_start + 1..else_block_end].to_string();
-                                    result.replace_range(start..else_block_end + 1, &else_content);
+                                    // Keep the else block content in place of the entire if-else
+                                    result.keep_range(
+                                        start..else_block_end + 1,
+                                        else_block_start + 1..else_block_end,
+                                    );
                                     *count += 1;
                                     continue;
                                 }
@@ -88,21 +94,17 @@ impl DeadCodeEliminator {
             }
             break;
         }
-
-        result
     }
 
     /// Eliminate if (true) { ... } else { ... } -> keep only if block
-    fn eliminate_true_blocks(&self, code: &str, count: &mut usize) -> String {
-        let mut result = code.to_string();
-
+    fn eliminate_true_blocks(&self, result: &mut RewrittenCode, count: &mut usize) {
         loop {
             if let Some(start) = result.find("if(true)") {
                 if let Some(block_start) = result[start..].find('{') {
                     let block_start_abs = start + block_start;
-                    if let Some(block_end) = self.find_matching_brace(&result, block_start_abs) {
-                        // Extract if block content
-                        let if_content = &result[block_start_abs + 1..block_end].to_string();
+                    if let Some(block_end) = self.find_matching_brace(result, block_start_abs) {
+                        // If block content
+                        let if_content = block_start_abs + 1..block_end;
 
                         // Check if there's an else clause to remove
                         let after_block = &result[block_end + 1..];
@@ -118,17 +120,17 @@ impl DeadCodeEliminator {
                                 let else_block_start = else_keyword_end
                                     + (result[else_keyword_end..].len() - after_else.len());
                                 if let Some(else_block_end) =
-                                    self.find_matching_brace(&result, else_block_start)
+                                    self.find_matching_brace(result, else_block_start)
                                 {
                                     // Replace if-else with just the if content
-                                    result.replace_range(start..else_block_end + 1, if_content);
+                                    result.keep_range(start..else_block_end + 1, if_content);
                                     *count += 1;
                                     continue;
                                 }
                             }
                         } else {
                             // No else, just unwrap the if block
-                            result.replace_range(start..block_end + 1, if_content);
+                            result.keep_range(start..block_end + 1, if_content);
                             *count += 1;
                             continue;
                         }
@@ -137,24 +139,20 @@ impl DeadCodeEliminator {
             }
             break;
         }
-
-        result
     }
 
     /// Eliminate ternary expressions with known conditions
-    fn eliminate_ternary(&self, code: &str, count: &mut usize) -> String {
-        let mut result = code.to_string();
-
+    fn eliminate_ternary(&self, result: &mut RewrittenCode, count: &mut usize) {
         // true ? a : b -> a
         while let Some(pos) = result.find("true?") {
             if let Some(colon) = result[pos..].find(':') {
                 let colon_abs = pos + colon;
                 // Find the end of the false branch (simplified)
                 // This is a basic implementation, full parser would be better
-                if let Some(end) = self.find_expression_end(&result, colon_abs + 1) {
+                if let Some(end) = self.find_expression_end(result, colon_abs + 1) {
                     let true_branch_start = pos + 5; // "true?".len()
-                    let true_branch = &result[true_branch_start..colon_abs].trim().to_string();
-                    result.replace_range(pos..end, true_branch);
+                    let true_branch = trimmed(result, true_branch_start..colon_abs);
+                    result.keep_range(pos..end, true_branch);
                     *count += 1;
                     continue;
                 }
@@ -166,52 +164,42 @@ impl DeadCodeEliminator {
         while let Some(pos) = result.find("false?") {
             if let Some(colon) = result[pos..].find(':') {
                 let colon_abs = pos + colon;
-                if let Some(end) = self.find_expression_end(&result, colon_abs + 1) {
+                if let Some(end) = self.find_expression_end(result, colon_abs + 1) {
                     let false_branch_start = colon_abs + 1;
-                    let false_branch = &result[false_branch_start..end].trim().to_string();
-                    result.replace_range(pos..end, false_branch);
+                    let false_branch = trimmed(result, false_branch_start..end);
+                    result.keep_range(pos..end, false_branch);
                     *count += 1;
                     continue;
                 }
             }
             break;
         }
-
-        result
     }
 
     /// Eliminate false && expr -> remove
-    fn eliminate_false_and(&self, code: &str, count: &mut usize) -> String {
-        let mut result = code.to_string();
-
+    fn eliminate_false_and(&self, result: &mut RewrittenCode, count: &mut usize) {
         // false && anything -> false
         while let Some(pos) = result.find("false&&") {
-            if let Some(end) = self.find_expression_end(&result, pos + 7) {
+            if let Some(end) = self.find_expression_end(result, pos + 7) {
                 result.replace_range(pos..end, "false");
                 *count += 1;
                 continue;
             }
             break;
         }
-
-        result
     }
 
     /// Eliminate true || expr -> true
-    fn eliminate_true_or(&self, code: &str, count: &mut usize) -> String {
-        let mut result = code.to_string();
-
+    fn eliminate_true_or(&self, result: &mut RewrittenCode, count: &mut usize) {
         // true || anything -> true
         while let Some(pos) = result.find("true||") {
-            if let Some(end) = self.find_expression_end(&result, pos + 6) {
+            if let Some(end) = self.find_expression_end(result, pos + 6) {
                 result.replace_range(pos..end, "true");
                 *count += 1;
                 continue;
             }
             break;
         }
-
-        result
     }
 
     /// Find the matching closing brace for an opening brace
@@ -262,6 +250,14 @@ impl DeadCodeEliminator {
     }
 }
 
+/// `range` of `code` without leading and trailing whitespace
+fn trimmed(code: &str, range: Range<usize>) -> Range<usize> {
+    let text = &code[range.clone()];
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.end - (text.len() - text.trim_end().len());
+    start..end.max(start)
+}
+
 impl Default for DeadCodeEliminator {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,270 @@
+use crate::core::models::BuildConfig;
+use crate::utils::{Logger, Result, SokuError};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+pub(crate) static HTML_COMMENT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static SCRIPT_TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<script\b([^>]*)>").unwrap());
+pub(crate) static SRC_ATTR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\bsrc\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+pub(crate) static TYPE_ATTR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\btype\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap());
+
+/// Conventional entry files, checked in order when nothing else names an entry
+const CONVENTIONAL_ENTRIES: &[&str] = &["src/main", "src/index", "main", "index"];
+const ENTRY_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mts", "mjs"];
+
+/// Resolve the modules the dependency graph walk starts from
+///
+/// In order of precedence:
+/// 1. `entry`/`entries` from soku.config.json or `--entry`
+/// 2. Local `<script src>` tags in the HTML pages (see [`html_pages`])
+/// 3. `src/main.*`, `src/index.*`, `main.*` or `index.*`
+pub fn resolve_entry_points(config: &BuildConfig, html_files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    if !config.entries.is_empty() {
+        let mut entries: Vec<(&String, &PathBuf)> = config.entries.iter().collect();
+        entries.sort();
+        return entries
+            .into_iter()
+            .map(|(name, path)| {
+                if path.is_file() {
+                    Ok(path.clone())
+                } else {
+                    Err(SokuError::config(format!(
+                        "Entry '{}' not found: {}",
+                        name,
+                        path.display()
+                    )))
+                }
+            })
+            .collect();
+    }
+
+    let mut entries = Vec::new();
+    for page in html_pages(config, html_files) {
+        if let Ok(html) = std::fs::read_to_string(&page) {
+            for entry in html_entry_points(&html, &page, &config.root) {
+                if !entries.contains(&entry) {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    if !entries.is_empty() {
+        Logger::debug(&format!("🎯 Entry points from HTML pages: {:?}", entries));
+        return Ok(entries);
+    }
+
+    for stem in CONVENTIONAL_ENTRIES {
+        for ext in ENTRY_EXTENSIONS {
+            let candidate = config.root.join(format!("{}.{}", stem, ext));
+            if candidate.is_file() {
+                Logger::debug(&format!("🎯 Entry point: {}", candidate.display()));
+                return Ok(vec![candidate]);
+            }
+        }
+    }
+
+    Err(SokuError::config(format!(
+        "No entry point found in {}: set \"entry\" in soku.config.json, pass --entry, or add a <script src> tag to index.html",
+        config.root.display()
+    )))
+}
+
+/// HTML pages of the project that act as entry points
+///
+/// `index.html` at the root comes first, then the other pages in path order.
+/// Pages inside the output directory or `node_modules` are skipped.
+pub fn html_pages(config: &BuildConfig, html_files: &[PathBuf]) -> Vec<PathBuf> {
+    let outdir = config
+        .outdir
+        .canonicalize()
+        .unwrap_or_else(|_| config.outdir.clone());
+    let index_html = config.root.join("index.html");
+
+    let mut pages: Vec<PathBuf> = html_files.to_vec();
+    if index_html.is_file() && !pages.contains(&index_html) {
+        pages.push(index_html.clone());
+    }
+    pages.retain(|page| {
+        let canonical = page.canonicalize().unwrap_or_else(|_| page.clone());
+        !canonical.starts_with(&outdir)
+            && !page.components().any(|c| c.as_os_str() == "node_modules")
+    });
+    pages.sort_by_key(|page| (*page != index_html, page.clone()));
+    pages.dedup();
+    pages
+}
+
+/// Local script files referenced by an HTML page
+///
+/// Remote scripts and scripts whose `type` is not JavaScript (JSON data,
+/// templates, ...) are skipped, as is anything inside HTML comments.
+pub fn html_entry_points(html: &str, page: &Path, root: &Path) -> Vec<PathBuf> {
+    let mut entries = Vec::new();
+    for src in html_script_sources(html) {
+        let Some(path) = resolve_page_url(&src, page, root) else {
+            continue;
+        };
+        if !path.is_file() {
+            Logger::warn(&format!(
+                "⚠️  {} references a missing script: {}",
+                page.file_name().unwrap_or_default().to_string_lossy(),
+                src
+            ));
+            continue;
+        }
+        if !entries.contains(&path) {
+            entries.push(path);
+        }
+    }
+    entries
+}
+
+/// File a URL in an HTML page points at, or `None` for remote and `data:` URLs
+///
+/// URLs starting with `/` are relative to the project root, anything else to
+/// the page's directory. Query strings and fragments are ignored.
+pub fn resolve_page_url(url: &str, page: &Path, root: &Path) -> Option<PathBuf> {
+    if url.contains("://") || url.starts_with("//") || url.starts_with("data:") {
+        return None;
+    }
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    if url.is_empty() {
+        return None;
+    }
+    Some(match url.strip_prefix('/') {
+        Some(path) => root.join(path),
+        None => page.parent().unwrap_or(root).join(url),
+    })
+}
+
+/// `src` attributes of the JavaScript `<script>` tags in an HTML page
+pub fn html_script_sources(html: &str) -> Vec<String> {
+    let html = HTML_COMMENT_REGEX.replace_all(html, "");
+    SCRIPT_TAG_REGEX
+        .captures_iter(&html)
+        .filter_map(|tag| {
+            let attributes = &tag[1];
+            if !is_javascript_script(attributes) {
+                return None;
+            }
+            attribute_value(&SRC_ATTR_REGEX, attributes)
+                .map(|src| src.trim().to_string())
+                .filter(|src| !src.is_empty())
+        })
+        .collect()
+}
+
+/// Whether a `<script>` tag with these attributes holds JavaScript
+pub fn is_javascript_script(attributes: &str) -> bool {
+    match attribute_value(&TYPE_ATTR_REGEX, attributes) {
+        Some(script_type) => matches!(
+            script_type.to_ascii_lowercase().as_str(),
+            "module" | "text/javascript" | "application/javascript"
+        ),
+        None => true,
+    }
+}
+
+/// Value of the attribute matched by `regex` in the attributes of a tag
+pub fn attribute_value<'a>(regex: &Regex, attributes: &'a str) -> Option<&'a str> {
+    let captures = regex.captures(attributes)?;
+    captures
+        .get(1)
+        .or_else(|| captures.get(2))
+        .or_else(|| captures.get(3))
+        .map(|m| m.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config_for(root: &Path) -> BuildConfig {
+        BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join("dist"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_html_script_sources() {
+        let html = r#"<!doctype html>
+<html>
+  <head>
+    <script type="application/json" src="data.json"></script>
+    <!-- <script src="/src/old.js"></script> -->
+    <script src="https://cdn.example.com/lib.js"></script>
+  </head>
+  <body>
+    <script type="module" src="/src/main.ts"></script>
+    <script src='./vendor.js' defer></script>
+  </body>
+</html>"#;
+        assert_eq!(
+            html_script_sources(html),
+            vec![
+                "https://cdn.example.com/lib.js",
+                "/src/main.ts",
+                "./vendor.js"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry_points_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.js"), "").unwrap();
+        std::fs::write(root.join("src/app.ts"), "").unwrap();
+        std::fs::write(root.join("stray.test.js"), "").unwrap();
+
+        // Conventional entry
+        let config = config_for(root);
+        assert_eq!(
+            resolve_entry_points(&config, &[]).unwrap(),
+            vec![root.join("src/main.js")]
+        );
+
+        // index.html script tags
+        std::fs::write(
+            root.join("index.html"),
+            r#"<script type="module" src="/src/app.ts?v=1"></script>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_entry_points(&config, &[]).unwrap(),
+            vec![root.join("src/app.ts")]
+        );
+
+        // Other pages add their scripts, relative to the page
+        std::fs::create_dir_all(root.join("admin")).unwrap();
+        std::fs::write(root.join("admin/panel.js"), "").unwrap();
+        std::fs::write(
+            root.join("admin/index.html"),
+            r#"<script type="module" src="panel.js"></script>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_entry_points(&config, &[root.join("admin/index.html")]).unwrap(),
+            vec![root.join("src/app.ts"), root.join("admin/panel.js")]
+        );
+
+        // Configured entries
+        let mut config = config_for(root);
+        config.entries = HashMap::from([("main".to_string(), root.join("src/main.js"))]);
+        assert_eq!(
+            resolve_entry_points(&config, &[]).unwrap(),
+            vec![root.join("src/main.js")]
+        );
+
+        config.entries = HashMap::from([("main".to_string(), root.join("src/missing.js"))]);
+        assert!(resolve_entry_points(&config, &[]).is_err());
+    }
+}
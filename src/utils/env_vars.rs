@@ -1,4 +1,4 @@
-use crate::utils::{Logger, Result, SokuError};
+use crate::utils::{Logger, Result, RewrittenCode, SokuError};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
@@ -153,21 +153,32 @@ impl EnvVarsManager {
 
     /// Replace environment variables in code
     /// Supports both process.env.VAR and import.meta.env.VAR
+    #[allow(dead_code)] // Part of public API
     pub fn replace_in_code(&self, code: &str) -> String {
-        let mut result = code.to_string();
-
-        // Replace process.env.VARIABLE
-        for (key, value) in &self.variables {
-            let pattern = format!("process.env.{}", key);
-            let replacement = self.format_value_for_js(value);
-            result = result.replace(&pattern, &replacement);
-        }
+        self.rewrite(code).into_string()
+    }
 
-        // Replace import.meta.env.VARIABLE
-        for (key, value) in &self.variables {
-            let pattern = format!("import.meta.env.{}", key);
-            let replacement = self.format_value_for_js(value);
-            result = result.replace(&pattern, &replacement);
+    /// `replace_in_code`, keeping track of the replacements for source maps
+    pub fn rewrite(&self, code: &str) -> RewrittenCode {
+        let mut result = RewrittenCode::new(code);
+
+        // Longest names first so `API` does not clobber `API_URL`, and in a
+        // fixed order so the output does not depend on hash iteration
+        let mut variables: Vec<(&String, &String)> = self.variables.iter().collect();
+        variables.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+        // Replace process.env.VARIABLE, then import.meta.env.VARIABLE
+        for prefix in ["process.env.", "import.meta.env."] {
+            for (key, value) in &variables {
+                let pattern = format!("{}{}", prefix, key);
+                let replacement = self.format_value_for_js(value);
+                let mut from = 0;
+                while let Some(found) = result[from..].find(&pattern) {
+                    let start = from + found;
+                    result.replace_range(start..start + pattern.len(), &replacement);
+                    from = start + replacement.len();
+                }
+            }
         }
 
         result
@@ -263,6 +274,16 @@ mod tests {
         assert!(result.contains("const port = 3000;"));
     }
 
+    #[test]
+    fn test_replace_prefers_longest_name() {
+        let mut manager = EnvVarsManager::new();
+        manager.set("API".to_string(), "a".to_string());
+        manager.set("API_URL".to_string(), "b".to_string());
+
+        let result = manager.replace_in_code("f(process.env.API_URL, import.meta.env.API);");
+        assert_eq!(result, r#"f("b", "a");"#);
+    }
+
     #[test]
     fn test_load_from_file() {
         let temp_dir = tempdir().unwrap();
@@ -8,6 +8,8 @@ pub struct ErrorContext {
     pub line: Option<usize>,
     pub column: Option<usize>,
     pub code_snippet: Option<String>,
+    /// Name of the plugin whose hook failed
+    pub plugin: Option<String>,
 }
 
 impl Default for ErrorContext {
@@ -23,6 +25,7 @@ impl ErrorContext {
             line: None,
             column: None,
             code_snippet: None,
+            plugin: None,
         }
     }
 
@@ -42,6 +45,43 @@ impl ErrorContext {
         self.code_snippet = Some(snippet);
         self
     }
+
+    pub fn with_plugin(mut self, plugin: &str) -> Self {
+        self.plugin = Some(plugin.to_string());
+        self
+    }
+
+    /// The snippet with the error line marked and a caret under its column
+    ///
+    /// Only snippets with numbered lines, as in `  12 │ code`, can be marked.
+    pub fn code_frame(&self) -> Option<String> {
+        let snippet = self.code_snippet.as_ref()?;
+        let mut frame = String::new();
+        for line in snippet.lines() {
+            let (number, code) = match line.split_once(" │ ") {
+                Some((number, code)) if number.trim().parse::<usize>().is_ok() => {
+                    (number.trim().parse::<usize>().ok(), code)
+                }
+                _ => (None, line),
+            };
+            let is_error_line = number.is_some() && number == self.line;
+            let gutter = number.map_or(String::new(), |n| n.to_string());
+            frame.push_str(&format!(
+                "{} {:>4} | {}\n",
+                if is_error_line { ">" } else { " " },
+                gutter,
+                code
+            ));
+            if let (true, Some(column)) = (is_error_line, self.column) {
+                frame.push_str(&format!(
+                    "{:>6} | {}^\n",
+                    "",
+                    " ".repeat(column.saturating_sub(1))
+                ));
+            }
+        }
+        Some(frame)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -52,13 +92,13 @@ pub enum SokuError {
     #[error("Parse error: {message}")]
     Parse {
         message: String,
-        context: Option<ErrorContext>,
+        context: Option<Box<ErrorContext>>,
     },
 
     #[error("Build error: {message}")]
     Build {
         message: String,
-        context: Option<ErrorContext>,
+        context: Option<Box<ErrorContext>>,
     },
 
     #[error("Tree shaking error: {0}")]
@@ -99,7 +139,7 @@ impl SokuError {
     pub fn parse_with_context(message: String, context: ErrorContext) -> Self {
         Self::Parse {
             message,
-            context: Some(context),
+            context: Some(Box::new(context)),
         }
     }
 
@@ -116,7 +156,7 @@ impl SokuError {
     pub fn build_with_context(message: String, context: ErrorContext) -> Self {
         Self::Build {
             message,
-            context: Some(context),
+            context: Some(Box::new(context)),
         }
     }
 
@@ -125,14 +165,50 @@ impl SokuError {
         Self::Config(message)
     }
 
+    /// Location and code of the error, when known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            SokuError::Parse { context, .. } | SokuError::Build { context, .. } => {
+                context.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Attribute the error to the plugin whose hook returned it
+    pub fn in_plugin(self, plugin: &str) -> Self {
+        match self {
+            SokuError::Parse { message, context } => SokuError::Parse {
+                message,
+                context: Some(Box::new(
+                    context
+                        .map_or_else(ErrorContext::new, |c| *c)
+                        .with_plugin(plugin),
+                )),
+            },
+            SokuError::Build { message, context } => SokuError::Build {
+                message,
+                context: Some(Box::new(
+                    context
+                        .map_or_else(ErrorContext::new, |c| *c)
+                        .with_plugin(plugin),
+                )),
+            },
+            other => SokuError::Build {
+                message: other.to_string(),
+                context: Some(Box::new(ErrorContext::new().with_plugin(plugin))),
+            },
+        }
+    }
+
     /// Format error with enhanced context display
     pub fn format_detailed(&self) -> String {
         match self {
             SokuError::Parse { message, context } => {
-                self.format_error_with_context("Parse Error", message, context)
+                self.format_error_with_context("Parse Error", message, context.as_deref())
             }
             SokuError::Build { message, context } => {
-                self.format_error_with_context("Build Error", message, context)
+                self.format_error_with_context("Build Error", message, context.as_deref())
             }
             _ => self.to_string(),
         }
@@ -142,11 +218,15 @@ impl SokuError {
         &self,
         error_type: &str,
         message: &str,
-        context: &Option<ErrorContext>,
+        context: Option<&ErrorContext>,
     ) -> String {
         let mut output = format!("❌ {}: {}", error_type, message);
 
         if let Some(ctx) = context {
+            if let Some(ref plugin) = ctx.plugin {
+                output.push_str(&format!("\n🔌 Plugin: {}", plugin));
+            }
+
             if let Some(ref file_path) = ctx.file_path {
                 output.push_str(&format!("\n📁 File: {}", file_path.display()));
             }
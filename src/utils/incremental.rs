@@ -3,6 +3,7 @@
 // Incremental build system for Soku Bundler
 // Tracks file changes and dependencies for smart rebuilds
 
+use crate::core::models::DependencyKind;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -69,6 +70,9 @@ pub struct DependencyGraph {
     dependencies: HashMap<PathBuf, HashSet<PathBuf>>,
     /// Reverse map: dependents of each file
     dependents: HashMap<PathBuf, HashSet<PathBuf>>,
+    /// How each file refers to each of its dependencies
+    #[serde(default)]
+    kinds: HashMap<PathBuf, HashMap<PathBuf, DependencyKind>>,
 }
 
 impl Default for DependencyGraph {
@@ -82,11 +86,25 @@ impl DependencyGraph {
         Self {
             dependencies: HashMap::new(),
             dependents: HashMap::new(),
+            kinds: HashMap::new(),
         }
     }
 
     /// Add a dependency relationship: `from` depends on `to`
-    pub fn add_dependency(&mut self, from: PathBuf, to: PathBuf) {
+    ///
+    /// When a file refers to the same dependency in several ways, an eager
+    /// import wins over `import()`.
+    pub fn add_dependency(&mut self, from: PathBuf, to: PathBuf, kind: DependencyKind) {
+        let recorded = self
+            .kinds
+            .entry(from.clone())
+            .or_default()
+            .entry(to.clone())
+            .or_insert(kind);
+        if *recorded == DependencyKind::Dynamic {
+            *recorded = kind;
+        }
+
         // Add to forward map
         self.dependencies
             .entry(from.clone())
@@ -102,6 +120,11 @@ impl DependencyGraph {
         self.dependencies.get(path)
     }
 
+    /// How `from` refers to its dependency `to`
+    pub fn dependency_kind(&self, from: &Path, to: &Path) -> Option<DependencyKind> {
+        self.kinds.get(from)?.get(to).copied()
+    }
+
     /// Get all files that depend on this file
     pub fn get_dependents(&self, path: &Path) -> Option<&HashSet<PathBuf>> {
         self.dependents.get(path)
@@ -132,10 +155,120 @@ impl DependencyGraph {
         affected
     }
 
+    /// Import cycles, each as a chain of files that ends where it started
+    ///
+    /// Finds the strongly connected components of the graph with Tarjan's
+    /// algorithm. `import()` edges are ignored: a dynamically imported module
+    /// runs after its importer has finished evaluating.
+    pub fn find_cycles(&self) -> Vec<Vec<PathBuf>> {
+        let mut files: Vec<&PathBuf> = self.dependencies.keys().collect();
+        files.sort();
+
+        let mut index: HashMap<&PathBuf, usize> = HashMap::new();
+        let mut low: HashMap<&PathBuf, usize> = HashMap::new();
+        let mut stack: Vec<&PathBuf> = Vec::new();
+        let mut on_stack: HashSet<&PathBuf> = HashSet::new();
+        let mut cycles = Vec::new();
+
+        for file in files {
+            if index.contains_key(file) {
+                continue;
+            }
+            // Iterative DFS: (file, its dependencies, index of the next one to visit)
+            let mut visiting = vec![(file, self.eager_dependencies(file), 0)];
+            index.insert(file, index.len());
+            low.insert(file, index[file]);
+            stack.push(file);
+            on_stack.insert(file);
+
+            while let Some((current, dependencies, next)) = visiting.last_mut() {
+                let current = *current;
+                if let Some(&dependency) = dependencies.get(*next) {
+                    *next += 1;
+                    if !index.contains_key(dependency) {
+                        index.insert(dependency, index.len());
+                        low.insert(dependency, index[dependency]);
+                        stack.push(dependency);
+                        on_stack.insert(dependency);
+                        visiting.push((dependency, self.eager_dependencies(dependency), 0));
+                    } else if on_stack.contains(dependency) {
+                        low.insert(current, low[current].min(index[dependency]));
+                    }
+                    continue;
+                }
+
+                visiting.pop();
+                if let Some((parent, _, _)) = visiting.last() {
+                    low.insert(*parent, low[*parent].min(low[current]));
+                }
+                if low[current] != index[current] {
+                    continue;
+                }
+
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(member);
+                    component.push(member);
+                    if member == current {
+                        break;
+                    }
+                }
+                if component.len() > 1 || self.eager_dependencies(current).contains(&current) {
+                    cycles.push(self.cycle_chain(&component));
+                }
+            }
+        }
+
+        cycles.sort();
+        cycles
+    }
+
+    /// Dependencies loaded while the file evaluates, in a stable order
+    fn eager_dependencies(&self, path: &Path) -> Vec<&PathBuf> {
+        let mut dependencies: Vec<&PathBuf> = self
+            .dependencies
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter(|to| self.dependency_kind(path, to) != Some(DependencyKind::Dynamic))
+            .collect();
+        dependencies.sort();
+        dependencies
+    }
+
+    /// Shortest import chain from the first file of a component back to itself
+    fn cycle_chain(&self, component: &[&PathBuf]) -> Vec<PathBuf> {
+        let members: HashSet<&PathBuf> = component.iter().copied().collect();
+        let start = *component.iter().min().expect("components are never empty");
+
+        let mut previous: HashMap<&PathBuf, &PathBuf> = HashMap::new();
+        let mut queue = std::collections::VecDeque::from([start]);
+        while let Some(file) = queue.pop_front() {
+            for dependency in self.eager_dependencies(file) {
+                if dependency == start {
+                    let mut chain = vec![start.clone(), file.clone()];
+                    let mut step = file;
+                    while let Some(&before) = previous.get(step) {
+                        chain.push(before.clone());
+                        step = before;
+                    }
+                    chain.reverse();
+                    return chain;
+                }
+                if members.contains(dependency) && !previous.contains_key(dependency) {
+                    previous.insert(dependency, file);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        vec![start.clone(), start.clone()]
+    }
+
     /// Clear all dependencies
     pub fn clear(&mut self) {
         self.dependencies.clear();
         self.dependents.clear();
+        self.kinds.clear();
     }
 }
 
@@ -167,8 +300,8 @@ impl IncrementalBuildState {
     }
 
     /// Add a dependency relationship
-    pub fn add_dependency(&mut self, from: PathBuf, to: PathBuf) {
-        self.graph.add_dependency(from, to);
+    pub fn add_dependency(&mut self, from: PathBuf, to: PathBuf, kind: DependencyKind) {
+        self.graph.add_dependency(from, to, kind);
     }
 
     /// Get changed files since last build
@@ -279,9 +412,9 @@ mod tests {
         let helpers = PathBuf::from("helpers.js");
 
         // main.js depends on utils.js
-        graph.add_dependency(main.clone(), utils.clone());
+        graph.add_dependency(main.clone(), utils.clone(), DependencyKind::Static);
         // utils.js depends on helpers.js
-        graph.add_dependency(utils.clone(), helpers.clone());
+        graph.add_dependency(utils.clone(), helpers.clone(), DependencyKind::Static);
 
         // Check dependencies
         assert!(graph.get_dependencies(&main).unwrap().contains(&utils));
@@ -306,9 +439,66 @@ mod tests {
         let main = PathBuf::from("main.js");
         let utils = PathBuf::from("utils.js");
 
-        state.add_dependency(main.clone(), utils.clone());
+        state.add_dependency(main.clone(), utils.clone(), DependencyKind::Static);
 
         // Check that dependency was added
         assert!(state.graph().get_dependencies(&main).is_some());
     }
+
+    #[test]
+    fn test_dependency_kinds() {
+        let mut graph = DependencyGraph::new();
+
+        let main = PathBuf::from("main.js");
+        let page = PathBuf::from("page.js");
+        let utils = PathBuf::from("utils.js");
+
+        graph.add_dependency(main.clone(), page.clone(), DependencyKind::Dynamic);
+        graph.add_dependency(main.clone(), utils.clone(), DependencyKind::Dynamic);
+        graph.add_dependency(main.clone(), utils.clone(), DependencyKind::Static);
+
+        assert_eq!(
+            graph.dependency_kind(&main, &page),
+            Some(DependencyKind::Dynamic)
+        );
+        assert_eq!(
+            graph.dependency_kind(&main, &utils),
+            Some(DependencyKind::Static)
+        );
+        assert_eq!(graph.dependency_kind(&page, &main), None);
+    }
+
+    #[test]
+    fn test_find_cycles() {
+        let mut graph = DependencyGraph::new();
+
+        let main = PathBuf::from("main.js");
+        let a = PathBuf::from("a.js");
+        let b = PathBuf::from("b.js");
+        let c = PathBuf::from("c.js");
+        let page = PathBuf::from("page.js");
+        let self_import = PathBuf::from("self.js");
+
+        graph.add_dependency(main.clone(), a.clone(), DependencyKind::Static);
+        graph.add_dependency(a.clone(), b.clone(), DependencyKind::Static);
+        graph.add_dependency(b.clone(), c.clone(), DependencyKind::ReExport);
+        graph.add_dependency(c.clone(), a.clone(), DependencyKind::Static);
+        graph.add_dependency(main.clone(), self_import.clone(), DependencyKind::Static);
+        graph.add_dependency(
+            self_import.clone(),
+            self_import.clone(),
+            DependencyKind::SideEffect,
+        );
+        // import() cycles are harmless
+        graph.add_dependency(main.clone(), page.clone(), DependencyKind::Dynamic);
+        graph.add_dependency(page.clone(), main.clone(), DependencyKind::Dynamic);
+
+        assert_eq!(
+            graph.find_cycles(),
+            vec![
+                vec![a.clone(), b.clone(), c.clone(), a.clone()],
+                vec![self_import.clone(), self_import.clone()],
+            ]
+        );
+    }
 }
@@ -0,0 +1,376 @@
+// Output file naming and the build manifest
+// Fills in `[name].[hash].js` style templates and records which output file
+// holds each entry and source module
+
+use crate::core::models::{BuildConfig, ModuleInfo};
+use crate::utils::{Result, SokuError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the manifest written next to the bundles
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Directory of the project whose files are copied as they are to the output
+/// directory, and served at the site root by the dev server
+pub const PUBLIC_DIR: &str = "public";
+
+/// Number of hex digits of the content hash used in file names
+const HASH_LENGTH: usize = 8;
+
+/// Fill in an output filename template
+///
+/// `[name]` is the bundle, chunk or asset name, `[ext]` its extension and
+/// `[hash]` a blake3 hash of the final file content.
+pub fn output_file_name(template: &str, name: &str, ext: &str, content: &[u8]) -> String {
+    let file_name = template.replace("[name]", name).replace("[ext]", ext);
+    if file_name.contains("[hash]") {
+        file_name.replace("[hash]", &content_hash(content))
+    } else {
+        file_name
+    }
+}
+
+/// Short blake3 hash of `content`, as used by `[hash]`
+pub fn content_hash(content: &[u8]) -> String {
+    blake3::hash(content).to_hex()[..HASH_LENGTH].to_string()
+}
+
+/// Check a filename template before anything is written
+pub fn validate_file_name_template(option: &str, template: &str) -> Result<()> {
+    if !template.contains("[name]") && !template.contains("[hash]") {
+        return Err(SokuError::config(format!(
+            "{} '{}' must contain [name] or [hash], otherwise every output gets the same file name",
+            option, template
+        )));
+    }
+    if Path::new(template).is_absolute() || template.split(['/', '\\']).any(|part| part == "..") {
+        return Err(SokuError::config(format!(
+            "{} '{}' must be a path inside the output directory",
+            option, template
+        )));
+    }
+    Ok(())
+}
+
+/// A record of `manifest.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ManifestEntry {
+    /// Output file, relative to the output directory
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Source file, relative to the project root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_entry: bool,
+    /// JavaScript chunks that have to be loaded first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
+    /// Stylesheets the chunk needs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub css: Vec<String>,
+    /// Other files the chunk loads at runtime (WebAssembly, ...)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<String>,
+    /// The file name contains the `[hash]` of its content, so the content
+    /// never changes under that name
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub hashed: bool,
+}
+
+/// Output JavaScript file together with what went into it
+pub struct ManifestChunk<'a> {
+    /// Unhashed chunk name, e.g. `vendor` or the entry name
+    pub name: &'a str,
+    /// Output file, relative to the output directory
+    pub file: &'a str,
+    /// Entry modules bundled into this chunk
+    pub entries: &'a [PathBuf],
+    pub modules: &'a [ModuleInfo],
+    pub imports: Vec<String>,
+}
+
+/// `manifest.json`: maps entries and source modules to their output files
+///
+/// Keys are source paths relative to the project root, like Vite's manifest.
+/// Chunks without a single source (vendor, shared chunks) use `_<name>.js`.
+#[derive(Debug, Default)]
+pub struct BuildManifest {
+    root: PathBuf,
+    entries: BTreeMap<String, ManifestEntry>,
+    /// Keys of the records that describe a chunk rather than a module
+    chunk_keys: Vec<String>,
+    /// Whether the `jsFileNames` and `assetFileNames` templates contain `[hash]`
+    js_hashed: bool,
+    assets_hashed: bool,
+}
+
+impl BuildManifest {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            entries: BTreeMap::new(),
+            chunk_keys: Vec::new(),
+            js_hashed: false,
+            assets_hashed: false,
+        }
+    }
+
+    /// Manifest of a build, marking the outputs whose file name template
+    /// contains `[hash]`
+    pub fn for_build(config: &BuildConfig) -> Self {
+        Self {
+            js_hashed: config.js_file_names.contains("[hash]"),
+            assets_hashed: config.asset_file_names.contains("[hash]"),
+            ..Self::new(&config.root)
+        }
+    }
+
+    /// Read the `manifest.json` of an output directory, if there is one
+    pub async fn read_entries(outdir: &Path) -> Option<BTreeMap<String, ManifestEntry>> {
+        let json = tokio::fs::read_to_string(outdir.join(MANIFEST_FILE_NAME))
+            .await
+            .ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Record a JavaScript chunk, its entry modules and the modules inside it
+    pub fn add_chunk(&mut self, chunk: ManifestChunk) {
+        let mut imports = chunk.imports;
+        imports.sort();
+        imports.dedup();
+
+        let mut keys = Vec::new();
+        for entry in chunk.entries {
+            let key = self.source_key(entry);
+            self.entries.insert(
+                key.clone(),
+                ManifestEntry {
+                    file: chunk.file.to_string(),
+                    name: Some(chunk.name.to_string()),
+                    src: Some(key.clone()),
+                    is_entry: true,
+                    imports: imports.clone(),
+                    hashed: self.js_hashed,
+                    ..Default::default()
+                },
+            );
+            keys.push(key);
+        }
+        if keys.is_empty() {
+            let key = format!("_{}.js", chunk.name);
+            self.entries.insert(
+                key.clone(),
+                ManifestEntry {
+                    file: chunk.file.to_string(),
+                    name: Some(chunk.name.to_string()),
+                    imports,
+                    hashed: self.js_hashed,
+                    ..Default::default()
+                },
+            );
+            keys.push(key);
+        }
+
+        for module in chunk.modules {
+            let key = self.source_key(&module.path);
+            // A module bundled into several chunks keeps the first one
+            self.entries.entry(key.clone()).or_insert(ManifestEntry {
+                file: chunk.file.to_string(),
+                src: Some(key),
+                hashed: self.js_hashed,
+                ..Default::default()
+            });
+        }
+        self.chunk_keys.extend(keys);
+    }
+
+    /// Record a stylesheet built from `sources`, needed by every chunk
+    pub fn add_css(&mut self, file: &str, sources: &[PathBuf]) {
+        self.add_asset_sources(file, sources);
+        for key in &self.chunk_keys {
+            if let Some(entry) = self.entries.get_mut(key) {
+                entry.css.push(file.to_string());
+            }
+        }
+    }
+
+    /// Record an asset loaded at runtime by every chunk
+    pub fn add_asset(&mut self, file: &str, source: &Path) {
+        self.add_asset_sources(file, std::slice::from_ref(&source.to_path_buf()));
+        for key in &self.chunk_keys {
+            if let Some(entry) = self.entries.get_mut(key) {
+                entry.assets.push(file.to_string());
+            }
+        }
+    }
+
+    /// Record a file a plugin emitted, keyed by its output name
+    pub fn add_emitted(&mut self, file: &str) {
+        self.entries.insert(
+            file.to_string(),
+            ManifestEntry {
+                file: file.to_string(),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn add_asset_sources(&mut self, file: &str, sources: &[PathBuf]) {
+        for source in sources {
+            let key = self.source_key(source);
+            self.entries.insert(
+                key.clone(),
+                ManifestEntry {
+                    file: file.to_string(),
+                    src: Some(key),
+                    hashed: self.assets_hashed,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    #[allow(dead_code)] // Part of public API
+    pub fn get(&self, key: &str) -> Option<&ManifestEntry> {
+        self.entries.get(key)
+    }
+
+    /// Record of a source file, e.g. an entry referenced by an HTML page
+    pub fn get_source(&self, path: &Path) -> Option<&ManifestEntry> {
+        self.entries.get(&self.source_key(path))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| SokuError::build(format!("Failed to serialize manifest: {}", e)))
+    }
+
+    fn source_key(&self, path: &Path) -> String {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        path.strip_prefix(&self.root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::ModuleType;
+
+    fn module(path: &str) -> ModuleInfo {
+        ModuleInfo {
+            path: PathBuf::from(path),
+            content: String::new(),
+            module_type: ModuleType::JavaScript,
+            dependencies: Vec::new(),
+            exports: Vec::new(),
+            resolved_imports: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_output_file_name() {
+        assert_eq!(
+            output_file_name("[name].js", "bundle", "js", b"x"),
+            "bundle.js"
+        );
+        let hashed = output_file_name("assets/[name].[hash].[ext]", "bundle", "css", b"x");
+        assert_eq!(hashed, format!("assets/bundle.{}.css", content_hash(b"x")));
+        assert_eq!(content_hash(b"x").len(), 8);
+        assert_ne!(content_hash(b"x"), content_hash(b"y"));
+
+        assert!(validate_file_name_template("jsFileNames", "[name].[hash].js").is_ok());
+        assert!(validate_file_name_template("jsFileNames", "bundle.js").is_err());
+        assert!(validate_file_name_template("jsFileNames", "../[name].js").is_err());
+    }
+
+    #[test]
+    fn test_manifest_records_chunks_and_assets() {
+        let mut manifest = BuildManifest::new(Path::new("/app"));
+        let app_modules = [module("/app/src/main.js"), module("/app/src/util.js")];
+        let vendor_modules = [module("/app/node_modules/lib/index.js")];
+
+        manifest.add_chunk(ManifestChunk {
+            name: "vendor",
+            file: "vendor.1234abcd.js",
+            entries: &[],
+            modules: &vendor_modules,
+            imports: Vec::new(),
+        });
+        manifest.add_chunk(ManifestChunk {
+            name: "app",
+            file: "app.5678abcd.js",
+            entries: &[PathBuf::from("/app/src/main.js")],
+            modules: &app_modules,
+            imports: vec!["vendor.1234abcd.js".to_string()],
+        });
+        manifest.add_css(
+            "bundle.0000abcd.css",
+            &[PathBuf::from("/app/src/style.css")],
+        );
+
+        let main = manifest.get("src/main.js").unwrap();
+        assert!(main.is_entry);
+        assert_eq!(main.file, "app.5678abcd.js");
+        assert_eq!(main.imports, vec!["vendor.1234abcd.js"]);
+        assert_eq!(main.css, vec!["bundle.0000abcd.css"]);
+        assert_eq!(manifest.get("src/util.js").unwrap().file, "app.5678abcd.js");
+        assert_eq!(
+            manifest.get("node_modules/lib/index.js").unwrap().file,
+            "vendor.1234abcd.js"
+        );
+        assert_eq!(
+            manifest.get("_vendor.js").unwrap().file,
+            "vendor.1234abcd.js"
+        );
+        assert_eq!(
+            manifest.get("src/style.css").unwrap().file,
+            "bundle.0000abcd.css"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&manifest.to_json().unwrap()).unwrap();
+        assert_eq!(json["src/main.js"]["isEntry"], true);
+        assert!(json["src/util.js"].get("isEntry").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manifest_marks_hashed_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BuildConfig {
+            root: dir.path().to_path_buf(),
+            js_file_names: "[name].[hash].js".to_string(),
+            asset_file_names: "[name].[ext]".to_string(),
+            ..Default::default()
+        };
+        let mut manifest = BuildManifest::for_build(&config);
+        manifest.add_chunk(ManifestChunk {
+            name: "bundle",
+            file: "bundle.1234abcd.js",
+            entries: &[dir.path().join("main.js")],
+            modules: &[],
+            imports: Vec::new(),
+        });
+        manifest.add_css("bundle.css", &[dir.path().join("style.css")]);
+        manifest.add_emitted("report-20261017.js");
+        std::fs::write(
+            dir.path().join(MANIFEST_FILE_NAME),
+            manifest.to_json().unwrap(),
+        )
+        .unwrap();
+
+        let entries = BuildManifest::read_entries(dir.path()).await.unwrap();
+        assert!(entries["main.js"].hashed);
+        assert!(!entries["style.css"].hashed);
+        assert!(!entries["report-20261017.js"].hashed);
+        assert_eq!(entries["main.js"].css, vec!["bundle.css"]);
+        assert!(BuildManifest::read_entries(&dir.path().join("missing"))
+            .await
+            .is_none());
+    }
+}
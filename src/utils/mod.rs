@@ -2,35 +2,43 @@
 pub mod advanced_performance;
 pub mod advanced_source_maps;
 pub mod bundle_analysis;
+pub mod code_map;
 pub mod config_loader;
 pub mod css_modules;
 pub mod custom_transformers;
 pub mod dead_code_eliminator;
+pub mod entry_points;
 pub mod env_vars;
 pub mod errors;
 pub mod incremental;
 pub mod logging;
+pub mod manifest;
 pub mod path_aliases;
 pub mod performance;
 pub mod plugin_system;
 pub mod soku_ui;
+pub mod tsconfig;
 pub mod wasm_support;
 pub mod watch;
 
 pub use advanced_performance::*;
 pub use advanced_source_maps::*;
 pub use bundle_analysis::*;
+pub use code_map::*;
 pub use config_loader::*;
 pub use css_modules::*;
 pub use custom_transformers::*;
 pub use dead_code_eliminator::*;
+pub use entry_points::*;
 pub use env_vars::*;
 pub use errors::*;
 pub use incremental::*;
 pub use logging::*;
+pub use manifest::*;
 pub use path_aliases::*;
 pub use performance::*;
 pub use plugin_system::*;
 pub use soku_ui::*;
+pub use tsconfig::*;
 pub use wasm_support::*;
 pub use watch::*;
@@ -1,10 +1,27 @@
 // Plugin System - Event-based extensibility for Soku Bundler
 #![allow(dead_code)] // Public API - used via examples and external integrations
 
-use crate::core::models::{BuildConfig, BuildResult, ModuleInfo};
+use crate::core::models::{BuildConfig, BuildResult, ModuleInfo, ModuleType, OutputFile};
 use crate::utils::Result;
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Prefix of module ids that only exist in plugins, e.g. `\0virtual:routes`
+pub const VIRTUAL_MODULE_PREFIX: char = '\0';
+
+/// Whether a module id or path is a virtual module served by a plugin's `load`
+pub fn is_virtual_module(path: &Path) -> bool {
+    path.to_string_lossy().starts_with(VIRTUAL_MODULE_PREFIX)
+}
+
+/// Printable name of a module path, without the virtual module prefix
+pub fn module_display_name(path: &Path) -> String {
+    let name = path.to_string_lossy();
+    name.strip_prefix(VIRTUAL_MODULE_PREFIX)
+        .unwrap_or(&name)
+        .to_string()
+}
 
 /// Plugin lifecycle events
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,16 +40,30 @@ pub enum PluginEvent {
     AfterModuleProcess,
     /// Before writing output files
     BeforeOutput,
+    /// With the output files about to be written
+    GenerateBundle,
     /// After writing output files
     AfterOutput,
 }
 
+/// A file added to the output by a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedFile {
+    /// Path relative to the output directory, e.g. `robots.txt` or `icons/app.png`
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// Files emitted during a build, shared by the contexts of its hooks
+pub type EmittedFiles = Arc<Mutex<Vec<EmittedFile>>>;
+
 /// Context passed to plugins during events
 #[derive(Debug, Clone)]
 pub struct PluginContext {
     pub config: BuildConfig,
     pub modules: Vec<ModuleInfo>,
     pub current_event: PluginEvent,
+    emitted_files: EmittedFiles,
 }
 
 impl PluginContext {
@@ -41,8 +72,42 @@ impl PluginContext {
             config,
             modules,
             current_event: event,
+            emitted_files: EmittedFiles::default(),
         }
     }
+
+    /// Collect emitted files in `files`, shared with other contexts of the build
+    pub fn with_emitted_files(mut self, files: EmittedFiles) -> Self {
+        self.emitted_files = files;
+        self
+    }
+
+    /// Add a file to the output directory
+    ///
+    /// Files emitted up to `generate_bundle` are written with the bundles and
+    /// listed in the manifest; emitting a file with the same name replaces it.
+    pub fn emit_file(&self, name: impl Into<String>, content: impl Into<Vec<u8>>) {
+        let name = name.into();
+        let mut files = self.emitted_files.lock().unwrap_or_else(|e| e.into_inner());
+        files.retain(|file| file.name != name);
+        files.push(EmittedFile {
+            name,
+            content: content.into(),
+        });
+    }
+
+    /// Files emitted so far, removed from the context
+    pub fn take_emitted_files(&self) -> Vec<EmittedFile> {
+        std::mem::take(&mut *self.emitted_files.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Module code provided by a plugin instead of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadResult {
+    pub code: String,
+    /// How the code is bundled; JSON is turned into an ES module like JSON files
+    pub module_type: ModuleType,
 }
 
 /// Plugin trait - implement this to create a plugin
@@ -91,11 +156,25 @@ pub trait Plugin: Send + Sync {
         Ok(None)
     }
 
+    /// Load a module by id instead of reading the file
+    async fn load(&self, _id: &str) -> Result<Option<LoadResult>> {
+        Ok(None)
+    }
+
     /// Called before writing output files
     async fn before_output(&self, _context: &PluginContext) -> Result<()> {
         Ok(())
     }
 
+    /// Inspect, rewrite, add or remove the output files before they are written
+    async fn generate_bundle(
+        &self,
+        _context: &PluginContext,
+        _outputs: &mut Vec<OutputFile>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Called after writing output files
     async fn after_output(&self, _context: &PluginContext) -> Result<()> {
         Ok(())
@@ -137,7 +216,10 @@ impl PluginManager {
     /// Trigger before_build event
     pub async fn trigger_before_build(&self, context: &PluginContext) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.before_build(context).await?;
+            plugin
+                .before_build(context)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -149,7 +231,10 @@ impl PluginManager {
         result: &BuildResult,
     ) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.after_build(context, result).await?;
+            plugin
+                .after_build(context, result)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -157,7 +242,10 @@ impl PluginManager {
     /// Trigger before_bundle event
     pub async fn trigger_before_bundle(&self, context: &PluginContext) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.before_bundle(context).await?;
+            plugin
+                .before_bundle(context)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -169,7 +257,10 @@ impl PluginManager {
         bundle_code: &str,
     ) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.after_bundle(context, bundle_code).await?;
+            plugin
+                .after_bundle(context, bundle_code)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -177,7 +268,10 @@ impl PluginManager {
     /// Transform code through all plugins
     pub async fn transform_code(&self, module: &ModuleInfo, mut code: String) -> Result<String> {
         for plugin in &self.plugins {
-            code = plugin.transform_code(module, code).await?;
+            code = plugin
+                .transform_code(module, code)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(code)
     }
@@ -189,17 +283,53 @@ impl PluginManager {
         from_file: &str,
     ) -> Result<Option<String>> {
         for plugin in &self.plugins {
-            if let Some(resolved) = plugin.resolve_import(import_path, from_file).await? {
+            if let Some(resolved) = plugin
+                .resolve_import(import_path, from_file)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?
+            {
                 return Ok(Some(resolved));
             }
         }
         Ok(None)
     }
 
+    /// Load a module through plugins (first plugin that returns Some wins)
+    pub async fn load(&self, id: &str) -> Result<Option<LoadResult>> {
+        for plugin in &self.plugins {
+            if let Some(loaded) = plugin
+                .load(id)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?
+            {
+                return Ok(Some(loaded));
+            }
+        }
+        Ok(None)
+    }
+
     /// Trigger before_output event
     pub async fn trigger_before_output(&self, context: &PluginContext) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.before_output(context).await?;
+            plugin
+                .before_output(context)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
+        }
+        Ok(())
+    }
+
+    /// Trigger generate_bundle event
+    pub async fn trigger_generate_bundle(
+        &self,
+        context: &PluginContext,
+        outputs: &mut Vec<OutputFile>,
+    ) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin
+                .generate_bundle(context, outputs)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -207,7 +337,10 @@ impl PluginManager {
     /// Trigger after_output event
     pub async fn trigger_after_output(&self, context: &PluginContext) -> Result<()> {
         for plugin in &self.plugins {
-            plugin.after_output(context).await?;
+            plugin
+                .after_output(context)
+                .await
+                .map_err(|e| e.in_plugin(plugin.name()))?;
         }
         Ok(())
     }
@@ -337,6 +470,7 @@ impl Plugin for ImportResolverPlugin {
 mod tests {
     use super::*;
     use crate::core::models::ModuleType;
+    use crate::utils::SokuError;
     use std::path::PathBuf;
 
     fn create_test_module(path: &str, content: &str) -> ModuleInfo {
@@ -346,6 +480,7 @@ mod tests {
             module_type: ModuleType::JavaScript,
             dependencies: Vec::new(),
             exports: Vec::new(),
+            resolved_imports: std::collections::HashMap::new(),
         }
     }
 
@@ -500,6 +635,85 @@ mod tests {
         assert_eq!(resolved, Some("./src/utils".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_plugin_errors_name_the_plugin() {
+        struct FailingPlugin;
+
+        #[async_trait]
+        impl Plugin for FailingPlugin {
+            fn name(&self) -> &str {
+                "failing"
+            }
+
+            async fn transform_code(&self, _module: &ModuleInfo, _code: String) -> Result<String> {
+                Err(SokuError::build("cannot transform".to_string()))
+            }
+        }
+
+        let mut manager = PluginManager::new();
+        manager.register(Arc::new(FailingPlugin));
+
+        let module = create_test_module("test.js", "code");
+        let error = manager
+            .transform_code(&module, module.content.clone())
+            .await
+            .unwrap_err();
+        let context = error.context().unwrap();
+        assert_eq!(context.plugin.as_deref(), Some("failing"));
+        assert_eq!(error.to_string(), "Build error: cannot transform");
+    }
+
+    #[test]
+    fn test_emitted_files_are_shared_by_contexts() {
+        let files = EmittedFiles::default();
+        let before = create_test_context().with_emitted_files(files.clone());
+        let after = create_test_context().with_emitted_files(files);
+
+        before.emit_file("robots.txt", "User-agent: *\n");
+        before.emit_file("icons/app.png", vec![0x89, b'P', b'N', b'G']);
+        after.emit_file("robots.txt", "Disallow: /admin\n");
+
+        let emitted = after.take_emitted_files();
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].name, "icons/app.png");
+        assert_eq!(emitted[1].content, b"Disallow: /admin\n");
+        assert!(before.take_emitted_files().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_plugin_manager_load() {
+        struct VirtualPlugin;
+
+        #[async_trait]
+        impl Plugin for VirtualPlugin {
+            fn name(&self) -> &str {
+                "virtual"
+            }
+
+            async fn load(&self, id: &str) -> Result<Option<LoadResult>> {
+                Ok((id == "\0virtual:env").then(|| LoadResult {
+                    code: "export default 'test';".to_string(),
+                    module_type: ModuleType::JavaScript,
+                }))
+            }
+        }
+
+        let mut manager = PluginManager::new();
+        manager.register(Arc::new(LoggerPlugin::new()));
+        manager.register(Arc::new(VirtualPlugin));
+
+        let loaded = manager.load("\0virtual:env").await.unwrap().unwrap();
+        assert_eq!(loaded.code, "export default 'test';");
+        assert_eq!(manager.load("src/main.js").await.unwrap(), None);
+
+        assert!(is_virtual_module(Path::new("\0virtual:env")));
+        assert!(!is_virtual_module(Path::new("virtual:env")));
+        assert_eq!(
+            module_display_name(Path::new("\0virtual:env")),
+            "virtual:env"
+        );
+    }
+
     #[tokio::test]
     async fn test_plugin_event_types() {
         assert_eq!(PluginEvent::BeforeBuild, PluginEvent::BeforeBuild);
@@ -0,0 +1,283 @@
+// tsconfig.json support
+// Reads the compiler options that change how TypeScript is emitted, following
+// `extends` chains like tsc does
+
+use crate::core::models::TypeScriptConfig;
+use crate::utils::{Logger, Result, SokuError};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// `extends` chains longer than this are treated as a cycle
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RawTsConfig {
+    extends: Option<Extends>,
+    compiler_options: RawCompilerOptions,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Extends {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RawCompilerOptions {
+    target: Option<String>,
+    use_define_for_class_fields: Option<bool>,
+    verbatim_module_syntax: Option<bool>,
+    experimental_decorators: Option<bool>,
+    emit_decorator_metadata: Option<bool>,
+}
+
+impl RawCompilerOptions {
+    /// Options of `self` win over the ones inherited from `base`
+    fn or(self, base: RawCompilerOptions) -> Self {
+        Self {
+            target: self.target.or(base.target),
+            use_define_for_class_fields: self
+                .use_define_for_class_fields
+                .or(base.use_define_for_class_fields),
+            verbatim_module_syntax: self.verbatim_module_syntax.or(base.verbatim_module_syntax),
+            experimental_decorators: self
+                .experimental_decorators
+                .or(base.experimental_decorators),
+            emit_decorator_metadata: self
+                .emit_decorator_metadata
+                .or(base.emit_decorator_metadata),
+        }
+    }
+}
+
+/// Load the TypeScript options of the project from `<root>/tsconfig.json`
+///
+/// Without a tsconfig.json the defaults apply. `useDefineForClassFields`
+/// defaults like in tsc: `false` when `target` is older than ES2022 or not
+/// set, as tsc then targets ES5, `true` otherwise.
+pub fn load_tsconfig(root: &Path) -> Result<TypeScriptConfig> {
+    let path = root.join("tsconfig.json");
+    if !path.is_file() {
+        return Ok(TypeScriptConfig::default());
+    }
+
+    Logger::debug(&format!(
+        "Loading TypeScript options from {}",
+        path.display()
+    ));
+    let options = read_compiler_options(&path, root, 0)?;
+
+    let use_define_for_class_fields = options.use_define_for_class_fields.unwrap_or_else(|| {
+        options
+            .target
+            .as_deref()
+            .is_some_and(|target| !is_before_es2022(target))
+    });
+    Ok(TypeScriptConfig {
+        use_define_for_class_fields,
+        verbatim_module_syntax: options.verbatim_module_syntax.unwrap_or(false),
+        experimental_decorators: options.experimental_decorators.unwrap_or(false),
+        emit_decorator_metadata: options.emit_decorator_metadata.unwrap_or(false),
+    })
+}
+
+/// Compiler options of a tsconfig file merged over the ones it extends
+fn read_compiler_options(path: &Path, root: &Path, depth: usize) -> Result<RawCompilerOptions> {
+    if depth > MAX_EXTENDS_DEPTH {
+        return Err(SokuError::config(format!(
+            "tsconfig \"extends\" chain is too deep (circular?) at {}",
+            path.display()
+        )));
+    }
+
+    let content = std::fs::read_to_string(path).map_err(SokuError::Io)?;
+    let config: RawTsConfig = serde_json::from_str(&strip_jsonc(&content))
+        .map_err(|e| SokuError::config(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+    let bases = match config.extends {
+        None => Vec::new(),
+        Some(Extends::One(base)) => vec![base],
+        Some(Extends::Many(bases)) => bases,
+    };
+    // Later entries of an `extends` array override earlier ones
+    let mut inherited = RawCompilerOptions::default();
+    for base in bases {
+        match resolve_extends(&base, path, root) {
+            Some(base_path) => {
+                inherited = read_compiler_options(&base_path, root, depth + 1)?.or(inherited)
+            }
+            None => Logger::warn(&format!(
+                "{}: cannot find the extended config '{}'",
+                path.display(),
+                base
+            )),
+        }
+    }
+
+    Ok(config.compiler_options.or(inherited))
+}
+
+/// File an `extends` value refers to: a relative path or a package config
+fn resolve_extends(specifier: &str, from: &Path, root: &Path) -> Option<PathBuf> {
+    let base = if specifier.starts_with("./") || specifier.starts_with("../") {
+        from.parent().unwrap_or(root).join(specifier)
+    } else if Path::new(specifier).is_absolute() {
+        PathBuf::from(specifier)
+    } else {
+        root.join("node_modules").join(specifier)
+    };
+
+    let mut candidates = vec![base.clone()];
+    if base.extension().is_none_or(|ext| ext != "json") {
+        candidates.push(base.with_extension("json"));
+    }
+    candidates.push(base.join("tsconfig.json"));
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+/// Whether a tsconfig `target` predates class fields (ES2022)
+fn is_before_es2022(target: &str) -> bool {
+    let target = target.to_ascii_lowercase();
+    match target.as_str() {
+        "es3" | "es5" | "es6" => true,
+        "esnext" => false,
+        _ => target
+            .strip_prefix("es")
+            .and_then(|year| year.parse::<u32>().ok())
+            .is_some_and(|year| year < 2022),
+    }
+}
+
+/// Turn tsconfig's JSON-with-comments into plain JSON
+///
+/// Drops `//` and `/* */` comments and trailing commas, leaving strings alone.
+fn strip_jsonc(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                output.push(c);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    // A comma followed only by whitespace and a closing bracket
+    let mut result = String::with_capacity(output.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in output.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && output[i + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let content = r#"{
+  // comment
+  "compilerOptions": {
+    "target": "ES2020", /* block */
+    "paths": { "@/*": ["./src/*"], },
+    "url": "http://example.com//not-a-comment",
+  },
+}"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(content)).unwrap();
+        assert_eq!(value["compilerOptions"]["target"], "ES2020");
+        assert_eq!(
+            value["compilerOptions"]["url"],
+            "http://example.com//not-a-comment"
+        );
+    }
+
+    #[test]
+    fn test_load_tsconfig_with_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert_eq!(load_tsconfig(root).unwrap(), TypeScriptConfig::default());
+
+        std::fs::create_dir_all(root.join("config")).unwrap();
+        std::fs::write(
+            root.join("config/base.json"),
+            r#"{ "compilerOptions": { "target": "es2019", "experimentalDecorators": true } }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tsconfig.json"),
+            r#"{
+  "extends": "./config/base",
+  // Overrides the base config
+  "compilerOptions": { "verbatimModuleSyntax": true, "emitDecoratorMetadata": true, },
+}"#,
+        )
+        .unwrap();
+
+        let config = load_tsconfig(root).unwrap();
+        assert!(!config.use_define_for_class_fields); // from the ES2019 target
+        assert!(config.verbatim_module_syntax);
+        assert!(config.experimental_decorators);
+        assert!(config.emit_decorator_metadata);
+
+        std::fs::write(
+            root.join("tsconfig.json"),
+            r#"{ "extends": "./config/base.json", "compilerOptions": { "target": "ESNext" } }"#,
+        )
+        .unwrap();
+        assert!(load_tsconfig(root).unwrap().use_define_for_class_fields);
+
+        // tsc targets ES5 when no target is set
+        std::fs::write(
+            root.join("tsconfig.json"),
+            r#"{ "compilerOptions": { "strict": true } }"#,
+        )
+        .unwrap();
+        assert!(!load_tsconfig(root).unwrap().use_define_for_class_fields);
+    }
+}
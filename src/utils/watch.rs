@@ -240,6 +240,7 @@ impl SokuWatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::models::OutputFormat;
 
     #[test]
     fn test_watch_config_default() {
@@ -258,6 +259,7 @@ mod tests {
             enable_tree_shaking: false,
             enable_minification: false,
             enable_source_maps: false,
+            source_map: Default::default(),
             enable_code_splitting: false,
             max_chunk_size: None,
             mode: "development".to_string(),
@@ -265,6 +267,16 @@ mod tests {
             external: Vec::new(),
             vendor_chunk: false,
             entries: std::collections::HashMap::new(),
+            format: OutputFormat::Iife,
+            global_name: None,
+            globals: std::collections::HashMap::new(),
+            fail_on_circular: false,
+            js_file_names: "[name].js".to_string(),
+            asset_file_names: "[name].[ext]".to_string(),
+            target: "es2020".to_string(),
+            jsx: Default::default(),
+            typescript: Default::default(),
+            hmr: false,
         };
         let watcher = SokuWatcher::new(config, build_config);
 
@@ -1,5 +1,5 @@
 use soku::core::interfaces::BuildService;
-use soku::core::models::BuildConfig;
+use soku::core::models::{BuildConfig, JsxConfig, OutputFormat};
 use soku::infrastructure::processors::ProcessingStrategy;
 use soku::infrastructure::{LightningCssProcessor, TokioFileSystemService, UnifiedJsProcessor};
 use std::path::PathBuf;
@@ -22,6 +22,7 @@ async fn test_simple_project_build() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -29,6 +30,16 @@ async fn test_simple_project_build() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -66,6 +77,7 @@ async fn test_typescript_project_build() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -73,6 +85,16 @@ async fn test_typescript_project_build() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -117,6 +139,7 @@ async fn test_source_maps_generation() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: true,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -124,6 +147,16 @@ async fn test_source_maps_generation() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -178,6 +211,7 @@ async fn test_demo_project_build() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -185,6 +219,16 @@ async fn test_demo_project_build() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -212,3 +256,1510 @@ async fn test_demo_project_build() {
     // Cleanup
     let _ = std::fs::remove_dir_all(config.outdir);
 }
+
+#[tokio::test]
+async fn test_bundle_order_is_deterministic() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("main.js"),
+        "import { b } from './b.js';\nimport { a } from './a.js';\nimport('./lazy.js');\nconsole.log('mark-main', a, b);\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("a.js"),
+        "import { shared } from './shared.js';\nconsole.log('mark-a');\nexport const a = shared + 1;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("b.js"),
+        "import { shared } from './shared.js';\nconsole.log('mark-b');\nexport const b = shared + 2;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("shared.js"),
+        "console.log('mark-shared');\nexport const shared = 1;\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("lazy.js"), "console.log('mark-lazy');\n").unwrap();
+
+    let mut bundles = Vec::new();
+    for outdir in ["dist-1", "dist-2"] {
+        let fs_service = std::sync::Arc::new(TokioFileSystemService);
+        let js_processor =
+            std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+        let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+        let mut build_service =
+            soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join(outdir),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            // Source map builds skip the JS bundle cache, so both builds link from scratch
+            enable_source_maps: true,
+            mode: "development".to_string(),
+            ..Default::default()
+        };
+        build_service.build(&config).await.unwrap();
+        bundles.push(std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap());
+    }
+
+    assert_eq!(bundles[0], bundles[1], "Builds should be byte-identical");
+
+    // Dependencies evaluate before their importers, in import order
+    let positions: Vec<usize> = ["mark-shared", "mark-b", "mark-a", "mark-main"]
+        .iter()
+        .map(|marker| bundles[0].find(marker).expect(marker))
+        .collect();
+    assert!(
+        positions.windows(2).all(|pair| pair[0] < pair[1]),
+        "Unexpected module order:\n{}",
+        bundles[0]
+    );
+    // Dynamically imported modules run last, once the import does
+    let output = std::process::Command::new("node")
+        .arg(root.join("dist-1/bundle.js"))
+        .output();
+    if let Ok(output) = output {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let markers: Vec<&str> = stdout
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .collect();
+        assert_eq!(
+            markers,
+            ["mark-shared", "mark-b", "mark-a", "mark-main", "mark-lazy"],
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_circular_dependencies_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("main.js"),
+        "import { a } from './a.js';\nconsole.log(a);\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("a.js"),
+        "import { b } from './b.js';\nexport const a = b + 1;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("b.js"),
+        "import { a } from './a.js';\nexport const b = 1;\nexport function getA() { return a; }\n",
+    )
+    .unwrap();
+
+    let build = |fail_on_circular: bool| {
+        let root = root.to_path_buf();
+        async move {
+            let fs_service = std::sync::Arc::new(TokioFileSystemService);
+            let js_processor =
+                std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+            let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+            let mut build_service = soku::core::services::SokuBuildService::new(
+                fs_service,
+                js_processor,
+                css_processor,
+            );
+            let config = BuildConfig {
+                outdir: root.join("dist"),
+                root,
+                enable_minification: false,
+                fail_on_circular,
+                ..Default::default()
+            };
+            build_service.build(&config).await
+        }
+    };
+
+    let result = build(false).await.unwrap();
+    assert_eq!(
+        result.warnings,
+        vec!["Circular dependency: a.js -> b.js -> a.js".to_string()]
+    );
+
+    let error = build(true).await.unwrap_err();
+    assert!(error.to_string().contains("a.js -> b.js -> a.js"));
+}
+
+#[tokio::test]
+async fn test_hashed_file_names_and_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("main.js"),
+        "import { greet } from './greet.js';\nimport './style.css';\nconsole.log(greet('soku'));\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("greet.js"),
+        "export function greet(name) { return 'hi ' + name; }\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("style.css"), "body { color: red; }\n").unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        js_file_names: "[name].[hash].js".to_string(),
+        asset_file_names: "assets/[name].[hash].[ext]".to_string(),
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(config.outdir.join("manifest.json")).unwrap(),
+    )
+    .unwrap();
+    let main = &manifest["main.js"];
+    assert_eq!(main["isEntry"], true);
+    assert_eq!(main["hashed"], true);
+
+    // The hash covers the final file content
+    let js_file = main["file"].as_str().unwrap();
+    let js = std::fs::read(config.outdir.join(js_file)).unwrap();
+    assert_eq!(
+        js_file,
+        format!("bundle.{}.js", soku::utils::content_hash(&js))
+    );
+    assert_eq!(manifest["greet.js"]["file"], js_file);
+
+    let css_file = main["css"][0].as_str().unwrap();
+    assert!(css_file.starts_with("assets/bundle.") && css_file.ends_with(".css"));
+    assert!(config.outdir.join(css_file).exists());
+    assert!(!config.outdir.join("bundle.js").exists());
+}
+
+#[tokio::test]
+async fn test_rebuild_removes_outputs_of_the_previous_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("main.js"),
+        "import './style.css';\nconsole.log(1);\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("style.css"), "body { color: red; }\n").unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_source_maps: true,
+        js_file_names: "[name].[hash].js".to_string(),
+        asset_file_names: "assets/[name].[hash].[ext]".to_string(),
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+    // Files the build did not write are left alone
+    std::fs::write(config.outdir.join("notes.txt"), "keep").unwrap();
+
+    std::fs::write(
+        root.join("main.js"),
+        "import './style.css';\nconsole.log(2);\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("style.css"), "body { color: blue; }\n").unwrap();
+    build_service.build(&config).await.unwrap();
+
+    let names = |dir: &std::path::Path| {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    let outputs = names(&config.outdir);
+    let bundles: Vec<&String> = outputs
+        .iter()
+        .filter(|name| name.ends_with(".js"))
+        .collect();
+    let maps: Vec<&String> = outputs
+        .iter()
+        .filter(|name| name.ends_with(".js.map"))
+        .collect();
+    assert_eq!(bundles.len(), 1, "{:?}", outputs);
+    assert_eq!(maps.len(), 1, "{:?}", outputs);
+    assert!(std::fs::read_to_string(config.outdir.join(bundles[0]))
+        .unwrap()
+        .contains("console.log(2)"));
+    assert!(outputs.contains(&"notes.txt".to_string()));
+    let assets = names(&config.outdir.join("assets"));
+    assert_eq!(
+        assets.iter().filter(|name| name.ends_with(".css")).count(),
+        1,
+        "{:?}",
+        assets
+    );
+}
+
+#[tokio::test]
+async fn test_html_pages_are_rewritten() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/main.js"), "console.log('home');\n").unwrap();
+    std::fs::write(root.join("src/theme.css"), "body { color: red; }\n").unwrap();
+    std::fs::write(
+        root.join("index.html"),
+        r#"<html>
+  <head>
+    <link rel="stylesheet" href="/src/theme.css">
+  </head>
+  <body>
+    <script type="module" src="/src/main.js"></script>
+  </body>
+</html>"#,
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        js_file_names: "[name].[hash].js".to_string(),
+        asset_file_names: "[name].[hash].[ext]".to_string(),
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    let manifest: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(config.outdir.join("manifest.json")).unwrap(),
+    )
+    .unwrap();
+    let js_file = manifest["src/main.js"]["file"].as_str().unwrap();
+    let css_file = manifest["src/theme.css"]["file"].as_str().unwrap();
+
+    // The linked stylesheet is bundled and both tags point at the hashed
+    // outputs, root-absolute like the URLs they replace
+    let html = std::fs::read_to_string(config.outdir.join("index.html")).unwrap();
+    assert!(html.contains(&format!(r#"<link rel="stylesheet" href="/{}">"#, css_file)));
+    assert!(html.contains(&format!(
+        r#"<script type="module" src="/{}"></script>"#,
+        js_file
+    )));
+    assert!(std::fs::read_to_string(config.outdir.join(css_file))
+        .unwrap()
+        .contains("red"));
+}
+
+#[tokio::test]
+async fn test_jsx_automatic_runtime_is_bundled() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/preact")).unwrap();
+    std::fs::write(
+        root.join("node_modules/preact/package.json"),
+        r#"{"name":"preact","version":"10.0.0","main":"index.js"}"#,
+    )
+    .unwrap();
+    std::fs::write(root.join("node_modules/preact/index.js"), "").unwrap();
+    std::fs::write(
+        root.join("node_modules/preact/jsx-runtime.js"),
+        "export function jsx(type, props) { return { type, props, runtime: 'preact' }; }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.tsx"),
+        "const label: string = 'hi';\nconsole.log(<p class=\"x\">{label}</p>);\n",
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_minification: false,
+        jsx: JsxConfig {
+            import_source: Some("preact".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    // The compiled JSX calls the runtime, which is bundled instead of left as an import
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(bundle.contains("runtime: 'preact'"));
+    assert!(!bundle.contains("<p"));
+    assert!(!bundle.contains("from \"preact/jsx-runtime\""));
+}
+
+#[tokio::test]
+async fn test_target_lowers_app_and_dependency_syntax() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/dep")).unwrap();
+    std::fs::write(
+        root.join("node_modules/dep/package.json"),
+        r#"{"name":"dep","version":"1.0.0","main":"index.js"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("node_modules/dep/index.js"),
+        "export const get = (o) => o?.a ?? 'none';\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        r#"import { get } from 'dep';
+class Store { items = []; #size = 0; add(item) { this.items.push({ ...item }); this.#size ||= 1; } }
+const store = new Store();
+store.add({ a: 1 });
+try { console.log(get(store.items[0])); } catch { console.log('failed'); }
+"#,
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_minification: true,
+        target: "es2017".to_string(),
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    // Neither the transform nor the minifier leaves syntax newer than ES2017
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    for syntax in ["?.", "??", "||=", "...", "#size", "catch{", "items=[]"] {
+        assert!(!bundle.contains(syntax), "{} in {}", syntax, bundle);
+    }
+    assert!(bundle.contains("WeakMap"));
+}
+
+#[tokio::test]
+async fn test_source_maps_point_at_original_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/util.ts"),
+        r#"export interface Point { x: number; y: number }
+
+export function scale(point: Point, factor: number): Point {
+  return { x: point.x * factor, y: point.y * factor };
+}
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.ts"),
+        r#"import { scale } from './util';
+
+const p: { x: number } = scale({ x: 1, y: 2 }, 3);
+if (process.env.NODE_ENV === 'test') {
+  console.log('never');
+}
+console.log("scaled", p.x);
+"#,
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_minification: true,
+        enable_source_maps: true,
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    let map = oxc_sourcemap::SourceMap::from_json_string(
+        &std::fs::read_to_string(config.outdir.join("bundle.js.map")).unwrap(),
+    )
+    .unwrap();
+    let sources: Vec<&str> = map.get_sources().map(|s| s.as_ref()).collect();
+    assert_eq!(sources, ["../src/util.ts", "../src/main.ts"]);
+
+    // Original source, line and column of generated code (all zero-based)
+    let table = map.generate_lookup_table();
+    let original = |needle: &str| {
+        let offset = bundle
+            .find(needle)
+            .unwrap_or_else(|| panic!("{} not in {}", needle, bundle));
+        let line = bundle[..offset].matches('\n').count() as u32;
+        let column = (offset - bundle[..offset].rfind('\n').map_or(0, |i| i + 1)) as u32;
+        let token = map.lookup_token(&table, line, column).unwrap();
+        (
+            map.get_source(token.get_source_id().unwrap())
+                .unwrap()
+                .to_string(),
+            token.get_src_line(),
+            token.get_src_col(),
+        )
+    };
+    assert_eq!(original("`scaled`"), ("../src/main.ts".to_string(), 6, 12));
+    let (source, line, _) = original("*");
+    assert_eq!((source.as_str(), line), ("../src/util.ts", 3));
+}
+
+#[tokio::test]
+async fn test_scss_imported_from_js_is_bundled_with_its_source_map() {
+    use soku::infrastructure::ScssProcessor;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/theme.scss"),
+        "$accent: #cc3300;\n\n.banner {\n  padding: 0;\n\n  em {\n    color: $accent;\n  }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/plain.css"), ".plain {\n  margin: 0;\n}\n").unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import './theme.scss';\nimport './plain.css';\nconsole.log('styled');\n",
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(ScssProcessor::with_css_processor(
+        true,
+        std::sync::Arc::new(LightningCssProcessor::new(true)),
+    ));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_minification: true,
+        enable_source_maps: true,
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.css")).unwrap();
+    assert!(bundle.contains(".banner em{"), "{}", bundle);
+    let map = oxc_sourcemap::SourceMap::from_json_string(
+        &std::fs::read_to_string(config.outdir.join("bundle.css.map")).unwrap(),
+    )
+    .unwrap();
+    let mut sources: Vec<&str> = map.get_sources().map(|s| s.as_ref()).collect();
+    sources.sort_unstable();
+    assert_eq!(sources, ["../src/plain.css", "../src/theme.scss"]);
+
+    let js = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(!js.contains("theme.scss"), "{}", js);
+}
+
+#[tokio::test]
+async fn test_css_source_maps_point_at_scss_and_css_modules() {
+    use soku::infrastructure::ScssProcessor;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("styles.scss"),
+        "$primary: #336699;\n\n.nav {\n  margin: 0;\n\n  a {\n    color: $primary;\n  }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/card.module.css"),
+        ".card {\n  border: 1px solid red;\n}\n\n.title {\n  font-weight: bold;\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import styles from './card.module.css';\nconsole.log(styles);\n",
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(ScssProcessor::with_css_processor(
+        true,
+        std::sync::Arc::new(LightningCssProcessor::new(true)),
+    ));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_minification: true,
+        enable_source_maps: true,
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.css")).unwrap();
+    assert!(bundle.ends_with("/*# sourceMappingURL=bundle.css.map */"));
+    let map = oxc_sourcemap::SourceMap::from_json_string(
+        &std::fs::read_to_string(config.outdir.join("bundle.css.map")).unwrap(),
+    )
+    .unwrap();
+    let sources: Vec<&str> = map.get_sources().map(|s| s.as_ref()).collect();
+    assert_eq!(sources, ["../styles.scss", "../src/card.module.css"]);
+
+    // Original source and line of generated CSS (zero-based)
+    let table = map.generate_lookup_table();
+    let original = |needle: &str| {
+        let offset = bundle
+            .find(needle)
+            .unwrap_or_else(|| panic!("{} not in {}", needle, bundle));
+        let line = bundle[..offset].matches('\n').count() as u32;
+        let column = (offset - bundle[..offset].rfind('\n').map_or(0, |i| i + 1)) as u32;
+        let token = map.lookup_token(&table, line, column).unwrap();
+        (
+            map.get_source(token.get_source_id().unwrap())
+                .unwrap()
+                .to_string(),
+            token.get_src_line(),
+        )
+    };
+    assert_eq!(original(".nav{"), ("../styles.scss".to_string(), 2));
+    assert_eq!(original(".nav a{"), ("../styles.scss".to_string(), 5));
+    // Scoped CSS module class names still map to the original selectors
+    assert_eq!(
+        original(".card_title"),
+        ("../src/card.module.css".to_string(), 4)
+    );
+}
+
+#[tokio::test]
+async fn test_source_map_modes_in_multi_entry_builds() {
+    use soku::utils::{SourceMapConfig, SourceMapMode};
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/shared.js"),
+        "export const greet = (name) => `Hi ${name}`;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import { greet } from './shared.js';\nconsole.log(greet('main'));\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/admin.js"),
+        "import { greet } from './shared.js';\nconsole.log(greet('admin'));\n",
+    )
+    .unwrap();
+
+    let build = |mode: SourceMapMode| {
+        let outdir = root.join(format!("dist-{:?}", mode).to_lowercase());
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir,
+            enable_source_maps: true,
+            source_map: SourceMapConfig {
+                mode,
+                include_sources_content: false,
+                source_root: Some("https://example.com/".to_string()),
+            },
+            entries: std::collections::HashMap::from([
+                ("main".to_string(), root.join("src/main.js")),
+                ("admin".to_string(), root.join("src/admin.js")),
+            ]),
+            ..Default::default()
+        };
+        async move {
+            let fs_service = std::sync::Arc::new(TokioFileSystemService);
+            let js_processor =
+                std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+            let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+            let mut build_service = soku::core::services::SokuBuildService::new(
+                fs_service,
+                js_processor,
+                css_processor,
+            );
+            build_service.build(&config).await.unwrap();
+            config.outdir
+        }
+    };
+
+    // Hidden: maps are written but not referenced
+    let outdir = build(SourceMapMode::Hidden).await;
+    for name in ["main", "admin"] {
+        let code = std::fs::read_to_string(outdir.join(format!("{}.js", name))).unwrap();
+        assert!(!code.contains("sourceMappingURL"), "{}", code);
+        let map: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(outdir.join(format!("{}.js.map", name))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(map["file"], format!("{}.js", name));
+        assert_eq!(map["sourceRoot"], "https://example.com/");
+        assert!(map.get("sourcesContent").is_none());
+        assert!(map["sources"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!(format!("../src/{}.js", name))));
+    }
+
+    // Inline: the map is a data URL and no map file is written
+    let outdir = build(SourceMapMode::Inline).await;
+    let code = std::fs::read_to_string(outdir.join("main.js")).unwrap();
+    assert!(code.contains("//# sourceMappingURL=data:application/json;charset=utf-8;base64,"));
+    assert!(!outdir.join("main.js.map").exists());
+
+    // External: the comment points at the map file
+    let outdir = build(SourceMapMode::External).await;
+    let code = std::fs::read_to_string(outdir.join("admin.js")).unwrap();
+    assert!(code.ends_with("//# sourceMappingURL=admin.js.map"));
+    assert!(outdir.join("admin.js.map").exists());
+}
+
+#[tokio::test]
+async fn test_hmr_build_registers_compiled_modules() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/counter.ts"),
+        "export function next(count: number): number {\n  return count + 1;\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.ts"),
+        "import { next } from './counter';\nconst start: number = 1;\nconsole.log(next(start));\n",
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        mode: "development".to_string(),
+        hmr: true,
+        ..Default::default()
+    };
+    let result = build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(bundle.contains("__soku_global.__soku_runtime__ = {"));
+    let counter = result
+        .module_linking
+        .iter()
+        .find(|linking| linking.id == "counter.ts")
+        .unwrap();
+    let definition = counter.definition.as_deref().unwrap();
+    assert!(definition.starts_with("__soku_modules[\"counter.ts\"]"));
+    assert!(definition.contains("function next(count)"));
+    assert!(bundle.contains(definition));
+    let main = result
+        .module_linking
+        .iter()
+        .find(|linking| linking.id == "main.ts")
+        .unwrap();
+    assert_eq!(main.dependencies, vec!["counter.ts".to_string()]);
+    assert!(!main.definition.as_deref().unwrap().contains(": number"));
+}
+
+#[tokio::test]
+async fn test_hmr_invalidate_reaches_the_client_in_either_load_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "globalThis.invalidateMain = () => import.meta.hot.invalidate();\n",
+    )
+    .unwrap();
+
+    let fs_service = std::sync::Arc::new(TokioFileSystemService);
+    let js_processor = std::sync::Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = std::sync::Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        mode: "development".to_string(),
+        hmr: true,
+        ..Default::default()
+    };
+    build_service.build(&config).await.unwrap();
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    let client = soku::infrastructure::generate_hmr_client_code(3001, None);
+
+    // A page with just enough DOM for the client, whose socket opens on demand
+    let page = r#"
+var sent = [];
+var element = function () { return { style: {}, appendChild: function () {}, remove: function () {} }; };
+globalThis.window = globalThis;
+window.addEventListener = function () {};
+window.requestAnimationFrame = function () {};
+window.document = { createElement: element, body: element(), getElementById: function () { return null; } };
+window.location = { reload: function () { sent.push('reload'); } };
+window.WebSocket = function () { this.send = function (message) { sent.push(JSON.parse(message).kind); }; };
+"#;
+    let open = "window.__SOKU_HMR__.ws.onopen();\n";
+    let invalidate = "invalidateMain();\nprocess.on('exit', function () { console.log('sent: ' + sent.join()); });\n";
+    let orders = [
+        // The deferred bundle runs after the socket opened
+        [page, client.as_str(), open, bundle.as_str(), invalidate],
+        [page, bundle.as_str(), client.as_str(), open, invalidate],
+    ];
+    for (index, scripts) in orders.iter().enumerate() {
+        let path = config.outdir.join(format!("page-{}.js", index));
+        std::fs::write(&path, scripts.concat()).unwrap();
+        let Ok(output) = std::process::Command::new("node").arg(&path).output() else {
+            return;
+        };
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.contains("sent: Invalidate\n"),
+            "order {}: {}",
+            index,
+            stdout
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_plugin_hooks_run_in_every_build_path() {
+    use async_trait::async_trait;
+    use soku::core::models::{BuildResult, ModuleInfo};
+    use soku::utils::{Plugin, PluginContext};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the hooks it gets, defines `__VERSION__` and serves `virtual:config`
+    #[derive(Default)]
+    struct RecordingPlugin {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingPlugin {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    #[async_trait]
+    impl Plugin for RecordingPlugin {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn before_build(&self, _context: &PluginContext) -> soku::utils::Result<()> {
+            self.record("before_build".to_string());
+            Ok(())
+        }
+
+        async fn before_bundle(&self, context: &PluginContext) -> soku::utils::Result<()> {
+            self.record(format!("before_bundle {}", context.modules.len()));
+            Ok(())
+        }
+
+        async fn after_bundle(
+            &self,
+            _context: &PluginContext,
+            bundle_code: &str,
+        ) -> soku::utils::Result<()> {
+            self.record(format!(
+                "after_bundle {}",
+                bundle_code.contains("\"1.2.3\"")
+            ));
+            Ok(())
+        }
+
+        async fn transform_code(
+            &self,
+            _module: &ModuleInfo,
+            code: String,
+        ) -> soku::utils::Result<String> {
+            Ok(code.replace("__VERSION__", "\"1.2.3\""))
+        }
+
+        async fn resolve_import(
+            &self,
+            import_path: &str,
+            _from_file: &str,
+        ) -> soku::utils::Result<Option<String>> {
+            Ok((import_path == "virtual:config").then(|| "./src/config".to_string()))
+        }
+
+        async fn before_output(&self, context: &PluginContext) -> soku::utils::Result<()> {
+            let written = context.config.outdir.join("manifest.json").exists();
+            self.record(format!("before_output {}", written));
+            Ok(())
+        }
+
+        async fn after_output(&self, context: &PluginContext) -> soku::utils::Result<()> {
+            let written = context.config.outdir.join("manifest.json").exists();
+            self.record(format!("after_output {}", written));
+            Ok(())
+        }
+
+        async fn after_build(
+            &self,
+            _context: &PluginContext,
+            _result: &BuildResult,
+        ) -> soku::utils::Result<()> {
+            self.record("after_build".to_string());
+            Ok(())
+        }
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/config.js"),
+        "export const version = __VERSION__;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import { version } from 'virtual:config';\nconsole.log(version);\n",
+    )
+    .unwrap();
+
+    let build = |outdir: &str, entries: bool| {
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join(outdir),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            entries: if entries {
+                std::collections::HashMap::from([("main".to_string(), root.join("src/main.js"))])
+            } else {
+                std::collections::HashMap::new()
+            },
+            ..Default::default()
+        };
+        async move {
+            let plugin = Arc::new(RecordingPlugin::default());
+            let fs_service = Arc::new(TokioFileSystemService);
+            let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+            let css_processor = Arc::new(LightningCssProcessor::new(false));
+            let mut build_service = soku::core::services::SokuBuildService::new(
+                fs_service,
+                js_processor,
+                css_processor,
+            )
+            .with_plugin(plugin.clone());
+            build_service.build(&config).await.unwrap();
+            let events = plugin.events.lock().unwrap().clone();
+            (config.outdir, events)
+        }
+    };
+
+    let expected = vec![
+        "before_build",
+        "before_bundle 2",
+        "after_bundle true",
+        "before_output false",
+        "after_output true",
+        "after_build",
+    ];
+    let (outdir, events) = build("dist", false).await;
+    assert_eq!(events, expected);
+    let bundle = std::fs::read_to_string(outdir.join("bundle.js")).unwrap();
+    assert!(bundle.contains("\"1.2.3\""), "{}", bundle);
+    assert!(!bundle.contains("virtual:config"), "{}", bundle);
+
+    let (outdir, events) = build("dist-entries", true).await;
+    assert_eq!(events, expected);
+    assert!(std::fs::read_to_string(outdir.join("main.js"))
+        .unwrap()
+        .contains("\"1.2.3\""));
+}
+
+#[tokio::test]
+async fn test_plugins_load_virtual_modules() {
+    use async_trait::async_trait;
+    use soku::core::models::ModuleType;
+    use soku::utils::{LoadResult, Plugin};
+    use std::sync::Arc;
+
+    /// Serves `virtual:routes` from memory, importing a page from disk
+    struct RoutesPlugin;
+
+    #[async_trait]
+    impl Plugin for RoutesPlugin {
+        fn name(&self) -> &str {
+            "routes"
+        }
+
+        async fn resolve_import(
+            &self,
+            import_path: &str,
+            _from_file: &str,
+        ) -> soku::utils::Result<Option<String>> {
+            Ok((import_path == "virtual:routes").then(|| "\0virtual:routes".to_string()))
+        }
+
+        async fn load(&self, id: &str) -> soku::utils::Result<Option<LoadResult>> {
+            Ok((id == "\0virtual:routes").then(|| LoadResult {
+                code: "import { home } from './src/home';\nexport const routes = [home];\n"
+                    .to_string(),
+                module_type: ModuleType::JavaScript,
+            }))
+        }
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/home.js"), "export const home = '/home';\n").unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import { routes } from 'virtual:routes';\nconsole.log(routes);\n",
+    )
+    .unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor)
+            .with_plugin(Arc::new(RoutesPlugin));
+    build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(bundle.contains("'/home'"));
+    assert!(bundle.contains("[home]"));
+    assert!(!bundle.contains('\0'));
+}
+
+#[tokio::test]
+async fn test_plugins_emit_files_and_rewrite_outputs() {
+    use async_trait::async_trait;
+    use soku::core::models::OutputFile;
+    use soku::utils::{Plugin, PluginContext};
+    use std::sync::Arc;
+
+    /// Emits a robots.txt and an icon, and adds a banner to the JavaScript bundles
+    struct AssetsPlugin;
+
+    #[async_trait]
+    impl Plugin for AssetsPlugin {
+        fn name(&self) -> &str {
+            "assets"
+        }
+
+        async fn before_build(&self, context: &PluginContext) -> soku::utils::Result<()> {
+            context.emit_file("robots.txt", "User-agent: *\n");
+            Ok(())
+        }
+
+        async fn generate_bundle(
+            &self,
+            context: &PluginContext,
+            outputs: &mut Vec<OutputFile>,
+        ) -> soku::utils::Result<()> {
+            for output in outputs.iter_mut() {
+                if output.path.extension().is_some_and(|ext| ext == "js") {
+                    *output = OutputFile::new(
+                        output.path.clone(),
+                        format!("/* banner */\n{}", output.text().unwrap()),
+                    );
+                }
+            }
+            context.emit_file("icons/app.png", vec![0x89, b'P', b'N', b'G', 0xff]);
+            Ok(())
+        }
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/main.js"), "console.log('main');\n").unwrap();
+    std::fs::write(root.join("src/admin.js"), "console.log('admin');\n").unwrap();
+
+    // The single bundle, multi-entry and vendor chunk paths, with their bundles
+    let builds = [
+        ("dist", Vec::new(), false, vec!["bundle.js"]),
+        (
+            "dist-entries",
+            vec![
+                ("main".to_string(), root.join("src/main.js")),
+                ("admin".to_string(), root.join("src/admin.js")),
+            ],
+            false,
+            vec!["main.js", "admin.js"],
+        ),
+        ("dist-vendor", Vec::new(), true, vec!["app.js"]),
+    ];
+    for (outdir, entries, vendor_chunk, bundles) in builds {
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join(outdir),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            entries: entries.into_iter().collect(),
+            vendor_chunk,
+            ..Default::default()
+        };
+        let fs_service = Arc::new(TokioFileSystemService);
+        let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+        let css_processor = Arc::new(LightningCssProcessor::new(false));
+        let mut build_service =
+            soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor)
+                .with_plugin(Arc::new(AssetsPlugin));
+        let result = build_service.build(&config).await.unwrap();
+
+        for bundle in bundles {
+            let bundle = std::fs::read_to_string(config.outdir.join(bundle)).unwrap();
+            assert!(bundle.starts_with("/* banner */\n"), "{}", outdir);
+        }
+        assert_eq!(
+            std::fs::read_to_string(config.outdir.join("robots.txt")).unwrap(),
+            "User-agent: *\n"
+        );
+        assert_eq!(
+            std::fs::read(config.outdir.join("icons/app.png")).unwrap(),
+            vec![0x89, b'P', b'N', b'G', 0xff]
+        );
+        for (file, size) in [("icons/app.png", 5), ("robots.txt", 14)] {
+            assert!(
+                result
+                    .output_files
+                    .iter()
+                    .any(|output| output.path == config.outdir.join(file) && output.size == size),
+                "{} missing from the outputs of {}",
+                file,
+                outdir
+            );
+        }
+
+        let manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(config.outdir.join("manifest.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest["robots.txt"]["file"], "robots.txt");
+        assert_eq!(manifest["icons/app.png"]["file"], "icons/app.png");
+    }
+}
+
+#[tokio::test]
+async fn test_public_files_stay_out_of_production_builds() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("public")).unwrap();
+    std::fs::write(root.join("src/main.js"), "console.log('app');\n").unwrap();
+    std::fs::write(root.join("public/robots.txt"), "User-agent: *\n").unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+    build_service.build(&config).await.unwrap();
+
+    assert!(config.outdir.join("bundle.js").exists());
+    assert!(!config.outdir.join("robots.txt").exists());
+}
+
+#[tokio::test]
+async fn test_mjs_entries_and_packages_are_bundled() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/esm-only")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/cjs-only")).unwrap();
+    std::fs::write(
+        root.join("node_modules/esm-only/package.json"),
+        r#"{"name": "esm-only", "version": "1.0.0", "main": "index.mjs"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("node_modules/esm-only/index.mjs"),
+        "export const shout = (text) => text.toUpperCase();\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("node_modules/cjs-only/package.json"),
+        r#"{"name": "cjs-only", "version": "1.0.0", "main": "index.cjs"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("node_modules/cjs-only/index.cjs"),
+        "module.exports = function exclaim(text) { return text + '!'; };\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.mjs"),
+        "import { shout } from 'esm-only';\nimport exclaim from 'cjs-only';\nconsole.log(exclaim(shout('soku')));\n",
+    )
+    .unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        format: OutputFormat::Cjs,
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+    let result = build_service.build(&config).await.unwrap();
+    assert_eq!(result.js_modules_processed, 3);
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(bundle.contains("toUpperCase"), "{}", bundle);
+    assert!(bundle.contains("text + '!'"), "{}", bundle);
+    assert!(!bundle.contains("require('cjs-only')"), "{}", bundle);
+    let output = std::process::Command::new("node")
+        .arg(config.outdir.join("bundle.js"))
+        .output();
+    if let Ok(output) = output {
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "SOKU!\n");
+    }
+}
+
+#[tokio::test]
+async fn test_builds_without_styles_write_no_css_bundle() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/main.js"), "console.log('no styles');\n").unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+    let result = build_service.build(&config).await.unwrap();
+    assert_eq!(result.css_files_processed, 0);
+
+    assert!(config.outdir.join("bundle.js").exists());
+    assert!(!config.outdir.join("bundle.css").exists());
+    let manifest = std::fs::read_to_string(config.outdir.join("manifest.json")).unwrap();
+    assert!(!manifest.contains("bundle.css"), "{}", manifest);
+}
+
+#[tokio::test]
+async fn test_cached_bundles_keep_their_linking_report() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/util.js"), "export const yes = 1;\n").unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import { yes, nope } from './util.js';\nconsole.log(yes, nope);\n",
+    )
+    .unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+
+    let first = build_service.build(&config).await.unwrap();
+    // Unchanged sources hit the JS cache
+    let second = build_service.build(&config).await.unwrap();
+    for result in [&first, &second] {
+        let ids: Vec<&str> = result
+            .module_linking
+            .iter()
+            .map(|linking| linking.id.as_str())
+            .collect();
+        assert_eq!(ids, ["util.js", "main.js"]);
+        assert!(
+            result
+                .warnings
+                .iter()
+                .any(|warning| warning.contains("'nope' is not exported by './util.js'")),
+            "{:?}",
+            result.warnings
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_stylesheet_imports_stay_out_of_the_js_bundle() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/style.css"), "body { color: red; }\n").unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import './style.css';\nconsole.log('styled');\n",
+    )
+    .unwrap();
+
+    for format in [OutputFormat::Iife, OutputFormat::Esm, OutputFormat::Cjs] {
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join("dist"),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            format,
+            ..Default::default()
+        };
+        let fs_service = Arc::new(TokioFileSystemService);
+        let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+        let css_processor = Arc::new(LightningCssProcessor::new(false));
+        let mut build_service =
+            soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+        build_service.build(&config).await.unwrap();
+
+        let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+        assert!(!bundle.contains("style.css"), "{}", bundle);
+        assert!(!bundle.contains("styleCss"), "{}", bundle);
+        let css = std::fs::read_to_string(config.outdir.join("bundle.css")).unwrap();
+        assert!(css.contains("color"), "{}", css);
+
+        if format == OutputFormat::Esm {
+            continue;
+        }
+        let output = std::process::Command::new("node")
+            .arg(config.outdir.join("bundle.js"))
+            .output();
+        if let Ok(output) = output {
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "styled\n");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_dynamic_imports_load_bundled_modules() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src/name.js"), "export const name = 'main';\n").unwrap();
+    std::fs::write(
+        root.join("src/lazy.js"),
+        "console.log('lazy loaded');\nexport const value = 42;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import { name } from './name.js';\nconsole.log(name);\nimport('./lazy.js').then((lazy) => console.log(lazy.value));\n",
+    )
+    .unwrap();
+
+    let config = BuildConfig {
+        root: root.to_path_buf(),
+        outdir: root.join("dist"),
+        enable_tree_shaking: false,
+        enable_minification: false,
+        format: OutputFormat::Cjs,
+        ..Default::default()
+    };
+    let fs_service = Arc::new(TokioFileSystemService);
+    let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+    let css_processor = Arc::new(LightningCssProcessor::new(false));
+    let mut build_service =
+        soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+    build_service.build(&config).await.unwrap();
+
+    let bundle = std::fs::read_to_string(config.outdir.join("bundle.js")).unwrap();
+    assert!(!bundle.contains("import('./lazy.js')"), "{}", bundle);
+    let output = std::process::Command::new("node")
+        .arg(config.outdir.join("bundle.js"))
+        .output();
+    if let Ok(output) = output {
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        // The module runs when it is imported, not with the bundle
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "main\nlazy loaded\n42\n"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_split_builds_share_one_module_registry() {
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("node_modules/greet")).unwrap();
+    std::fs::write(
+        root.join("node_modules/greet/package.json"),
+        r#"{"name": "greet", "version": "1.0.0", "main": "index.js"}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("node_modules/greet/index.js"),
+        "module.exports = function greet(name) { return 'hello ' + name; };\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/helper.js"), "export const helper = '!';\n").unwrap();
+    std::fs::write(
+        root.join("src/util.js"),
+        "import { helper } from './helper.js';\nexport const util = () => 'util' + helper;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/other.js"),
+        "import { helper } from './helper.js';\nexport const other = () => 'other' + helper;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.js"),
+        "import greet from 'greet';\nimport { util } from './util.js';\nimport { other } from './other.js';\nconsole.log(greet(util()), other());\n",
+    )
+    .unwrap();
+
+    for (outdir, vendor_chunk, enable_code_splitting) in
+        [("dist-vendor", true, false), ("dist-chunks", false, true)]
+    {
+        let config = BuildConfig {
+            root: root.to_path_buf(),
+            outdir: root.join(outdir),
+            enable_tree_shaking: false,
+            enable_minification: false,
+            vendor_chunk,
+            enable_code_splitting,
+            target: "es2017".to_string(),
+            ..Default::default()
+        };
+        let fs_service = Arc::new(TokioFileSystemService);
+        let js_processor = Arc::new(UnifiedJsProcessor::new(ProcessingStrategy::Standard));
+        let css_processor = Arc::new(LightningCssProcessor::new(false));
+        let mut build_service =
+            soku::core::services::SokuBuildService::new(fs_service, js_processor, css_processor);
+        build_service.build(&config).await.unwrap();
+
+        // The chunks the entry imports are loaded before it, as in a page
+        let manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(config.outdir.join("manifest.json")).unwrap(),
+        )
+        .unwrap();
+        let entry = &manifest["src/main.js"];
+        let mut files: Vec<&str> = entry["imports"]
+            .as_array()
+            .map(|imports| imports.iter().filter_map(|i| i.as_str()).collect())
+            .unwrap_or_default();
+        assert!(!files.is_empty(), "{}: {}", outdir, manifest);
+        files.push(entry["file"].as_str().unwrap());
+        let page: String = files
+            .iter()
+            .map(|file| std::fs::read_to_string(config.outdir.join(file)).unwrap())
+            .collect();
+        assert!(!page.contains("})(greet);"), "{}", page);
+        // ES2017 runtimes have no `globalThis`, so it is only read when defined
+        assert!(!page
+            .replace("typeof globalThis !== 'undefined' ? globalThis", "")
+            .contains("globalThis"));
+        std::fs::write(config.outdir.join("page.js"), &page).unwrap();
+
+        let output = std::process::Command::new("node")
+            .arg(config.outdir.join("page.js"))
+            .output();
+        if let Ok(output) = output {
+            assert!(
+                output.status.success(),
+                "{}: {}",
+                outdir,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout),
+                "hello util! other!\n"
+            );
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use soku::core::interfaces::BuildService;
-use soku::core::models::BuildConfig;
+use soku::core::models::{BuildConfig, OutputFormat};
 use soku::infrastructure::processors::ProcessingStrategy;
 use soku::infrastructure::{LightningCssProcessor, TokioFileSystemService, UnifiedJsProcessor};
 use std::path::PathBuf;
@@ -21,6 +21,7 @@ async fn test_css_modules_detection() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -28,6 +29,16 @@ async fn test_css_modules_detection() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -62,6 +73,7 @@ async fn test_css_modules_scoping() {
         enable_tree_shaking: false,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -69,6 +81,16 @@ async fn test_css_modules_scoping() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -1,5 +1,5 @@
 use soku::core::interfaces::BuildService;
-use soku::core::models::BuildConfig;
+use soku::core::models::{BuildConfig, OutputFormat};
 use soku::infrastructure::processors::ProcessingStrategy;
 use soku::infrastructure::{
     LightningCssProcessor, RegexTreeShaker, TokioFileSystemService, UnifiedJsProcessor,
@@ -26,6 +26,7 @@ async fn test_tree_shaking_removes_unused_code() {
         enable_tree_shaking: true,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -33,6 +34,16 @@ async fn test_tree_shaking_removes_unused_code() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -80,6 +91,7 @@ async fn test_tree_shaking_preserves_used_code() {
         enable_tree_shaking: true,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -87,6 +99,16 @@ async fn test_tree_shaking_preserves_used_code() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;
@@ -123,6 +145,7 @@ async fn test_tree_shaking_with_typescript() {
         enable_tree_shaking: true,
         enable_minification: false,
         enable_source_maps: false,
+        source_map: Default::default(),
         enable_code_splitting: false,
         max_chunk_size: None,
         mode: "development".to_string(),
@@ -130,6 +153,16 @@ async fn test_tree_shaking_with_typescript() {
         external: Vec::new(),
         vendor_chunk: false,
         entries: std::collections::HashMap::new(),
+        format: OutputFormat::Iife,
+        global_name: None,
+        globals: std::collections::HashMap::new(),
+        fail_on_circular: false,
+        js_file_names: "[name].js".to_string(),
+        asset_file_names: "[name].[ext]".to_string(),
+        target: "es2020".to_string(),
+        jsx: Default::default(),
+        typescript: Default::default(),
+        hmr: false,
     };
 
     let result = build_service.build(&config).await;